@@ -0,0 +1,268 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::{Path, PathBuf}};
+
+use gfx::definitions::{UiAtlas, UiAtlasTexture};
+use image::{GenericImage, ImageError, Rgba, RgbaImage};
+
+/// Default for `EditorConfig::assets_dir`.
+pub const ASSETS_DIR: &str = "./app/assets";
+/// wgpu's default `max_texture_dimension_2d`, which `RenderState` requests.
+const MAX_ATLAS_SIZE: u32 = 8192;
+/// Gutter around every entry, filled with copies of its edge pixels so linear filtering at the edge of one entry
+/// blends with more of itself rather than a neighbour or transparent black.
+const PADDING: u32 = 2;
+/// Side of the checker drawn for textures the atlas has no entry for, see `UiAtlas::set_missing`.
+const MISSING_SIZE: u32 = 16;
+/// Side of each square of the missing texture's checker.
+const MISSING_SQUARE: u32 = 4;
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(PathBuf, io::Error),
+    Image(PathBuf, ImageError),
+    /// Two assets would both be looked up as `name`.
+    DuplicateName { name: String, first: PathBuf, second: PathBuf },
+    /// The packed atlas, or a single asset, is bigger than the GPU texture limit.
+    TooLarge { name: String, width: u32, height: u32 },
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Io(path, e) => write!(f, "Unable to read {}: {}", path.display(), e),
+            AtlasError::Image(path, e) => write!(f, "Unable to load {}: {}", path.display(), e),
+            AtlasError::DuplicateName { name, first, second } => write!(
+                f, "{} and {} would both be named {:?} in the atlas, rename one of them", first.display(), second.display(), name,
+            ),
+            AtlasError::TooLarge { name, width, height } => write!(
+                f, "{} is {}x{}, larger than the {}x{} texture limit", name, width, height, MAX_ATLAS_SIZE, MAX_ATLAS_SIZE,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// Packs every image in `assets_dir` into one roughly square texture, named by file stem.
+/// Entries are ordered by name before packing, so the same assets always give the same atlas.
+/// Whatever their format, assets are converted to RGBA8 first. A checker is packed alongside them as the
+/// atlas's missing texture.
+pub fn generate_texture_atlas(assets_dir: &Path) -> Result<(RgbaImage, UiAtlas), AtlasError> {
+    generate_texture_atlas_with_progress(assets_dir, |_| {})
+}
+
+/// Like `generate_texture_atlas`, calling `progress` with the fraction done, from 0.0 to 1.0, before each file is
+/// loaded and once the atlas is packed.
+pub fn generate_texture_atlas_with_progress(assets_dir: &Path, mut progress: impl FnMut(f32)) -> Result<(RgbaImage, UiAtlas), AtlasError> {
+    let images = load_assets(assets_dir, &mut progress)?;
+
+    for (name, image) in &images {
+        if image.width() + 2 * PADDING > MAX_ATLAS_SIZE || image.height() + 2 * PADDING > MAX_ATLAS_SIZE {
+            return Err(AtlasError::TooLarge { name: name.clone(), width: image.width(), height: image.height() });
+        }
+    }
+
+    // The missing texture packs after the assets, it's never looked up by name
+    let missing = missing_texture();
+    let sizes: Vec<(u32, u32)> = images.values().chain(std::iter::once(&missing)).map(RgbaImage::dimensions).collect();
+    let (mut placements, width, height) = pack(&sizes);
+    if width > MAX_ATLAS_SIZE || height > MAX_ATLAS_SIZE {
+        return Err(AtlasError::TooLarge { name: "The packed atlas".to_string(), width, height });
+    }
+
+    let mut atlas = RgbaImage::new(width, height);
+    let mut atlas_data = UiAtlas::new(width, height);
+    let (missing_x, missing_y) = placements.pop().unwrap_or_default();
+    for ((name, image), (x, y)) in images.iter().zip(placements) {
+        atlas.copy_from(image, x, y)
+            .map_err(|e| AtlasError::Image(assets_dir.join(name), e))?;
+        bleed_edges(&mut atlas, x, y, image.width(), image.height());
+        atlas_data.add_entry(UiAtlasTexture::new(name.clone(), x, y, image.width(), image.height()));
+    }
+    atlas.copy_from(&missing, missing_x, missing_y)
+        .map_err(|e| AtlasError::Image(assets_dir.to_path_buf(), e))?;
+    bleed_edges(&mut atlas, missing_x, missing_y, MISSING_SIZE, MISSING_SIZE);
+    atlas_data.set_missing(UiAtlasTexture::new("missing".to_string(), missing_x, missing_y, MISSING_SIZE, MISSING_SIZE));
+
+    progress(1.0);
+    Ok((atlas, atlas_data))
+}
+
+/// Every image in `assets_dir` as RGBA8, keyed by file stem, which keeps them sorted by name.
+/// Packing counts as one more step than there are files for `progress`.
+fn load_assets(assets_dir: &Path, progress: &mut impl FnMut(f32)) -> Result<BTreeMap<String, RgbaImage>, AtlasError> {
+    let read_dir = fs::read_dir(assets_dir).map_err(|e| AtlasError::Io(assets_dir.to_path_buf(), e))?;
+    let mut paths = read_dir
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, io::Error>>()
+        .map_err(|e| AtlasError::Io(assets_dir.to_path_buf(), e))?;
+    paths.sort();
+
+    let mut images = BTreeMap::new();
+    let mut sources: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let steps = paths.len() + 1;
+    for (loaded, path) in paths.into_iter().enumerate() {
+        progress(loaded as f32 / steps as f32);
+        if !path.is_file() {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string) else {
+            log::warn!("Skipping asset with a non UTF-8 name: {}", path.display());
+            continue;
+        };
+        if let Some(first) = sources.get(&name) {
+            return Err(AtlasError::DuplicateName { name, first: first.clone(), second: path });
+        }
+
+        // Grayscale, palette and 16-bit images all end up the layout the atlas is
+        let image = image::open(&path).map_err(|e| AtlasError::Image(path.clone(), e))?.into_rgba8();
+        sources.insert(name.clone(), path);
+        images.insert(name, image);
+    }
+    Ok(images)
+}
+
+/// Shelf packing: the tallest images go first, filling rows left to right up to a width chosen
+/// to make the atlas about square. Returns the position of each of `sizes`, in order, and the atlas size.
+fn pack(sizes: &[(u32, u32)]) -> (Vec<(u32, u32)>, u32, u32) {
+    let padded: Vec<(u32, u32)> = sizes.iter().map(|&(width, height)| (width + 2 * PADDING, height + 2 * PADDING)).collect();
+
+    let area: u64 = padded.iter().map(|&(width, height)| width as u64 * height as u64).sum();
+    let widest = padded.iter().map(|&(width, _)| width).max().unwrap_or(1);
+    let shelf_width = ((area as f64).sqrt().ceil() as u32).max(widest);
+
+    // Stable, so images of equal height stay in name order
+    let mut order: Vec<usize> = (0..padded.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(padded[index].1));
+
+    let mut placements = vec![(0, 0); padded.len()];
+    let (mut x, mut y, mut shelf_height, mut used_width) = (0, 0, 0, 0);
+    for index in order {
+        let (width, height) = padded[index];
+        if x + width > shelf_width {
+            y += shelf_height;
+            x = 0;
+            shelf_height = 0;
+        }
+        placements[index] = (x + PADDING, y + PADDING);
+        x += width;
+        used_width = used_width.max(x);
+        shelf_height = shelf_height.max(height);
+    }
+
+    (placements, used_width.max(1), (y + shelf_height).max(1))
+}
+
+/// Magenta and black checker, loud enough that a bad texture name shows up wherever it's drawn.
+fn missing_texture() -> RgbaImage {
+    RgbaImage::from_fn(MISSING_SIZE, MISSING_SIZE, |x, y| {
+        if (x / MISSING_SQUARE + y / MISSING_SQUARE).is_multiple_of(2) {
+            Rgba([255, 0, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    })
+}
+
+/// Fills the `PADDING` wide gutter around the `width` by `height` entry at `(x, y)` with its nearest edge pixel,
+/// corners included. The gutter is inside the atlas, `pack` leaves room for it on every side.
+fn bleed_edges(atlas: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    for gutter_y in y - PADDING..y + height + PADDING {
+        for gutter_x in x - PADDING..x + width + PADDING {
+            let inside = (x..x + width).contains(&gutter_x) && (y..y + height).contains(&gutter_y);
+            if inside {
+                continue;
+            }
+            let source_x = gutter_x.clamp(x, x + width - 1);
+            let source_y = gutter_y.clamp(y, y + height - 1);
+            let pixel = *atlas.get_pixel(source_x, source_y);
+            atlas.put_pixel(gutter_x, gutter_y, pixel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayAlphaImage, GrayImage, Luma, LumaA, Rgb, RgbImage};
+
+    use super::*;
+    use crate::storage::tests::TestDir;
+
+    /// Assets in every layout `image::open` hands back, each a different size and a color of its own.
+    fn mixed_assets(dir: &TestDir) {
+        RgbImage::from_pixel(7, 3, Rgb([255, 0, 0])).save(dir.join("rgb.png")).unwrap();
+        RgbaImage::from_pixel(4, 9, Rgba([0, 0, 255, 128])).save(dir.join("rgba.png")).unwrap();
+        GrayImage::from_pixel(5, 5, Luma([200])).save(dir.join("gray.png")).unwrap();
+        GrayAlphaImage::from_pixel(2, 6, LumaA([60, 255])).save(dir.join("gray_alpha.png")).unwrap();
+    }
+
+    #[test]
+    fn assets_of_every_layout_are_packed_as_rgba() {
+        let dir = TestDir::new("atlas_layouts");
+        mixed_assets(&dir);
+        let (image, atlas) = generate_texture_atlas(&dir.join("")).unwrap();
+
+        assert_eq!(atlas.names().collect::<Vec<_>>(), ["gray", "gray_alpha", "rgb", "rgba"]);
+        for (name, size, color) in [
+            ("rgb", (7, 3), Rgba([255, 0, 0, 255])),
+            ("rgba", (4, 9), Rgba([0, 0, 255, 128])),
+            ("gray", (5, 5), Rgba([200, 200, 200, 255])),
+            ("gray_alpha", (2, 6), Rgba([60, 60, 60, 255])),
+        ] {
+            let entry = atlas.get(name).unwrap();
+            let (x, y, width, height) = entry.pixel_rect();
+            assert_eq!((width, height), size, "size of {}", name);
+            assert!((x..x + width).all(|px| (y..y + height).all(|py| *image.get_pixel(px, py) == color)), "pixels of {}", name);
+        }
+    }
+
+    #[test]
+    fn linear_filtering_at_an_entry_edge_never_reaches_a_neighbour() {
+        let dir = TestDir::new("atlas_bleed");
+        mixed_assets(&dir);
+        let (image, atlas) = generate_texture_atlas(&dir.join("")).unwrap();
+        let (atlas_width, atlas_height) = (atlas.width() as f32, atlas.height() as f32);
+
+        for name in atlas.names() {
+            let entry = atlas.get(name).unwrap();
+            let (x, y, width, height) = entry.pixel_rect();
+            let ((u_0, v_0), (u_1, v_1)) = (entry.start_coord.unwrap(), entry.end_coord.unwrap());
+            for (u, v) in [(u_0, v_0), (u_1, v_0), (u_0, v_1), (u_1, v_1)] {
+                // Bilinear filtering blends the two texels on either side of the sample point in each direction
+                let (left, top) = ((u * atlas_width - 0.5).floor() as u32, (v * atlas_height - 0.5).floor() as u32);
+                for (texel_x, texel_y) in [(left, top), (left + 1, top), (left, top + 1), (left + 1, top + 1)] {
+                    let nearest = image.get_pixel(texel_x.clamp(x, x + width - 1), texel_y.clamp(y, y + height - 1));
+                    assert_eq!(image.get_pixel(texel_x, texel_y), nearest, "{} samples ({}, {}) at uv ({}, {})", name, texel_x, texel_y, u, v);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_gutter_copies_each_entrys_edge() {
+        let dir = TestDir::new("atlas_gutter");
+        RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8 * 80, y as u8 * 80, 0])).save(dir.join("gradient.png")).unwrap();
+        let (image, atlas) = generate_texture_atlas(&dir.join("")).unwrap();
+
+        let (x, y, width, height) = atlas.get("gradient").unwrap().pixel_rect();
+        for gutter_y in y - PADDING..y + height + PADDING {
+            for gutter_x in x - PADDING..x + width + PADDING {
+                let edge = image.get_pixel(gutter_x.clamp(x, x + width - 1), gutter_y.clamp(y, y + height - 1));
+                assert_eq!(image.get_pixel(gutter_x, gutter_y), edge);
+            }
+        }
+    }
+
+    #[test]
+    fn an_undecodable_asset_is_reported_by_path() {
+        let dir = TestDir::new("atlas_broken");
+        mixed_assets(&dir);
+        fs::write(dir.join("broken.png"), b"not a png").unwrap();
+        match generate_texture_atlas(&dir.join("")) {
+            Err(AtlasError::Image(path, _)) => assert_eq!(path, dir.join("broken.png")),
+            other => panic!("expected an image error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::{fs, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use gfx::RenderOptions;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{atlas, storage::{self, SaveError}, theme::{AccessibilityConfig, AppearanceConfig}};
+
+pub const CONFIG_FILE: &str = "editor_config.toml";
+pub const SESSION_FILE: &str = "session.toml";
+pub const RECENT_PROJECTS_FILE: &str = "recent_projects.toml";
+pub const KEYBINDINGS_FILE: &str = "keybindings.toml";
+/// Directory under the platform's config directory holding `SESSION_FILE` and `RECENT_PROJECTS_FILE`,
+/// e.g. `~/.config/level_editor` on Linux.
+const STATE_DIR: &str = "level_editor";
+
+/// Range `EditorConfig::font_scale` is clamped to, anything outside it is unreadable or doesn't fit the panels.
+pub const MIN_FONT_SCALE: f32 = 0.5;
+pub const MAX_FONT_SCALE: f32 = 2.0;
+
+/// Where `file`, `SESSION_FILE` or `RECENT_PROJECTS_FILE`, is kept. It's in `STATE_DIR`, so it's found whichever
+/// directory the editor was started from, or in the working directory on platforms without a config directory.
+pub fn state_path(file: &str) -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join(STATE_DIR).join(file),
+        None => PathBuf::from(file),
+    }
+}
+
+/// Saves `value` to `state_path(file)`, creating `STATE_DIR` on the first save.
+pub fn save_state<T: Serialize>(file: &str, value: &T) -> Result<(), SaveError> {
+    let path = state_path(file);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| SaveError::from_io(&path, e))?;
+    }
+    storage::save_toml(&path, value)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EditorConfig {
+    pub projects_dir: PathBuf,
+    /// How many `.bakN` copies are kept of each level on save.
+    pub backup_count: usize,
+    pub show_backups_in_explorer: bool,
+    pub accessibility: AccessibilityConfig,
+    pub appearance: AppearanceConfig,
+    /// Samples per pixel, 1 or 4. Drawing falls back to 1 when the GPU can't do 4.
+    pub msaa_samples: u32,
+    pub render: RenderConfig,
+    /// Multiplies every text scale in the interface. Read through `font_scale()`, which clamps hand-edited values.
+    pub font_scale: f32,
+    /// Images packed into the UI atlas at startup and by Reload assets.
+    pub assets_dir: PathBuf,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            projects_dir: Path::new(".").join("projects"),
+            backup_count: 3,
+            show_backups_in_explorer: false,
+            accessibility: AccessibilityConfig::default(),
+            appearance: AppearanceConfig::default(),
+            msaa_samples: 4,
+            render: RenderConfig::default(),
+            font_scale: 1.0,
+            assets_dir: PathBuf::from(atlas::ASSETS_DIR),
+        }
+    }
+}
+
+impl EditorConfig {
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save_toml(CONFIG_FILE.as_ref(), self)
+    }
+
+    /// `font_scale` within `MIN_FONT_SCALE..=MAX_FONT_SCALE`, or 1.0 if it isn't a number.
+    pub fn font_scale(&self) -> f32 {
+        if self.font_scale.is_finite() {
+            self.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE)
+        } else {
+            1.0
+        }
+    }
+
+    /// Sets `font_scale`, clamped like `font_scale()`. Rounded to hundredths so repeated steps don't drift.
+    pub fn set_font_scale(&mut self, scale: f32) {
+        self.font_scale = (scale * 100.0).round() / 100.0;
+        self.font_scale = self.font_scale();
+    }
+}
+
+/// Which GPU the editor draws with and how, the `[render]` table of the config.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RenderConfig {
+    pub backend: BackendPreference,
+    pub power: PowerPreference,
+    /// Draws with the software adapter, slow but independent of GPU drivers.
+    pub fallback_adapter: bool,
+    pub present_mode: PresentModePreference,
+}
+
+impl RenderConfig {
+    pub fn options(&self, sample_count: u32) -> RenderOptions {
+        RenderOptions {
+            backends: self.backend.backends(),
+            power_preference: self.power.preference(),
+            force_fallback_adapter: self.fallback_adapter,
+            present_mode: self.present_mode.present_mode(),
+            sample_count,
+        }
+    }
+
+    /// Whether presenting waits for vertical sync. Only `Fifo` does, `Mailbox` and `Immediate` don't.
+    pub fn vsync(&self) -> bool {
+        self.present_mode == PresentModePreference::Fifo
+    }
+
+    /// Turning vsync off presents immediately, which tears but has the least latency.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.present_mode = if vsync { PresentModePreference::Fifo } else { PresentModePreference::Immediate };
+    }
+}
+
+/// Graphics API tried first, every other one is still tried if it has no usable adapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendPreference {
+    /// Vulkan, Metal, DX12 or WebGPU, whichever the platform has.
+    #[default]
+    Primary,
+    Vulkan,
+    Dx12,
+    Metal,
+    Gl,
+    All,
+}
+
+impl BackendPreference {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "primary" => Some(Self::Primary),
+            "vulkan" => Some(Self::Vulkan),
+            "dx12" => Some(Self::Dx12),
+            "metal" => Some(Self::Metal),
+            "gl" => Some(Self::Gl),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    fn backends(&self) -> wgpu::Backends {
+        match self {
+            Self::Primary => wgpu::Backends::PRIMARY,
+            Self::Vulkan => wgpu::Backends::VULKAN,
+            Self::Dx12 => wgpu::Backends::DX12,
+            Self::Metal => wgpu::Backends::METAL,
+            Self::Gl => wgpu::Backends::GL,
+            Self::All => wgpu::Backends::all(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerPreference {
+    #[default]
+    Default,
+    LowPower,
+    HighPerformance,
+}
+
+impl PowerPreference {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::Default),
+            "low-power" => Some(Self::LowPower),
+            "high-performance" => Some(Self::HighPerformance),
+            _ => None,
+        }
+    }
+
+    fn preference(&self) -> wgpu::PowerPreference {
+        match self {
+            Self::Default => wgpu::PowerPreference::None,
+            Self::LowPower => wgpu::PowerPreference::LowPower,
+            Self::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Falls back to `Fifo` when the surface can't present the chosen way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresentModePreference {
+    /// Waits for vertical sync, supported everywhere.
+    #[default]
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl PresentModePreference {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fifo" => Some(Self::Fifo),
+            "mailbox" => Some(Self::Mailbox),
+            "immediate" => Some(Self::Immediate),
+            _ => None,
+        }
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        match self {
+            Self::Fifo => wgpu::PresentMode::Fifo,
+            Self::Mailbox => wgpu::PresentMode::Mailbox,
+            Self::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StartupOptions {
+    pub safe_mode: bool,
+    /// Set by `--backend=`, `--power=`, `--fallback-adapter` and `--present-mode=`, wins over the config for this run.
+    pub render_overrides: RenderOverrides,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderOverrides {
+    pub backend: Option<BackendPreference>,
+    pub power: Option<PowerPreference>,
+    pub fallback_adapter: bool,
+    pub present_mode: Option<PresentModePreference>,
+}
+
+impl RenderOverrides {
+    /// `config` with the overrides applied, the config itself is left alone so they aren't saved.
+    pub fn apply(&self, config: &RenderConfig) -> RenderConfig {
+        RenderConfig {
+            backend: self.backend.unwrap_or(config.backend),
+            power: self.power.unwrap_or(config.power),
+            fallback_adapter: self.fallback_adapter || config.fallback_adapter,
+            present_mode: self.present_mode.unwrap_or(config.present_mode),
+        }
+    }
+}
+
+impl StartupOptions {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self::default();
+        for arg in args {
+            let overrides = &mut options.render_overrides;
+            match arg.split_once('=') {
+                Some(("--backend", name)) if BackendPreference::from_name(name).is_some() => overrides.backend = BackendPreference::from_name(name),
+                Some(("--power", name)) if PowerPreference::from_name(name).is_some() => overrides.power = PowerPreference::from_name(name),
+                Some(("--present-mode", name)) if PresentModePreference::from_name(name).is_some() => overrides.present_mode = PresentModePreference::from_name(name),
+                _ => match arg.as_str() {
+                    "--safe-mode" => options.safe_mode = true,
+                    "--fallback-adapter" => overrides.fallback_adapter = true,
+                    other => log::warn!("Ignoring unknown argument: {}", other),
+                },
+            }
+        }
+        options
+    }
+}
+
+/// A settings file that failed to parse and was moved aside so the editor could start with defaults.
+#[derive(Debug, Clone)]
+pub struct RecoveredFile {
+    pub original: PathBuf,
+    pub backup: Option<PathBuf>,
+    pub reason: String,
+}
+
+impl RecoveredFile {
+    pub fn describe(&self) -> String {
+        let reason = self.reason.lines().next().unwrap_or("unknown error");
+        match &self.backup {
+            Some(backup) => format!("{} could not be read ({}). It was backed up to {} and defaults are in use.", self.original.display(), reason, backup.display()),
+            None => format!("{} could not be read ({}) and could not be backed up. Defaults are in use.", self.original.display(), reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StartupReport {
+    pub safe_mode: bool,
+    pub recovered: Vec<RecoveredFile>,
+    /// Crash report left by the previous session, if the user hasn't seen it yet.
+    pub crash_report: Option<PathBuf>,
+}
+
+impl StartupReport {
+    pub fn needs_banner(&self) -> bool {
+        self.safe_mode || !self.recovered.is_empty()
+    }
+
+    pub fn backup_location(&self) -> Option<PathBuf> {
+        self.recovered.iter()
+            .find_map(|file| file.backup.as_ref())
+            .map(|backup| match backup.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            })
+    }
+}
+
+/// Loads a TOML file, falling back to `T::default()` when it is missing.
+/// A file that exists but can't be parsed is renamed to `<name>.broken-<timestamp>` and reported in `recovered`.
+pub fn load_or_recover<T: DeserializeOwned + Default>(path: &Path, recovered: &mut Vec<RecoveredFile>) -> T {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return T::default(),
+        Err(e) => {
+            recover(path, e.to_string(), recovered);
+            return T::default();
+        }
+    };
+
+    match toml::from_str::<T>(&contents) {
+        Ok(value) => value,
+        Err(e) => {
+            recover(path, e.to_string(), recovered);
+            T::default()
+        }
+    }
+}
+
+/// Checks that a file is well-formed TOML without interpreting it, so a corrupt
+/// session or keybinding file is moved aside before anything tries to read it.
+pub fn validate_or_recover(path: &Path, recovered: &mut Vec<RecoveredFile>) {
+    load_or_recover::<toml::Table>(path, recovered);
+}
+
+fn recover(path: &Path, reason: String, recovered: &mut Vec<RecoveredFile>) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".broken-{}", timestamp));
+    let backup = PathBuf::from(backup);
+
+    let backup = match fs::rename(path, &backup) {
+        Ok(()) => {
+            log::warn!("{:?} could not be parsed ({}), moved it to {:?}", path, reason, backup);
+            Some(backup)
+        }
+        Err(e) => {
+            log::error!("{:?} could not be parsed ({}) and backing it up failed: {}", path, reason, e);
+            None
+        }
+    };
+
+    recovered.push(RecoveredFile {
+        original: path.to_path_buf(),
+        backup,
+        reason,
+    });
+}
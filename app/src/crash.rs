@@ -0,0 +1,121 @@
+use std::{backtrace::Backtrace, collections::VecDeque, fmt::Write, fs, io, panic::{self, PanicHookInfo}, path::{Path, PathBuf}, sync::{Mutex, TryLockError}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::storage::{self, SaveError};
+
+pub const CRASH_DIR: &str = "crash_reports";
+const BREADCRUMB_COUNT: usize = 50;
+const REVIEWED_SUFFIX: &str = ".reviewed";
+
+/// What the panic hook knows about the session, filled in as the editor runs.
+struct CrashContext {
+    breadcrumbs: VecDeque<String>,
+    adapter: Option<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext { breadcrumbs: VecDeque::new(), adapter: None });
+
+/// Writes a report to `CRASH_DIR` on panic, after the default hook has printed the panic as usual.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_report(info) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Unable to write crash report: {}", e),
+        }
+    }));
+}
+
+/// Remembers an action for the crash report, keeping the last `BREADCRUMB_COUNT`.
+pub fn record(breadcrumb: String) {
+    // Poisoning only means a panic happened mid-record, the ring itself is still fine
+    let mut context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    if context.breadcrumbs.len() == BREADCRUMB_COUNT {
+        context.breadcrumbs.pop_front();
+    }
+    context.breadcrumbs.push_back(breadcrumb);
+}
+
+pub fn set_adapter(adapter: String) {
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).adapter = Some(adapter);
+}
+
+/// How `path` appears in breadcrumbs. Paths inside `project_dir` are kept relative to it,
+/// anything else may point into the user's own files and is replaced with a placeholder.
+pub fn redact_path(path: &Path, project_dir: &Path) -> String {
+    match path.strip_prefix(project_dir) {
+        Ok(relative) => Path::new("<projects>").join(relative).display().to_string(),
+        Err(_) => "<redacted path>".to_string(),
+    }
+}
+
+fn write_report(info: &PanicHookInfo) -> Result<PathBuf, SaveError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Level editor crash report");
+    let _ = writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "Time: {} (unix)", timestamp);
+
+    // try_lock, since the panic may have come from a thread that holds the lock
+    match CONTEXT.try_lock() {
+        Ok(context) => write_context(&mut report, &context),
+        Err(TryLockError::Poisoned(e)) => write_context(&mut report, &e.into_inner()),
+        Err(TryLockError::WouldBlock) => {
+            let _ = writeln!(report, "Adapter and last actions unavailable, the crash context was locked");
+        }
+    }
+
+    let _ = writeln!(report, "\n{}", info);
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    let path = Path::new(CRASH_DIR).join(format!("crash-{}.txt", timestamp));
+    fs::create_dir_all(CRASH_DIR).map_err(|e| SaveError::from_io(&path, e))?;
+    storage::atomic_write(&path, report.as_bytes())?;
+    Ok(path)
+}
+
+fn write_context(report: &mut String, context: &CrashContext) {
+    let _ = writeln!(report, "Adapter: {}", context.adapter.as_deref().unwrap_or("not created yet"));
+    let _ = writeln!(report, "\nLast actions, oldest first:");
+    for breadcrumb in &context.breadcrumbs {
+        let _ = writeln!(report, "  {}", breadcrumb);
+    }
+}
+
+/// Newest report from an earlier session that the user hasn't been shown yet.
+pub fn pending_report() -> Option<PathBuf> {
+    pending_reports().into_iter()
+        .max_by_key(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+}
+
+/// Renames every pending report to `crash-<time>.reviewed.txt` so it isn't offered again.
+pub fn mark_reviewed() -> io::Result<()> {
+    for path in pending_reports() {
+        let Some(stem) = path.file_stem() else {
+            continue;
+        };
+        let mut reviewed = stem.to_owned();
+        reviewed.push(format!("{}.txt", REVIEWED_SUFFIX));
+        fs::rename(&path, path.with_file_name(reviewed))?;
+    }
+    Ok(())
+}
+
+fn pending_reports() -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(CRASH_DIR) else {
+        return Vec::new();
+    };
+    read_dir
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.starts_with("crash-") && !stem.ends_with(REVIEWED_SUFFIX))
+                && path.extension().is_some_and(|extension| extension == "txt")
+        })
+        .collect()
+}
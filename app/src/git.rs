@@ -0,0 +1,149 @@
+use std::{collections::BTreeMap, ffi::OsString, fmt, io, path::Path, process::Command};
+
+use crate::theme::OverlayColor;
+
+/// Working tree state shown next to an explorer row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum GitMark {
+    // Ordered by how much attention they need, a folder shows the highest mark of anything inside it
+    Untracked,
+    Modified,
+    Added,
+    Deleted,
+    Conflicted,
+}
+
+impl GitMark {
+    pub(crate) fn letter(&self) -> &'static str {
+        match self {
+            GitMark::Untracked => "?",
+            GitMark::Modified => "M",
+            GitMark::Added => "A",
+            GitMark::Deleted => "D",
+            GitMark::Conflicted => "U",
+        }
+    }
+
+    pub(crate) fn color(&self) -> OverlayColor {
+        match self {
+            GitMark::Untracked | GitMark::Added => OverlayColor::DiffAdded,
+            GitMark::Modified => OverlayColor::DiffModified,
+            GitMark::Deleted => OverlayColor::DiffRemoved,
+            GitMark::Conflicted => OverlayColor::ValidationError,
+        }
+    }
+
+    /// From the two-letter `XY` code of `git status --porcelain`.
+    fn from_code(code: &str) -> Option<GitMark> {
+        match code {
+            "??" => Some(GitMark::Untracked),
+            "!!" => None,
+            "AA" | "DD" => Some(GitMark::Conflicted),
+            _ if code.contains('U') => Some(GitMark::Conflicted),
+            _ if code.contains('D') => Some(GitMark::Deleted),
+            _ if code.starts_with('A') => Some(GitMark::Added),
+            _ if code.trim().is_empty() => None,
+            _ => Some(GitMark::Modified),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum GitError {
+    /// git couldn't be started, usually because it isn't installed.
+    Spawn(io::Error),
+    /// git ran but reported an error.
+    Failed(String),
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::Spawn(e) => write!(f, "unable to run git: {}", e),
+            GitError::Failed(stderr) => write!(f, "git status failed: {}", stderr),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+/// Result of a single `git status` run for a directory, mapped onto that directory's entries.
+#[derive(Debug, Default)]
+pub(crate) struct GitStatus {
+    branch: Option<String>,
+    /// Keyed by the file name of the directory entry each change falls under.
+    marks: BTreeMap<OsString, GitMark>,
+}
+
+impl GitStatus {
+    /// `Ok(None)` when `directory` isn't inside a git repository.
+    pub(crate) fn read(directory: &Path) -> Result<Option<GitStatus>, GitError> {
+        let prefix = Command::new("git")
+            .arg("-C").arg(directory)
+            .args(["rev-parse", "--show-prefix"])
+            .output()
+            .map_err(GitError::Spawn)?;
+        if !prefix.status.success() {
+            return Ok(None);
+        }
+        let prefix = String::from_utf8_lossy(&prefix.stdout).trim_end().to_string();
+
+        let output = Command::new("git")
+            .arg("-C").arg(directory)
+            .args(["status", "--porcelain=v1", "-z", "--branch", "--", "."])
+            .output()
+            .map_err(GitError::Spawn)?;
+        if !output.status.success() {
+            return Err(GitError::Failed(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+
+        Ok(Some(Self::parse(&String::from_utf8_lossy(&output.stdout), &prefix)))
+    }
+
+    /// `prefix` is the directory's path from the repository root, as printed by `git rev-parse --show-prefix`.
+    fn parse(output: &str, prefix: &str) -> GitStatus {
+        let mut status = GitStatus::default();
+        let mut records = output.split('\0');
+
+        while let Some(record) = records.next() {
+            if let Some(header) = record.strip_prefix("## ") {
+                status.branch = Some(branch_name(header).to_string());
+                continue;
+            }
+            let (Some(code), Some(path)) = (record.get(..2), record.get(3..)) else {
+                continue;
+            };
+            // Renames and copies are followed by a record holding the original path
+            if code.contains(['R', 'C']) {
+                records.next();
+            }
+
+            let Some(mark) = GitMark::from_code(code) else {
+                continue;
+            };
+            let Some(entry) = path.strip_prefix(prefix).and_then(|rest| rest.split('/').find(|part| !part.is_empty())) else {
+                continue;
+            };
+            let existing = status.marks.entry(OsString::from(entry)).or_insert(mark);
+            *existing = (*existing).max(mark);
+        }
+
+        status
+    }
+
+    pub(crate) fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// Mark for an entry of the directory the status was read for.
+    pub(crate) fn mark(&self, path: &Path) -> Option<GitMark> {
+        path.file_name().and_then(|name| self.marks.get(name)).copied()
+    }
+}
+
+/// Branch from a `## main...origin/main [ahead 1]` style header.
+fn branch_name(header: &str) -> &str {
+    let header = header.strip_prefix("No commits yet on ").unwrap_or(header);
+    let header = header.split("...").next().unwrap_or(header);
+    header.split(' ').next().unwrap_or(header)
+}
@@ -0,0 +1,236 @@
+use std::{fs, io::{self, Read, Write}, path::{Path, PathBuf}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::storage::{self, SaveError};
+
+pub const HISTORY_DIR: &str = ".history";
+/// Active editing time between snapshots.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// The most one gap between edits adds to the snapshot timer, so time away from the editor counts as at most this.
+const IDLE_GAP: Duration = Duration::from_secs(60);
+/// Total size of one level's snapshots before the oldest are pruned.
+const MAX_HISTORY_BYTES: u64 = 64 * 1024 * 1024;
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+
+/// One stored snapshot, named by the unix time in milliseconds it was taken at. `size` is what it takes on disk,
+/// compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub taken: SystemTime,
+    pub size: u64,
+}
+
+impl Snapshot {
+    /// How the History panel shows when this was taken, e.g. "12 min ago".
+    pub fn relative_age(&self, now: SystemTime) -> String {
+        relative_age(self.taken, now)
+    }
+
+    /// The snapshot's full contents, for previewing or restoring it.
+    pub fn read(&self) -> io::Result<String> {
+        let mut contents = String::new();
+        GzDecoder::new(fs::File::open(&self.path)?).read_to_string(&mut contents)?;
+        Ok(contents)
+    }
+}
+
+/// How long before `now` `then` was, e.g. "12 min ago" or "2 days ago". Times after `now` are "just now".
+pub fn relative_age(then: SystemTime, now: SystemTime) -> String {
+    let seconds = now.duration_since(then).unwrap_or_default().as_secs();
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} min ago", seconds / 60),
+        3600..86400 => format!("{} h ago", seconds / 3600),
+        86400..172800 => "1 day ago".to_string(),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+/// Periodic full snapshots of one level, kept gzip-compressed in `.history/<level name>/` next to it.
+#[derive(Debug)]
+pub struct History {
+    dir: PathBuf,
+    max_bytes: u64,
+    active: Duration,
+    last_edit: Option<Instant>,
+}
+
+impl History {
+    pub fn for_level(level_path: &Path) -> Self {
+        let parent = level_path.parent().unwrap_or(Path::new("."));
+        let name = level_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        Self { dir: parent.join(HISTORY_DIR).join(name), max_bytes: MAX_HISTORY_BYTES, active: Duration::ZERO, last_edit: None }
+    }
+
+    /// Counts the time since the previous edit towards the next snapshot, capped at `IDLE_GAP`.
+    /// Returns true once a snapshot is due.
+    pub fn record_edit(&mut self, now: Instant) -> bool {
+        if let Some(last_edit) = self.last_edit {
+            self.active += now.saturating_duration_since(last_edit).min(IDLE_GAP);
+        }
+        self.last_edit = Some(now);
+        self.active >= SNAPSHOT_INTERVAL
+    }
+
+    /// Stores `contents` as a new snapshot, resets the timer and prunes down to the size cap. The timer is reset
+    /// even when storing fails, so the next try waits for another interval of editing.
+    pub fn snapshot(&mut self, contents: &[u8], taken: SystemTime) -> Result<Snapshot, SaveError> {
+        self.active = Duration::ZERO;
+        fs::create_dir_all(&self.dir).map_err(|e| SaveError::from_io(&self.dir, e))?;
+        let compressed = compress(contents).map_err(|e| SaveError::from_io(&self.dir, e))?;
+
+        let millis = taken.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        // Two snapshots in the same millisecond would otherwise overwrite each other
+        let mut path = self.dir.join(format!("{}.{}", millis, SNAPSHOT_EXTENSION));
+        let mut suffix = 1;
+        while path.exists() {
+            path = self.dir.join(format!("{}-{}.{}", millis, suffix, SNAPSHOT_EXTENSION));
+            suffix += 1;
+        }
+        storage::atomic_write(&path, &compressed)?;
+
+        let snapshot = Snapshot { path, taken, size: compressed.len() as u64 };
+        self.prune(&snapshot);
+        Ok(snapshot)
+    }
+
+    /// Every snapshot, newest first. Files that don't look like snapshots are ignored.
+    pub fn list(&self) -> Vec<Snapshot> {
+        let Ok(read_dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut snapshots: Vec<(u64, u32, Snapshot)> = read_dir
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension().and_then(|extension| extension.to_str()) != Some(SNAPSHOT_EXTENSION) {
+                    return None;
+                }
+                let stem = path.file_stem()?.to_str()?;
+                let (millis, suffix) = match stem.split_once('-') {
+                    Some((millis, suffix)) => (millis.parse().ok()?, suffix.parse().ok()?),
+                    None => (stem.parse().ok()?, 0),
+                };
+                let size = fs::metadata(&path).ok()?.len();
+                Some((millis, suffix, Snapshot { taken: UNIX_EPOCH + Duration::from_millis(millis), size, path }))
+            })
+            .collect();
+        // Snapshots of the same millisecond are ordered by their suffix, `1000-1` was taken after `1000`
+        snapshots.sort_by_key(|&(millis, suffix, _)| std::cmp::Reverse((millis, suffix)));
+        snapshots.into_iter().map(|(_, _, snapshot)| snapshot).collect()
+    }
+
+    /// Deletes the oldest snapshots until the rest fit in `max_bytes`. `newest` is always kept,
+    /// even on its own over the cap, so there's something to restore.
+    fn prune(&self, newest: &Snapshot) {
+        let mut total = 0;
+        let mut over = false;
+        for snapshot in self.list() {
+            total += snapshot.size;
+            // Once one doesn't fit, everything older goes too, so pruning stays strictly oldest-first
+            over |= total > self.max_bytes && snapshot.path != newest.path;
+            if over && let Err(e) = fs::remove_file(&snapshot.path) {
+                log::warn!("Unable to prune snapshot {}: {}", snapshot.path.display(), e);
+            }
+        }
+    }
+}
+
+fn compress(contents: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(contents)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::TestDir;
+
+    const LEVEL: &str = "version = 1\ntiles = [\"grass\"]\ncells = [[0, 0, 0], [1, 0, 0], [2, 0, 0], [3, 0, 0]]\n";
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn snapshots_are_compressed_and_read_back() {
+        let dir = TestDir::new("history_round_trip");
+        let mut history = History::for_level(&dir.join("level.toml"));
+        let contents = LEVEL.repeat(100);
+
+        let snapshot = history.snapshot(contents.as_bytes(), at(1_000)).unwrap();
+        assert!(snapshot.path.starts_with(dir.join(HISTORY_DIR).join("level")));
+        assert!(snapshot.size < contents.len() as u64);
+        assert_eq!(history.list(), vec![snapshot.clone()]);
+        assert_eq!(snapshot.read().unwrap(), contents);
+    }
+
+    #[test]
+    fn snapshots_list_newest_first_and_ignore_other_files() {
+        let dir = TestDir::new("history_list");
+        let mut history = History::for_level(&dir.join("level.toml"));
+        let first = history.snapshot(LEVEL.as_bytes(), at(1_000)).unwrap();
+        let second = history.snapshot(LEVEL.as_bytes(), at(2_000)).unwrap();
+        // Taken in the same millisecond, it gets a suffix rather than replacing `second`
+        let third = history.snapshot(LEVEL.as_bytes(), at(2_000)).unwrap();
+        fs::write(dir.join(HISTORY_DIR).join("level").join("notes.txt"), "not a snapshot").unwrap();
+
+        assert_ne!(second.path, third.path);
+        assert_eq!(history.list(), vec![third, second, first]);
+    }
+
+    #[test]
+    fn pruning_drops_the_oldest_until_the_rest_fit() {
+        let dir = TestDir::new("history_prune");
+        let mut history = History::for_level(&dir.join("level.toml"));
+        let size = history.snapshot(LEVEL.as_bytes(), at(1_000)).unwrap().size;
+        history.max_bytes = 2 * size;
+
+        history.snapshot(LEVEL.as_bytes(), at(2_000)).unwrap();
+        history.snapshot(LEVEL.as_bytes(), at(3_000)).unwrap();
+        let taken: Vec<SystemTime> = history.list().iter().map(|snapshot| snapshot.taken).collect();
+        assert_eq!(taken, vec![at(3_000), at(2_000)]);
+    }
+
+    #[test]
+    fn the_newest_snapshot_is_kept_even_over_the_cap() {
+        let dir = TestDir::new("history_prune_newest");
+        let mut history = History::for_level(&dir.join("level.toml"));
+        history.max_bytes = 1;
+
+        history.snapshot(LEVEL.as_bytes(), at(1_000)).unwrap();
+        let newest = history.snapshot(LEVEL.as_bytes(), at(2_000)).unwrap();
+        assert_eq!(history.list(), vec![newest]);
+    }
+
+    #[test]
+    fn only_active_editing_counts_towards_a_snapshot() {
+        let dir = TestDir::new("history_timer");
+        let mut history = History::for_level(&dir.join("level.toml"));
+        let start = Instant::now();
+
+        assert!(!history.record_edit(start));
+        // An hour away from the editor counts as one idle gap
+        assert!(!history.record_edit(start + Duration::from_secs(3600)));
+        let mut now = start + Duration::from_secs(3600);
+        while !history.record_edit(now) {
+            now += Duration::from_secs(30);
+        }
+        assert_eq!(now - start - Duration::from_secs(3600), SNAPSHOT_INTERVAL - IDLE_GAP);
+
+        history.snapshot(LEVEL.as_bytes(), SystemTime::now()).unwrap();
+        assert!(!history.record_edit(now + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn relative_ages() {
+        assert_eq!(relative_age(at(1_000), at(1_030)), "just now");
+        assert_eq!(relative_age(at(1_030), at(1_000)), "just now");
+        assert_eq!(relative_age(at(0), at(12 * 60)), "12 min ago");
+        assert_eq!(relative_age(at(0), at(3 * 3600)), "3 h ago");
+        assert_eq!(relative_age(at(0), at(86400)), "1 day ago");
+        assert_eq!(relative_age(at(0), at(5 * 86400)), "5 days ago");
+    }
+}
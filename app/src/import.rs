@@ -0,0 +1,67 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+
+/// Extensions of files that can be imported as assets, all formats the `image` crate decodes by default.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "tga", "webp"];
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file's extension isn't one of `IMAGE_EXTENSIONS`.
+    Unsupported(PathBuf),
+    /// The asset folder already has a file with this stem. The atlas names assets by stem, so a second one
+    /// would stop it from packing.
+    NameTaken(String),
+    CreateDir(PathBuf, io::Error),
+    Copy(PathBuf, io::Error),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Unsupported(path) => write!(f, "{} is not an image that can be imported", path.display()),
+            ImportError::NameTaken(name) => write!(f, "there is already an asset named {:?}", name),
+            ImportError::CreateDir(path, e) => write!(f, "Unable to create {}: {}", path.display(), e),
+            ImportError::Copy(path, e) => write!(f, "Unable to copy {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::CreateDir(_, e) | ImportError::Copy(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `path` has one of `IMAGE_EXTENSIONS`, in any case.
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(extension)))
+}
+
+/// Copies the image at `source` into `asset_dir`, creating the folder if the project doesn't have one yet.
+/// Returns where the copy was written. Nothing is overwritten, an asset with the same name is an error.
+pub fn import_asset(source: &Path, asset_dir: &Path) -> Result<PathBuf, ImportError> {
+    let (Some(file_name), Some(stem)) = (source.file_name(), source.file_stem()) else {
+        return Err(ImportError::Unsupported(source.to_path_buf()));
+    };
+    if !is_image(source) {
+        return Err(ImportError::Unsupported(source.to_path_buf()));
+    }
+    fs::create_dir_all(asset_dir).map_err(|e| ImportError::CreateDir(asset_dir.to_path_buf(), e))?;
+
+    // `hero.png` next to an existing `hero.jpg` is as much a clash as another `hero.png`
+    let taken = fs::read_dir(asset_dir)
+        .map_err(|e| ImportError::Copy(source.to_path_buf(), e))?
+        .filter_map(Result::ok)
+        .any(|entry| entry.path().file_stem() == Some(stem));
+    if taken {
+        return Err(ImportError::NameTaken(stem.to_string_lossy().into_owned()));
+    }
+
+    let target = asset_dir.join(file_name);
+    fs::copy(source, &target).map_err(|e| ImportError::Copy(source.to_path_buf(), e))?;
+    Ok(target)
+}
@@ -0,0 +1,545 @@
+use std::{collections::{BTreeSet, HashMap, HashSet}, fmt, fs, io, path::{Path, PathBuf}};
+
+use gfx::{definitions::TileInstance, TILE_CHUNK_SIZE};
+use glam::IVec2;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{atlas::{self, AtlasError}, project::Project, storage::{self, SaveError}, undo::Command};
+
+/// Index of a tile in the project's `TileSet`.
+pub type TileId = u16;
+
+/// Stands in for a tile the level names but the tile set doesn't have, drawn as a checkerboard.
+pub const PLACEHOLDER_TILE: TileId = TileId::MAX;
+/// Version `Level::save` writes. Files with a higher one are from a newer editor and are refused.
+pub const LEVEL_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(PathBuf, io::Error),
+    /// Not a level file, or one cut short.
+    Malformed(PathBuf, String),
+    /// Written by a newer editor, or not a version any editor wrote.
+    UnsupportedVersion { path: PathBuf, version: u32 },
+    Save(SaveError),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(path, e) => write!(f, "Unable to read {}: {}", path.display(), e),
+            LevelError::Malformed(path, reason) => write!(f, "{} is damaged or incomplete: {}", path.display(), reason),
+            LevelError::UnsupportedVersion { path, version } if *version > LEVEL_VERSION => write!(
+                f, "{} was saved by a newer editor as version {}, this one reads up to version {}", path.display(), version, LEVEL_VERSION,
+            ),
+            LevelError::UnsupportedVersion { path, version } => write!(f, "{} has unknown version {}", path.display(), version),
+            LevelError::Save(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LevelError::Io(_, e) => Some(e),
+            LevelError::Save(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A level file's contents. Cells refer to their tile by name through `tiles`, so adding or removing images
+/// in the asset folder doesn't repaint the level.
+#[derive(Serialize, Deserialize)]
+struct LevelFile {
+    version: u32,
+    /// Names of the tiles the level uses.
+    #[serde(default)]
+    tiles: Vec<String>,
+    /// `[x, y, index into tiles]` for every occupied cell.
+    #[serde(default)]
+    cells: Vec<[i32; 3]>,
+}
+
+/// Read before the rest of the file, so a newer layout is refused by its version rather than failing to parse.
+#[derive(Deserialize)]
+struct LevelHeader {
+    version: u32,
+}
+
+/// A level being edited: which tile, if any, is in each cell of an unbounded grid.
+/// Cells are grouped in chunks of `TILE_CHUNK_SIZE` by `TILE_CHUNK_SIZE`, the unit the preview uploads them in.
+#[derive(Debug, Default, Clone)]
+pub struct Level {
+    tiles: HashMap<IVec2, TileId>,
+    /// Names of the tiles of `PLACEHOLDER_TILE` cells, written back out as they were read.
+    unknown: HashMap<IVec2, String>,
+    /// Chunks with a cell changed since the last `take_dirty_chunks`.
+    dirty: HashSet<IVec2>,
+}
+
+impl Level {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a level saved by `save`, numbering its tiles by `tile_set`. Tiles the set doesn't have become
+    /// `PLACEHOLDER_TILE`, see `missing_tiles`.
+    pub fn load(path: &Path, tile_set: &TileSet) -> Result<Level, LevelError> {
+        let contents = fs::read_to_string(path).map_err(|e| LevelError::Io(path.to_path_buf(), e))?;
+        Self::parse(&contents, path, tile_set)
+    }
+
+    /// Reads a level from what `serialize` wrote. `path` is where it came from, for the errors.
+    pub fn parse(contents: &str, path: &Path, tile_set: &TileSet) -> Result<Level, LevelError> {
+        let malformed = |reason: String| LevelError::Malformed(path.to_path_buf(), reason);
+
+        let header: LevelHeader = toml::from_str(contents).map_err(|e| malformed(e.to_string()))?;
+        if header.version == 0 || header.version > LEVEL_VERSION {
+            return Err(LevelError::UnsupportedVersion { path: path.to_path_buf(), version: header.version });
+        }
+        let file: LevelFile = toml::from_str(contents).map_err(|e| malformed(e.to_string()))?;
+
+        let mut level = Level::new();
+        for [x, y, index] in file.cells {
+            let cell = IVec2::new(x, y);
+            let name = usize::try_from(index).ok()
+                .and_then(|index| file.tiles.get(index))
+                .ok_or_else(|| malformed(format!("cell ({}, {}) uses tile {} of {}", x, y, index, file.tiles.len())))?;
+            match tile_set.find(name) {
+                Some(id) => {
+                    level.tiles.insert(cell, id);
+                }
+                None => {
+                    level.tiles.insert(cell, PLACEHOLDER_TILE);
+                    level.unknown.insert(cell, name.clone());
+                }
+            }
+        }
+        level.mark_all_dirty();
+        Ok(level)
+    }
+
+    /// Writes the level to `path`, keeping `backups` previous saves, see `storage::save_with_backups`.
+    pub fn save(&self, path: &Path, tile_set: &TileSet, backups: usize) -> Result<(), LevelError> {
+        let contents = self.serialize(tile_set).map_err(|e| LevelError::Save(SaveError::Serialize(path.to_path_buf(), e.to_string())))?;
+        storage::save_with_backups(path, contents.as_bytes(), backups).map_err(LevelError::Save)?;
+        Ok(())
+    }
+
+    /// The level as `save` writes it. Tiles are stored by their name in `tile_set`, and cells listed in row order so
+    /// small edits make small diffs.
+    pub fn serialize(&self, tile_set: &TileSet) -> Result<String, toml::ser::Error> {
+        let mut occupied: Vec<(IVec2, TileId)> = self.tiles.iter().map(|(&cell, &id)| (cell, id)).collect();
+        occupied.sort_by_key(|(cell, _)| (cell.y, cell.x));
+
+        let mut file = LevelFile { version: LEVEL_VERSION, tiles: Vec::new(), cells: Vec::with_capacity(occupied.len()) };
+        let mut indices: HashMap<&str, i32> = HashMap::new();
+        for (cell, id) in occupied {
+            let name = match self.unknown.get(&cell) {
+                Some(name) => name.as_str(),
+                None => match tile_set.get(id) {
+                    Some(tile) => tile.name.as_str(),
+                    None => {
+                        log::warn!("Cell {} has tile {}, which isn't in the tile set, leaving it out", cell, id);
+                        continue;
+                    }
+                },
+            };
+            let index = *indices.entry(name).or_insert_with(|| {
+                file.tiles.push(name.to_string());
+                file.tiles.len() as i32 - 1
+            });
+            file.cells.push([cell.x, cell.y, index]);
+        }
+        toml::to_string(&file)
+    }
+
+    pub fn tile(&self, cell: IVec2) -> Option<TileId> {
+        self.tiles.get(&cell).copied()
+    }
+
+    pub fn cell_state(&self, cell: IVec2) -> CellState {
+        match (self.tiles.get(&cell), self.unknown.get(&cell)) {
+            (None, _) => CellState::Empty,
+            (Some(_), Some(name)) => CellState::Missing(name.clone()),
+            (Some(&id), None) => CellState::Tile(id),
+        }
+    }
+
+    /// Puts `cell` back in `state`. Returns whether that changed anything.
+    pub fn restore_cell(&mut self, cell: IVec2, state: &CellState) -> bool {
+        match state {
+            CellState::Empty => self.erase_tile(cell),
+            CellState::Tile(id) => self.set_tile(cell, *id),
+            CellState::Missing(name) => {
+                let renamed = self.unknown.insert(cell, name.clone()).as_ref() != Some(name);
+                let changed = self.tiles.insert(cell, PLACEHOLDER_TILE) != Some(PLACEHOLDER_TILE) || renamed;
+                if changed {
+                    self.touch(cell);
+                }
+                changed
+            }
+        }
+    }
+
+    /// Names of the tiles drawn as `PLACEHOLDER_TILE`, in order.
+    pub fn missing_tiles(&self) -> BTreeSet<&str> {
+        self.unknown.values().map(String::as_str).collect()
+    }
+
+    /// Puts `id` in `cell`. Returns whether that changed anything.
+    pub fn set_tile(&mut self, cell: IVec2, id: TileId) -> bool {
+        let was_placeholder = self.unknown.remove(&cell).is_some();
+        let changed = self.tiles.insert(cell, id) != Some(id) || was_placeholder;
+        if changed {
+            self.touch(cell);
+        }
+        changed
+    }
+
+    /// Empties `cell`. Returns whether it had a tile.
+    pub fn erase_tile(&mut self, cell: IVec2) -> bool {
+        self.unknown.remove(&cell);
+        let changed = self.tiles.remove(&cell).is_some();
+        if changed {
+            self.touch(cell);
+        }
+        changed
+    }
+
+    /// Numbers the level's tiles by `new` instead of `old`, matching them by name, for when the asset folder was
+    /// packed again. Tiles `new` doesn't have become placeholders and placeholders it does have come back.
+    pub fn rebind_tiles(&mut self, old: &TileSet, new: &TileSet) {
+        for (cell, id) in self.tiles.iter_mut() {
+            let name = match self.unknown.get(cell) {
+                Some(name) => name.clone(),
+                None => match old.get(*id) {
+                    Some(tile) => tile.name.clone(),
+                    None => continue,
+                },
+            };
+            match new.find(&name) {
+                Some(new_id) => {
+                    *id = new_id;
+                    self.unknown.remove(cell);
+                }
+                None => {
+                    *id = PLACEHOLDER_TILE;
+                    self.unknown.insert(*cell, name);
+                }
+            }
+        }
+        self.mark_all_dirty();
+    }
+
+    /// Every occupied cell of `chunk` with its tile, row by row from the bottom.
+    pub fn chunk_tiles(&self, chunk: IVec2) -> impl Iterator<Item = (IVec2, TileId)> + '_ {
+        let origin = chunk * TILE_CHUNK_SIZE;
+        (0..TILE_CHUNK_SIZE)
+            .flat_map(move |y| (0..TILE_CHUNK_SIZE).map(move |x| origin + IVec2::new(x, y)))
+            .filter_map(move |cell| self.tile(cell).map(|id| (cell, id)))
+    }
+
+    /// Chunks changed since the last call, emptied ones included so the renderer drops them.
+    pub fn take_dirty_chunks(&mut self) -> Vec<IVec2> {
+        std::mem::take(&mut self.dirty).into_iter().collect()
+    }
+
+    /// Marks every occupied chunk as changed, for when all of them have to be uploaded again,
+    /// such as after the tile atlas was repacked.
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.extend(self.tiles.keys().map(|&cell| chunk_of(cell)));
+    }
+
+    /// Whether a cell changed since the last `take_dirty_chunks`.
+    pub fn has_changes(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    fn touch(&mut self, cell: IVec2) {
+        self.dirty.insert(chunk_of(cell));
+    }
+}
+
+/// What a cell holds, as far as putting it back goes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellState {
+    Empty,
+    Tile(TileId),
+    /// A `PLACEHOLDER_TILE`, with the name of the tile the tile set doesn't have.
+    Missing(String),
+}
+
+/// The tool an edit was made with, only edits with the same tool merge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditKind {
+    Paint(TileId),
+    Erase,
+    Fill(TileId),
+    /// Replacing the whole level, see `TileEdit::replace`.
+    Revert,
+}
+
+/// An undoable edit of a level, see `UndoStack`. Consecutive paints of the same tile, or erases, merge, so a stroke
+/// is undone in one go.
+#[derive(Debug)]
+pub struct TileEdit {
+    kind: EditKind,
+    /// Each cell the edit changes, as it was before and after.
+    changes: HashMap<IVec2, (CellState, CellState)>,
+}
+
+impl TileEdit {
+    /// Painting `tile` into `cells` of `level`, or erasing them for `None`. Cells already that way are left out.
+    pub fn brush(level: &Level, tile: Option<TileId>, cells: impl IntoIterator<Item = IVec2>) -> Self {
+        let (kind, after) = match tile {
+            Some(id) => (EditKind::Paint(id), CellState::Tile(id)),
+            None => (EditKind::Erase, CellState::Empty),
+        };
+        Self::new(level, kind, after, cells)
+    }
+
+    /// Filling the rectangle of `level` with corners `a` and `b`, both included, in either order, with `tile`.
+    pub fn fill_rect(level: &Level, a: IVec2, b: IVec2, tile: TileId) -> Self {
+        let (min, max) = (a.min(b), a.max(b));
+        let cells = (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)));
+        Self::new(level, EditKind::Fill(tile), CellState::Tile(tile), cells)
+    }
+
+    /// Making `level` the same as `other`, such as a backup of it. Undoing it brings back what the level had.
+    pub fn replace(level: &Level, other: &Level) -> Self {
+        let changes = level.tiles.keys().chain(other.tiles.keys())
+            .map(|&cell| (cell, (level.cell_state(cell), other.cell_state(cell))))
+            .filter(|(_, (before, after))| before != after)
+            .collect();
+        Self { kind: EditKind::Revert, changes }
+    }
+
+    fn new(level: &Level, kind: EditKind, after: CellState, cells: impl IntoIterator<Item = IVec2>) -> Self {
+        let changes = cells.into_iter()
+            .map(|cell| (cell, level.cell_state(cell)))
+            .filter(|(_, before)| *before != after)
+            .map(|(cell, before)| (cell, (before, after.clone())))
+            .collect();
+        Self { kind, changes }
+    }
+}
+
+impl Command for TileEdit {
+    type Target = Level;
+
+    fn apply(&self, level: &mut Level) {
+        for (&cell, (_, after)) in &self.changes {
+            level.restore_cell(cell, after);
+        }
+    }
+
+    fn revert(&self, level: &mut Level) {
+        for (&cell, (before, _)) in &self.changes {
+            level.restore_cell(cell, before);
+        }
+    }
+
+    /// A fill or a revert is a single edit, it never merges.
+    fn merge_with(&mut self, next: &Self) -> bool {
+        if self.kind != next.kind || matches!(self.kind, EditKind::Fill(_) | EditKind::Revert) {
+            return false;
+        }
+        for (&cell, (before, after)) in &next.changes {
+            // A cell this edit already changed keeps its state from before this edit
+            self.changes.entry(cell)
+                .and_modify(|change| change.1 = after.clone())
+                .or_insert_with(|| (before.clone(), after.clone()));
+        }
+        true
+    }
+
+    fn is_noop(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        let names: usize = self.changes.values()
+            .filter_map(|(before, _)| match before {
+                CellState::Missing(name) => Some(name.capacity()),
+                _ => None,
+            })
+            .sum();
+        std::mem::size_of::<Self>() + self.changes.capacity() * std::mem::size_of::<(IVec2, (CellState, CellState))>() + names
+    }
+}
+
+/// Chunk holding `cell`, rounding towards negative infinity so the chunks left of and below the origin
+/// are as big as the rest.
+pub fn chunk_of(cell: IVec2) -> IVec2 {
+    cell.div_euclid(IVec2::splat(TILE_CHUNK_SIZE))
+}
+
+/// Every cell a straight stroke from `from` to `to` passes through, both included, so a fast mouse move paints
+/// an unbroken line.
+pub fn cells_between(from: IVec2, to: IVec2) -> impl Iterator<Item = IVec2> {
+    let steps = (to - from).abs().max_element();
+    (0..=steps).map(move |step| {
+        let t = if steps == 0 { 0.0 } else { step as f32 / steps as f32 };
+        (from.as_vec2() + (to - from).as_vec2() * t).round().as_ivec2()
+    })
+}
+
+/// A tile that can be painted, one image of the project's asset folder.
+#[derive(Debug, Clone)]
+pub struct Tile {
+    /// File stem of the image.
+    pub name: String,
+    /// `(u, v, width, height)` in the tile atlas, see `TileInstance::uv_rect`.
+    uv_rect: [f32; 4],
+}
+
+/// The tiles of a project, numbered in name order by `TileId`.
+#[derive(Debug, Clone, Default)]
+pub struct TileSet {
+    tiles: Vec<Tile>,
+}
+
+impl TileSet {
+    /// Packs the images in `project`'s asset folder into the atlas tiles are drawn from. A project without
+    /// an asset folder has no tiles.
+    pub fn load(project: &Project) -> Result<(RgbaImage, TileSet), AtlasError> {
+        let asset_dir = project.asset_path();
+        if !asset_dir.is_dir() {
+            log::info!("{} has no asset folder at {}, it has no tiles", project.name, asset_dir.display());
+            return Ok((RgbaImage::new(1, 1), TileSet::default()));
+        }
+        let (atlas_image, atlas) = atlas::generate_texture_atlas(&asset_dir)?;
+        // The atlas lists its entries in name order, so ids only shift when images are added or removed
+        let tiles = atlas.entries()
+            .filter_map(|entry| {
+                let ((u0, v0), (u1, v1)) = (entry.start_coord?, entry.end_coord?);
+                Some(Tile { name: entry.name.clone(), uv_rect: [u0, v0, u1 - u0, v1 - v0] })
+            })
+            .collect();
+        Ok((atlas_image, TileSet { tiles }))
+    }
+
+    pub fn get(&self, id: TileId) -> Option<&Tile> {
+        self.tiles.get(id as usize)
+    }
+
+    /// Id of the tile named `name`.
+    pub fn find(&self, name: &str) -> Option<TileId> {
+        self.tiles.binary_search_by(|tile| tile.name.as_str().cmp(name)).ok().map(|index| index as TileId)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TileId, &Tile)> {
+        self.tiles.iter().enumerate().map(|(index, tile)| (index as TileId, tile))
+    }
+
+    /// How the renderer draws `id` in `cell`. Ids the set doesn't have, such as `PLACEHOLDER_TILE`,
+    /// are drawn as a placeholder.
+    pub fn instance(&self, cell: IVec2, id: TileId) -> TileInstance {
+        match self.get(id) {
+            Some(tile) => TileInstance { cell: cell.to_array(), uv_rect: tile.uv_rect },
+            None => TileInstance::placeholder(cell.to_array()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::undo::UndoStack;
+
+    fn states(level: &Level, cells: &[IVec2]) -> Vec<CellState> {
+        cells.iter().map(|&cell| level.cell_state(cell)).collect()
+    }
+
+    /// Tiles named `names`, which have to be in order like the atlas lists them.
+    fn tile_set(names: &[&str]) -> TileSet {
+        TileSet { tiles: names.iter().map(|name| Tile { name: name.to_string(), uv_rect: [0.0; 4] }).collect() }
+    }
+
+    fn round_trip(level: &Level, tile_set: &TileSet) -> Level {
+        let contents = level.serialize(tile_set).unwrap();
+        Level::parse(&contents, Path::new("level.lvl"), tile_set).unwrap()
+    }
+
+    #[test]
+    fn empty_level_round_trips() {
+        let tile_set = tile_set(&["grass"]);
+        let contents = Level::new().serialize(&tile_set).unwrap();
+        assert!(contents.contains(&format!("version = {}", LEVEL_VERSION)));
+        let loaded = Level::parse(&contents, Path::new("level.lvl"), &tile_set).unwrap();
+        assert_eq!(loaded.tiles.len(), 0);
+        assert!(loaded.missing_tiles().is_empty());
+    }
+
+    #[test]
+    fn negative_cells_round_trip() {
+        let tile_set = tile_set(&["grass", "stone"]);
+        let cells = [IVec2::new(-1, -1), IVec2::new(-300, 7), IVec2::new(12, -45), IVec2::new(0, 0), IVec2::new(-2, 5)];
+        let mut level = Level::new();
+        level.set_tile(cells[0], 0);
+        level.set_tile(cells[1], 1);
+        level.set_tile(cells[2], 0);
+        level.restore_cell(cells[4], &CellState::Missing("lava".to_string()));
+
+        let loaded = round_trip(&level, &tile_set);
+        assert_eq!(states(&loaded, &cells), states(&level, &cells));
+        assert_eq!(loaded.tiles.len(), 4);
+    }
+
+    #[test]
+    fn tiles_missing_from_the_set_load_as_placeholders() {
+        let mut level = Level::new();
+        level.set_tile(IVec2::new(-3, 2), 1);
+        let contents = level.serialize(&tile_set(&["grass", "stone"])).unwrap();
+
+        let loaded = Level::parse(&contents, Path::new("level.lvl"), &tile_set(&["grass"])).unwrap();
+        assert_eq!(loaded.tile(IVec2::new(-3, 2)), Some(PLACEHOLDER_TILE));
+        assert_eq!(loaded.missing_tiles(), BTreeSet::from(["stone"]));
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let contents = format!("version = {}\ntiles = [\"grass\"]\ncells = [[0, 0, 0]]\n", LEVEL_VERSION + 1);
+        let error = Level::parse(&contents, Path::new("level.lvl"), &tile_set(&["grass"])).unwrap_err();
+        assert!(matches!(error, LevelError::UnsupportedVersion { version, .. } if version == LEVEL_VERSION + 1));
+        assert_eq!(
+            error.to_string(),
+            format!("level.lvl was saved by a newer editor as version {}, this one reads up to version {}", LEVEL_VERSION + 1, LEVEL_VERSION),
+        );
+    }
+
+    #[test]
+    fn replacing_a_level_is_one_undoable_edit() {
+        let cells = [IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(-4, 2)];
+        let mut level = Level::new();
+        level.set_tile(cells[0], 1);
+        level.set_tile(cells[1], 2);
+        let mut backup = Level::new();
+        backup.set_tile(cells[0], 3);
+        backup.restore_cell(cells[2], &CellState::Missing("lava".to_string()));
+        let before = states(&level, &cells);
+
+        let mut undo = UndoStack::new();
+        undo.push(TileEdit::replace(&level, &backup), &mut level);
+        assert_eq!(states(&level, &cells), states(&backup, &cells));
+        assert!(!undo.is_saved());
+
+        assert!(undo.undo(&mut level));
+        assert_eq!(states(&level, &cells), before);
+        assert!(undo.is_saved());
+    }
+
+    #[test]
+    fn replacing_a_level_with_itself_changes_nothing() {
+        let mut level = Level::new();
+        level.set_tile(IVec2::new(5, 5), 1);
+        assert!(TileEdit::replace(&level, &level.clone()).is_noop());
+    }
+}
@@ -1,64 +1,57 @@
-use std::{fs, io};
 #[allow(unused_imports)]
 use std::{error::Error, ffi::{c_char, CStr}, io::Read, path::PathBuf};
-use gfx::definitions::{UiAtlas, UiAtlasTexture};
-use image::{DynamicImage, GenericImage, ImageBuffer};
 #[allow(unused_imports)]
 use libloading::{Library, Symbol};
 #[allow(unused_imports)]
 use serde::Deserialize;
 
-use crate::window::gui::EditorApp;
-
+use crate::config::{EditorConfig, StartupOptions, StartupReport};
+use crate::window::{gui::EditorApp, recent::RecentProjects, session::Session};
+
+mod atlas;
+mod config;
+mod crash;
+mod git;
+mod history;
+mod import;
+mod level;
+mod platform;
+mod project;
+mod storage;
+mod theme;
+mod undo;
 mod window;
 
 fn main() {
-    //load_lib().unwrap();
+    // Before anything else, so warnings about the arguments and config files are shown
+    env_logger::init();
+    crash::install_panic_hook();
+
+    let startup = StartupOptions::from_args(std::env::args().skip(1));
+    let mut report = StartupReport { safe_mode: startup.safe_mode, crash_report: crash::pending_report(), ..Default::default() };
+
+    let editor_config: EditorConfig = config::load_or_recover(config::CONFIG_FILE.as_ref(), &mut report.recovered);
+    config::validate_or_recover(config::KEYBINDINGS_FILE.as_ref(), &mut report.recovered);
+    // Safe mode starts from a fresh session and leaves the saved one alone
+    let session = if startup.safe_mode { Session::default() } else { Session::load() };
+    let recent_projects = if startup.safe_mode { RecentProjects::default() } else { RecentProjects::load() };
+    if !startup.safe_mode {
+        //load_lib().unwrap();
+    }
     //let mut config_buf: String = String::new();
     //let file = std::fs::File::open("config.toml").expect("Failed to open config file...").read_to_string(&mut config_buf);
     //let config = toml::from_str::<Config>(&config_buf).unwrap();
 
     //println!("{:?}", config.keys.github);
-    EditorApp::new(generate_texture_atlas()).unwrap();
-    //run(gui_interface).unwrap();
-}
-
-fn generate_texture_atlas() -> UiAtlas {
-    let mut images: Vec<(DynamicImage, String)> = Vec::new();
-    //let a = include_bytes!(".././assets/folder-1484.png");
-    let assets_dir = fs::read_dir(r"./app/assets").unwrap()
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, io::Error>>().unwrap();
-    for asset in assets_dir {
-        images.push((image::open(asset.as_path()).unwrap(), asset.file_stem().unwrap().to_str().unwrap().to_string()));
-    }
-
-    let mut new_width = 0;
-    let mut new_height = 0;
-
-    let mut last_image: Option<DynamicImage> = None;
-    for image in &images {
-        if last_image.is_none() {
-            new_height = image.0.height();
-        } else {
-            new_height = image.0.height().max(last_image.unwrap().height().max(new_height));
+    let (atlas_image, atlas) = match atlas::generate_texture_atlas(&editor_config.assets_dir) {
+        Ok(atlas) => atlas,
+        Err(e) => {
+            eprintln!("Unable to build the texture atlas: {}", e);
+            std::process::exit(1);
         }
-        new_width += image.0.width();
-        last_image = Some(image.0.clone());
-    }
-
-    let mut atlas = ImageBuffer::new(new_width, new_height);
-    let mut atlas_data = UiAtlas::new(new_width, new_height);
-
-    let mut last_coordinate = 0;
-    for image in images {
-        atlas_data.add_entry(UiAtlasTexture::new(image.1, last_coordinate, 0, image.0.width(), image.0.height()));
-        atlas.copy_from(&image.0, last_coordinate, 0).unwrap();
-        last_coordinate += &image.0.width();
-    }
-
-    atlas.save("./app/atlas.png").unwrap();
-    atlas_data
+    };
+    let render_options = startup.render_overrides.apply(&editor_config.render).options(editor_config.msaa_samples);
+    EditorApp::new(atlas_image, atlas, editor_config, report, render_options, session, recent_projects).unwrap();
 }
 
 /*
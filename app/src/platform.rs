@@ -0,0 +1,14 @@
+use std::{io, path::Path, process::Command};
+
+/// Opens a directory in the platform's file manager.
+pub fn open_in_file_manager(path: &Path) -> io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    Command::new(program).arg(path).spawn().map(|_| ())
+}
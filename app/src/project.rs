@@ -0,0 +1,123 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{self, SaveError};
+
+/// File in a project's root directory that makes it a project.
+pub const MANIFEST_FILE: &str = "project.toml";
+/// File in a project's root directory holding its level, see `Level::save`.
+pub const LEVEL_FILE: &str = "level.lvl";
+
+/// A project directory and what its `project.toml` says about it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Project {
+    /// Directory holding the manifest, filled in by `open` rather than read from the file.
+    #[serde(skip)]
+    pub root: PathBuf,
+    pub name: String,
+    /// Scene to reopen, relative to `root`.
+    #[allow(dead_code)]
+    #[serde(default)]
+    pub last_scene: Option<PathBuf>,
+    /// Where the project's assets live, relative to `root`.
+    #[serde(default = "default_asset_dir")]
+    pub asset_dir: PathBuf,
+}
+
+fn default_asset_dir() -> PathBuf {
+    PathBuf::from("assets")
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    /// The directory has no `project.toml`.
+    NotAProject(PathBuf),
+    Unreadable(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+    /// A new project's name was rejected, the string says why.
+    InvalidName(String),
+    AlreadyExists(PathBuf),
+    CreateFailed(PathBuf, io::Error),
+    Save(SaveError),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::NotAProject(path) => write!(f, "{} is not a project, it has no {}", path.display(), MANIFEST_FILE),
+            ProjectError::Unreadable(path, e) => write!(f, "Unable to read {}: {}", path.display(), e),
+            ProjectError::Malformed(path, reason) => write!(f, "{} is malformed: {}", path.display(), reason.lines().next().unwrap_or("unknown error")),
+            ProjectError::InvalidName(reason) => write!(f, "{}", reason),
+            ProjectError::AlreadyExists(path) => write!(f, "{} already exists", path.display()),
+            ProjectError::CreateFailed(path, e) => write!(f, "Unable to create {}: {}", path.display(), e),
+            ProjectError::Save(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProjectError::Unreadable(_, e) | ProjectError::CreateFailed(_, e) => Some(e),
+            ProjectError::Save(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Project {
+    /// Reads the manifest of the project at `path`, which is either the project directory or its `project.toml`.
+    pub fn open(path: &Path) -> Result<Project, ProjectError> {
+        let root = if path.file_name().is_some_and(|name| name == MANIFEST_FILE) {
+            path.parent().unwrap_or(Path::new(".")).to_path_buf()
+        } else {
+            path.to_path_buf()
+        };
+        let manifest = root.join(MANIFEST_FILE);
+        let contents = match fs::read_to_string(&manifest) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(ProjectError::NotAProject(root)),
+            Err(e) => return Err(ProjectError::Unreadable(manifest, e)),
+        };
+        let mut project: Project = toml::from_str(&contents).map_err(|e| ProjectError::Malformed(manifest, e.to_string()))?;
+        project.root = root;
+        Ok(project)
+    }
+
+    /// Makes a directory called `name` in `projects_dir` with a starter manifest.
+    /// The name can't be empty, contain a path separator or be taken already.
+    pub fn create(projects_dir: &Path, name: &str) -> Result<Project, ProjectError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ProjectError::InvalidName("Enter a name for the project".to_string()));
+        }
+        if name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(ProjectError::InvalidName(format!("{:?} can't be used as a folder name", name)));
+        }
+        let root = projects_dir.join(name);
+        if root.exists() {
+            return Err(ProjectError::AlreadyExists(root));
+        }
+        fs::create_dir_all(&root).map_err(|e| ProjectError::CreateFailed(root.clone(), e))?;
+
+        let project = Project { root, name: name.to_string(), last_scene: None, asset_dir: default_asset_dir() };
+        storage::save_toml(&project.root.join(MANIFEST_FILE), &project).map_err(ProjectError::Save)?;
+        Ok(project)
+    }
+
+    /// The project's asset folder, which its tiles are packed from.
+    pub fn asset_path(&self) -> PathBuf {
+        self.root.join(&self.asset_dir)
+    }
+
+    /// Where the project's level is saved.
+    pub fn level_path(&self) -> PathBuf {
+        self.root.join(LEVEL_FILE)
+    }
+
+    /// Whether `directory` has a manifest, without reading it.
+    pub fn is_project(directory: &Path) -> bool {
+        directory.join(MANIFEST_FILE).is_file()
+    }
+}
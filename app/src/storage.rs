@@ -0,0 +1,326 @@
+use std::{fmt, fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, thread, time::{Duration, SystemTime}};
+
+use serde::Serialize;
+
+#[derive(Debug)]
+pub enum SaveError {
+    DiskFull(PathBuf),
+    ReadOnly(PathBuf),
+    Serialize(PathBuf, String),
+    Io(PathBuf, io::Error),
+}
+
+impl SaveError {
+    pub fn from_io(path: &Path, e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => SaveError::DiskFull(path.to_path_buf()),
+            io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::PermissionDenied => SaveError::ReadOnly(path.to_path_buf()),
+            _ => SaveError::Io(path.to_path_buf(), e),
+        }
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::DiskFull(path) => write!(f, "Not enough disk space to save {}", path.display()),
+            SaveError::ReadOnly(path) => write!(f, "{} is read-only or you don't have permission to write it", path.display()),
+            SaveError::Serialize(path, reason) => write!(f, "Unable to serialize {}: {}", path.display(), reason),
+            SaveError::Io(path, e) => write!(f, "Unable to save {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Replaces `path` with `contents` without ever leaving a truncated file behind.
+/// The data is written and synced to a temp file next to the target, which is then renamed over it.
+pub fn atomic_write(path: &Path, contents: &[u8]) -> Result<(), SaveError> {
+    write_via_temp(path, contents, write_temp, || Ok(()), replace)
+}
+
+pub fn save_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), SaveError> {
+    let contents = toml::to_string_pretty(value)
+        .map_err(|e| SaveError::Serialize(path.to_path_buf(), e.to_string()))?;
+    atomic_write(path, contents.as_bytes())
+}
+
+/// Saves like [`atomic_write`], shifting the previous contents into `<name>.bak1` on the way, pushing older backups
+/// up to `<name>.bak<keep>` and dropping anything beyond that. Backups are only rotated once the new contents are
+/// safely in the temp file, so a save that can't be written leaves all of them in place.
+pub fn save_with_backups(path: &Path, contents: &[u8], keep: usize) -> Result<(), SaveError> {
+    write_via_temp(path, contents, write_temp, || rotate_backups(path, keep), replace)
+}
+
+/// Existing backups of `path`, newest first, with the time each one was written.
+pub fn list_backups(path: &Path, keep: usize) -> Vec<(PathBuf, SystemTime)> {
+    (1..=keep)
+        .map(|index| backup_path(path, index))
+        .filter_map(|backup| {
+            let modified = fs::metadata(&backup).and_then(|metadata| metadata.modified()).ok()?;
+            Some((backup, modified))
+        })
+        .collect()
+}
+
+pub fn is_backup(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| extension.strip_prefix("bak"))
+        .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Writes `contents` to a temp file next to `path`, runs `prepare`, then renames the temp file over `path`.
+/// `path` is only touched by the rename, and the temp file is removed if any step fails.
+/// The steps are passed in so tests can make each of them fail.
+fn write_via_temp(
+    path: &Path,
+    contents: &[u8],
+    write: impl FnOnce(&Path, &Path, &[u8]) -> Result<(), SaveError>,
+    prepare: impl FnOnce() -> Result<(), SaveError>,
+    rename: impl FnOnce(&Path, &Path) -> Result<(), SaveError>,
+) -> Result<(), SaveError> {
+    let temp_path = temp_path_for(path);
+
+    let result = write(path, &temp_path, contents)
+        .and_then(|()| prepare())
+        .and_then(|()| rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Copies `path` to `<name>.bak1`, moving each older backup up one and dropping `<name>.bak<keep>`.
+fn rotate_backups(path: &Path, keep: usize) -> Result<(), SaveError> {
+    if keep == 0 || !path.exists() {
+        return Ok(());
+    }
+    let _ = fs::remove_file(backup_path(path, keep));
+    for index in (1..keep).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, index + 1)).map_err(|e| SaveError::from_io(path, e))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1)).map_err(|e| SaveError::from_io(path, e))?;
+    Ok(())
+}
+
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".bak{}", index));
+    PathBuf::from(backup)
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()))
+}
+
+fn write_temp(path: &Path, temp_path: &Path, contents: &[u8]) -> Result<(), SaveError> {
+    let existing = fs::metadata(path).ok();
+    if existing.as_ref().is_some_and(|metadata| metadata.permissions().readonly()) {
+        return Err(SaveError::ReadOnly(path.to_path_buf()));
+    }
+
+    let mut file = File::create(temp_path).map_err(|e| SaveError::from_io(path, e))?;
+    file.write_all(contents).map_err(|e| SaveError::from_io(path, e))?;
+    file.sync_all().map_err(|e| SaveError::from_io(path, e))?;
+
+    if let Some(metadata) = existing {
+        fs::set_permissions(temp_path, metadata.permissions()).map_err(|e| SaveError::from_io(path, e))?;
+    }
+
+    Ok(())
+}
+
+fn replace(temp_path: &Path, path: &Path) -> Result<(), SaveError> {
+    // On Windows rename maps to MoveFileEx with MOVEFILE_REPLACE_EXISTING, which fails with
+    // access denied while another process (antivirus, indexer) briefly holds the target open.
+    let mut attempts = if cfg!(target_os = "windows") { 5 } else { 1 };
+    loop {
+        attempts -= 1;
+        match fs::rename(temp_path, path) {
+            Ok(()) => break,
+            Err(e) if attempts > 0 && e.kind() == io::ErrorKind::PermissionDenied => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(SaveError::from_io(path, e)),
+        }
+    }
+
+    sync_parent_dir(path);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if let Err(e) = File::open(parent).and_then(|dir| dir.sync_all()) {
+        log::warn!("Unable to sync directory {:?} after saving {:?}: {}", parent, path, e);
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed again when dropped. `name` has to be unique among the
+    /// crate's tests, they run in parallel.
+    pub(crate) struct TestDir(PathBuf);
+
+    impl TestDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("level_editor_test_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        pub(crate) fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Gets half the contents into the temp file before failing, like a disk filling up mid-write.
+    fn fail_write(_path: &Path, temp_path: &Path, contents: &[u8]) -> Result<(), SaveError> {
+        fs::write(temp_path, &contents[..contents.len() / 2]).unwrap();
+        Err(SaveError::DiskFull(temp_path.to_path_buf()))
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_contents() {
+        let dir = TestDir::new("replace");
+        let path = dir.join("level.lvl");
+        fs::write(&path, "original").unwrap();
+
+        atomic_write(&path, b"replacement").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "replacement");
+        assert!(!temp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn atomic_write_creates_a_missing_file() {
+        let dir = TestDir::new("create");
+        let path = dir.join("session.toml");
+
+        atomic_write(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+
+    #[test]
+    fn original_survives_a_failed_temp_write() {
+        let dir = TestDir::new("failed_write");
+        let path = dir.join("level.lvl");
+        fs::write(&path, "original").unwrap();
+
+        let result = write_via_temp(&path, b"replacement", fail_write, || Ok(()), replace);
+
+        assert!(matches!(result, Err(SaveError::DiskFull(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert!(!temp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn original_survives_a_failed_rename() {
+        let dir = TestDir::new("failed_rename");
+        let path = dir.join("level.lvl");
+        fs::write(&path, "original").unwrap();
+
+        let result = write_via_temp(&path, b"replacement", write_temp, || Ok(()), |temp_path: &Path, path: &Path| {
+            // The new contents made it to disk, only the rename over the original is missing
+            assert_eq!(fs::read_to_string(temp_path).unwrap(), "replacement");
+            Err(SaveError::Io(path.to_path_buf(), io::Error::other("rename failed")))
+        });
+
+        assert!(matches!(result, Err(SaveError::Io(..))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert!(!temp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn read_only_target_is_reported() {
+        let dir = TestDir::new("read_only");
+        let path = dir.join("level.lvl");
+        fs::write(&path, "original").unwrap();
+        let writable = fs::metadata(&path).unwrap().permissions();
+        let mut read_only = writable.clone();
+        read_only.set_readonly(true);
+        fs::set_permissions(&path, read_only).unwrap();
+
+        let result = atomic_write(&path, b"replacement");
+
+        fs::set_permissions(&path, writable).unwrap();
+        assert!(matches!(result, Err(SaveError::ReadOnly(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert!(!temp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn saves_rotate_backups_up_to_keep() {
+        let dir = TestDir::new("rotate");
+        let path = dir.join("level.lvl");
+        for contents in ["one", "two", "three", "four"] {
+            save_with_backups(&path, contents.as_bytes(), 2).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "four");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "three");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "two");
+        assert!(!backup_path(&path, 3).exists());
+        let listed: Vec<PathBuf> = list_backups(&path, 2).into_iter().map(|(backup, _)| backup).collect();
+        assert_eq!(listed, vec![backup_path(&path, 1), backup_path(&path, 2)]);
+    }
+
+    #[test]
+    fn failed_save_keeps_every_backup() {
+        let dir = TestDir::new("failed_backup_save");
+        let path = dir.join("level.lvl");
+        fs::write(&path, "current").unwrap();
+        fs::write(backup_path(&path, 1), "newer").unwrap();
+        fs::write(backup_path(&path, 2), "older").unwrap();
+        let writable = fs::metadata(&path).unwrap().permissions();
+        let mut read_only = writable.clone();
+        read_only.set_readonly(true);
+        fs::set_permissions(&path, read_only).unwrap();
+
+        let result = save_with_backups(&path, b"replacement", 2);
+
+        fs::set_permissions(&path, writable).unwrap();
+        assert!(matches!(result, Err(SaveError::ReadOnly(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "current");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "newer");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "older");
+    }
+
+    #[test]
+    fn is_backup_only_matches_numbered_backups() {
+        assert!(is_backup(Path::new("level.lvl.bak1")));
+        assert!(is_backup(Path::new("level.lvl.bak12")));
+        assert!(!is_backup(Path::new("level.lvl.bak")));
+        assert!(!is_backup(Path::new("level.lvl.bakup")));
+        assert!(!is_backup(Path::new("level.lvl")));
+    }
+}
@@ -0,0 +1,281 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::{Path, PathBuf}};
+
+use gfx::gui::{color::Color, style::Style, theme::{self as ui_theme, ColorRole}};
+use serde::{Deserialize, Serialize};
+
+/// Built-in interface color themes, see `AppearanceConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl ThemePreset {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemePreset::Dark => "Dark",
+            ThemePreset::Light => "Light",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            ThemePreset::Dark => ThemePreset::Light,
+            ThemePreset::Light => ThemePreset::Dark,
+        }
+    }
+
+    fn ui_theme(&self) -> ui_theme::Theme {
+        match self {
+            ThemePreset::Dark => ui_theme::Theme::dark(),
+            ThemePreset::Light => ui_theme::Theme::light(),
+        }
+    }
+}
+
+/// The `[appearance]` table of the config.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppearanceConfig {
+    pub theme: ThemePreset,
+    /// TOML file of `role = "#rrggbbaa"` colors laid over `theme`, keyed by `ColorRole::key`. Roles it leaves
+    /// out keep the preset's color.
+    pub custom_theme: Option<PathBuf>,
+}
+
+impl AppearanceConfig {
+    /// The interface colors to draw with. A custom theme that can't be loaded is logged and left out.
+    pub fn ui_theme(&self) -> ui_theme::Theme {
+        let theme = self.theme.ui_theme();
+        match &self.custom_theme {
+            Some(path) => load_custom_theme(path, theme.clone()).unwrap_or_else(|e| {
+                log::error!("{}", e);
+                theme
+            }),
+            None => theme,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Unreadable(PathBuf, io::Error),
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Unreadable(path, e) => write!(f, "Unable to read theme {}: {}", path.display(), e),
+            ThemeError::Malformed(path, reason) => write!(f, "Theme {} is malformed: {}", path.display(), reason.lines().next().unwrap_or("unknown error")),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ThemeError::Unreadable(_, e) => Some(e),
+            ThemeError::Malformed(..) => None,
+        }
+    }
+}
+
+/// `base` with the colors in the theme file at `path` laid over it. Unknown roles are warned about and skipped,
+/// a color that doesn't parse fails the whole file.
+fn load_custom_theme(path: &Path, mut base: ui_theme::Theme) -> Result<ui_theme::Theme, ThemeError> {
+    let contents = fs::read_to_string(path).map_err(|e| ThemeError::Unreadable(path.to_path_buf(), e))?;
+    let colors: BTreeMap<String, String> = toml::from_str(&contents).map_err(|e| ThemeError::Malformed(path.to_path_buf(), e.to_string()))?;
+    for (key, hex) in colors {
+        let Some(role) = ColorRole::ALL.into_iter().find(|role| role.key() == key) else {
+            log::warn!("Ignoring unknown color {:?} in theme {}", key, path.display());
+            continue;
+        };
+        *base.color_mut(role) = Color::try_from_hex(&hex)
+            .map_err(|e| ThemeError::Malformed(path.to_path_buf(), format!("{}: {}", key, e)))?;
+    }
+    Ok(base)
+}
+
+/// Built-in sets of overlay colors. Every overlay reads its color through [`Theme::overlay`],
+/// so switching preset recolors collision, selection, diff and validation drawing together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PalettePreset {
+    #[default]
+    Default,
+    Deuteranopia,
+    HighContrast,
+}
+
+impl PalettePreset {
+    pub const ALL: [PalettePreset; 3] = [PalettePreset::Default, PalettePreset::Deuteranopia, PalettePreset::HighContrast];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PalettePreset::Default => "Default",
+            PalettePreset::Deuteranopia => "Deuteranopia",
+            PalettePreset::HighContrast => "High Contrast",
+        }
+    }
+
+    /// The preset after this one in `ALL`, wrapping around.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|preset| preset == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn color(&self, overlay: OverlayColor) -> &'static str {
+        match (self, overlay) {
+            (PalettePreset::Default, OverlayColor::Collision) => "#e5534bcc",
+            (PalettePreset::Default, OverlayColor::Selection) => "#539bf5ff",
+            (PalettePreset::Default, OverlayColor::DiffAdded) => "#57ab5aff",
+            (PalettePreset::Default, OverlayColor::DiffRemoved) => "#e5534bff",
+            (PalettePreset::Default, OverlayColor::DiffModified) => "#daaa3fff",
+            (PalettePreset::Default, OverlayColor::ValidationError) => "#f47067ff",
+            (PalettePreset::Default, OverlayColor::ValidationWarning) => "#c69026ff",
+
+            // Blue/orange pairs stay distinguishable without relying on the red-green axis.
+            (PalettePreset::Deuteranopia, OverlayColor::Collision) => "#e69f00cc",
+            (PalettePreset::Deuteranopia, OverlayColor::Selection) => "#56b4e9ff",
+            (PalettePreset::Deuteranopia, OverlayColor::DiffAdded) => "#0072b2ff",
+            (PalettePreset::Deuteranopia, OverlayColor::DiffRemoved) => "#d55e00ff",
+            (PalettePreset::Deuteranopia, OverlayColor::DiffModified) => "#cc79a7ff",
+            (PalettePreset::Deuteranopia, OverlayColor::ValidationError) => "#d55e00ff",
+            (PalettePreset::Deuteranopia, OverlayColor::ValidationWarning) => "#f0e442ff",
+
+            (PalettePreset::HighContrast, OverlayColor::Collision) => "#ff00ffff",
+            (PalettePreset::HighContrast, OverlayColor::Selection) => "#ffffffff",
+            (PalettePreset::HighContrast, OverlayColor::DiffAdded) => "#00ffffff",
+            (PalettePreset::HighContrast, OverlayColor::DiffRemoved) => "#ffff00ff",
+            (PalettePreset::HighContrast, OverlayColor::DiffModified) => "#ff8c00ff",
+            (PalettePreset::HighContrast, OverlayColor::ValidationError) => "#ff0000ff",
+            (PalettePreset::HighContrast, OverlayColor::ValidationWarning) => "#ffa500ff",
+        }
+    }
+}
+
+/// Semantic names for the colors overlays draw with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayColor {
+    Collision,
+    Selection,
+    DiffAdded,
+    DiffRemoved,
+    DiffModified,
+    ValidationError,
+    ValidationWarning,
+}
+
+impl OverlayColor {
+    pub const ALL: [OverlayColor; 7] = [
+        OverlayColor::Collision,
+        OverlayColor::Selection,
+        OverlayColor::DiffAdded,
+        OverlayColor::DiffRemoved,
+        OverlayColor::DiffModified,
+        OverlayColor::ValidationError,
+        OverlayColor::ValidationWarning,
+    ];
+
+    /// Key used for this color in the `[accessibility.overlay_colors]` config table.
+    pub fn key(&self) -> &'static str {
+        match self {
+            OverlayColor::Collision => "collision",
+            OverlayColor::Selection => "selection",
+            OverlayColor::DiffAdded => "diff_added",
+            OverlayColor::DiffRemoved => "diff_removed",
+            OverlayColor::DiffModified => "diff_modified",
+            OverlayColor::ValidationError => "validation_error",
+            OverlayColor::ValidationWarning => "validation_warning",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OverlayColor::Collision => "Collision",
+            OverlayColor::Selection => "Selection",
+            OverlayColor::DiffAdded => "Diff Added",
+            OverlayColor::DiffRemoved => "Diff Removed",
+            OverlayColor::DiffModified => "Diff Modified",
+            OverlayColor::ValidationError => "Validation Error",
+            OverlayColor::ValidationWarning => "Validation Warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    pub palette: PalettePreset,
+    /// Per-color `#rrggbbaa` overrides applied on top of the preset. `#rgb`, `#rgba` and `#rrggbb` work too.
+    pub overlay_colors: BTreeMap<String, String>,
+    /// `None` follows the OS preference, which is off for now, see `reduce_motion_enabled`.
+    pub reduce_motion: Option<bool>,
+}
+
+impl AccessibilityConfig {
+    /// Whether animations should jump straight to their end state.
+    /// winit 0.30 doesn't report the OS reduced-motion preference, so following the OS currently means off.
+    pub fn reduce_motion_enabled(&self) -> bool {
+        self.reduce_motion.unwrap_or(false)
+    }
+
+    pub fn cycle_reduce_motion(&mut self) {
+        self.reduce_motion = match self.reduce_motion {
+            None => Some(true),
+            Some(true) => Some(false),
+            Some(false) => None,
+        };
+    }
+
+    pub fn reduce_motion_label(&self) -> String {
+        let state = if self.reduce_motion_enabled() { "On" } else { "Off" };
+        match self.reduce_motion {
+            // Says so rather than implying the OS setting is read
+            None => format!("Follow OS (unavailable, {})", state),
+            Some(_) => state.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    overlay_colors: Vec<(OverlayColor, String)>,
+}
+
+impl Theme {
+    pub fn from_config(config: &AccessibilityConfig) -> Self {
+        let overlay_colors = OverlayColor::ALL.iter()
+            .map(|&overlay| {
+                let color = match config.overlay_colors.get(overlay.key()).map(|custom| (custom, Color::try_from_hex(custom))) {
+                    Some((custom, Ok(_))) => custom.clone(),
+                    Some((_, Err(e))) => {
+                        log::warn!("Ignoring overlay color {}: {}", overlay.key(), e);
+                        config.palette.color(overlay).to_string()
+                    }
+                    None => config.palette.color(overlay).to_string(),
+                };
+                (overlay, color)
+            })
+            .collect();
+
+        Self { overlay_colors }
+    }
+
+    /// Interface-wide defaults, panels and elements override individual fields. Colors come from the
+    /// interface's theme, see `AppearanceConfig::ui_theme`.
+    pub fn ui_style(&self) -> Style {
+        Style::new()
+            .with_text_scale(0.7)
+    }
+
+    pub fn overlay(&self, overlay: OverlayColor) -> &str {
+        self.overlay_colors.iter()
+            .find(|(candidate, _)| *candidate == overlay)
+            .map(|(_, color)| color.as_str())
+            .unwrap_or("#ff00ffff")
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::VecDeque;
+
+/// Most commands kept for undoing, older ones are dropped.
+const MAX_COMMANDS: usize = 1000;
+/// Most memory the kept commands may take, by `Command::size`, before older ones are dropped.
+const MAX_BYTES: usize = 32 * 1024 * 1024;
+
+/// An edit that can be undone, see `UndoStack`.
+pub trait Command {
+    /// What the command edits.
+    type Target;
+
+    fn apply(&self, target: &mut Self::Target);
+    /// Undoes `apply`, on a target in the state `apply` left it in.
+    fn revert(&self, target: &mut Self::Target);
+
+    /// Folds `next`, applied straight after this one, into this command so one undo reverts both.
+    /// Returns false to keep them separate.
+    fn merge_with(&mut self, _next: &Self) -> bool {
+        false
+    }
+
+    /// Whether applying changes nothing, such commands aren't kept.
+    fn is_noop(&self) -> bool {
+        false
+    }
+
+    /// Roughly how much memory the command holds, counted against the stack's budget.
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+/// Commands done and undone, capped at `MAX_COMMANDS` and `MAX_BYTES` by dropping the oldest.
+/// Remembers the point the target was last saved at, so undoing back to it counts as unmodified again.
+pub struct UndoStack<C> {
+    done: VecDeque<C>,
+    /// Most recently undone last, cleared by a new command.
+    undone: Vec<C>,
+    /// Commands dropped off the bottom of `done`, so positions stay comparable with `saved_at`.
+    dropped: usize,
+    /// Position of the last save, `None` once it can't be got back to by undoing or redoing.
+    saved_at: Option<usize>,
+    /// Whether the next command may merge into the last one.
+    open: bool,
+    bytes: usize,
+}
+
+impl<C: Command> UndoStack<C> {
+    /// An empty stack, at the save point.
+    pub fn new() -> Self {
+        Self { done: VecDeque::new(), undone: Vec::new(), dropped: 0, saved_at: Some(0), open: false, bytes: 0 }
+    }
+
+    /// Applies `command` to `target` and keeps it for undoing, merged into the last command if that one is still
+    /// open, see `seal`. Anything undone can no longer be redone.
+    pub fn push(&mut self, command: C, target: &mut C::Target) {
+        if command.is_noop() {
+            return;
+        }
+        command.apply(target);
+
+        for undone in self.undone.drain(..) {
+            self.bytes -= undone.size();
+        }
+        if self.saved_at.is_some_and(|saved| saved > self.position()) {
+            self.saved_at = None;
+        }
+
+        if self.open
+            && let Some(last) = self.done.back_mut() {
+            let size = last.size();
+            if last.merge_with(&command) {
+                self.bytes = self.bytes - size + last.size();
+                self.trim();
+                return;
+            }
+        }
+        self.bytes += command.size();
+        self.done.push_back(command);
+        self.open = true;
+        self.trim();
+    }
+
+    /// Reverts the last command. Returns false when there's nothing to undo.
+    pub fn undo(&mut self, target: &mut C::Target) -> bool {
+        let Some(command) = self.done.pop_back() else {
+            return false;
+        };
+        command.revert(target);
+        self.undone.push(command);
+        self.open = false;
+        true
+    }
+
+    /// Applies the last undone command again. Returns false when there's nothing to redo.
+    pub fn redo(&mut self, target: &mut C::Target) -> bool {
+        let Some(command) = self.undone.pop() else {
+            return false;
+        };
+        command.apply(target);
+        self.done.push_back(command);
+        self.open = false;
+        true
+    }
+
+    /// Keeps the next command from merging into the last one, such as at the end of a paint stroke.
+    pub fn seal(&mut self) {
+        self.open = false;
+    }
+
+    /// Marks the target as saved in its current state. Seals the last command, merging into it would move the
+    /// save point.
+    pub fn mark_saved(&mut self) {
+        self.saved_at = Some(self.position());
+        self.open = false;
+    }
+
+    /// Whether the target is as it was when last saved, or as it started without a save.
+    pub fn is_saved(&self) -> bool {
+        self.saved_at == Some(self.position())
+    }
+
+    /// Forgets every command, for when they no longer fit the target. Whether it's saved is kept.
+    pub fn clear(&mut self) {
+        let saved = self.is_saved();
+        *self = Self::new();
+        if !saved {
+            self.saved_at = None;
+        }
+    }
+
+    /// Commands done since the stack started, dropped ones included.
+    fn position(&self) -> usize {
+        self.dropped + self.done.len()
+    }
+
+    /// Drops the oldest commands until the caps are met. The last command is kept whatever its size.
+    fn trim(&mut self) {
+        while self.done.len() > 1 && (self.done.len() > MAX_COMMANDS || self.bytes > MAX_BYTES) {
+            if let Some(oldest) = self.done.pop_front() {
+                self.bytes -= oldest.size();
+                self.dropped += 1;
+            }
+        }
+        if self.saved_at.is_some_and(|saved| saved < self.dropped) {
+            self.saved_at = None;
+        }
+    }
+}
+
+impl<C: Command> Default for UndoStack<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec2;
+
+    use super::*;
+    use crate::level::{Level, TileEdit};
+
+    fn paint(stack: &mut UndoStack<TileEdit>, level: &mut Level, tile: u16, cells: &[IVec2]) {
+        let edit = TileEdit::brush(level, Some(tile), cells.iter().copied());
+        stack.push(edit, level);
+    }
+
+    #[test]
+    fn paints_undo_and_redo_in_order() {
+        let (a, b) = (IVec2::new(0, 0), IVec2::new(1, 0));
+        let mut level = Level::new();
+        let mut stack = UndoStack::new();
+        paint(&mut stack, &mut level, 1, &[a]);
+        stack.seal();
+        paint(&mut stack, &mut level, 2, &[a, b]);
+        stack.seal();
+
+        assert!(stack.undo(&mut level));
+        assert_eq!((level.tile(a), level.tile(b)), (Some(1), None));
+        assert!(stack.undo(&mut level));
+        assert_eq!((level.tile(a), level.tile(b)), (None, None));
+        assert!(!stack.undo(&mut level));
+
+        assert!(stack.redo(&mut level));
+        assert_eq!((level.tile(a), level.tile(b)), (Some(1), None));
+        assert!(stack.redo(&mut level));
+        assert_eq!((level.tile(a), level.tile(b)), (Some(2), Some(2)));
+        assert!(!stack.redo(&mut level));
+    }
+
+    #[test]
+    fn a_stroke_of_one_tile_undoes_in_one_go() {
+        let cells = [IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(2, 0)];
+        let mut level = Level::new();
+        let mut stack = UndoStack::new();
+        for cell in cells {
+            paint(&mut stack, &mut level, 1, &[cell]);
+        }
+        assert!(stack.undo(&mut level));
+        assert!(cells.iter().all(|&cell| level.tile(cell).is_none()));
+        assert!(!stack.undo(&mut level));
+    }
+
+    #[test]
+    fn a_new_edit_drops_what_was_undone() {
+        let (a, b) = (IVec2::new(0, 0), IVec2::new(0, 1));
+        let mut level = Level::new();
+        let mut stack = UndoStack::new();
+        paint(&mut stack, &mut level, 1, &[a]);
+        stack.seal();
+        paint(&mut stack, &mut level, 2, &[a]);
+        stack.seal();
+        assert!(stack.undo(&mut level));
+
+        paint(&mut stack, &mut level, 3, &[b]);
+        assert!(!stack.redo(&mut level));
+        assert_eq!((level.tile(a), level.tile(b)), (Some(1), Some(3)));
+
+        assert!(stack.undo(&mut level));
+        assert!(stack.undo(&mut level));
+        assert_eq!((level.tile(a), level.tile(b)), (None, None));
+    }
+
+    #[test]
+    fn undoing_back_to_the_save_point_is_saved_again() {
+        let cell = IVec2::new(-2, 3);
+        let mut level = Level::new();
+        let mut stack = UndoStack::new();
+        paint(&mut stack, &mut level, 1, &[cell]);
+        stack.mark_saved();
+        paint(&mut stack, &mut level, 2, &[cell]);
+        assert!(!stack.is_saved());
+
+        stack.undo(&mut level);
+        assert!(stack.is_saved());
+        stack.undo(&mut level);
+        assert!(!stack.is_saved());
+        stack.redo(&mut level);
+        assert!(stack.is_saved());
+
+        // Editing from before the save point loses the way back to it
+        stack.undo(&mut level);
+        paint(&mut stack, &mut level, 3, &[cell]);
+        assert!(!stack.is_saved());
+        stack.undo(&mut level);
+        assert!(!stack.is_saved());
+    }
+
+    #[test]
+    fn the_oldest_commands_are_dropped_past_the_cap() {
+        let mut level = Level::new();
+        let mut stack = UndoStack::new();
+        for x in 0..MAX_COMMANDS as i32 + 5 {
+            paint(&mut stack, &mut level, 1, &[IVec2::new(x, 0)]);
+            stack.seal();
+        }
+        let mut undone = 0;
+        while stack.undo(&mut level) {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_COMMANDS);
+        assert_eq!(level.tile(IVec2::new(4, 0)), Some(1));
+        assert_eq!(level.tile(IVec2::new(5, 0)), None);
+        // The start is gone, so nothing can be undone back to it
+        assert!(!stack.is_saved());
+    }
+}
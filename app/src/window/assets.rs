@@ -0,0 +1,55 @@
+use std::{path::PathBuf, sync::mpsc::{self, Receiver, TryRecvError}, thread};
+
+use gfx::definitions::UiAtlas;
+use image::RgbaImage;
+
+use crate::atlas::{self, AtlasError};
+
+/// What the packing thread sends: any number of progress updates, then the result.
+enum ReloadMessage {
+    Progress(f32),
+    Finished(Result<(RgbaImage, UiAtlas), AtlasError>),
+}
+
+/// The assets folder being packed into a new atlas on a background thread, see `EditorApp::reload_assets`.
+pub(crate) struct AssetReload {
+    receiver: Receiver<ReloadMessage>,
+    /// The latest progress `poll` took, from 0.0 to 1.0.
+    progress: f32,
+}
+
+impl AssetReload {
+    /// Starts packing `assets_dir`. The thread calls `notify` after everything it sends, so the event loop wakes up
+    /// to `poll`. Dropping the reload abandons it, the thread stops sending once it notices.
+    pub(crate) fn start(assets_dir: PathBuf, notify: impl Fn() + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = atlas::generate_texture_atlas_with_progress(&assets_dir, |progress| {
+                if sender.send(ReloadMessage::Progress(progress)).is_ok() {
+                    notify();
+                }
+            });
+            if sender.send(ReloadMessage::Finished(result)).is_ok() {
+                notify();
+            }
+        });
+        Self { receiver, progress: 0.0 }
+    }
+
+    pub(crate) fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Takes everything the thread sent since the last call, keeping the latest progress. Returns the packed atlas,
+    /// or why it couldn't be packed, once the thread is done; the reload has nothing more to give after that.
+    pub(crate) fn poll(&mut self) -> Option<Result<(RgbaImage, UiAtlas), String>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(ReloadMessage::Progress(progress)) => self.progress = progress,
+                Ok(ReloadMessage::Finished(result)) => return Some(result.map_err(|e| e.to_string())),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => return Some(Err("Packing the assets stopped without a result".to_string())),
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::{fmt, path::PathBuf};
+
+use gfx::gui::interface::UiTree;
+
+use crate::window::event::GuiEvent;
+
+/// Characters a paste may bring a text input up to. Longer text is cut off there.
+pub(crate) const MAX_INPUT_LENGTH: usize = 260;
+
+/// Where copied text goes and pasted text comes from. The editor uses `SystemClipboard`, anything holding a
+/// string will do in its place.
+pub(crate) trait Clipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError>;
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+}
+
+#[derive(Debug)]
+pub(crate) enum ClipboardError {
+    /// There's no clipboard to use, e.g. without a display server or on a compositor arboard doesn't support.
+    Unavailable(String),
+    /// The clipboard holds nothing, or nothing that's text.
+    Empty,
+    Failed(String),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::Unavailable(reason) => write!(f, "The clipboard isn't available: {}", reason),
+            ClipboardError::Empty => write!(f, "There's no text on the clipboard"),
+            ClipboardError::Failed(reason) => write!(f, "The clipboard couldn't be used: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+impl From<arboard::Error> for ClipboardError {
+    fn from(error: arboard::Error) -> Self {
+        match error {
+            arboard::Error::ContentNotAvailable => ClipboardError::Empty,
+            arboard::Error::ClipboardNotSupported => ClipboardError::Unavailable(error.to_string()),
+            other => ClipboardError::Failed(other.to_string()),
+        }
+    }
+}
+
+/// The desktop's clipboard. It's opened on first use and again after a failure, so a clipboard that wasn't there
+/// at startup, or went away, is picked up once it is.
+#[derive(Default)]
+pub(crate) struct SystemClipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl SystemClipboard {
+    fn open(&mut self) -> Result<&mut arboard::Clipboard, ClipboardError> {
+        if self.inner.is_none() {
+            let clipboard = arboard::Clipboard::new().map_err(|e| ClipboardError::Unavailable(e.to_string()))?;
+            self.inner = Some(clipboard);
+        }
+        Ok(self.inner.as_mut().expect("opened above"))
+    }
+
+    /// Drops the connection after anything but an empty clipboard, so the next use opens a new one.
+    fn check<T>(&mut self, result: Result<T, arboard::Error>) -> Result<T, ClipboardError> {
+        result.map_err(|e| {
+            let error = ClipboardError::from(e);
+            if !matches!(error, ClipboardError::Empty) {
+                self.inner = None;
+            }
+            error
+        })
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError> {
+        let result = self.open()?.get_text();
+        self.check(result)
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        let result = self.open()?.set_text(text);
+        self.check(result)
+    }
+}
+
+/// What a clipboard shortcut does to the focused text input, see `Shortcuts::input_edit_for`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InputEdit {
+    /// Copies the whole text, inputs have no selection.
+    Copy,
+    /// Copies the whole text and empties the input.
+    Cut,
+    /// Adds the clipboard's text at the end, where the caret is, see `paste_text`.
+    Paste,
+}
+
+impl InputEdit {
+    /// The edit sent by a clipboard shortcut, `None` for any other event.
+    pub(crate) fn for_event(event: &GuiEvent) -> Option<InputEdit> {
+        match event {
+            GuiEvent::Copy => Some(InputEdit::Copy),
+            GuiEvent::Cut => Some(InputEdit::Cut),
+            GuiEvent::Paste => Some(InputEdit::Paste),
+            _ => None,
+        }
+    }
+}
+
+/// Applies `edit` to `interface`'s focused input. Returns whether its text changed, false without a focused input.
+/// On failure the input is left as it was, a cut that can't copy doesn't empty it.
+pub(crate) fn edit_focused_input(clipboard: &mut dyn Clipboard, interface: &mut UiTree<GuiEvent>, edit: InputEdit) -> Result<bool, ClipboardError> {
+    let Some(text) = interface.focused_text() else {
+        return Ok(false);
+    };
+    // With nothing to copy the clipboard keeps what it has
+    if text.is_empty() && edit != InputEdit::Paste {
+        return Ok(false);
+    }
+    match edit {
+        InputEdit::Copy => {
+            clipboard.set_text(text)?;
+            Ok(false)
+        }
+        InputEdit::Cut => {
+            clipboard.set_text(text)?;
+            Ok(interface.take_focused_text().is_some())
+        }
+        InputEdit::Paste => {
+            let pasted = paste_text(&clipboard.get_text()?, text.chars().count());
+            Ok(interface.insert_text(&pasted))
+        }
+    }
+}
+
+/// `raw` made fit for a single line input already holding `current_length` characters: line breaks at either end
+/// are dropped, those inside become spaces, and it's cut off where the input would pass `MAX_INPUT_LENGTH`.
+pub(crate) fn paste_text(raw: &str, current_length: usize) -> String {
+    let room = MAX_INPUT_LENGTH.saturating_sub(current_length);
+    raw.trim_matches(['\r', '\n'])
+        .lines()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(room)
+        .collect()
+}
+
+/// `paths` as absolute paths, one per line, for copying.
+pub(crate) fn path_text(paths: &[PathBuf]) -> String {
+    paths.iter()
+        .map(|path| std::path::absolute(path).unwrap_or_else(|_| path.clone()))
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use gfx::{definitions::UiAtlas, gui::interface::{Coordinate, Element, Panel}};
+
+    use super::*;
+
+    /// Holds the text last set. `fail_with` is returned by the next call instead.
+    #[derive(Default)]
+    struct MockClipboard {
+        text: Option<String>,
+        fail_with: Option<ClipboardError>,
+    }
+
+    impl MockClipboard {
+        fn holding(text: &str) -> Self {
+            Self { text: Some(text.to_string()), fail_with: None }
+        }
+
+        fn failing(error: ClipboardError) -> Self {
+            Self { text: None, fail_with: Some(error) }
+        }
+    }
+
+    impl Clipboard for MockClipboard {
+        fn get_text(&mut self) -> Result<String, ClipboardError> {
+            if let Some(error) = self.fail_with.take() {
+                return Err(error);
+            }
+            self.text.clone().ok_or(ClipboardError::Empty)
+        }
+
+        fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+            if let Some(error) = self.fail_with.take() {
+                return Err(error);
+            }
+            self.text = Some(text.to_string());
+            Ok(())
+        }
+    }
+
+    /// One text input holding `text`, focused or not.
+    fn input_tree(text: &str, focused: bool) -> UiTree<GuiEvent> {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0));
+        panel.add_element(Element::text_input(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.1)));
+        interface.add_panel(panel);
+        interface.focus(Some((0, 0)));
+        interface.insert_text(text);
+        if !focused {
+            interface.focus(None);
+        }
+        interface
+    }
+
+    fn input_text(interface: &mut UiTree<GuiEvent>) -> String {
+        interface.focus(Some((0, 0)));
+        interface.focused_text().unwrap().to_string()
+    }
+
+    #[test]
+    fn pasted_lines_are_trimmed_and_joined() {
+        assert_eq!(paste_text("\r\nfirst\nsecond\r\nthird\n\n", 0), "first second third");
+        assert_eq!(paste_text("one line", 0), "one line");
+        assert_eq!(paste_text("\n\n", 0), "");
+    }
+
+    #[test]
+    fn pastes_are_cut_off_at_the_max_length() {
+        let pasted = paste_text(&"é".repeat(MAX_INPUT_LENGTH), 10);
+        assert_eq!(pasted.chars().count(), MAX_INPUT_LENGTH - 10);
+
+        let mut interface = input_tree("name", true);
+        let mut clipboard = MockClipboard::holding(&"x".repeat(MAX_INPUT_LENGTH));
+        assert!(edit_focused_input(&mut clipboard, &mut interface, InputEdit::Paste).unwrap());
+        assert_eq!(input_text(&mut interface).chars().count(), MAX_INPUT_LENGTH);
+    }
+
+    #[test]
+    fn a_full_input_takes_no_more() {
+        assert_eq!(paste_text("more", MAX_INPUT_LENGTH), "");
+        assert_eq!(paste_text("more", MAX_INPUT_LENGTH + 5), "");
+
+        let full = "x".repeat(MAX_INPUT_LENGTH);
+        let mut interface = input_tree(&full, true);
+        let mut clipboard = MockClipboard::holding("more");
+        assert!(!edit_focused_input(&mut clipboard, &mut interface, InputEdit::Paste).unwrap());
+        assert_eq!(input_text(&mut interface), full);
+    }
+
+    #[test]
+    fn copy_and_cut_put_the_text_on_the_clipboard() {
+        let mut interface = input_tree("name", true);
+        let mut clipboard = MockClipboard::default();
+        assert!(!edit_focused_input(&mut clipboard, &mut interface, InputEdit::Copy).unwrap());
+        assert_eq!(clipboard.text.as_deref(), Some("name"));
+        assert_eq!(input_text(&mut interface), "name");
+
+        let mut clipboard = MockClipboard::default();
+        assert!(edit_focused_input(&mut clipboard, &mut interface, InputEdit::Cut).unwrap());
+        assert_eq!(clipboard.text.as_deref(), Some("name"));
+        assert_eq!(input_text(&mut interface), "");
+    }
+
+    #[test]
+    fn a_cut_that_cannot_copy_keeps_the_text() {
+        let mut interface = input_tree("name", true);
+        let mut clipboard = MockClipboard::failing(ClipboardError::Failed("busy".to_string()));
+        let result = edit_focused_input(&mut clipboard, &mut interface, InputEdit::Cut);
+        assert!(matches!(result, Err(ClipboardError::Failed(_))));
+        assert_eq!(input_text(&mut interface), "name");
+    }
+
+    #[test]
+    fn empty_and_unavailable_clipboards_are_errors() {
+        let mut interface = input_tree("name", true);
+        let result = edit_focused_input(&mut MockClipboard::default(), &mut interface, InputEdit::Paste);
+        assert!(matches!(result, Err(ClipboardError::Empty)));
+
+        let mut clipboard = MockClipboard::failing(ClipboardError::Unavailable("no display".to_string()));
+        let result = edit_focused_input(&mut clipboard, &mut interface, InputEdit::Paste);
+        assert!(matches!(result, Err(ClipboardError::Unavailable(_))));
+        assert_eq!(input_text(&mut interface), "name");
+    }
+
+    #[test]
+    fn copy_and_cut_without_a_focused_input_do_nothing() {
+        let mut interface = input_tree("name", false);
+        let mut clipboard = MockClipboard::holding("before");
+        for edit in [InputEdit::Copy, InputEdit::Cut] {
+            assert!(!edit_focused_input(&mut clipboard, &mut interface, edit).unwrap());
+        }
+        assert_eq!(clipboard.text.as_deref(), Some("before"));
+        assert_eq!(input_text(&mut interface), "name");
+    }
+}
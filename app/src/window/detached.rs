@@ -0,0 +1,239 @@
+use std::{sync::Arc, time::Instant};
+
+use gfx::{definitions::InteractionStyle, gui::{interface::UiTree, scroll::ScrollEvent}, GpuContext, RenderOptions, RenderState};
+use winit::{dpi::PhysicalPosition, event::{Ime, MouseButton, WindowEvent}, event_loop::ActiveEventLoop, keyboard::{Key, ModifiersState, NamedKey, PhysicalKey}, window::{CursorIcon, Window, WindowAttributes, WindowId}};
+
+use crate::window::{clipboard::InputEdit, event::GuiEvent, shortcuts::Shortcuts};
+
+/// A page open in a window of its own next to the main one, see `GuiEvent::DetachPage`. It draws with the
+/// main window's device and atlas through its own surface, and its interface is rebuilt along with the main one.
+pub(crate) struct WindowContext {
+    pub(crate) window: Arc<Window>,
+    pub(crate) render_state: RenderState,
+    pub(crate) interface: UiTree<GuiEvent>,
+    /// Id of the page shown, one of `pages`.
+    pub(crate) page: String,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    /// Last icon given to `Window::set_cursor`.
+    cursor_icon: CursorIcon,
+}
+
+/// What an event routed to a `WindowContext` leaves for the application to handle.
+#[derive(Default)]
+pub(crate) struct WindowInput {
+    /// Sent by the window's elements or shortcuts, handled like the main window's.
+    pub(crate) events: Vec<GuiEvent>,
+    /// Offset of the panel the wheel scrolled.
+    pub(crate) scrolled: Option<f32>,
+    /// The window changed size or scale factor, pages sized in native pixels need building again.
+    pub(crate) resized: bool,
+    /// Typing changed the text of the focused input.
+    pub(crate) text_changed: bool,
+    /// Input Enter was pressed in, which took focus off it.
+    pub(crate) submitted: Option<(usize, usize)>,
+    /// Input Escape was pressed in, which took focus off it.
+    pub(crate) dismissed: Option<(usize, usize)>,
+    /// Clipboard shortcut pressed in the focused input, for the application to apply with its clipboard.
+    pub(crate) edit: Option<InputEdit>,
+}
+
+impl WindowContext {
+    /// Opens a window showing `page` on the device and atlas of `gpu`. Its interface is empty until the
+    /// application builds the page into it with `replace_interface`.
+    pub(crate) fn open(
+        event_loop: &ActiveEventLoop,
+        attributes: WindowAttributes,
+        gpu: Arc<GpuContext>,
+        page: &str,
+        mut interface: UiTree<GuiEvent>,
+        options: &RenderOptions,
+    ) -> anyhow::Result<WindowContext> {
+        let window = Arc::new(event_loop.create_window(attributes)?);
+        let render_state = RenderState::new_window(gpu, window.clone(), &mut interface, options)?;
+        Ok(Self {
+            window,
+            render_state,
+            interface,
+            page: page.to_string(),
+            cursor_position: None,
+            cursor_icon: CursorIcon::Default,
+        })
+    }
+
+    pub(crate) fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// Swaps in a newly built tree for the page, keeping what the user was doing in the old one, and uploads it.
+    pub(crate) fn replace_interface(&mut self, mut interface: UiTree<GuiEvent>) {
+        interface.inherit_floating_windows(&mut self.interface);
+        interface.inherit_groups(&mut self.interface);
+        interface.inherit_clicks(&mut self.interface);
+        interface.inherit_focus(&mut self.interface);
+        interface.inherit_selection(&mut self.interface);
+        interface.inherit_toasts(&mut self.interface);
+        interface.set_scale_factor(self.render_state.scale_factor() as f32);
+        self.interface = interface;
+        self.render_state.update_interface(&mut self.interface);
+    }
+
+    /// Moves the interface's animations and spinners on, see `EditorApp::about_to_wait`.
+    pub(crate) fn tick(&mut self, now: Instant) {
+        if self.interface.tick(now) {
+            self.render_state.update_interface(&mut self.interface);
+        }
+        let turned = self.interface.step_spinners(now);
+        if !turned.iter().all(|&index| self.render_state.update_interface_element(&self.interface, index)) {
+            self.render_state.update_interface(&mut self.interface);
+        }
+    }
+
+    pub(crate) fn next_wakeup(&self) -> Option<Instant> {
+        self.interface.next_wakeup()
+    }
+
+    /// Asks the window for a frame if its renderer or interface was invalidated since the last one.
+    pub(crate) fn request_pending_redraw(&self) {
+        if self.render_state.take_redraw_request(&self.interface) {
+            self.window.request_redraw();
+        }
+    }
+
+    /// Routes `event` to the window's interface. Keys pressed while no input has focus are looked up in
+    /// `shortcuts`, after Escape has closed any open menu. In a focused input only the clipboard's are. There's no viewport here, input nothing in the
+    /// interface takes goes nowhere.
+    pub(crate) fn handle_event(&mut self, event: &WindowEvent, shortcuts: &Shortcuts, modifiers: ModifiersState) -> WindowInput {
+        let mut input = WindowInput::default();
+        let screen_size = self.window.inner_size();
+        let interface = &mut self.interface;
+        let rs = &mut self.render_state;
+
+        match event {
+            WindowEvent::Resized(size) => {
+                rs.resize(size.width, size.height, interface);
+                input.resized = true;
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                rs.set_scale_factor(*scale_factor, interface);
+                input.resized = true;
+            }
+            WindowEvent::RedrawRequested => {
+                if let Err(e) = rs.render(interface) {
+                    log::error!("Unable to render {}", e);
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let previous = self.cursor_position.replace(*position);
+                if interface.captured_element().is_some() {
+                    if interface.handle_captured_move(*position, screen_size) {
+                        rs.update_interface(interface);
+                    }
+                } else {
+                    if let Some(previous) = previous
+                        && interface.pressed_element().is_some() {
+                        let delta = ((position.x - previous.x) as f32, (position.y - previous.y) as f32);
+                        interface.handle_interaction(*position, screen_size, InteractionStyle::OnDrag { delta });
+                    }
+                    let hovered = interface.handle_interaction(*position, screen_size, InteractionStyle::OnHover);
+                    let updated = hovered.hover_changed.iter()
+                        .all(|&index| rs.update_interface_element(interface, index));
+                    if !updated | interface.track_tooltip(*position, Instant::now()) {
+                        rs.update_interface(interface);
+                    }
+                }
+            }
+            WindowEvent::CursorLeft { .. } if interface.hide_tooltip() => {
+                rs.update_interface(interface);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                if let Some(position) = self.cursor_position {
+                    let scroll = ScrollEvent::from_wheel(*delta, position, rs.scale_factor(), modifiers.shift_key());
+                    let result = interface.handle_scroll(scroll.position, screen_size, scroll.delta_lines);
+                    if let Some((_, offset)) = result.scrolled {
+                        input.scrolled = Some(offset);
+                        rs.update_interface(interface);
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                let interaction = if state.is_pressed() {
+                    InteractionStyle::OnPress
+                } else {
+                    interface.end_capture();
+                    InteractionStyle::OnRelease
+                };
+                if let Some(position) = self.cursor_position {
+                    interface.set_modifiers(modifiers);
+                    interface.handle_interaction(position, screen_size, interaction);
+                    self.window.set_ime_allowed(interface.focused_element().is_some());
+                    rs.update_interface(interface);
+                }
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Right, .. } if state.is_pressed() => {
+                if let Some(position) = self.cursor_position {
+                    interface.handle_interaction(position, screen_size, InteractionStyle::OnRightClick);
+                    rs.update_interface(interface);
+                }
+            }
+            WindowEvent::Focused(false) => {
+                interface.end_capture();
+                interface.cancel_press();
+            }
+            WindowEvent::KeyboardInput { event: key_event, .. } if key_event.state.is_pressed()
+                && key_event.logical_key == Key::Named(NamedKey::Escape)
+                && interface.is_dropdown_open() => {
+                interface.close_dropdown();
+                rs.update_interface(interface);
+            }
+            WindowEvent::KeyboardInput { event: key_event, .. } if key_event.state.is_pressed() => {
+                if interface.focused_element().is_some() {
+                    input.edit = shortcuts.input_edit_for(modifiers, key_event.physical_key);
+                    let (changed, edited) = match &key_event.logical_key {
+                        Key::Named(NamedKey::Escape) => {
+                            input.dismissed = interface.focused_element();
+                            (interface.focus(None), false)
+                        }
+                        Key::Named(NamedKey::Enter) => {
+                            input.submitted = interface.focused_element();
+                            (interface.focus(None), false)
+                        }
+                        Key::Named(NamedKey::Tab) => (interface.focus_next(modifiers.shift_key()), false),
+                        Key::Named(NamedKey::Backspace) => {
+                            let edited = interface.delete_back();
+                            (edited, edited)
+                        }
+                        _ if input.edit.is_some() => (false, false),
+                        _ => {
+                            let edited = key_event.text.as_deref().is_some_and(|text| interface.insert_text(text));
+                            (edited, edited)
+                        }
+                    };
+                    input.text_changed = edited;
+                    self.window.set_ime_allowed(interface.focused_element().is_some());
+                    if changed {
+                        rs.update_interface(interface);
+                    }
+                } else if !key_event.repeat
+                    && let PhysicalKey::Code(key) = key_event.physical_key
+                    && let Some(event) = shortcuts.event_for(modifiers, key) {
+                    input.events.push(event);
+                }
+            }
+            WindowEvent::Ime(Ime::Commit(text)) if interface.insert_text(text) => {
+                input.text_changed = true;
+                rs.update_interface(interface);
+            }
+            _ => {}
+        }
+
+        // Element callbacks queue their events on the interface, taken once the input has been routed
+        input.events.extend(interface.drain_events());
+
+        let cursor_icon = interface.cursor_icon();
+        if cursor_icon != self.cursor_icon {
+            self.window.set_cursor(cursor_icon);
+            self.cursor_icon = cursor_icon;
+        }
+        input
+    }
+}
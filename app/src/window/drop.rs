@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use crate::import;
+
+/// Files dragged over or dropped on the main window. winit reports each file of a drag as its own event, so they're
+/// collected here and handled together once the event loop has delivered the whole batch.
+#[derive(Debug, Default)]
+pub(crate) struct FileDrop {
+    hovered: Vec<PathBuf>,
+    dropped: Vec<PathBuf>,
+}
+
+impl FileDrop {
+    /// Adds a file to the drag over the window.
+    pub(crate) fn hover(&mut self, path: PathBuf) {
+        self.hovered.push(path);
+    }
+
+    /// Forgets the drag once it leaves the window. Returns whether there was one.
+    pub(crate) fn cancel_hover(&mut self) -> bool {
+        let hovering = self.is_hovering();
+        self.hovered.clear();
+        hovering
+    }
+
+    /// Queues a dropped file for `take_dropped`. The drag it was part of is over.
+    pub(crate) fn drop_file(&mut self, path: PathBuf) {
+        self.hovered.clear();
+        self.dropped.push(path);
+    }
+
+    pub(crate) fn is_hovering(&self) -> bool {
+        !self.hovered.is_empty()
+    }
+
+    pub(crate) fn hovered(&self) -> &[PathBuf] {
+        &self.hovered
+    }
+
+    /// The files dropped since the last call, in the order winit reported them.
+    pub(crate) fn take_dropped(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+/// What dropping a set of files on the window does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DropAction {
+    /// Opens the first folder as a project. Only one project is open at a time, `ignored` counts the other paths.
+    OpenProject { path: PathBuf, ignored: usize },
+    /// Copies the images into the open project's asset folder. `unsupported` counts the files that aren't images.
+    Import { images: Vec<PathBuf>, unsupported: usize },
+}
+
+impl DropAction {
+    /// A folder among `paths` takes precedence over files, `None` when there are no paths.
+    pub(crate) fn for_paths(paths: &[PathBuf]) -> Option<Self> {
+        if paths.is_empty() {
+            return None;
+        }
+        if let Some(folder) = paths.iter().find(|path| path.is_dir()) {
+            return Some(DropAction::OpenProject { path: folder.clone(), ignored: paths.len() - 1 });
+        }
+        let (images, other): (Vec<&PathBuf>, Vec<&PathBuf>) = paths.iter().partition(|path| import::is_image(path));
+        Some(DropAction::Import { images: images.into_iter().cloned().collect(), unsupported: other.len() })
+    }
+}
+
+/// Name of `path` as results list it, without the folders it's in.
+pub(crate) fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// `count` followed by `noun`, with an "s" unless there's one, e.g. "3 assets".
+pub(crate) fn count_of(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+
+use crate::{crash, level::TileId, theme::PalettePreset, window::explorer::ExplorerSort};
+
+/// Events returned by interface callbacks and handled in `EditorApp::window_event`.
+#[derive(PartialEq, Debug, Clone)]
+pub enum GuiEvent {
+    /// Switches to the page registered under this id, see `PageRegistry`.
+    Navigate(String),
+    /// Opens the page registered under this id in a window of its own, or brings that window forward.
+    DetachPage(String),
+    /// Opens the settings menu, remembering the config so `CancelSettings` can put it back.
+    DisplaySettingsMenu,
+    /// Changes the font scale by this much, see `EditorConfig::set_font_scale`.
+    AdjustFontScale(f32),
+    ToggleVsync,
+    CyclePalettePreset,
+    /// Switches the interface between the dark and light themes.
+    ToggleTheme,
+    /// Takes what was typed into the settings menu's inputs, sent by Enter in one of them.
+    ApplySettingsInput,
+    /// Writes the settings to the config file and closes the menu.
+    SaveSettings,
+    /// Puts the config back the way it was when the settings menu opened and closes it.
+    CancelSettings,
+    /// Closes the editor like closing the window does, asking first if there are unsaved changes.
+    RequestExit,
+    /// Closes the editor without asking, from the exit confirmation.
+    ConfirmExit,
+    OpenBackupLocation,
+    DismissStartupBanner,
+    ToggleBackupFiles,
+    SelectPalettePreset(PalettePreset),
+    ToggleReduceMotion,
+    /// A click changed which explorer rows are selected, read back from the interface showing them.
+    SelectExplorerEntry,
+    /// Opens the explorer's selection, sent by Enter. Only one project is open at a time, so it has to be one entry.
+    OpenSelection,
+    OpenProject(PathBuf),
+    /// Takes the project at this path off the project view's recent projects, leaving its files alone.
+    RemoveRecentProject(PathBuf),
+    /// Opens the new project dialog.
+    NewProject,
+    /// Creates a project named after the new project dialog's name input.
+    CreateProject,
+    /// Asks before deleting the explorer's selection, or only this entry when it isn't part of the selection.
+    RequestDelete(PathBuf),
+    /// Asks before deleting the explorer's selection, sent by Delete.
+    RequestDeleteSelection,
+    ConfirmDeleteSelection,
+    /// Shows an input over the explorer row of this entry to type a new name into.
+    RenameEntry(PathBuf),
+    /// Renames the entry to what was typed, sent by Enter in the rename input.
+    CommitRename,
+    /// Puts the row back without renaming, sent by Escape in the rename input.
+    CancelRename,
+    MoveSelectionTo(PathBuf),
+    CancelDialog,
+    /// Closes the topmost menu or dialog the way its cancel button would, sent by Escape.
+    CloseMenu,
+    /// Switches the window between fullscreen and windowed, sent by F11.
+    ToggleFullscreen,
+    /// Shows or hides the frame timing overlay, sent by F3.
+    ToggleDebugOverlay,
+    RefreshExplorer,
+    SetExplorerSort(ExplorerSort),
+    /// Lists plain files and dotfiles in the explorer too, or goes back to only directories.
+    ToggleExplorerShowAll,
+    OpenCrashReportLocation,
+    DismissCrashReport,
+    ReloadAssets,
+    /// Builds the render pipelines again from the shader files on disk, sent by Ctrl+Shift+R in debug builds.
+    ReloadShaders,
+    /// Logs the main window's `RenderDiagnostics` and shows their summary in a toast.
+    DumpDiagnostics,
+    /// Makes this the tile left-clicking in the preview paints.
+    SelectTile(TileId),
+    /// Writes the level to the open project, sent by Save and Ctrl+S.
+    SaveLevel,
+    /// Reverts the last level edit, sent by Undo and Ctrl+Z.
+    Undo,
+    /// Applies the last undone level edit again, sent by Ctrl+Shift+Z and Ctrl+Y.
+    Redo,
+    /// Replaces the level with this backup of it as one edit that can be undone, sent by File > Revert To Backup.
+    /// The level file keeps its contents until the next save.
+    RevertToBackup(PathBuf),
+    /// Shows or hides the History panel, which lists the level's snapshots.
+    ToggleHistory,
+    /// Shows the snapshot stored at this path read-only in the preview, in place of the level.
+    PreviewSnapshot(PathBuf),
+    /// Replaces the level with the snapshot being previewed as one edit that can be undone, like `RevertToBackup`.
+    RestoreSnapshot,
+    /// Shows the level again instead of the snapshot being previewed.
+    ClosePreview,
+    /// Copies the absolute paths of the explorer's selection, sent by Ctrl+C. In a focused text input the shortcut
+    /// copies its text instead, see `InputEdit`.
+    Copy,
+    /// Sent by Ctrl+X, only does anything in a focused text input, where it cuts its text.
+    Cut,
+    /// Sent by Ctrl+V, only does anything in a focused text input, where it pastes at the end.
+    Paste,
+    /// Copies the absolute path of this explorer entry, from its context menu.
+    CopyPath(PathBuf),
+}
+
+/// Sent to the event loop from background threads, handled in `EditorApp::user_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserEvent {
+    /// An explorer scan finished, its listing is waiting for `ExplorerState::poll_scan`.
+    ExplorerScanned,
+    /// Packing the assets moved on or finished, see `AssetReload::poll`.
+    AssetsReloading,
+}
+
+impl GuiEvent {
+    /// How the event is listed in crash reports, with paths outside `project_dir` redacted.
+    pub fn breadcrumb(&self, project_dir: &Path) -> String {
+        match self {
+            GuiEvent::OpenProject(path) => format!("OpenProject({})", crash::redact_path(path, project_dir)),
+            GuiEvent::RemoveRecentProject(path) => format!("RemoveRecentProject({})", crash::redact_path(path, project_dir)),
+            GuiEvent::MoveSelectionTo(path) => format!("MoveSelectionTo({})", crash::redact_path(path, project_dir)),
+            GuiEvent::RequestDelete(path) => format!("RequestDelete({})", crash::redact_path(path, project_dir)),
+            GuiEvent::RenameEntry(path) => format!("RenameEntry({})", crash::redact_path(path, project_dir)),
+            GuiEvent::CopyPath(path) => format!("CopyPath({})", crash::redact_path(path, project_dir)),
+            GuiEvent::RevertToBackup(path) => format!("RevertToBackup({})", crash::redact_path(path, project_dir)),
+            GuiEvent::PreviewSnapshot(path) => format!("PreviewSnapshot({})", crash::redact_path(path, project_dir)),
+            other => format!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::{cmp::Reverse, collections::HashSet, fs, io, path::{Path, PathBuf}, sync::mpsc::{self, Receiver, TryRecvError}, thread, time::SystemTime};
+
+use crate::{git::{GitError, GitStatus}, project::Project, storage};
+
+/// Order of the explorer's rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExplorerSort {
+    /// By name, ignoring ASCII case.
+    #[default]
+    NameAscending,
+    NameDescending,
+    /// Most recently modified first, by name between entries modified at the same time.
+    Modified,
+}
+
+/// Rows listed by the file explorer and which of them are selected.
+#[derive(Default)]
+pub(crate) struct ExplorerState {
+    directory: PathBuf,
+    /// Everything the last scan listed, which `entries` is filtered and sorted from.
+    listing: Vec<ListedEntry>,
+    /// The rows shown, see `update_entries`.
+    entries: Vec<PathBuf>,
+    /// Entries that are directories with a project manifest.
+    projects: HashSet<PathBuf>,
+    selected: Vec<PathBuf>,
+    scroll_offset: f32,
+    /// As typed, matched trimmed and ignoring case.
+    filter: String,
+    sort: ExplorerSort,
+    /// Lists plain files and dotfiles too, which are hidden by default.
+    show_all: bool,
+    git: Option<GitStatus>,
+    git_stale: bool,
+    git_error_logged: bool,
+    scan: Option<Receiver<Scan>>,
+    /// Why the last scan couldn't list the directory, shown in place of the entries.
+    error: Option<String>,
+    /// The running scan is re-reading git status, so a refresh that replaces it must too.
+    scan_reloads_git: bool,
+    /// Entry being renamed and the name typed for it so far, see `start_rename`.
+    renaming: Option<(PathBuf, String)>,
+}
+
+/// What a background `refresh` read, `git` is only set when the status was due for a reload.
+struct Scan {
+    entries: io::Result<Vec<ListedEntry>>,
+    projects: HashSet<PathBuf>,
+    git: Option<Result<Option<GitStatus>, GitError>>,
+}
+
+/// An entry as a scan found it, with what filtering and sorting need so they don't touch the disk.
+struct ListedEntry {
+    path: PathBuf,
+    is_dir: bool,
+    modified: Option<SystemTime>,
+}
+
+impl ExplorerState {
+    /// Starts re-reading `directory` on a background thread, creating it if it's missing. The thread calls `notify`
+    /// once the listing is ready for `poll_scan` to swap in. Switching to another directory clears the selection
+    /// straight away. Git status is only re-read when the directory changes or after `invalidate_git_status`.
+    /// A scan still running from an earlier call is abandoned.
+    pub(crate) fn refresh(&mut self, directory: &Path, show_backups: bool, notify: impl FnOnce() + Send + 'static) {
+        if self.directory != directory {
+            self.directory = directory.to_path_buf();
+            self.clear_selection();
+            self.scroll_offset = 0.0;
+            self.git_stale = true;
+        }
+        let reload_git = std::mem::take(&mut self.git_stale) || (self.scan.is_some() && self.scan_reloads_git);
+        self.scan_reloads_git = reload_git;
+
+        let (sender, receiver) = mpsc::channel();
+        let directory = directory.to_path_buf();
+        thread::spawn(move || {
+            let entries = list_entries(&directory, show_backups);
+            let projects = entries.iter().flatten()
+                .filter(|entry| entry.is_dir && Project::is_project(&entry.path))
+                .map(|entry| entry.path.clone())
+                .collect();
+            let git = reload_git.then(|| GitStatus::read(&directory));
+            // The receiver is gone when a newer refresh replaced this scan
+            if sender.send(Scan { entries, projects, git }).is_ok() {
+                notify();
+            }
+        });
+        self.scan = Some(receiver);
+    }
+
+    /// Applies a finished scan. Returns true when the listing changed and the page needs rebuilding.
+    pub(crate) fn poll_scan(&mut self) -> bool {
+        let Some(receiver) = &self.scan else {
+            return false;
+        };
+        let scan = match receiver.try_recv() {
+            Ok(scan) => scan,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => {
+                log::error!("Listing {:?} stopped without a result", self.directory);
+                self.error = Some(format!("Listing {} stopped without a result", self.directory.display()));
+                self.listing.clear();
+                self.scan = None;
+                self.update_entries();
+                return true;
+            }
+        };
+        self.scan = None;
+
+        if let Some(git) = scan.git {
+            self.apply_git_status(git);
+        }
+        match scan.entries {
+            Ok(listing) => {
+                self.listing = listing;
+                self.error = None;
+            }
+            Err(e) => {
+                log::error!("Unable to list {:?}: {}", self.directory, e);
+                self.listing.clear();
+                self.error = Some(format!("Unable to read {}: {}", self.directory.display(), e));
+            }
+        }
+        self.projects = scan.projects;
+        self.update_entries();
+        true
+    }
+
+    /// Filters and sorts `listing` into `entries`, dropping selected rows that are no longer shown.
+    fn update_entries(&mut self) {
+        let filter = self.filter.trim().to_lowercase();
+        let mut shown: Vec<&ListedEntry> = self.listing.iter()
+            .filter(|entry| {
+                let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+                (self.show_all || (entry.is_dir && !name.starts_with('.')))
+                    && (filter.is_empty() || name.to_lowercase().contains(&filter))
+            })
+            .collect();
+        // Stable sorts, so entries that compare equal keep the order they were listed in
+        shown.sort_by_cached_key(|entry| sort_name(&entry.path));
+        match self.sort {
+            ExplorerSort::NameAscending => {}
+            ExplorerSort::NameDescending => shown.reverse(),
+            ExplorerSort::Modified => shown.sort_by_key(|entry| Reverse(entry.modified)),
+        }
+        self.entries = shown.into_iter().map(|entry| entry.path.clone()).collect();
+
+        self.selected.retain(|path| self.entries.contains(path));
+        if self.renaming.as_ref().is_some_and(|(path, _)| !self.entries.contains(path)) {
+            self.renaming = None;
+        }
+    }
+
+    /// Why the directory couldn't be listed by the last scan, `None` when it could.
+    pub(crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Whether a `refresh` is still reading the directory.
+    pub(crate) fn is_scanning(&self) -> bool {
+        self.scan.is_some()
+    }
+
+    /// Runs `git status` again on the next `refresh`.
+    pub(crate) fn invalidate_git_status(&mut self) {
+        self.git_stale = true;
+    }
+
+    /// `None` outside a git repository.
+    pub(crate) fn git_status(&self) -> Option<&GitStatus> {
+        self.git.as_ref()
+    }
+
+    fn apply_git_status(&mut self, result: Result<Option<GitStatus>, GitError>) {
+        self.git = match result {
+            Ok(status) => status,
+            Err(e) => {
+                // Usually means git isn't installed, which won't change until restart
+                if !self.git_error_logged {
+                    log::error!("Unable to read git status for {:?}: {}", self.directory, e);
+                    self.git_error_logged = true;
+                }
+                None
+            }
+        };
+    }
+
+    pub(crate) fn entries(&self) -> &[PathBuf] {
+        &self.entries
+    }
+
+    /// Whether the entry at `path` had a project manifest when it was listed.
+    pub(crate) fn is_project(&self, path: &Path) -> bool {
+        self.projects.contains(path)
+    }
+
+    /// How far the list is scrolled, kept here so rebuilding the page doesn't jump back to the top.
+    pub(crate) fn scroll_offset(&self) -> f32 {
+        self.scroll_offset
+    }
+
+    pub(crate) fn set_scroll_offset(&mut self, offset: f32) {
+        self.scroll_offset = offset;
+    }
+
+    pub(crate) fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Only lists entries whose name contains `filter`, ignoring case and surrounding whitespace.
+    /// Applies to the current listing straight away.
+    pub(crate) fn set_filter(&mut self, filter: &str) {
+        if self.filter != filter {
+            self.filter = filter.to_string();
+            self.update_entries();
+        }
+    }
+
+    pub(crate) fn sort(&self) -> ExplorerSort {
+        self.sort
+    }
+
+    pub(crate) fn set_sort(&mut self, sort: ExplorerSort) {
+        if self.sort != sort {
+            self.sort = sort;
+            self.update_entries();
+        }
+    }
+
+    /// Whether plain files and dotfiles are listed, only directories are by default.
+    pub(crate) fn shows_all(&self) -> bool {
+        self.show_all
+    }
+
+    pub(crate) fn set_show_all(&mut self, show_all: bool) {
+        if self.show_all != show_all {
+            self.show_all = show_all;
+            self.update_entries();
+        }
+    }
+
+    pub(crate) fn selection(&self) -> &[PathBuf] {
+        &self.selected
+    }
+
+    pub(crate) fn is_selected(&self, path: &Path) -> bool {
+        self.selected.iter().any(|selected| selected == path)
+    }
+
+    pub(crate) fn clear_selection(&mut self) {
+        self.selected.clear();
+    }
+
+    /// Selects `path` alone, e.g. before acting on a row that was right-clicked outside the selection.
+    pub(crate) fn select_only(&mut self, path: &Path) {
+        self.selected = vec![path.to_path_buf()];
+    }
+
+    /// Replaces the selection with the entries of `paths` that are listed, in listing order. The explorer's rows
+    /// work out clicks themselves, see `UiTree::selection`, this keeps what they settled on.
+    pub(crate) fn set_selection(&mut self, paths: &[PathBuf]) {
+        self.selected = self.entries.iter().filter(|entry| paths.contains(entry)).cloned().collect();
+    }
+
+    /// Directories a selection can be moved into.
+    pub(crate) fn move_targets(&self) -> Vec<&PathBuf> {
+        self.entries.iter()
+            .filter(|path| path.is_dir() && !self.is_selected(path))
+            .collect()
+    }
+
+    /// Deletes every selected entry, returning the ones that failed.
+    pub(crate) fn delete_selection(&mut self) -> Vec<(PathBuf, io::Error)> {
+        let mut failed = Vec::new();
+        for path in std::mem::take(&mut self.selected) {
+            let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+            if let Err(e) = result {
+                failed.push((path, e));
+            }
+        }
+        failed
+    }
+
+    /// Shows an input over `path`'s row holding its current name, replacing any rename in progress.
+    pub(crate) fn start_rename(&mut self, path: &Path) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        self.renaming = Some((path.to_path_buf(), name));
+    }
+
+    /// Entry being renamed and the name typed for it.
+    pub(crate) fn renaming(&self) -> Option<(&Path, &str)> {
+        self.renaming.as_ref().map(|(path, name)| (path.as_path(), name.as_str()))
+    }
+
+    /// Keeps what's been typed into the rename input, so rebuilding the page doesn't lose it.
+    pub(crate) fn set_rename_text(&mut self, name: &str) {
+        if let Some((_, typed)) = self.renaming.as_mut() {
+            *typed = name.to_string();
+        }
+    }
+
+    pub(crate) fn cancel_rename(&mut self) {
+        self.renaming = None;
+    }
+
+    /// Renames the entry being renamed to the name typed for it, within the same directory. Returns the new path,
+    /// or `None` when nothing was being renamed. The selection follows the entry to its new name.
+    pub(crate) fn finish_rename(&mut self) -> Option<io::Result<PathBuf>> {
+        let (path, name) = self.renaming.take()?;
+        let name = name.trim();
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Some(Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} isn't a valid name", name))));
+        }
+        let destination = path.with_file_name(name);
+        if destination == path {
+            return Some(Ok(path));
+        }
+        if destination.exists() {
+            return Some(Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", destination.display()))));
+        }
+        if let Err(e) = fs::rename(&path, &destination) {
+            return Some(Err(e));
+        }
+        for selected in &mut self.selected {
+            if *selected == path {
+                *selected = destination.clone();
+            }
+        }
+        Some(Ok(destination))
+    }
+
+    /// Moves every selected entry into `target`, returning the ones that failed.
+    pub(crate) fn move_selection_to(&mut self, target: &Path) -> Vec<(PathBuf, io::Error)> {
+        let mut failed = Vec::new();
+        for path in std::mem::take(&mut self.selected) {
+            let Some(file_name) = path.file_name() else {
+                continue;
+            };
+            let destination = target.join(file_name);
+            if destination.exists() {
+                failed.push((path, io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", destination.display()))));
+            } else if let Err(e) = fs::rename(&path, &destination) {
+                failed.push((path, e));
+            }
+        }
+        failed
+    }
+}
+
+/// `directory`'s entries in no particular order, without backups unless `show_backups`.
+/// A missing directory is created empty, the first run has no projects folder yet.
+fn list_entries(directory: &Path, show_backups: bool) -> io::Result<Vec<ListedEntry>> {
+    fs::create_dir_all(directory)?;
+    Ok(fs::read_dir(directory)?
+        .filter_map(Result::ok)
+        .filter(|entry| show_backups || !storage::is_backup(&entry.path()))
+        .map(|entry| {
+            let metadata = entry.metadata().ok();
+            ListedEntry {
+                path: entry.path(),
+                // Followed through symlinks, a linked project folder is still a folder
+                is_dir: entry.path().is_dir(),
+                modified: metadata.and_then(|metadata| metadata.modified().ok()),
+            }
+        })
+        .collect())
+}
+
+/// What rows are sorted by name on: the file name's bytes with ASCII letters lowercased, the same in every locale.
+fn sort_name(path: &Path) -> Vec<u8> {
+    path.file_name().unwrap_or_default().as_encoded_bytes().to_ascii_lowercase()
+}
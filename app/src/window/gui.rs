@@ -1,310 +1,3087 @@
-use std::{fs, io, sync::{Arc, Mutex}};
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Arc, time::{Duration, Instant, SystemTime}};
 
-use gfx::{definitions::{GuiEvent, GuiMenuState, GuiPageState, InteractionStyle}, gui::interface::{Alignment, Coordinate, Element, HorizontalAlignment, Interface, Panel, VerticalAlignment}, RenderState};
-use winit::{application::ApplicationHandler, dpi::PhysicalPosition, event::{MouseButton, WindowEvent}, event_loop::{ActiveEventLoop, EventLoop}, window::Window};
+use gfx::{definitions::{GuiMenuState, InteractionStyle, TileInstance, UiAtlas}, gui::{animation::{AnimProperty, Easing}, floating::TITLE_BAR_HEIGHT, layout_file::LayoutFile, scroll::ScrollEvent, theme::Theme as UiTheme, interface::{Alignment, ColorRole, Coordinate, Dimension, Element, FloatingWindow, FontWeight, Fonts, HorizontalAlignment, Layout, MenuItem, Panel, ScrollDirection, SplitDirection, Style, TextStyle, ToastLevel, UiTree, VerticalAlignment, Wrap}}, RenderOptions, RenderState, PREVIEW_TARGET};
+use glam::{IVec2, Vec2};
+use image::RgbaImage;
+use winit::{application::ApplicationHandler, dpi::{PhysicalPosition, PhysicalSize}, event::{Ime, MouseButton, MouseScrollDelta, WindowEvent}, event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy}, keyboard::{Key, ModifiersState, NamedKey, PhysicalKey}, window::{CursorIcon, Fullscreen, Window, WindowId}};
 
-use crate::UiAtlas;
+use crate::{config::{EditorConfig, StartupReport}, crash, history::{self, History, Snapshot}, import, level::{self, Level, LevelError, TileEdit, TileId, TileSet}, platform, project::Project, storage, theme::{OverlayColor, PalettePreset, Theme}, window::{assets::AssetReload, clipboard::{self, Clipboard, ClipboardError, SystemClipboard}, detached::WindowContext, drop::{self, DropAction, FileDrop}, event::{GuiEvent, UserEvent}, explorer::{ExplorerSort, ExplorerState}, pages::{self, Page, PageCtx, PageRegistry}, placement, recent::RecentProjects, session::Session, shortcuts::Shortcuts}, undo::UndoStack};
+
+/// Id of the project view's header panel, which the project name is added to.
+const PROJECT_HEADER: &str = "header";
+/// Save, Export and Undo in the project view header, which need an open document.
+const DOCUMENT_TOOLS: &str = "document_tools";
+/// Id of the settings menu's panel.
+const SETTINGS_MENU: &str = "settings_menu";
+/// Id of the History panel's floating window.
+const HISTORY_PANEL: &str = "history_panel";
+/// History panel rows, in pixels at a scale factor of 1.
+const HISTORY_ROW_HEIGHT: f32 = 28.0;
+/// Ids of the settings menu's inputs, read by `commit_settings_inputs`.
+const FONT_SCALE_INPUT: &str = "settings:font_scale";
+const ASSETS_DIR_INPUT: &str = "settings:assets_dir";
+/// Id of the settings menu's theme toggle, relabelled when the theme switches without a rebuild.
+const THEME_TOGGLE: &str = "settings:theme";
+/// Id of the progress bar shown while assets are packed, see `step_asset_reload`.
+const ASSET_RELOAD_PROGRESS: &str = "asset_reload_progress";
+/// Font scale change per click of the settings menu's - and + buttons.
+const FONT_SCALE_STEP: f32 = 0.1;
+const WINDOW_TITLE: &str = "Level Editor";
+/// Every label is drawn in this, built into the binary so it doesn't depend on the working directory.
+const UI_FONT: &str = "ComicMono";
+const UI_FONT_BYTES: &[u8] = include_bytes!("../../../ComicMono.ttf");
+/// Page headers, and each banner line and settings menu row below them, in pixels at a scale factor of 1.
+const HEADER_HEIGHT: f32 = 24.0;
+/// Explorer list rows and the gap between them, in pixels at a scale factor of 1.
+const EXPLORER_ROW_HEIGHT: f32 = 26.0;
+const EXPLORER_ROW_SPACING: f32 = 8.0;
+/// Panel layers, pages stay on the default layer 0.
+const BANNER_LAYER: i32 = 1;
+const MENU_LAYER: i32 = 2;
+const DIALOG_LAYER: i32 = 3;
+/// Over dialogs too, so a drop that would be rejected says so while it's dragged.
+const DROP_LAYER: i32 = 4;
+/// Id of the name input in the new project dialog.
+const NEW_PROJECT_NAME: &str = "new_project_name";
+/// Id of the explorer's search input, which filters the rows as it's typed in.
+const EXPLORER_FILTER_INPUT: &str = "explorer_filter";
+/// Id of the input shown over an explorer row while it's renamed, see `ExplorerState::start_rename`.
+const EXPLORER_RENAME_INPUT: &str = "explorer_rename";
+/// Id of the explorer's scrolling list of rows, which `step_explorer_loading` appends to.
+const EXPLORER_LIST: &str = "explorer_list";
+/// Id of the explorer header's spinner, shown while a listing fills in.
+const EXPLORER_LOADING: &str = "explorer_loading";
+/// The project view's list of recent projects, shown while no project is open.
+const RECENT_PROJECTS: &str = "recent_projects";
+/// Recent project rows, tall enough for a name over a path, and the gap between them, in pixels at a scale factor of 1.
+const RECENT_ROW_HEIGHT: f32 = 40.0;
+const RECENT_ROW_SPACING: f32 = 6.0;
+/// Panes of the project view, split around the viewport.
+const SCENE_TREE: &str = "scene_tree";
+const VIEWPORT: &str = "viewport";
+const ASSET_BROWSER: &str = "asset_browser";
+const INSPECTOR: &str = "inspector";
+
+/// Preview zoom factor per wheel notch.
+const ZOOM_STEP: f32 = 1.1;
+/// Explorer rows added per frame while a listing fills in.
+const EXPLORER_ROWS_PER_STEP: usize = 200;
+/// How soon to check again on a directory scan or rows still to build, about one frame.
+const LOADING_POLL_INTERVAL: Duration = Duration::from_millis(16);
+/// How long an error toast stays up unless it's clicked away.
+const ERROR_TOAST_DURATION: Duration = Duration::from_secs(8);
+/// How long the settings menu takes to fade in, and how far above its place it slides in from, in pixels.
+const MENU_TRANSITION: Duration = Duration::from_millis(150);
+const MENU_SLIDE: f32 = 12.0;
+/// How often the debug overlay's numbers are refreshed, rarely enough that its text isn't requeued every frame.
+const DEBUG_OVERLAY_INTERVAL: Duration = Duration::from_millis(250);
+/// Asset browser rows listing the project's tiles, in pixels at a scale factor of 1.
+const TILE_ROW_HEIGHT: f32 = 22.0;
+
+/// What a mouse button held down over the preview is doing to the level.
+#[derive(Clone, Copy, Debug)]
+enum LevelStroke {
+    /// Paints `tile` into every cell the cursor passes over, or erases them when it's `None`.
+    /// `last_cell` is where the cursor last was, so a fast move still paints every cell in between.
+    Brush { tile: Option<TileId>, last_cell: IVec2 },
+    /// A shift-drag filling the rectangle from `anchor` to the cell it's released over with `tile`.
+    Rect { tile: TileId, anchor: IVec2 },
+}
+
+impl LevelStroke {
+    /// The button holding the stroke, right for erasing and left for everything else.
+    fn button(&self) -> MouseButton {
+        match self {
+            LevelStroke::Brush { tile: None, .. } => MouseButton::Right,
+            _ => MouseButton::Left,
+        }
+    }
+}
+
+/// Appends `rows` to the explorer list in `interface` and uploads only them, hiding the header's spinner once the
+/// listing is `loaded`. Returns false, changing nothing, when `interface` has no list.
+fn append_explorer_rows(interface: &mut UiTree<GuiEvent>, render_state: &mut RenderState, rows: Vec<Element<GuiEvent>>, loaded: bool) -> bool {
+    let Some(list) = interface.panel_index(EXPLORER_LIST) else {
+        return false;
+    };
+    let appended = interface.append_elements(list, rows);
+    render_state.update_interface_appended(interface, list, appended);
+    if loaded
+        && let Some(spinner) = interface.element_index(EXPLORER_LOADING)
+        && interface.set_element_visible(spinner, false)
+        && !render_state.update_interface_element(interface, spinner) {
+        render_state.update_interface(interface);
+    }
+    true
+}
+
+/// Focuses the explorer's rename input when `interface` has one and nothing else has focus, so it opens ready to
+/// type into. Returns true when it took focus.
+fn focus_rename_input(interface: &mut UiTree<GuiEvent>) -> bool {
+    interface.focused_element().is_none()
+        && interface.element_index(EXPLORER_RENAME_INPUT).is_some_and(|input| interface.focus(Some(input)))
+}
+
+/// Id of the explorer row listing `path`, which is how a selection made in the interface is matched back to entries.
+fn explorer_row_id(path: &Path) -> String {
+    format!("explorer:{}", path.display())
+}
+
+/// Clipboards come and go with the display server, so failing to use one only warns with a toast in `interface`,
+/// which needs uploading after.
+fn warn_clipboard_failed(interface: &mut UiTree<GuiEvent>, error: ClipboardError) {
+    log::warn!("Clipboard: {}", error);
+    interface.push_toast(ToastLevel::Warning, error.to_string(), ERROR_TOAST_DURATION);
+}
+
+/// What handling a window's input and GUI events left to do afterwards, see `EditorApp::apply_pending`.
+#[derive(Default)]
+struct PendingChanges {
+    page_change: Option<String>,
+    menu_change: Option<(bool, Option<GuiMenuState>)>,
+    rebuild: bool,
+    /// Like `rebuild` without rescanning the explorer directory.
+    page_rebuild: bool,
+    redraw: bool,
+}
 
 pub struct EditorApp {
-    layout: GuiPageState,
-    interface: Arc<Mutex<Interface>>,
+    /// Id of the page on screen, one of `pages`.
+    page: String,
+    pages: PageRegistry,
+    interface: UiTree<GuiEvent>,
     atlas: Option<UiAtlas>,
-    render_state: Option<gfx::RenderState>,
+    fonts: Fonts,
+    /// Pixels for `atlas`, handed to the renderer once it's created.
+    atlas_image: Option<RgbaImage>,
+    render_state: Option<RenderState>,
+    /// Pages open in windows of their own, see `GuiEvent::DetachPage`.
+    detached: HashMap<WindowId, WindowContext>,
     cursor_position: Option<PhysicalPosition<f64>>,
+    /// Last icon given to `Window::set_cursor`, so it's only set when the interface asks for another.
+    cursor_icon: CursorIcon,
+    /// Whether the interface took the last cursor move, so a panel drawn over the preview doesn't show the paint cursor.
+    cursor_over_interface: bool,
+    /// Cursor position at the last step of a middle-button drag panning the preview.
+    preview_drag: Option<PhysicalPosition<f64>>,
     window_ref: Option<Arc<Window>>,
     menu_open: (bool, Option<GuiMenuState>),
-    last_hovered_element_index: Option<(usize, usize)>,
+    config: EditorConfig,
+    theme: Theme,
+    /// Interface colors from `config.appearance`, kept so rebuilds don't read a custom theme file again.
+    ui_theme: UiTheme,
+    startup: StartupReport,
+    banner_open: bool,
+    explorer: ExplorerState,
+    /// Explorer rows built so far, grown by `EXPLORER_ROWS_PER_STEP` a frame so long listings don't stall one.
+    explorer_rows: usize,
+    /// The current page's layout file, read again whenever the whole interface is rebuilt, see `load_page_layout`.
+    page_layout: Option<LayoutFile>,
+    /// Packing of the assets folder started by `reload_assets`, until `step_asset_reload` applies it.
+    asset_reload: Option<AssetReload>,
+    /// Lets background work such as explorer scans wake the event loop, set once the loop exists.
+    event_proxy: Option<EventLoopProxy<UserEvent>>,
+    modifiers: ModifiersState,
+    /// Keys that send events while no text input has focus.
+    shortcuts: Shortcuts,
+    /// Where Ctrl+C, Ctrl+X, Ctrl+V and "Copy path" go.
+    clipboard: Box<dyn Clipboard>,
+    current_project: Option<Project>,
+    /// What was typed in the new project dialog, kept so a rejected name is still there after the rebuild.
+    new_project_name: String,
+    /// Why the last name in the new project dialog was rejected.
+    new_project_error: Option<String>,
+    /// Config and command line choices for the renderer, used when the window is created.
+    render_options: RenderOptions,
+    /// Restored from the last launch and saved again on exit, except in safe mode.
+    session: Session,
+    /// Listed on the project view while no project is open, saved whenever it changes except in safe mode.
+    recent_projects: RecentProjects,
+    /// Config as it was when the settings menu opened. Changes in the menu apply straight away, Cancel puts this back.
+    settings_backup: Option<EditorConfig>,
+    /// When the debug overlay's numbers are next refreshed, `None` while F3 has it off.
+    debug_overlay_due: Option<Instant>,
+    /// The level painted in the preview.
+    level: Level,
+    /// Tiles of `current_project`, empty without one.
+    tile_set: TileSet,
+    /// Tile a left-click in the preview paints, picked in the asset browser.
+    selected_tile: Option<TileId>,
+    /// Paint or erase stroke in progress, ended by releasing its button.
+    level_stroke: Option<LevelStroke>,
+    /// Edits of `level`, which also tell whether it has unsaved ones.
+    undo: UndoStack<TileEdit>,
+    /// Files dragged over the main window, and dropped ones waiting for `about_to_wait`.
+    file_drop: FileDrop,
+    /// Snapshots of the open project's level, `None` without a project.
+    history: Option<History>,
+    /// What the History panel lists, newest first. Read again when it's opened or a snapshot is taken.
+    snapshots: Vec<Snapshot>,
+    history_open: bool,
+    /// The snapshot shown read-only in the preview in place of `level`, and the level it holds.
+    history_preview: Option<(Snapshot, Level)>,
 }
 
 impl EditorApp {
-    pub fn new(atlas: UiAtlas) -> anyhow::Result<()> {
+    pub fn new(atlas_image: RgbaImage, atlas: UiAtlas, config: EditorConfig, startup: StartupReport, render_options: RenderOptions, session: Session, recent_projects: RecentProjects) -> anyhow::Result<()> {
+        let mut fonts = Fonts::new();
+        fonts.add(UI_FONT, UI_FONT_BYTES.to_vec())?;
+        let mut interface = UiTree::new(atlas.clone());
+        interface.set_fonts(fonts.clone());
+        interface.define_group(DOCUMENT_TOOLS, ["save", "export", "undo"]);
+        // Enabled by `start_level` once a project, and with it its level, is open
+        interface.set_group_enabled(DOCUMENT_TOOLS, false);
+
+        let pages = Self::register_pages();
+        let page = session.page.clone()
+            .filter(|page| pages.contains(page))
+            .unwrap_or_else(|| pages::PROJECT_VIEW.to_string());
+        let current_project = session.project.as_deref()
+            .filter(|root| Project::is_project(root))
+            .and_then(|root| Project::open(root)
+                .inspect_err(|e| log::warn!("Unable to reopen project {:?}: {}", root, e))
+                .ok());
+
         let mut app = EditorApp {
-            layout: GuiPageState::ProjectView,
-            interface: Arc::new(Mutex::new(Interface::new(atlas.clone()))),
+            page,
+            pages,
+            interface,
             atlas: Some(atlas),
+            fonts,
+            atlas_image: Some(atlas_image),
             render_state: None,
+            detached: HashMap::new(),
             cursor_position: None,
+            cursor_icon: CursorIcon::Default,
+            cursor_over_interface: false,
+            preview_drag: None,
             window_ref: None,
-            menu_open: (false, None),
-            last_hovered_element_index: None,
+            menu_open: if startup.crash_report.is_some() { (true, Some(GuiMenuState::CrashReport)) } else { (false, None) },
+            theme: Theme::from_config(&config.accessibility),
+            ui_theme: config.appearance.ui_theme(),
+            config,
+            banner_open: startup.needs_banner(),
+            startup,
+            explorer: ExplorerState::default(),
+            explorer_rows: 0,
+            page_layout: None,
+            asset_reload: None,
+            event_proxy: None,
+            modifiers: ModifiersState::default(),
+            shortcuts: Shortcuts::default(),
+            clipboard: Box::new(SystemClipboard::default()),
+            current_project,
+            new_project_name: String::new(),
+            new_project_error: None,
+            render_options,
+            session,
+            recent_projects,
+            settings_backup: None,
+            debug_overlay_due: None,
+            level: Level::new(),
+            tile_set: TileSet::default(),
+            selected_tile: None,
+            level_stroke: None,
+            undo: UndoStack::new(),
+            file_drop: FileDrop::default(),
+            history: None,
+            snapshots: Vec::new(),
+            history_open: false,
+            history_preview: None,
+        };
+
+        let event_loop = EventLoop::with_user_event().build()?;
+        app.event_proxy = Some(event_loop.create_proxy());
+        // Frames are only drawn when something asks for one, see `request_pending_redraw`
+        event_loop.set_control_flow(ControlFlow::Wait);
+
+        event_loop.run_app(&mut app)?;
+
+        Ok(())
+    }
+
+    /// Starts packing the configured assets folder again on a background thread, replacing a reload already running.
+    /// The caller rebuilds the interface so it shows the reload's progress bar, `step_asset_reload` applies the result.
+    fn reload_assets(&mut self) {
+        let proxy = self.event_proxy.clone();
+        self.asset_reload = Some(AssetReload::start(self.config.assets_dir.clone(), move || {
+            if let Some(proxy) = proxy.as_ref() {
+                // Fails only once the event loop has exited
+                let _ = proxy.send_event(UserEvent::AssetsReloading);
+            }
+        }));
+    }
+
+    /// Moves the asset reload's progress bar along, or applies the atlas once it's packed and rebuilds the page.
+    /// The bar is rewritten on its own, the rest of the interface is left as it is.
+    fn step_asset_reload(&mut self) {
+        let Some(reload) = self.asset_reload.as_mut() else {
+            return;
+        };
+        let previous = reload.progress();
+        match reload.poll() {
+            Some(result) => {
+                self.asset_reload = None;
+                self.apply_reloaded_assets(result);
+                self.rebuild_page();
+            }
+            None if reload.progress() != previous => {
+                let progress = reload.progress();
+                let updated = self.interface.element_mut(ASSET_RELOAD_PROGRESS)
+                    .is_some_and(|bar| bar.set_progress(progress));
+                if let Some(rs) = self.render_state.as_mut()
+                    && updated {
+                    let index = self.interface.element_index(ASSET_RELOAD_PROGRESS);
+                    if !index.is_some_and(|index| rs.update_interface_element(&self.interface, index)) {
+                        rs.update_interface(&mut self.interface);
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Hands a reloaded atlas to the renderer, keeping the old one if packing or uploading it failed.
+    /// The caller rebuilds the interface so icon sizes are measured against the new atlas.
+    fn apply_reloaded_assets(&mut self, result: Result<(RgbaImage, UiAtlas), String>) {
+        let (atlas_image, atlas) = match result {
+            Ok(atlas) => atlas,
+            Err(e) => {
+                log::error!("Unable to reload assets: {}", e);
+                self.show_toast(ToastLevel::Error, format!("Unable to reload assets: {}", e));
+                return;
+            }
+        };
+        match self.render_state.as_mut() {
+            Some(rs) => match rs.reload_atlas(&atlas_image, atlas.clone(), &mut self.interface) {
+                Ok(()) => self.atlas = Some(atlas),
+                Err(e) => {
+                    log::error!("Unable to reload assets: {}", e);
+                    self.show_toast(ToastLevel::Error, format!("Unable to reload assets: {}", e));
+                }
+            },
+            // Not rendering yet, the renderer will pick the new atlas up when it's created
+            None => {
+                self.atlas_image = Some(atlas_image);
+                self.atlas = Some(atlas);
+            }
+        }
+    }
+
+    /// Loads the open project's tiles and the level it saved, or starts an empty level if it has none.
+    /// Without a project the level is empty and can't be saved.
+    fn start_level(&mut self) {
+        self.level = Level::new();
+        self.undo = UndoStack::new();
+        self.history = self.current_project.as_ref().map(|project| History::for_level(&project.level_path()));
+        self.snapshots = self.history.as_ref().map(History::list).unwrap_or_default();
+        self.history_preview = None;
+        self.tile_set = TileSet::default();
+        self.level_stroke = None;
+        self.selected_tile = None;
+        if let Some(rs) = self.render_state.as_mut() {
+            rs.clear_tiles();
+        }
+        self.load_project_tiles();
+
+        if self.interface.set_group_enabled(DOCUMENT_TOOLS, self.current_project.is_some())
+            && let Some(rs) = self.render_state.as_mut() {
+            rs.update_interface(&mut self.interface);
+        }
+        let Some(path) = self.current_project.as_ref().map(Project::level_path).filter(|path| path.exists()) else {
+            return;
+        };
+        match Level::load(&path, &self.tile_set) {
+            Ok(level) => {
+                let missing = level.missing_tiles();
+                if !missing.is_empty() {
+                    let names = missing.into_iter().collect::<Vec<_>>().join(", ");
+                    log::warn!("{:?} uses tiles that aren't in the asset folder: {}", path, names);
+                    self.show_toast(ToastLevel::Warning, format!("Missing tiles are drawn as placeholders: {}", names));
+                }
+                self.level = level;
+                self.sync_level_tiles();
+            }
+            // Saving over it keeps the damaged file as a backup, see `Level::save`
+            Err(e) => {
+                log::error!("Unable to load the level: {}", e);
+                self.show_toast(ToastLevel::Error, format!("Unable to load the level: {}", e));
+            }
+        }
+    }
+
+    /// Writes the level to the open project. Returns whether it was saved.
+    fn save_level(&mut self) -> bool {
+        let Some(path) = self.current_project.as_ref().map(Project::level_path) else {
+            log::warn!("There's no project open to save the level to");
+            return false;
+        };
+        match self.level.save(&path, &self.tile_set, self.config.backup_count) {
+            Ok(()) => {
+                log::info!("Saved the level to {:?}", path);
+                self.undo.mark_saved();
+                true
+            }
+            Err(e) => {
+                log::error!("Unable to save the level: {}", e);
+                self.show_toast(ToastLevel::Error, format!("Unable to save the level: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Replaces the level with the backup at `path` as one edit, so it can be undone and is unsaved like any other.
+    /// The level file keeps its contents until the next save.
+    fn revert_to_backup(&mut self, path: &Path) {
+        let backup = match Level::load(path, &self.tile_set) {
+            Ok(backup) => backup,
+            Err(e) => {
+                log::error!("Unable to load the backup: {}", e);
+                self.show_toast(ToastLevel::Error, format!("Unable to load the backup: {}", e));
+                return;
+            }
+        };
+        self.replace_level(&backup);
+        self.show_toast(ToastLevel::Info, format!("Reverted to {}, save to keep it", drop::display_name(path)));
+    }
+
+    /// Makes the level the same as `other` with one edit, so it can be undone and is unsaved like any other.
+    /// A snapshot being previewed is closed first, the change wouldn't show otherwise.
+    fn replace_level(&mut self, other: &Level) {
+        self.close_history_preview();
+        let was_modified = self.level_modified();
+        self.undo.seal();
+        self.undo.push(TileEdit::replace(&self.level, other), &mut self.level);
+        self.undo.seal();
+        self.level_edited(was_modified);
+    }
+
+    /// Counts an edit towards the level's next snapshot, and takes the snapshot once it's due. A failed one is only
+    /// logged, the next is tried after another interval of editing.
+    fn record_history(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        if !history.record_edit(Instant::now()) {
+            return;
+        }
+        let taken = self.level.serialize(&self.tile_set)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| history.snapshot(contents.as_bytes(), SystemTime::now()).map_err(|e| e.to_string()));
+        match taken {
+            Ok(snapshot) => log::info!("Took a snapshot of the level: {:?}", snapshot.path),
+            Err(e) => log::warn!("Unable to take a snapshot of the level: {}", e),
+        }
+        self.snapshots = history.list();
+        if self.history_open {
+            self.rebuild_page();
+        }
+    }
+
+    /// Shows the snapshot at `path` read-only in the preview in place of the level, until `close_history_preview`.
+    fn preview_snapshot(&mut self, path: &Path) {
+        let Some(snapshot) = self.snapshots.iter().find(|snapshot| snapshot.path == path).cloned() else {
+            return;
+        };
+        let level = snapshot.read()
+            .map_err(|e| LevelError::Io(path.to_path_buf(), e))
+            .and_then(|contents| Level::parse(&contents, path, &self.tile_set));
+        match level {
+            Ok(level) => {
+                self.level_stroke = None;
+                self.history_preview = Some((snapshot, level));
+                if let Some(rs) = self.render_state.as_mut() {
+                    rs.clear_tiles();
+                }
+                self.sync_level_tiles();
+            }
+            Err(e) => {
+                log::error!("Unable to read the snapshot: {}", e);
+                self.show_toast(ToastLevel::Error, format!("Unable to read the snapshot: {}", e));
+            }
+        }
+    }
+
+    /// Shows the level again instead of the snapshot being previewed. Returns whether one was.
+    fn close_history_preview(&mut self) -> bool {
+        if self.history_preview.take().is_none() {
+            return false;
+        }
+        if let Some(rs) = self.render_state.as_mut() {
+            rs.clear_tiles();
+        }
+        self.level.mark_all_dirty();
+        self.sync_level_tiles();
+        true
+    }
+
+    /// Replaces the level with the snapshot being previewed, see `replace_level`.
+    fn restore_snapshot(&mut self) {
+        let Some((snapshot, level)) = self.history_preview.as_ref() else {
+            return;
+        };
+        let age = snapshot.relative_age(SystemTime::now());
+        let level = level.clone();
+        self.replace_level(&level);
+        self.show_toast(ToastLevel::Info, format!("Restored the snapshot from {}, save to keep it", age));
+    }
+
+    /// Whether the level has edits since it was loaded or last saved, undone ones aside.
+    fn level_modified(&self) -> bool {
+        !self.undo.is_saved()
+    }
+
+    /// Uploads what an edit, undo or redo changed, and rebuilds the page if that flipped the header's unsaved mark.
+    /// The edit counts towards the next snapshot, see `record_history`.
+    fn level_edited(&mut self, was_modified: bool) {
+        if self.level.has_changes() {
+            self.record_history();
+        }
+        self.sync_level_tiles();
+        if self.level_modified() != was_modified {
+            self.rebuild_page();
+        }
+    }
+
+    /// Packs the open project's asset folder into the tile atlas. Cells keep their tiles by name, so every chunk is
+    /// uploaded again with the texture coordinates of the new atlas.
+    fn load_project_tiles(&mut self) {
+        let (atlas_image, tile_set) = match self.current_project.as_ref().map(TileSet::load).transpose() {
+            Ok(Some(loaded)) => loaded,
+            Ok(None) => (RgbaImage::new(1, 1), TileSet::default()),
+            Err(e) => {
+                log::error!("Unable to load the project's tiles: {}", e);
+                self.show_toast(ToastLevel::Error, format!("Unable to load the project's tiles: {}", e));
+                (RgbaImage::new(1, 1), TileSet::default())
+            }
+        };
+        let old_tile_set = std::mem::replace(&mut self.tile_set, tile_set);
+        self.level.rebind_tiles(&old_tile_set, &self.tile_set);
+        if let Some((_, preview)) = self.history_preview.as_mut() {
+            preview.rebind_tiles(&old_tile_set, &self.tile_set);
+        }
+        // Edits refer to tiles by id, which are numbered differently once images were added or removed
+        if !old_tile_set.iter().map(|(_, tile)| &tile.name).eq(self.tile_set.iter().map(|(_, tile)| &tile.name)) {
+            self.undo.clear();
+        }
+        // Defaults to the first tile so there's something to paint with straight away
+        self.selected_tile = self.selected_tile
+            .filter(|&id| self.tile_set.get(id).is_some())
+            .or_else(|| self.tile_set.iter().next().map(|(id, _)| id));
+
+        if let Some(rs) = self.render_state.as_mut()
+            && let Err(e) = rs.set_tile_atlas(&atlas_image) {
+            log::error!("Unable to upload the tile atlas: {}", e);
+            self.show_toast(ToastLevel::Error, format!("Unable to upload the tile atlas: {}", e));
+        }
+        self.sync_level_tiles();
+    }
+
+    /// Uploads the chunks edited since the last call, leaving the rest of the level where it is on the GPU.
+    /// While a snapshot is previewed its chunks are uploaded instead, the level's wait for `close_history_preview`.
+    fn sync_level_tiles(&mut self) {
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+        let level = match self.history_preview.as_mut() {
+            Some((_, preview)) => preview,
+            None => &mut self.level,
+        };
+        for chunk in level.take_dirty_chunks() {
+            let instances: Vec<TileInstance> = level.chunk_tiles(chunk)
+                .map(|(cell, id)| self.tile_set.instance(cell, id))
+                .collect();
+            rs.set_tile_chunk(chunk, &instances);
+        }
+    }
+
+    /// The wheel or trackpad `delta` at the cursor, `None` before the cursor has entered the window.
+    fn scroll_event(&self, delta: MouseScrollDelta) -> Option<ScrollEvent> {
+        let scale_factor = self.render_state.as_ref().map_or(1.0, RenderState::scale_factor);
+        self.cursor_position.map(|position| ScrollEvent::from_wheel(delta, position, scale_factor, self.modifiers.shift_key()))
+    }
+
+    /// Pops up `message` in the corner for `ERROR_TOAST_DURATION`, see `UiTree::push_toast`.
+    fn show_toast(&mut self, level: ToastLevel, message: String) {
+        self.interface.push_toast(level, message, ERROR_TOAST_DURATION);
+        if let Some(rs) = self.render_state.as_mut() {
+            rs.update_interface(&mut self.interface);
+        }
+    }
+
+    /// Puts `text` on the clipboard, with a warning toast if that fails.
+    fn copy_to_clipboard(&mut self, text: &str) {
+        if let Err(e) = self.clipboard.set_text(text) {
+            self.clipboard_failed(e);
+        }
+    }
+
+    /// Warns about `error` in the main window, see `warn_clipboard_failed`.
+    fn clipboard_failed(&mut self, error: ClipboardError) {
+        warn_clipboard_failed(&mut self.interface, error);
+        if let Some(rs) = self.render_state.as_mut() {
+            rs.update_interface(&mut self.interface);
+        }
+    }
+
+    /// Input the interface didn't consume. Editor tools and the preview camera hook in here.
+    fn handle_viewport_input(&mut self, event: &WindowEvent) {
+        log::trace!("Viewport input: {:?}", event);
+        let was_modified = self.level_modified();
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+
+        match event {
+            WindowEvent::MouseInput { state, button: MouseButton::Middle, .. } => {
+                self.preview_drag = self.cursor_position.filter(|&cursor| state.is_pressed() && rs.is_cursor_over_preview(cursor));
+            }
+            // Left paints the selected tile, or fills a rectangle while shift is held, and right erases
+            WindowEvent::MouseInput { state, button: button @ (MouseButton::Left | MouseButton::Right), .. } => {
+                let cell = self.cursor_position.and_then(|cursor| rs.cursor_to_cell(cursor));
+                if state.is_pressed() {
+                    // A snapshot being previewed is read-only
+                    let Some(cell) = cell.filter(|_| self.history_preview.is_none()) else {
+                        return;
+                    };
+                    let tile = if *button == MouseButton::Right { None } else { self.selected_tile };
+                    // Each stroke is undone on its own, even when it paints the same tile as the last one
+                    self.undo.seal();
+                    self.level_stroke = match tile {
+                        Some(tile) if self.modifiers.shift_key() => Some(LevelStroke::Rect { tile, anchor: cell }),
+                        None if *button == MouseButton::Left => None,
+                        tile => {
+                            self.undo.push(TileEdit::brush(&self.level, tile, [cell]), &mut self.level);
+                            Some(LevelStroke::Brush { tile, last_cell: cell })
+                        }
+                    };
+                } else if self.level_stroke.is_some_and(|stroke| stroke.button() == *button) {
+                    // A rectangle released outside the preview is dropped
+                    if let Some(LevelStroke::Rect { tile, anchor }) = self.level_stroke.take()
+                        && let Some(cell) = cell {
+                        self.undo.push(TileEdit::fill_rect(&self.level, anchor, cell, tile), &mut self.level);
+                    }
+                    self.undo.seal();
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let Some(previous) = self.preview_drag {
+                    rs.pan_camera(Vec2::new((position.x - previous.x) as f32, (position.y - previous.y) as f32));
+                    self.preview_drag = Some(*position);
+                }
+                if let Some(LevelStroke::Brush { tile, last_cell }) = self.level_stroke.as_mut()
+                    && let Some(cell) = rs.cursor_to_cell(*position)
+                    && cell != *last_cell {
+                    self.undo.push(TileEdit::brush(&self.level, *tile, level::cells_between(*last_cell, cell)), &mut self.level);
+                    *last_cell = cell;
+                }
+            }
+            // Scrolling up zooms in and sideways pans, by the pixels a panel would have moved
+            WindowEvent::MouseWheel { delta, .. } => {
+                if let Some(cursor) = self.cursor_position
+                    && rs.is_cursor_over_preview(cursor) {
+                    let scroll = ScrollEvent::from_wheel(*delta, cursor, rs.scale_factor(), self.modifiers.shift_key());
+                    if scroll.delta_lines.y != 0.0 {
+                        rs.zoom_at(cursor, ZOOM_STEP.powf(-scroll.delta_lines.y));
+                    }
+                    if scroll.delta_pixels.x != 0.0 {
+                        rs.pan_camera(Vec2::new(-scroll.delta_pixels.x, 0.0));
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.level_edited(was_modified);
+    }
+
+    fn rebuild_interface(&mut self) {
+        // The listing arrives later as a `UserEvent::ExplorerScanned`, until then the page shows what it had
+        if self.shows_page(pages::FILE_EXPLORER) {
+            let proxy = self.event_proxy.clone();
+            self.explorer.refresh(&self.config.projects_dir, self.config.show_backups_in_explorer, move || {
+                if let Some(proxy) = proxy {
+                    // Fails only once the event loop has exited
+                    let _ = proxy.send_event(UserEvent::ExplorerScanned);
+                }
+            });
+        }
+        self.load_page_layout();
+        self.rebuild_page();
+    }
+
+    /// Reads the current page's layout file if it has one, so editing it shows after the next full rebuild.
+    /// One that can't be read or names actions `layout_actions` doesn't have is reported and left out,
+    /// the page is then built from code.
+    fn load_page_layout(&mut self) {
+        let path = pages::layout_path(&self.page);
+        if !path.exists() {
+            self.page_layout = None;
+            return;
+        }
+        let atlas = self.atlas.clone().unwrap();
+        // Built once here so a bad action is reported when the file is read, not on every rebuild after it
+        let loaded = LayoutFile::load(&path)
+            .and_then(|layout| layout.build(atlas, &pages::layout_actions()).map(|_: UiTree<GuiEvent>| layout));
+        self.page_layout = match loaded {
+            Ok(layout) => Some(layout),
+            Err(e) => {
+                log::error!("Unable to use layout {}: {}", path.display(), e);
+                self.show_toast(ToastLevel::Error, format!("Unable to use layout {}: {}", path.display(), e));
+                None
+            }
+        };
+    }
+
+    /// Rebuilds the page from the current state without rescanning the explorer directory.
+    fn rebuild_page(&mut self) {
+        log::debug!("Rebuilding interface for page: {}", self.page);
+        let atlas = self.atlas.clone().unwrap();
+
+        let explorer_window = match self.detached.values().find(|detached| detached.page == pages::FILE_EXPLORER) {
+            Some(detached) => Some(&detached.window),
+            None => self.window_ref.as_ref().filter(|_| self.page == pages::FILE_EXPLORER),
+        };
+        if let Some(window) = explorer_window {
+            match self.explorer.git_status().and_then(|status| status.branch()) {
+                Some(branch) => window.set_title(&format!("{} ({})", WINDOW_TITLE, branch)),
+                None => window.set_title(WINDOW_TITLE),
+            }
+        }
+
+        let page_interface_data = self.pages.build(&self.page, &self.page_ctx(&atlas));
+
+        let page_interface_data = if self.banner_open {
+            Self::display_startup_banner(page_interface_data, &self.startup)
+        } else {
+            page_interface_data
+        };
+
+        let page_interface_data = Self::display_settings_menu(page_interface_data, &self.config, self.menu_open == (true, Some(GuiMenuState::SettingsMenu)));
+        let page_interface_data = if self.page == pages::PROJECT_VIEW && self.current_project.is_some() {
+            let previewed = self.history_preview.as_ref().map(|(snapshot, _)| snapshot);
+            let interface = Self::display_history(page_interface_data, &self.snapshots, previewed.map(|snapshot| snapshot.path.as_path()), self.history_open);
+            match previewed {
+                Some(snapshot) => Self::display_preview_banner(interface, snapshot),
+                None => interface,
+            }
+        } else {
+            page_interface_data
+        };
+        let page_interface_data = match self.asset_reload.as_ref() {
+            Some(reload) => Self::display_asset_reload(page_interface_data, reload.progress()),
+            None => page_interface_data,
+        };
+        let page_interface_data = match self.menu_open {
+            (true, Some(GuiMenuState::ConfirmDelete)) => Self::display_confirm_delete(page_interface_data, &self.explorer),
+            (true, Some(GuiMenuState::CrashReport)) => Self::display_crash_report(page_interface_data, &self.startup),
+            (true, Some(GuiMenuState::ConfirmExit)) => Self::display_confirm_exit(page_interface_data),
+            (true, Some(GuiMenuState::NewProjectMenu)) => Self::display_new_project(page_interface_data, &self.new_project_name, self.new_project_error.as_deref()),
+            _ => page_interface_data
+        };
+        let mut modified_interface_data = if self.file_drop.is_hovering() {
+            Self::display_drop_overlay(page_interface_data, &self.drop_message())
+        } else {
+            page_interface_data
+        };
+        self.style_interface(&mut modified_interface_data);
+        self.set_backdrop(&mut modified_interface_data, &self.page);
+
+        // Dividers stay where they were dragged across page changes and sessions, not only over rebuilds of one page
+        self.session.splits.extend(self.interface.split_ratios().map(|(id, ratio)| (id.to_string(), ratio)));
+        for (id, ratio) in &self.session.splits {
+            modified_interface_data.set_split_ratio(id, *ratio);
+        }
+
+        modified_interface_data.inherit_floating_windows(&mut self.interface);
+        modified_interface_data.inherit_groups(&mut self.interface);
+        modified_interface_data.inherit_clicks(&mut self.interface);
+        modified_interface_data.inherit_focus(&mut self.interface);
+        modified_interface_data.inherit_selection(&mut self.interface);
+        modified_interface_data.inherit_toasts(&mut self.interface);
+        modified_interface_data.inherit_debug_overlay(&mut self.interface);
+        self.interface = modified_interface_data;
+        if focus_rename_input(&mut self.interface)
+            && let Some(window) = self.window_ref.as_ref() {
+            window.set_ime_allowed(true);
+        }
+
+        // The renderer keeps its buffers and text brushes, they're reused for the new tree
+        if let Some(rs) = self.render_state.as_mut() {
+            self.interface.set_scale_factor(rs.scale_factor() as f32);
+            rs.update_interface(&mut self.interface);
+        } else {
+            log::warn!("Attempted to rebuild interface but render_state was None. It's uploaded once there is one.");
+        }
+        self.rebuild_detached_windows(&atlas);
+    }
+
+    /// What the page builders read, as things are now in the main window.
+    fn page_ctx<'a>(&'a self, atlas: &'a UiAtlas) -> PageCtx<'a> {
+        PageCtx {
+            atlas,
+            config: &self.config,
+            theme: &self.theme,
+            explorer: &self.explorer,
+            explorer_rows: self.explorer_rows,
+            project: self.current_project.as_ref(),
+            recent_projects: &self.recent_projects,
+            tile_set: &self.tile_set,
+            selected_tile: self.selected_tile,
+            level_modified: self.level_modified(),
+            screen_size: self.render_state.as_ref().map(|rs| rs.size),
+            scale_factor: self.render_state.as_ref().map_or(1.0, |rs| rs.scale_factor() as f32),
+            layout: self.page_layout.as_ref(),
+            detached: false,
+        }
+    }
+
+    /// Builds the page of every detached window again from the current state. Menus and dialogs only
+    /// open in the main window, so these are just their pages.
+    fn rebuild_detached_windows(&mut self, atlas: &UiAtlas) {
+        let ctx = self.page_ctx(atlas);
+        let interfaces: Vec<_> = self.detached.iter()
+            .map(|(&id, detached)| {
+                let ctx = PageCtx {
+                    screen_size: Some(detached.render_state.size),
+                    scale_factor: detached.render_state.scale_factor() as f32,
+                    layout: None,
+                    detached: true,
+                    ..ctx
+                };
+                let mut interface = self.pages.build(&detached.page, &ctx);
+                self.style_interface(&mut interface);
+                self.set_backdrop(&mut interface, &detached.page);
+                (id, interface)
+            })
+            .collect();
+        for (id, interface) in interfaces {
+            if let Some(detached) = self.detached.get_mut(&id) {
+                detached.replace_interface(interface);
+                if focus_rename_input(&mut detached.interface) {
+                    detached.window.set_ime_allowed(true);
+                    detached.render_state.update_interface(&mut detached.interface);
+                }
+            }
+        }
+    }
+
+    /// Gives a newly built tree the editor's style, theme, text scale and fonts.
+    fn style_interface(&self, interface: &mut UiTree<GuiEvent>) {
+        interface.set_style(self.theme.ui_style());
+        interface.set_theme(self.ui_theme.clone());
+        interface.set_text_scale_factor(self.config.font_scale());
+        interface.set_fonts(self.fonts.clone());
+        if self.config.accessibility.reduce_motion_enabled() {
+            interface.set_hover_transition(Duration::ZERO);
+            interface.set_spinners_turn(false);
+        }
+    }
+
+    /// Clears the window behind a newly built tree for `page` to the page's backdrop, if it has one of its own.
+    fn set_backdrop(&self, interface: &mut UiTree<GuiEvent>, page: &str) {
+        if let Some(role) = self.pages.backdrop(page) {
+            interface.set_clear_color(self.ui_theme.color(role).clone());
+        }
+    }
+
+    /// Shows or hides the settings window in place. Returns false when there's nothing to update yet
+    /// and the interface has to be rebuilt instead.
+    fn show_settings_menu(&mut self, visible: bool) -> bool {
+        let Some(rs) = self.render_state.as_mut() else {
+            return false;
+        };
+        let interface = &mut self.interface;
+        if interface.floating_window_panels(SETTINGS_MENU).is_none() {
+            return false;
+        }
+        if interface.set_floating_visible(SETTINGS_MENU, visible) {
+            // Nothing in the menu stays hovered for the next time it opens
+            interface.clear_hover();
+            rs.update_interface(interface);
+        }
+        true
+    }
+
+    /// Shows the frame rate, frame time, draw calls and vertex buffer size in the debug overlay, with a graph of the
+    /// latest frame times, once `debug_overlay_due` passes, then waits `DEBUG_OVERLAY_INTERVAL` for the next refresh.
+    fn refresh_debug_overlay(&mut self, now: Instant) {
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+        if self.debug_overlay_due.is_none_or(|due| due > now) {
+            return;
+        }
+        let interface = &mut self.interface;
+        let lines = vec![
+            format!("{:.0} fps", rs.fps()),
+            format!("{:.2} ms frame", rs.last_frame_time().as_secs_f64() * 1000.0),
+            format!("{} draw calls", rs.draw_calls()),
+            format!("{} KiB vertex buffer", rs.vertex_buffer_size() / 1024),
+        ];
+        interface.show_debug_overlay(lines, rs.frame_times());
+        rs.update_interface(interface);
+        self.debug_overlay_due = Some(now + DEBUG_OVERLAY_INTERVAL);
+    }
+
+    /// Fades the settings menu in while sliding it down into place, called whenever it opens.
+    /// With reduced motion it just appears.
+    fn animate_settings_menu_in(&mut self) {
+        if self.config.accessibility.reduce_motion_enabled() {
+            return;
+        }
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+        let interface = &mut self.interface;
+        let Some((frame, content)) = interface.floating_window_panels(SETTINGS_MENU) else {
+            return;
+        };
+        // The frame and content move as one
+        for panel_idx in [frame, content] {
+            interface.animate_panel(panel_idx, AnimProperty::Opacity(0.0), Duration::ZERO, Easing::Linear);
+            interface.animate_panel(panel_idx, AnimProperty::Offset(0.0, -MENU_SLIDE), Duration::ZERO, Easing::Linear);
+            interface.animate_panel(panel_idx, AnimProperty::Opacity(1.0), MENU_TRANSITION, Easing::EaseOutCubic);
+            interface.animate_panel(panel_idx, AnimProperty::Offset(0.0, 0.0), MENU_TRANSITION, Easing::EaseOutCubic);
+        }
+        // The first step is written right away, so no frame shows the menu before it starts fading in
+        rs.update_interface(interface);
+    }
+
+    /// Takes what was typed into the settings menu's inputs. A font scale that isn't a number is ignored and one
+    /// out of range is clamped, an assets folder that doesn't exist is ignored.
+    fn commit_settings_inputs(&mut self) {
+        let interface = &self.interface;
+        if let Some(typed) = interface.element(FONT_SCALE_INPUT).and_then(Element::text) {
+            match typed.trim().parse::<f32>() {
+                Ok(scale) => self.config.set_font_scale(scale),
+                Err(_) => log::warn!("Ignoring font scale {:?}, it isn't a number", typed),
+            }
+        }
+        if let Some(typed) = interface.element(ASSETS_DIR_INPUT).and_then(Element::text) {
+            let assets_dir = PathBuf::from(typed.trim());
+            if assets_dir.is_dir() {
+                self.config.assets_dir = assets_dir;
+            } else {
+                log::warn!("Ignoring assets folder {:?}, it isn't a directory", assets_dir);
+            }
+        }
+    }
+
+    /// What closing the topmost menu or dialog sends, the event of its cancel button, or dismissing the startup
+    /// banner when nothing is open. `None` when there's nothing to close.
+    fn close_menu_event(&self) -> Option<GuiEvent> {
+        match self.menu_open {
+            (true, Some(GuiMenuState::SettingsMenu)) => Some(GuiEvent::CancelSettings),
+            (true, Some(GuiMenuState::CrashReport)) => Some(GuiEvent::DismissCrashReport),
+            (true, Some(_)) => Some(GuiEvent::CancelDialog),
+            _ if self.banner_open => Some(GuiEvent::DismissStartupBanner),
+            _ => None,
+        }
+    }
+
+    /// Puts back the config the settings menu opened with, undoing the present mode, palette and theme it changed.
+    fn cancel_settings(&mut self) {
+        let Some(saved) = self.settings_backup.take() else {
+            return;
+        };
+        let vsync_changed = saved.render.present_mode != self.config.render.present_mode;
+        let theme_changed = saved.appearance.theme != self.config.appearance.theme;
+        self.config = saved;
+        self.theme = Theme::from_config(&self.config.accessibility);
+        if theme_changed {
+            self.ui_theme = self.config.appearance.ui_theme();
+        }
+        if vsync_changed && let Some(rs) = self.render_state.as_mut() {
+            rs.set_present_mode(self.config.render.present_mode.present_mode());
+        }
+    }
+
+    /// Whether closing now would lose anything: settings changed in the open settings menu, or unsaved level edits.
+    fn has_unsaved_changes(&self) -> bool {
+        self.settings_backup.is_some() || self.level_modified()
+    }
+
+    /// Applies a finished explorer scan, or builds the next chunk of rows once one has landed.
+    /// Returns true while there are rows left to build. A running scan wakes the loop itself when it's done.
+    fn step_explorer_loading(&mut self) -> bool {
+        if !self.shows_page(pages::FILE_EXPLORER) {
+            return false;
+        }
+        if self.explorer.poll_scan() {
+            // A rescan of the same directory keeps the rows it already showed instead of collapsing to one chunk
+            self.explorer_rows = self.explorer_rows.max(EXPLORER_ROWS_PER_STEP);
+            self.rebuild_page();
+        } else if !self.explorer.is_scanning() && self.explorer_rows < self.explorer.entries().len() {
+            let shown = self.explorer_rows;
+            self.explorer_rows = (shown + EXPLORER_ROWS_PER_STEP).min(self.explorer.entries().len());
+            self.append_explorer_rows(shown);
+        }
+        !self.explorer.is_scanning() && self.explorer_rows < self.explorer.entries().len()
+    }
+
+    /// Adds the explorer's rows from `shown` up to `explorer_rows` to its list in every window showing it, uploading
+    /// only theirs, so each step costs the same however many rows came before. The page is rebuilt instead when
+    /// there's an error row after the rows, or no list or renderer to append to.
+    fn append_explorer_rows(&mut self, shown: usize) {
+        let atlas = self.atlas.as_ref().unwrap();
+        let loaded = self.explorer_rows == self.explorer.entries().len();
+        let files = &self.explorer.entries()[shown..self.explorer_rows];
+        let rows = |screen_size: PhysicalSize<u32>, scale_factor: f32| {
+            let icon_size = Self::explorer_icon_size(atlas, Some(screen_size), scale_factor);
+            files.iter().flat_map(|file| Self::explorer_row(&self.explorer, &self.theme, file, icon_size)).collect()
+        };
+
+        let mut appended = self.explorer.error().is_none();
+        if appended && self.page == pages::FILE_EXPLORER {
+            appended = match self.render_state.as_mut() {
+                Some(rs) => append_explorer_rows(&mut self.interface, rs, rows(rs.size, rs.scale_factor() as f32), loaded),
+                None => false,
+            };
+        }
+        for detached in self.detached.values_mut().filter(|detached| detached.page == pages::FILE_EXPLORER) {
+            if !appended {
+                break;
+            }
+            let rows = rows(detached.render_state.size, detached.render_state.scale_factor() as f32);
+            appended = append_explorer_rows(&mut detached.interface, &mut detached.render_state, rows, loaded);
+        }
+        if !appended {
+            self.rebuild_page();
+        }
+    }
+
+    /// Asks each window for a frame if its renderer was invalidated since the last one, or renders continuously.
+    /// Everything that changes what's on screen invalidates it, so an idle editor draws nothing.
+    fn request_pending_redraw(&self) {
+        if let Some(rs) = self.render_state.as_ref()
+            && rs.take_redraw_request(&self.interface)
+            && let Some(window_arc) = self.window_ref.as_ref() {
+            window_arc.request_redraw();
+        }
+        for detached in self.detached.values() {
+            detached.request_pending_redraw();
+        }
+    }
+
+    fn register_pages() -> PageRegistry {
+        let mut pages = PageRegistry::default();
+        pages.register(pages::PROJECT_VIEW, Page::new(Self::build_project_view_interface).with_preview());
+        // Darker than the project view's backdrop, so the list panel stands out from it
+        pages.register(pages::FILE_EXPLORER, Page::new(Self::build_file_explorer_interface).with_backdrop(ColorRole::Header));
+        pages.register(pages::ACCESSIBILITY_SETTINGS, Page::new(Self::build_accessibility_interface));
+        pages.register(pages::PIXEL_GRID, Page::new(pages::pixel_grid_page));
+        pages
+    }
+
+    fn build_project_view_interface(ctx: &PageCtx) -> UiTree<GuiEvent> {
+        let built = ctx.layout.and_then(|layout| {
+            layout.build_with(ctx.atlas.clone(), &pages::layout_actions(), |panel| Self::fill_project_view_panel(panel, ctx))
+                .inspect_err(|e| log::error!("Unable to build the project view from its layout file, using the built in one: {}", e))
+                .ok()
+        });
+        let mut interface = built.unwrap_or_else(|| Self::build_project_view_panes(ctx));
+        // Until a project is open the page is a landing page for picking one
+        if ctx.project.is_none() {
+            interface.add_panel(Self::recent_projects_panel(ctx));
+        }
+        interface
+    }
+
+    /// The project view's header and panes, when there's no layout file for them or it didn't build.
+    fn build_project_view_panes(ctx: &PageCtx) -> UiTree<GuiEvent> {
+        let mut interface = UiTree::new(ctx.atlas.clone());
+        let mut header = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::mixed(1.0, Dimension::Pixels(HEADER_HEIGHT)))
+            .with_id(PROJECT_HEADER)
+            .with_color_role(ColorRole::Header);
+        
+        let element1 = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.025, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "File", 0.7)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_dropdown(vec![
+                MenuItem::new("Open Project", GuiEvent::Navigate(pages::FILE_EXPLORER.to_string())),
+                MenuItem::new("Save", GuiEvent::SaveLevel),
+                MenuItem::submenu("Revert To Backup", Self::backup_items(ctx)),
+                MenuItem::new("History", GuiEvent::ToggleHistory),
+                MenuItem::new("Settings", GuiEvent::DisplaySettingsMenu),
+                MenuItem::new("GPU Diagnostics", GuiEvent::DumpDiagnostics),
+                MenuItem::new("Pixel Grid", GuiEvent::Navigate(pages::PIXEL_GRID.to_string())),
+                MenuItem::new("Exit", GuiEvent::RequestExit),
+            ]);
+
+        header.add_element(element1);
+
+        for (i, (id, label)) in [("save", "Save"), ("export", "Export"), ("undo", "Undo")].into_iter().enumerate() {
+            let x = 0.03 + 0.035 * i as f32;
+            let tool = Element::new(Coordinate::new(x, 0.0), Coordinate::new(x + 0.03, 1.0), "solid")
+                .with_id(id)
+                .with_color_role(ColorRole::Header)
+                .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, label)
+                .with_hover_color_role(ColorRole::Hover);
+            header.add_element(match id {
+                "save" => tool.with_fn(|| Some(GuiEvent::SaveLevel), InteractionStyle::OnClick),
+                "undo" => tool.with_fn(|| Some(GuiEvent::Undo), InteractionStyle::OnClick),
+                _ => tool,
+            });
+        }
+        Self::fill_project_view_panel(&mut header, ctx);
+
+        let mut preview = Panel::new(Coordinate::mixed(0.0, Dimension::Pixels(HEADER_HEIGHT)), Coordinate::new(1.0, 1.0))
+            .with_id(VIEWPORT)
+            .with_color_role(ColorRole::Background);
+        // Tinted white so the preview shows in its own colors
+        preview.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+            .with_color("#ffffffff")
+            .with_render_target(PREVIEW_TARGET));
+
+        interface.add_panel(header);
+        interface.add_panel(preview);
+        // The splits place these, so the start and end coordinates just cover the page
+        for (id, title) in [(SCENE_TREE, "Scene"), (ASSET_BROWSER, "Assets"), (INSPECTOR, "Inspector")] {
+            let mut pane = Panel::new(Coordinate::mixed(0.0, Dimension::Pixels(HEADER_HEIGHT)), Coordinate::new(1.0, 1.0))
+                .with_id(id)
+                .with_color_role(ColorRole::Panel);
+            pane.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::mixed(1.0, Dimension::Pixels(HEADER_HEIGHT)), "solid")
+                .with_color_role(ColorRole::Header)
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, title, 0.7, Wrap::TruncateWithEllipsis));
+            Self::fill_project_view_panel(&mut pane, ctx);
+            interface.add_panel(pane);
+        }
+        // Scene tree | (viewport over assets) | inspector
+        let center = interface.add_split(SplitDirection::Horizontal, VIEWPORT, ASSET_BROWSER, 0.75);
+        let right = interface.add_split(SplitDirection::Vertical, &center, INSPECTOR, 0.78);
+        interface.add_split(SplitDirection::Vertical, SCENE_TREE, &right, 0.18);
+        interface
+    }
+
+    /// Lists the recent projects over the middle of the project view, clicking one opens it. Projects whose folder is
+    /// gone are dimmed and can only be taken off the list.
+    fn recent_projects_panel(ctx: &PageCtx) -> Panel<GuiEvent> {
+        let mut panel = Panel::new(Coordinate::new(0.3, 0.15), Coordinate::new(0.7, 0.85))
+            .with_id(RECENT_PROJECTS)
+            .with_color_role(ColorRole::Panel)
+            .with_corner_radius(6.0)
+            .with_layout(Layout::Column { spacing: RECENT_ROW_SPACING, padding: RECENT_ROW_SPACING })
+            .with_clipping();
+        panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+            .with_fixed_height(Dimension::Pixels(HEADER_HEIGHT))
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Recent projects", TextStyle::new().with_scale(0.8).with_weight_hint(FontWeight::Bold)));
+        if ctx.recent_projects.entries().is_empty() {
+            panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(RECENT_ROW_HEIGHT))
+                .with_color_role(ColorRole::Header)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Projects you open show up here", TextStyle::new().with_scale(0.6).with_color_role(ColorRole::TextSecondary)));
+            return panel;
+        }
+
+        // The row's fill takes the hover, the text on it is drawn by transparent elements that only pass clicks on
+        let overlay = |start: Coordinate, end: Coordinate| Element::new(start, end, "solid")
+            .with_shared_cell()
+            .with_color("#00000000")
+            .with_style(Style::new().with_pressed_color("#00000000"));
+        let now = SystemTime::now();
+        for project in ctx.recent_projects.entries() {
+            let exists = project.exists();
+            let open = {
+                let path = project.path.clone();
+                move || Some(GuiEvent::OpenProject(path.clone()))
+            };
+            let name_color = if exists { ColorRole::Text } else { ColorRole::TextSecondary };
+            let row = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_id(&format!("recent:{}", project.path.display()))
+                .with_fixed_height(Dimension::Pixels(RECENT_ROW_HEIGHT))
+                .with_color_role(ColorRole::Header);
+            let name = overlay(Coordinate::new(0.0, 0.0), Coordinate::new(0.55, 0.5))
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &project.name, TextStyle::new().with_scale(0.7).with_color_role(name_color), Wrap::TruncateWithEllipsis);
+            let path = overlay(Coordinate::new(0.0, 0.5), Coordinate::new(0.9, 1.0))
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &project.path.display().to_string(), TextStyle::new().with_scale(0.5).with_color_role(ColorRole::TextSecondary), Wrap::TruncateWithEllipsis);
+            let age = overlay(Coordinate::new(0.55, 0.0), Coordinate::new(0.9, 0.5))
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Right }, &history::relative_age(project.last_opened(), now), TextStyle::new().with_scale(0.5).with_color_role(ColorRole::TextSecondary));
+            if exists {
+                panel.add_element(row
+                    .with_hover_color_role(ColorRole::Hover)
+                    .with_tooltip(&project.path.display().to_string())
+                    .with_fn(open.clone(), InteractionStyle::OnClick));
+                panel.add_element(name.with_fn(open.clone(), InteractionStyle::OnClick));
+                panel.add_element(path.with_fn(open.clone(), InteractionStyle::OnClick));
+                panel.add_element(age.with_fn(open, InteractionStyle::OnClick));
+            } else {
+                panel.add_element(row.with_tooltip(&format!("{} no longer exists", project.path.display())));
+                panel.add_element(name);
+                panel.add_element(path);
+                panel.add_element(age);
+                panel.add_element(Element::new(Coordinate::new(0.56, 0.1), Coordinate::new(0.7, 0.45), "solid")
+                    .with_shared_cell()
+                    .with_color_role(ColorRole::Error)
+                    .with_corner_radius(3.0)
+                    .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "missing", 0.5));
+            }
+            let removed = project.path.clone();
+            panel.add_element(Element::new(Coordinate::new(0.92, 0.2), Coordinate::new(0.98, 0.8), "solid")
+                .with_shared_cell()
+                .with_color_role(ColorRole::Header)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "x", 0.6)
+                .with_tooltip("Remove from recent projects")
+                .with_fn(move || Some(GuiEvent::RemoveRecentProject(removed.clone())), InteractionStyle::OnClick));
+        }
+        panel
+    }
+
+    /// Items of File > Revert To Backup, the open project's level backups newest first with how old each one is.
+    fn backup_items(ctx: &PageCtx) -> Vec<MenuItem<GuiEvent>> {
+        let backups = ctx.project
+            .map(|project| storage::list_backups(&project.level_path(), ctx.config.backup_count))
+            .unwrap_or_default();
+        if backups.is_empty() {
+            return vec![MenuItem::text("No backups yet")];
+        }
+        let now = SystemTime::now();
+        backups.into_iter()
+            .map(|(path, modified)| {
+                let label = format!("{} ({})", drop::display_name(&path), history::relative_age(modified, now));
+                MenuItem::new(&label, GuiEvent::RevertToBackup(path))
+            })
+            .collect()
+    }
+
+    /// Adds what the project view shows of the editor's state to its panels, whether they came from
+    /// `layouts/project_view.ron` or from code: the project name to the header and the tiles to the asset browser.
+    fn fill_project_view_panel(panel: &mut Panel<GuiEvent>, ctx: &PageCtx) {
+        match panel.id() {
+            Some(PROJECT_HEADER) => {
+                // Starred while the level has unsaved edits
+                let project_name = match ctx.project {
+                    Some(project) if ctx.level_modified => format!("{}*", project.name),
+                    Some(project) => project.name.clone(),
+                    None => "No project open".to_string(),
+                };
+                panel.add_element(Element::new(Coordinate::new(0.7, 0.0), Coordinate::new(0.995, 1.0), "solid")
+                    .with_color_role(ColorRole::Header)
+                    .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Right }, &project_name, 0.7, Wrap::TruncateWithEllipsis));
+            }
+            Some(ASSET_BROWSER) => Self::add_tile_palette(panel, ctx),
+            _ => {}
+        }
+    }
+
+    /// Lists the project's tiles under the asset browser's title, clicking one picks it for painting.
+    fn add_tile_palette(pane: &mut Panel<GuiEvent>, ctx: &PageCtx) {
+        let row = |index: usize| {
+            let top = HEADER_HEIGHT + TILE_ROW_HEIGHT * index as f32;
+            (Coordinate::mixed(0.0, Dimension::Pixels(top)), Coordinate::mixed(1.0, Dimension::Pixels(top + TILE_ROW_HEIGHT)))
+        };
+        if ctx.tile_set.is_empty() {
+            let message = match ctx.project {
+                Some(project) => format!("No tiles in {}", project.asset_dir.display()),
+                None => "Open a project to paint tiles".to_string(),
+            };
+            let (start, end) = row(0);
+            pane.add_element(Element::new(start, end, "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &message, TextStyle::new().with_scale(0.6).with_color_role(ColorRole::TextSecondary), Wrap::TruncateWithEllipsis));
+            return;
+        }
+        for (index, (id, tile)) in ctx.tile_set.iter().enumerate() {
+            let row_color = if ctx.selected_tile == Some(id) { ColorRole::Accent } else { ColorRole::Header };
+            let (start, end) = row(index);
+            pane.add_element(Element::new(start, end, "solid")
+                .with_id(&format!("tile:{}", tile.name))
+                .with_color_role(row_color)
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &tile.name, 0.6, Wrap::TruncateWithEllipsis)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(move || Some(GuiEvent::SelectTile(id)), InteractionStyle::OnClick));
+        }
+    }
+
+    /// Size of an atlas icon as a fraction of a panel that is `panel_size` pixels at a scale factor of 1,
+    /// at its native resolution but scaled down uniformly if it wouldn't fit in `max`.
+    fn native_icon_size(atlas: &UiAtlas, name: &str, panel_size: (f32, f32), max: (f32, f32)) -> (f32, f32) {
+        let Some(icon) = atlas.get(name) else {
+            log::warn!("Atlas has no icon named {:?}", name);
+            return max;
+        };
+        let (width, height) = icon.size();
+        let native = (width as f32 / panel_size.0, height as f32 / panel_size.1);
+        let fit = (max.0 / native.0).min(max.1 / native.1).min(1.0);
+        (native.0 * fit, native.1 * fit)
+    }
+
+    /// What right-clicking the explorer row of `file` offers. A row in a selection of several deletes or moves all
+    /// of them, like the selection's own menu would.
+    fn explorer_row_menu(explorer: &ExplorerState, file: &Path) -> Vec<MenuItem<GuiEvent>> {
+        let mut items = Vec::new();
+        if file.is_dir() {
+            items.push(MenuItem::new("Open", GuiEvent::OpenProject(file.to_path_buf())));
+        }
+        items.push(MenuItem::new("Rename", GuiEvent::RenameEntry(file.to_path_buf())));
+        items.push(MenuItem::new("Copy path", GuiEvent::CopyPath(file.to_path_buf())));
+        let count = explorer.selection().len();
+        let delete = if explorer.is_selected(file) && count > 1 { format!("Delete {} items", count) } else { "Delete".to_string() };
+        items.push(MenuItem::new(&delete, GuiEvent::RequestDelete(file.to_path_buf())));
+        if explorer.is_selected(file) {
+            items.extend(explorer.move_targets().into_iter().take(8).map(|path| {
+                MenuItem::new(&format!("Move to {}", path.file_name().unwrap_or_default().to_string_lossy()), GuiEvent::MoveSelectionTo(path.clone()))
+            }));
+        }
+        items
+    }
+
+    /// Size of the explorer's row icons, as fractions of their row's cell.
+    fn explorer_icon_size(atlas: &UiAtlas, screen_size: Option<PhysicalSize<u32>>, scale_factor: f32) -> (f32, f32) {
+        match screen_size {
+            Some(size) => Self::native_icon_size(atlas, "folder-1484", (0.6 * size.width as f32 / scale_factor, EXPLORER_ROW_HEIGHT), (0.02, 0.8)),
+            None => (0.02, 0.8),
+        }
+    }
+
+    /// The elements of `file`'s row in the explorer list, which share one cell of its column.
+    /// Coordinates are fractions of that cell.
+    fn explorer_row(explorer: &ExplorerState, theme: &Theme, file: &Path, icon_size: (f32, f32)) -> Vec<Element<GuiEvent>> {
+        let (icon_center_x, icon_center_y) = (0.025, 0.5);
+        let row_color = if explorer.is_selected(file) { ColorRole::Accent } else { ColorRole::Header };
+        let file_image = Element::image(
+            Coordinate::new(icon_center_x - icon_size.0 / 2.0, icon_center_y - icon_size.1 / 2.0),
+            Coordinate::new(icon_center_x + icon_size.0 / 2.0, icon_center_y + icon_size.1 / 2.0),
+            "folder-1484"
+        ).with_shared_cell();
+        let buffer_space = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.04, 1.0), "solid")
+            .with_color_role(row_color)
+            .with_shared_cell();
+
+        let path = file.to_path_buf();
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        // Folders still open from here, they just fail with an error saying why
+        let (label, text_style) = if file.is_dir() && !explorer.is_project(file) {
+            (format!("{} (not a project)", name), TextStyle::new().with_color_role(ColorRole::TextSecondary))
+        } else {
+            (name.into_owned(), TextStyle::new().with_color_role(ColorRole::Text))
+        };
+        // The accent fill is bright in both themes, a shadow keeps the selected row's name readable on it
+        let text_style = if explorer.is_selected(file) {
+            text_style.with_shadow((1.0, 1.0), "#00000099")
+        } else {
+            text_style
+        };
+        let element = match explorer.renaming() {
+            Some((renamed, typed)) if renamed == file => Element::text_input(Coordinate::new(0.04, 0.0), Coordinate::new(1.0, 1.0))
+                .with_id(EXPLORER_RENAME_INPUT)
+                .with_fixed_height(Dimension::Pixels(EXPLORER_ROW_HEIGHT))
+                .with_color_role(ColorRole::Header)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, typed, 0.8),
+            _ => Element::new(Coordinate::new(0.04, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_id(&explorer_row_id(file))
+                .with_fixed_height(Dimension::Pixels(EXPLORER_ROW_HEIGHT))
+                .with_color_role(ColorRole::Header)
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left}, &label, text_style.with_scale(0.8), Wrap::TruncateWithEllipsis)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_selected_color_role(ColorRole::Accent)
+                .with_selected(explorer.is_selected(file))
+                .with_context_menu(Self::explorer_row_menu(explorer, file))
+                .with_fn(|| Some(GuiEvent::SelectExplorerEntry), InteractionStyle::OnClick)
+                .with_fn(move || Some(GuiEvent::OpenProject(path.clone())), InteractionStyle::OnDoubleClick),
         };
 
-        env_logger::init();
+        let mut row = vec![element, buffer_space, file_image];
+        if let Some(mark) = explorer.git_status().and_then(|status| status.mark(file)) {
+            let badge = Element::new(Coordinate::new(0.965, 0.1), Coordinate::new(0.99, 0.9), "solid")
+                .with_shared_cell()
+                .with_color(theme.overlay(mark.color()))
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, mark.letter(), 0.6);
+            row.push(badge);
+        }
+        row
+    }
+
+    fn build_file_explorer_interface(ctx: &PageCtx) -> UiTree<GuiEvent> {
+        let PageCtx { atlas, config, theme, explorer, explorer_rows, screen_size, scale_factor, detached, .. } = *ctx;
+        let mut panel = Panel::new(Coordinate::new(0.2, 0.1), Coordinate::new(0.8, 0.9))
+            .with_id(EXPLORER_LIST)
+            .with_color_role(ColorRole::Panel)
+            .with_layout(Layout::Column { spacing: EXPLORER_ROW_SPACING, padding: 0.0 })
+            .with_scroll(ScrollDirection::Vertical)
+            .with_scroll_offset(explorer.scroll_offset());
+        let icon_size = Self::explorer_icon_size(atlas, screen_size, scale_factor);
+        for file in explorer.entries().iter().take(explorer_rows) {
+            for element in Self::explorer_row(explorer, theme, file, icon_size) {
+                panel.add_element(element);
+            }
+        }
+        if let Some(error) = explorer.error() {
+            let error_row = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(EXPLORER_ROW_HEIGHT))
+                .with_color("#5a1d1dff")
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, error, 0.6, Wrap::TruncateWithEllipsis);
+            panel.add_element(error_row);
+        }
+        if !explorer.is_scanning() && explorer.error().is_none() && explorer.entries().is_empty() {
+            let empty = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(EXPLORER_ROW_HEIGHT))
+                .with_color_role(ColorRole::Header)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "No matching projects", TextStyle::new().with_scale(0.8).with_color_role(ColorRole::TextSecondary));
+            panel.add_element(empty);
+        }
+        
+        let mut interface = UiTree::new(atlas.clone());
+
+        let mut header = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::mixed(1.0, Dimension::Pixels(HEADER_HEIGHT)))
+            .with_color_role(ColorRole::Header);
+        
+        let element1 = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.025, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Test", 0.7)
+            .with_fn(|| Some(GuiEvent::Navigate(pages::PROJECT_VIEW.to_string())), InteractionStyle::OnClick);
+
+        let backup_toggle_text = if config.show_backups_in_explorer { "Hide Backups" } else { "Show Backups" };
+        let backup_toggle = Element::new(Coordinate::new(0.025, 0.0), Coordinate::new(0.085, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, backup_toggle_text, 0.7)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ToggleBackupFiles), InteractionStyle::OnClick);
+
+        let refresh = Element::new(Coordinate::new(0.085, 0.0), Coordinate::new(0.125, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Refresh", 0.7)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::RefreshExplorer), InteractionStyle::OnClick);
+
+        let new_project = Element::new(Coordinate::new(0.255, 0.0), Coordinate::new(0.315, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "New Project", 0.7)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::NewProject), InteractionStyle::OnClick);
+
+        // Filters as it's typed in, see the KeyboardInput arm of window_event
+        let filter = Element::text_input(Coordinate::new(0.13, 0.1), Coordinate::new(0.25, 0.9))
+            .with_id(EXPLORER_FILTER_INPUT)
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, explorer.filter(), 0.7);
+
+        let sort_buttons = [
+            (ExplorerSort::NameAscending, "A-Z", "Sort by name, A to Z"),
+            (ExplorerSort::NameDescending, "Z-A", "Sort by name, Z to A"),
+            (ExplorerSort::Modified, "Newest", "Sort by last modified, newest first"),
+        ];
+        for (index, (sort, label, tooltip)) in sort_buttons.into_iter().enumerate() {
+            let x = 0.32 + 0.035 * index as f32;
+            let color = if explorer.sort() == sort { ColorRole::Accent } else { ColorRole::Header };
+            header.add_element(Element::new(Coordinate::new(x, 0.0), Coordinate::new(x + 0.035, 1.0), "solid")
+                .with_color_role(color)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, label, 0.7)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_tooltip(tooltip)
+                .with_fn(move || Some(GuiEvent::SetExplorerSort(sort)), InteractionStyle::OnClick));
+        }
+        let show_all_text = if explorer.shows_all() { "Folders Only" } else { "Show All" };
+        let show_all = Element::new(Coordinate::new(0.43, 0.0), Coordinate::new(0.49, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, show_all_text, 0.7)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ToggleExplorerShowAll), InteractionStyle::OnClick);
+
+        header.add_element(element1);
+        header.add_element(backup_toggle);
+        header.add_element(refresh);
+        header.add_element(filter);
+        header.add_element(new_project);
+        header.add_element(show_all);
+
+        // Keeps the listing open next to the project view, for opening one project after another
+        if !detached {
+            let detach = Element::new(Coordinate::new(0.495, 0.0), Coordinate::new(0.535, 1.0), "solid")
+                .with_color_role(ColorRole::Header)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Detach", 0.7)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_tooltip("Open the explorer in a window of its own")
+                .with_fn(|| Some(GuiEvent::DetachPage(pages::FILE_EXPLORER.to_string())), InteractionStyle::OnClick);
+            header.add_element(detach);
+        }
+
+        // In the header rather than under the last row, so rows loaded in chunks are appended after the others.
+        // `step_explorer_loading` hides it once they all are.
+        if explorer.is_scanning() || explorer_rows < explorer.entries().len() {
+            let loading = Element::spinner(Coordinate::new(0.54, 0.1), Coordinate::new(0.56, 0.9))
+                .with_id(EXPLORER_LOADING)
+                .with_color_role(ColorRole::Header)
+                .with_tooltip("Listing projects");
+            header.add_element(loading);
+        }
+
+        if let Some(branch) = explorer.git_status().and_then(|status| status.branch()) {
+            let branch_label = Element::new(Coordinate::new(0.85, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_color_role(ColorRole::Header)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Right }, &format!("Branch: {}", branch), 0.7);
+            header.add_element(branch_label);
+        }
+
+        interface.add_panel(header);
+
+        interface.add_panel(panel);
+
+        interface
+    }
+
+    fn build_accessibility_interface(ctx: &PageCtx) -> UiTree<GuiEvent> {
+        let PageCtx { atlas, config, theme, .. } = *ctx;
+        let mut interface = UiTree::new(atlas.clone());
+
+        let mut header = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::mixed(1.0, Dimension::Pixels(HEADER_HEIGHT)))
+            .with_color_role(ColorRole::Header);
+        let back = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.025, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Back", 0.7)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::Navigate(pages::PROJECT_VIEW.to_string())), InteractionStyle::OnClick);
+        header.add_element(back);
+        interface.add_panel(header);
+
+        let mut panel = Panel::new(Coordinate::new(0.3, 0.1), Coordinate::new(0.7, 0.65))
+            .with_color_role(ColorRole::Panel);
+
+        let title = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.08), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Accessibility", 0.9);
+        panel.add_element(title);
+
+        let palette_label = Element::new(Coordinate::new(0.0, 0.1), Coordinate::new(0.4, 0.16), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Overlay palette", 0.7);
+        panel.add_element(palette_label);
+
+        let preset_width = 0.6 / PalettePreset::ALL.len() as f32;
+        for (index, &preset) in PalettePreset::ALL.iter().enumerate() {
+            let x = 0.4 + index as f32 * preset_width;
+            let color = if preset == config.accessibility.palette { ColorRole::Accent } else { ColorRole::Header };
+            let button = Element::new(Coordinate::new(x + 0.005, 0.1), Coordinate::new(x + preset_width - 0.005, 0.16), "solid")
+                .with_color_role(color)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, preset.label(), 0.6)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(move || Some(GuiEvent::SelectPalettePreset(preset)), InteractionStyle::OnClick);
+            panel.add_element(button);
+        }
+
+        let motion_label = Element::new(Coordinate::new(0.0, 0.18), Coordinate::new(0.4, 0.24), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Reduce motion", 0.7);
+        // The rest of the row, the label for following the OS is long
+        let motion_toggle = Element::new(Coordinate::new(0.405, 0.18), Coordinate::new(0.995, 0.24), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, &config.accessibility.reduce_motion_label(), 0.6)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ToggleReduceMotion), InteractionStyle::OnClick);
+        panel.add_element(motion_label);
+        panel.add_element(motion_toggle);
+
+        let mut y = 0.28;
+        for overlay in OverlayColor::ALL {
+            let label = Element::new(Coordinate::new(0.0, y), Coordinate::new(0.4, y + 0.06), "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, overlay.label(), 0.6);
+            let swatch = Element::new(Coordinate::new(0.405, y + 0.01), Coordinate::new(0.5, y + 0.05), "solid")
+                .with_color(theme.overlay(overlay));
+            panel.add_element(label);
+            panel.add_element(swatch);
+            y += 0.07;
+        }
+
+        let hint = Element::new(Coordinate::new(0.0, y + 0.02), Coordinate::new(1.0, y + 0.08), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Custom colors: [accessibility.overlay_colors] in editor_config.toml", 0.5);
+        panel.add_element(hint);
+
+        interface.add_panel(panel);
+        interface
+    }
+
+    fn display_startup_banner(mut interface: UiTree<GuiEvent>, startup: &StartupReport) -> UiTree<GuiEvent> {
+        let mut notices = Vec::new();
+        if startup.safe_mode {
+            notices.push("Safe mode: plugins, scripts and session restore are disabled.".to_string());
+        }
+        notices.extend(startup.recovered.iter().map(|file| file.describe()));
+
+        let line_height = 1.0 / notices.len() as f32;
+        let banner_bottom = HEADER_HEIGHT * (notices.len() + 1) as f32;
+        let mut banner = Panel::new(Coordinate::mixed(0.0, Dimension::Pixels(HEADER_HEIGHT)), Coordinate::mixed(1.0, Dimension::Pixels(banner_bottom)))
+            .with_color("#5a1d1dff")
+            .with_layer(BANNER_LAYER);
+
+        for (i, notice) in notices.iter().enumerate() {
+            let line = Element::new(Coordinate::new(0.0, i as f32 * line_height), Coordinate::new(0.8, (i + 1) as f32 * line_height), "solid")
+                .with_color("#5a1d1dff")
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, notice, 0.6);
+            banner.add_element(line);
+        }
+
+        if startup.backup_location().is_some() {
+            let open_backup = Element::new(Coordinate::new(0.8, 0.0), Coordinate::new(0.9, line_height), "solid")
+                .with_color_role(ColorRole::Header)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(|| Some(GuiEvent::OpenBackupLocation), InteractionStyle::OnClick)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Open Backups", 0.6);
+            banner.add_element(open_backup);
+        }
+
+        let dismiss = Element::new(Coordinate::new(0.9, 0.0), Coordinate::new(1.0, line_height), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::DismissStartupBanner), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Dismiss", 0.6);
+        banner.add_element(dismiss);
+
+        interface.add_panel(banner);
+        interface
+    }
+
+    fn display_crash_report(mut interface: UiTree<GuiEvent>, startup: &StartupReport) -> UiTree<GuiEvent> {
+        let report_name = startup.crash_report.as_ref()
+            .and_then(|report| report.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let lines = [
+            "The editor crashed during the last session.".to_string(),
+            format!("A report was saved as {} in {}.", report_name, crash::CRASH_DIR),
+        ];
+
+        let row_height = 1.0 / (lines.len() + 1) as f32;
+        let mut dialog = Panel::new(Coordinate::new(0.35, 0.35), Coordinate::new(0.65, 0.35 + 0.025 * (lines.len() + 1) as f32))
+            .with_color_role(ColorRole::Panel)
+            .with_layer(DIALOG_LAYER)
+            .with_border(1.0, "#30363dff")
+            .with_clipping();
+        for (i, line) in lines.iter().enumerate() {
+            let text = Element::new(Coordinate::new(0.0, i as f32 * row_height), Coordinate::new(1.0, (i + 1) as f32 * row_height), "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, line, 0.6);
+            dialog.add_element(text);
+        }
+
+        let buttons_y = lines.len() as f32 * row_height;
+        let open = Element::new(Coordinate::new(0.5, buttons_y), Coordinate::new(0.75, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::OpenCrashReportLocation), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Open Location", 0.6);
+        let dismiss = Element::new(Coordinate::new(0.75, buttons_y), Coordinate::new(1.0, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::DismissCrashReport), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Dismiss", 0.6);
+        dialog.add_element(open);
+        dialog.add_element(dismiss);
+
+        interface.add_panel(dialog);
+        interface
+    }
+
+    fn display_new_project(mut interface: UiTree<GuiEvent>, name: &str, error: Option<&str>) -> UiTree<GuiEvent> {
+        let mut dialog = Panel::new(Coordinate::new(0.35, 0.4), Coordinate::new(0.65, 0.5))
+            .with_color_role(ColorRole::Panel)
+            .with_layer(DIALOG_LAYER)
+            .with_border(1.0, "#30363dff")
+            .with_clipping();
+
+        let title = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.3), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "New project", 0.7);
+        // Creates on Enter too, see the KeyboardInput arm of window_event
+        let name_input = Element::text_input(Coordinate::new(0.02, 0.33), Coordinate::new(0.98, 0.6))
+            .with_id(NEW_PROJECT_NAME)
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, name, 0.6);
+        let create = Element::new(Coordinate::new(0.5, 0.7), Coordinate::new(0.75, 1.0), "solid")
+            .with_color_role(ColorRole::Accent)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::CreateProject), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Create", 0.6);
+        let cancel = Element::new(Coordinate::new(0.75, 0.7), Coordinate::new(1.0, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::CancelDialog), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Cancel", 0.6);
+        dialog.add_element(title);
+        dialog.add_element(name_input);
+        if let Some(error) = error {
+            let message = Element::new(Coordinate::new(0.0, 0.7), Coordinate::new(0.5, 1.0), "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_style(Style::new().with_text_color_role(ColorRole::Error))
+                .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, error, 0.5, Wrap::TruncateWithEllipsis);
+            dialog.add_element(message);
+        }
+        dialog.add_element(create);
+        dialog.add_element(cancel);
+
+        interface.add_panel(dialog);
+        // Ready to type into as soon as it opens
+        let name_input = interface.element_index(NEW_PROJECT_NAME);
+        interface.focus(name_input);
+        interface
+    }
+
+    fn display_confirm_delete(mut interface: UiTree<GuiEvent>, explorer: &ExplorerState) -> UiTree<GuiEvent> {
+        const LISTED: usize = 5;
+        let selection = explorer.selection();
+
+        let mut lines = vec![format!("Delete {} item{}? This can't be undone.", selection.len(), if selection.len() == 1 { "" } else { "s" })];
+        lines.extend(selection.iter().take(LISTED).map(|path| path.file_name().unwrap_or_default().to_string_lossy().into_owned()));
+        if selection.len() > LISTED {
+            lines.push(format!("...and {} more", selection.len() - LISTED));
+        }
+
+        let row_height = 1.0 / (lines.len() + 1) as f32;
+        let mut dialog = Panel::new(Coordinate::new(0.35, 0.35), Coordinate::new(0.65, 0.35 + 0.025 * (lines.len() + 1) as f32))
+            .with_color_role(ColorRole::Panel)
+            .with_layer(DIALOG_LAYER)
+            .with_border(1.0, "#30363dff")
+            .with_clipping();
+        for (i, line) in lines.iter().enumerate() {
+            let text = Element::new(Coordinate::new(0.0, i as f32 * row_height), Coordinate::new(1.0, (i + 1) as f32 * row_height), "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, line, 0.6);
+            dialog.add_element(text);
+        }
+
+        let buttons_y = lines.len() as f32 * row_height;
+        let delete = Element::new(Coordinate::new(0.5, buttons_y), Coordinate::new(0.75, 1.0), "solid")
+            .with_color("#5a1d1dff")
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ConfirmDeleteSelection), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Delete", 0.6);
+        let cancel = Element::new(Coordinate::new(0.75, buttons_y), Coordinate::new(1.0, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::CancelDialog), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Cancel", 0.6);
+        dialog.add_element(delete);
+        dialog.add_element(cancel);
+
+        interface.add_panel(dialog);
+        interface
+    }
+
+    /// Asks before closing with unsaved changes. A backdrop over the whole window keeps clicks and the
+    /// viewport's input away from everything behind the dialog.
+    fn display_confirm_exit(mut interface: UiTree<GuiEvent>) -> UiTree<GuiEvent> {
+        let backdrop = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0))
+            .with_color("#00000080")
+            .with_layer(DIALOG_LAYER);
+        interface.add_panel(backdrop);
+
+        let mut dialog = Panel::new(Coordinate::new(0.35, 0.4), Coordinate::new(0.65, 0.475))
+            .with_color_role(ColorRole::Panel)
+            .with_layer(DIALOG_LAYER)
+            .with_border(1.0, "#30363dff")
+            .with_clipping();
+
+        let title = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0 / 3.0), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Exit the editor?", 0.7);
+        let message = Element::new(Coordinate::new(0.0, 1.0 / 3.0), Coordinate::new(1.0, 2.0 / 3.0), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Unsaved changes will be lost.", 0.6);
+        let exit = Element::new(Coordinate::new(0.5, 2.0 / 3.0), Coordinate::new(0.75, 1.0), "solid")
+            .with_color("#5a1d1dff")
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ConfirmExit), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Exit", 0.6);
+        let cancel = Element::new(Coordinate::new(0.75, 2.0 / 3.0), Coordinate::new(1.0, 1.0), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::CancelDialog), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Cancel", 0.6);
+        dialog.add_element(title);
+        dialog.add_element(message);
+        dialog.add_element(exit);
+        dialog.add_element(cancel);
+
+        interface.add_panel(dialog);
+        interface
+    }
+
+    /// A strip along the bottom right showing how far packing the assets has got, above everything but dialogs.
+    /// Input goes through it to whatever is underneath, the preview included.
+    /// Dims the whole window while files are dragged over it and says what dropping them does.
+    /// The History panel, built into the project view as a floating window hidden unless `visible`, like the settings
+    /// menu. Lists `snapshots` newest first with the `previewed` one highlighted, clicking one previews it.
+    fn display_history(mut interface: UiTree<GuiEvent>, snapshots: &[Snapshot], previewed: Option<&Path>, visible: bool) -> UiTree<GuiEvent> {
+        let mut list = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0))
+            .with_color_role(ColorRole::Panel)
+            .with_layout(Layout::Column { spacing: 2.0, padding: 4.0 })
+            .with_scroll(ScrollDirection::Vertical);
+        if snapshots.is_empty() {
+            list.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(HISTORY_ROW_HEIGHT))
+                .with_color_role(ColorRole::Panel)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Snapshots are taken as you edit", TextStyle::new().with_scale(0.55).with_color_role(ColorRole::TextSecondary)));
+        }
+        let now = SystemTime::now();
+        for snapshot in snapshots {
+            let path = snapshot.path.clone();
+            let color = if previewed == Some(snapshot.path.as_path()) { ColorRole::Accent } else { ColorRole::Header };
+            list.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(HISTORY_ROW_HEIGHT))
+                .with_color_role(color)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(move || Some(GuiEvent::PreviewSnapshot(path.clone())), InteractionStyle::OnClick)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &format!("{}  ({} KB)", snapshot.relative_age(now), snapshot.size.div_ceil(1024)), 0.6));
+        }
+
+        let window = FloatingWindow::new(HISTORY_PANEL, "History", Coordinate::new(0.72, 0.15), Coordinate::new(0.95, 0.6), list)
+            .with_min_size(200.0, TITLE_BAR_HEIGHT + HISTORY_ROW_HEIGHT)
+            .with_close_event(GuiEvent::ToggleHistory)
+            .with_visible(visible);
+        interface.add_floating_window(window);
+        interface
+    }
+
+    /// Says over the preview that it shows `snapshot` rather than the level, with its Restore and Close buttons.
+    fn display_preview_banner(mut interface: UiTree<GuiEvent>, snapshot: &Snapshot) -> UiTree<GuiEvent> {
+        let mut banner = Panel::new(Coordinate::mixed(0.0, Dimension::Pixels(HEADER_HEIGHT)), Coordinate::mixed(1.0, Dimension::Pixels(2.0 * HEADER_HEIGHT)))
+            .with_color_role(ColorRole::Accent)
+            .with_layer(BANNER_LAYER);
+        let message = format!("Previewing the snapshot from {}, read-only", snapshot.relative_age(SystemTime::now()));
+        banner.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.8, 1.0), "solid")
+            .with_color_role(ColorRole::Accent)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &message, TextStyle::new().with_scale(0.6).with_color("#ffffffff")));
+        let buttons = [(0.8, "Restore", GuiEvent::RestoreSnapshot), (0.9, "Close", GuiEvent::ClosePreview)];
+        for (x, label, event) in buttons {
+            banner.add_element(Element::new(Coordinate::new(x, 0.0), Coordinate::new(x + 0.1, 1.0), "solid")
+                .with_color_role(ColorRole::Header)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(move || Some(event.clone()), InteractionStyle::OnClick)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, label, 0.6));
+        }
+        interface.add_panel(banner);
+        interface
+    }
+
+    fn display_drop_overlay(mut interface: UiTree<GuiEvent>, message: &str) -> UiTree<GuiEvent> {
+        let mut overlay = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0))
+            .with_color("#000000a0")
+            .with_layer(DROP_LAYER)
+            .with_input_passthrough();
+        overlay.add_element(Element::new(Coordinate::new(0.3, 0.44), Coordinate::new(0.7, 0.56), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_border(2.0, "#30363dff")
+            .with_corner_radius(6.0)
+            .with_text_wrapped(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, message, 0.9, Wrap::TruncateWithEllipsis));
+        interface.add_panel(overlay);
+        interface
+    }
+
+    fn display_asset_reload(mut interface: UiTree<GuiEvent>, progress: f32) -> UiTree<GuiEvent> {
+        let mut status = Panel::new(Coordinate::new(0.7, 0.92), Coordinate::new(0.98, 0.98))
+            .with_color_role(ColorRole::Panel)
+            .with_layer(MENU_LAYER)
+            .with_border(1.0, "#30363dff")
+            .with_input_passthrough();
+
+        let label = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.4, 1.0), "solid")
+            .with_color_role(ColorRole::Panel)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Reloading assets", 0.6);
+        let bar = Element::progress_bar(Coordinate::new(0.42, 0.3), Coordinate::new(0.97, 0.7), progress)
+            .with_id(ASSET_RELOAD_PROGRESS)
+            .with_color_role(ColorRole::Header);
+        status.add_element(label);
+        status.add_element(bar);
+
+        interface.add_panel(status);
+        interface
+    }
+
+    /// Built into every page as a floating window, hidden unless `visible`, so `show_settings_menu` can open and close
+    /// it without a rebuild. Where it was dragged to is kept over rebuilds.
+    /// Shows `config` as it is, the settings events change it and rebuild.
+    fn display_settings_menu(mut interface: UiTree<GuiEvent>, config: &EditorConfig, visible: bool) -> UiTree<GuiEvent> {
+        const ROWS: usize = 7;
+        let row_height = 1.0 / ROWS as f32;
+        let row = |i: usize| (i as f32 * row_height, (i + 1) as f32 * row_height);
+        let mut dialog = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0))
+            .with_color_role(ColorRole::Panel)
+            .with_clipping();
+
+        // Labels on the left, controls from 0.45 across
+        let labels = ["Font scale", "VSync", "Overlay palette", "Theme", "Assets folder"];
+        for (i, label) in labels.into_iter().enumerate() {
+            let (top, bottom) = row(i);
+            dialog.add_element(Element::new(Coordinate::new(0.0, top), Coordinate::new(0.44, bottom), "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, label, TextStyle::new().with_scale(0.6).with_color_role(ColorRole::TextSecondary)));
+        }
+
+        let (top, bottom) = row(0);
+        for (x_min, x_max, label, step) in [(0.45, 0.53, "-", -FONT_SCALE_STEP), (0.92, 1.0, "+", FONT_SCALE_STEP)] {
+            dialog.add_element(Element::new(Coordinate::new(x_min, top), Coordinate::new(x_max, bottom), "solid")
+                .with_color_role(ColorRole::Header)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(move || Some(GuiEvent::AdjustFontScale(step)), InteractionStyle::OnClick)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, label, 0.6));
+        }
+        // Applied on Enter or Save, see `commit_settings_inputs`
+        dialog.add_element(Element::text_input(Coordinate::new(0.54, top), Coordinate::new(0.91, bottom))
+            .with_id(FONT_SCALE_INPUT)
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &format!("{:.2}", config.font_scale()), 0.6));
+
+        let (top, bottom) = row(1);
+        dialog.add_element(Element::new(Coordinate::new(0.45, top), Coordinate::new(1.0, bottom), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ToggleVsync), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, if config.render.vsync() { "On" } else { "Off" }, 0.6));
+
+        let (top, bottom) = row(2);
+        dialog.add_element(Element::new(Coordinate::new(0.45, top), Coordinate::new(1.0, bottom), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::CyclePalettePreset), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, config.accessibility.palette.label(), 0.6));
+
+        let (top, bottom) = row(3);
+        dialog.add_element(Element::new(Coordinate::new(0.45, top), Coordinate::new(1.0, bottom), "solid")
+            .with_id(THEME_TOGGLE)
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::ToggleTheme), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, config.appearance.theme.label(), 0.6));
+
+        let (top, bottom) = row(4);
+        dialog.add_element(Element::text_input(Coordinate::new(0.45, top), Coordinate::new(1.0, bottom))
+            .with_id(ASSETS_DIR_INPUT)
+            .with_color_role(ColorRole::Header)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &config.assets_dir.display().to_string(), 0.6));
+
+        let (top, bottom) = row(5);
+        let links = [
+            (0.0, 0.5, "Accessibility...", GuiEvent::Navigate(pages::ACCESSIBILITY_SETTINGS.to_string())),
+            (0.5, 1.0, "Reload assets", GuiEvent::ReloadAssets),
+        ];
+        for (x_min, x_max, label, event) in links {
+            dialog.add_element(Element::new(Coordinate::new(x_min, top), Coordinate::new(x_max, bottom), "solid")
+                .with_color_role(ColorRole::Panel)
+                .with_hover_color_role(ColorRole::Hover)
+                .with_fn(move || Some(event.clone()), InteractionStyle::OnClick)
+                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, label, 0.6));
+        }
+
+        let (top, bottom) = row(6);
+        // White in both themes, the light theme's dark text would be lost on the accent fill
+        let save = Element::new(Coordinate::new(0.5, top), Coordinate::new(0.75, bottom), "solid")
+            .with_color_role(ColorRole::Accent)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::SaveSettings), InteractionStyle::OnClick)
+            .with_text(
+                Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center },
+                "Save",
+                TextStyle::new().with_scale(0.6).with_color("#ffffffff").with_weight_hint(FontWeight::Bold).with_shadow((1.0, 1.0), "#00000099"),
+            );
+        let cancel = Element::new(Coordinate::new(0.75, top), Coordinate::new(1.0, bottom), "solid")
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Hover)
+            .with_fn(|| Some(GuiEvent::CancelSettings), InteractionStyle::OnClick)
+            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Cancel", 0.6);
+        dialog.add_element(save);
+        dialog.add_element(cancel);
+
+        let window = FloatingWindow::new(
+            SETTINGS_MENU,
+            "Settings",
+            Coordinate::new(0.35, 0.25),
+            Coordinate::mixed(0.65, Dimension::Anchored { fraction: 0.25 + 0.03 * ROWS as f32, pixels: TITLE_BAR_HEIGHT }),
+            dialog,
+        )
+            .with_min_size(320.0, TITLE_BAR_HEIGHT + 20.0 * ROWS as f32)
+            .with_max_size(960.0, TITLE_BAR_HEIGHT + 48.0 * ROWS as f32)
+            .with_close_event(GuiEvent::CancelSettings)
+            .with_visible(visible);
+        interface.add_floating_window(window);
+        interface
+    }
+
+    /// Handles the events elements and shortcuts sent, from the main window or a detached one.
+    /// What has to happen to the interface afterwards is added to `pending`.
+    fn handle_gui_events(&mut self, event_loop: &ActiveEventLoop, events: Vec<GuiEvent>, pending: &mut PendingChanges) {
+        for event in events {
+            println!("Received GUI event: {:?}", event);
+            crash::record(event.breadcrumb(&self.config.projects_dir));
+            match event {
+                GuiEvent::Navigate(page) => {
+                    if let Some(detached) = self.detached.values().find(|detached| detached.page == page) {
+                        // The page is open in a window of its own, that's brought forward instead
+                        detached.window.focus_window();
+                    } else if self.page != page {
+                        // Leaving through the settings menu's Accessibility link drops its unsaved changes
+                        self.cancel_settings();
+                        self.menu_open = (false, None);
+                        if page == pages::FILE_EXPLORER {
+                            self.explorer.invalidate_git_status();
+                        }
+                        pending.page_change = Some(page);
+                    }
+                }
+                GuiEvent::SelectPalettePreset(preset) => {
+                    if preset != self.config.accessibility.palette {
+                        self.config.accessibility.palette = preset;
+                        self.theme = Theme::from_config(&self.config.accessibility);
+                        if let Err(e) = self.config.save() {
+                            log::error!("{}", e);
+                        }
+                        pending.rebuild = true;
+                    }
+                }
+                GuiEvent::ToggleReduceMotion => {
+                    self.config.accessibility.cycle_reduce_motion();
+                    if let Err(e) = self.config.save() {
+                        log::error!("{}", e);
+                    }
+                    pending.rebuild = true;
+                }
+                GuiEvent::RequestExit => {
+                    if !self.has_unsaved_changes() {
+                        event_loop.exit();
+                    } else if self.menu_open == (true, Some(GuiMenuState::ConfirmExit)) {
+                        // Already asking, bring it to the front rather than asking twice
+                        if let Some(window) = self.window_ref.as_ref() {
+                            window.focus_window();
+                        }
+                    } else {
+                        pending.menu_change = Some((true, Some(GuiMenuState::ConfirmExit)));
+                    }
+                }
+                GuiEvent::ConfirmExit => event_loop.exit(),
+                GuiEvent::DisplaySettingsMenu => {
+                    if self.menu_open != (true, Some(GuiMenuState::SettingsMenu)) {
+                        self.settings_backup = Some(self.config.clone());
+                        pending.menu_change = Some((true, Some(GuiMenuState::SettingsMenu)));
+                    }
+                }
+                GuiEvent::AdjustFontScale(step) => {
+                    self.commit_settings_inputs();
+                    self.config.set_font_scale(self.config.font_scale() + step);
+                    pending.rebuild = true;
+                }
+                GuiEvent::ToggleVsync => {
+                    self.commit_settings_inputs();
+                    self.config.render.set_vsync(!self.config.render.vsync());
+                    let present_mode = self.config.render.present_mode.present_mode();
+                    for rs in self.render_state.iter_mut().chain(self.detached.values_mut().map(|detached| &mut detached.render_state)) {
+                        rs.set_present_mode(present_mode);
+                    }
+                    pending.rebuild = true;
+                }
+                GuiEvent::CyclePalettePreset => {
+                    self.commit_settings_inputs();
+                    self.config.accessibility.palette = self.config.accessibility.palette.next();
+                    self.theme = Theme::from_config(&self.config.accessibility);
+                    pending.rebuild = true;
+                }
+                // Recolors the interface in place, only the toggle's label changes in the tree
+                GuiEvent::ToggleTheme => {
+                    self.config.appearance.theme = self.config.appearance.theme.toggled();
+                    self.ui_theme = self.config.appearance.ui_theme();
+                    self.interface.set_theme(self.ui_theme.clone());
+                    if let Some(toggle) = self.interface.element_mut(THEME_TOGGLE) {
+                        toggle.set_text(self.config.appearance.theme.label());
+                    }
+                    if let Some(rs) = self.render_state.as_mut() {
+                        rs.update_interface(&mut self.interface);
+                    }
+                    for detached in self.detached.values_mut() {
+                        detached.interface.set_theme(self.ui_theme.clone());
+                        detached.render_state.update_interface(&mut detached.interface);
+                    }
+                    pending.redraw = true;
+                }
+                GuiEvent::ApplySettingsInput => {
+                    self.commit_settings_inputs();
+                    pending.rebuild = true;
+                }
+                GuiEvent::SaveSettings => {
+                    self.commit_settings_inputs();
+                    let assets_changed = self.settings_backup.take()
+                        .is_some_and(|saved| saved.assets_dir != self.config.assets_dir);
+                    if let Err(e) = self.config.save() {
+                        log::error!("{}", e);
+                    }
+                    if assets_changed {
+                        self.reload_assets();
+                    }
+                    self.menu_open = (false, None);
+                    pending.rebuild = true;
+                }
+                GuiEvent::CancelSettings => {
+                    self.cancel_settings();
+                    self.menu_open = (false, None);
+                    pending.rebuild = true;
+                }
+                GuiEvent::OpenBackupLocation => {
+                    if let Some(location) = self.startup.backup_location()
+                        && let Err(e) = platform::open_in_file_manager(&location) {
+                        log::error!("Unable to open backup location {:?}: {}", location, e);
+                    }
+                }
+                GuiEvent::ToggleBackupFiles => {
+                    self.config.show_backups_in_explorer = !self.config.show_backups_in_explorer;
+                    if let Err(e) = self.config.save() {
+                        log::error!("{}", e);
+                    }
+                    pending.rebuild = true;
+                }
+                GuiEvent::SelectExplorerEntry => {
+                    if let Some(interface) = self.page_interface(pages::FILE_EXPLORER) {
+                        let ids: Vec<String> = interface.selection().iter().filter_map(|handle| handle.id().map(str::to_string)).collect();
+                        let selected: Vec<PathBuf> = self.explorer.entries().iter()
+                            .filter(|path| ids.contains(&explorer_row_id(path)))
+                            .cloned()
+                            .collect();
+                        self.explorer.set_selection(&selected);
+                    }
+                    // Rows' menus and the spacer beside them follow the selection
+                    pending.rebuild = true;
+                }
+                GuiEvent::OpenSelection => {
+                    if self.shows_page(pages::FILE_EXPLORER) && !self.menu_open.0 {
+                        match self.explorer.selection() {
+                            [] => {}
+                            [path] => {
+                                let path = path.clone();
+                                self.open_project(&path, pending);
+                            }
+                            _ => self.show_toast(ToastLevel::Info, "Select a single project to open".to_string()),
+                        }
+                    }
+                }
+                GuiEvent::OpenProject(path) => self.open_project(&path, pending),
+                GuiEvent::RemoveRecentProject(path) => {
+                    if self.recent_projects.remove(&path) {
+                        self.save_recent_projects();
+                        pending.page_rebuild = true;
+                    }
+                }
+                GuiEvent::RequestDelete(path) => {
+                    // The dialog lists and deletes the selection, a row right-clicked outside it is deleted alone
+                    if !self.explorer.is_selected(&path) {
+                        self.explorer.select_only(&path);
+                    }
+                    pending.menu_change = Some((true, Some(GuiMenuState::ConfirmDelete)));
+                }
+                GuiEvent::RequestDeleteSelection => {
+                    if self.shows_page(pages::FILE_EXPLORER) && !self.menu_open.0 && !self.explorer.selection().is_empty() {
+                        pending.menu_change = Some((true, Some(GuiMenuState::ConfirmDelete)));
+                    }
+                }
+                GuiEvent::ConfirmDeleteSelection => {
+                    let failed = self.explorer.delete_selection();
+                    for (path, e) in &failed {
+                        log::error!("Unable to delete {:?}: {}", path, e);
+                    }
+                    if let [(path, e), rest @ ..] = failed.as_slice() {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        match rest.len() {
+                            0 => self.show_toast(ToastLevel::Error, format!("Unable to delete {}: {}", name, e)),
+                            more => self.show_toast(ToastLevel::Error, format!("Unable to delete {} and {} more: {}", name, more, e)),
+                        }
+                    }
+                    self.explorer.invalidate_git_status();
+                    pending.menu_change = Some((false, None));
+                }
+                GuiEvent::MoveSelectionTo(target) => {
+                    for (path, e) in self.explorer.move_selection_to(&target) {
+                        log::error!("Unable to move {:?}: {}", path, e);
+                    }
+                    self.explorer.invalidate_git_status();
+                    pending.rebuild = true;
+                }
+                GuiEvent::RenameEntry(path) => {
+                    self.explorer.start_rename(&path);
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::CommitRename => {
+                    match self.explorer.finish_rename() {
+                        Some(Ok(path)) => log::info!("Renamed to {:?}", path),
+                        Some(Err(e)) => {
+                            log::error!("Unable to rename: {}", e);
+                            self.show_toast(ToastLevel::Error, format!("Unable to rename: {}", e));
+                        }
+                        None => {}
+                    }
+                    self.explorer.invalidate_git_status();
+                    pending.rebuild = true;
+                }
+                GuiEvent::CancelRename => {
+                    self.explorer.cancel_rename();
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::RefreshExplorer => {
+                    self.explorer.invalidate_git_status();
+                    pending.rebuild = true;
+                }
+                GuiEvent::SetExplorerSort(sort) => {
+                    self.explorer.set_sort(sort);
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::ToggleExplorerShowAll => {
+                    self.explorer.set_show_all(!self.explorer.shows_all());
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::NewProject => {
+                    self.new_project_name.clear();
+                    self.new_project_error = None;
+                    pending.menu_change = Some((true, Some(GuiMenuState::NewProjectMenu)));
+                }
+                GuiEvent::CreateProject => {
+                    self.new_project_name = self.interface
+                        .element(NEW_PROJECT_NAME)
+                        .and_then(Element::text)
+                        .unwrap_or_default()
+                        .to_string();
+                    match Project::create(&self.config.projects_dir, &self.new_project_name) {
+                        Ok(project) => {
+                            log::info!("Created project {:?} in {:?}", project.name, project.root);
+                            self.current_project = Some(project);
+                            self.start_level();
+                            self.new_project_error = None;
+                            self.menu_open = (false, None);
+                            self.explorer.invalidate_git_status();
+                            pending.page_change = Some(pages::PROJECT_VIEW.to_string());
+                        }
+                        Err(e) => {
+                            // Shown in the dialog, rebuilt with the rejected name still typed in
+                            self.new_project_error = Some(e.to_string());
+                            pending.rebuild = true;
+                        }
+                    }
+                }
+                GuiEvent::CancelDialog => {
+                    // Cancelling the exit confirmation goes back to the settings it interrupted
+                    pending.menu_change = Some(match self.settings_backup {
+                        Some(_) => (true, Some(GuiMenuState::SettingsMenu)),
+                        None => (false, None),
+                    });
+                }
+                GuiEvent::OpenCrashReportLocation | GuiEvent::DismissCrashReport => {
+                    if event == GuiEvent::OpenCrashReportLocation
+                        && let Err(e) = platform::open_in_file_manager(crash::CRASH_DIR.as_ref()) {
+                        log::error!("Unable to open {}: {}", crash::CRASH_DIR, e);
+                    }
+                    if let Err(e) = crash::mark_reviewed() {
+                        log::error!("Unable to mark crash reports as reviewed: {}", e);
+                    }
+                    self.startup.crash_report = None;
+                    pending.menu_change = Some((false, None));
+                }
+                GuiEvent::ReloadAssets => {
+                    self.reload_assets();
+                    self.load_project_tiles();
+                    pending.rebuild = true;
+                }
+                GuiEvent::ReloadShaders => {
+                    if self.render_state.is_some() {
+                        // Failures are logged by `reload_shaders`, the old pipelines keep drawing
+                        let result = self.render_state.iter_mut()
+                            .chain(self.detached.values_mut().map(|detached| &mut detached.render_state))
+                            .map(RenderState::reload_shaders)
+                            .fold(Ok(()), Result::and);
+                        match result {
+                            Ok(()) => self.show_toast(ToastLevel::Info, "Shaders reloaded".to_string()),
+                            Err(e) => self.show_toast(ToastLevel::Error, e.to_string()),
+                        }
+                    }
+                }
+                GuiEvent::Copy => {
+                    if self.shows_page(pages::FILE_EXPLORER) && !self.explorer.selection().is_empty() {
+                        let text = clipboard::path_text(self.explorer.selection());
+                        self.copy_to_clipboard(&text);
+                    }
+                }
+                GuiEvent::CopyPath(path) => self.copy_to_clipboard(&clipboard::path_text(&[path])),
+                // Outside a text input there's nothing to cut or paste into, in one they're handled as keys
+                GuiEvent::Cut | GuiEvent::Paste => {}
+                GuiEvent::DumpDiagnostics => {
+                    if let Some(rs) = self.render_state.as_ref() {
+                        let diagnostics = rs.diagnostics();
+                        log::info!("Render diagnostics:\n{}", diagnostics);
+                        self.show_toast(ToastLevel::Info, diagnostics.summary());
+                    }
+                }
+                GuiEvent::DetachPage(page) => {
+                    if let Some(detached) = self.detached.values().find(|detached| detached.page == page) {
+                        detached.window.focus_window();
+                    } else if let Err(e) = self.detach_page(event_loop, &page, pending) {
+                        log::error!("Unable to open a window for {}: {}", page, e);
+                        self.show_toast(ToastLevel::Error, format!("Unable to open a new window: {}", e));
+                    }
+                }
+                GuiEvent::SelectTile(id) => {
+                    self.selected_tile = Some(id);
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::SaveLevel => {
+                    if self.save_level() {
+                        pending.page_rebuild = true;
+                    }
+                }
+                GuiEvent::RevertToBackup(path) => self.revert_to_backup(&path),
+                GuiEvent::ToggleHistory => {
+                    self.history_open = !self.history_open;
+                    self.snapshots = self.history.as_ref().map(History::list).unwrap_or_default();
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::PreviewSnapshot(path) => {
+                    self.preview_snapshot(&path);
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::RestoreSnapshot => {
+                    self.restore_snapshot();
+                    pending.page_rebuild = true;
+                }
+                GuiEvent::ClosePreview => {
+                    if self.close_history_preview() {
+                        pending.page_rebuild = true;
+                    }
+                }
+                // The level isn't shown while a snapshot is previewed, so neither would the undo be
+                GuiEvent::Undo | GuiEvent::Redo if self.history_preview.is_some() => {
+                    self.show_toast(ToastLevel::Info, "Close the snapshot preview to undo or redo".to_string());
+                }
+                GuiEvent::Undo | GuiEvent::Redo => {
+                    let was_modified = self.level_modified();
+                    let changed = match event {
+                        GuiEvent::Undo => self.undo.undo(&mut self.level),
+                        _ => self.undo.redo(&mut self.level),
+                    };
+                    if changed {
+                        self.level_edited(was_modified);
+                    }
+                }
+                GuiEvent::DismissStartupBanner => {
+                    self.banner_open = false;
+                    pending.rebuild = true;
+                }
+                GuiEvent::ToggleFullscreen => {
+                    if let Some(window) = self.window_ref.as_ref() {
+                        window.set_fullscreen(match window.fullscreen() {
+                            Some(_) => None,
+                            None => Some(Fullscreen::Borderless(None)),
+                        });
+                    }
+                }
+                GuiEvent::ToggleDebugOverlay => {
+                    if self.debug_overlay_due.take().is_none() {
+                        // Refreshed straight away by `about_to_wait`
+                        self.debug_overlay_due = Some(Instant::now());
+                    } else if let Some(rs) = self.render_state.as_mut()
+                        && self.interface.hide_debug_overlay() {
+                        rs.update_interface(&mut self.interface);
+                    }
+                }
+                // Swapped for the menu's own cancel event when the key is pressed, see `close_menu_event`
+                GuiEvent::CloseMenu => {}
+            }
+            pending.redraw = true;
+        }
+    }
+
+    /// Switches page, opens or closes menus, rebuilds and asks for a frame as handling events decided.
+    fn apply_pending(&mut self, mut pending: PendingChanges) {
+        if let Some(new_page) = pending.page_change {
+            // A detached explorer keeps its selection and rows whatever the main window shows
+            if !self.detached.values().any(|detached| detached.page == pages::FILE_EXPLORER) {
+                if new_page != pages::FILE_EXPLORER {
+                    self.explorer.clear_selection();
+                }
+                // Entering the explorer shows only its chrome and the loading spinner until the scan lands
+                self.explorer_rows = 0;
+            }
+            self.render_state.as_mut().unwrap().show_preview = self.pages.shows_preview(&new_page);
+            self.page = new_page;
+            self.rebuild_interface();
+            pending.redraw = true;
+        }
 
-        let event_loop = EventLoop::with_user_event().build()?;
+        if let Some(menu_opened) = pending.menu_change {
+            let previous = std::mem::replace(&mut self.menu_open, menu_opened);
+            // The settings menu is always built, opening or closing it only has to switch its panel's visibility
+            let settings_only = [&previous, &self.menu_open].into_iter()
+                .all(|menu| matches!(menu, (false, None) | (true, Some(GuiMenuState::SettingsMenu))));
+            if !(settings_only && self.show_settings_menu(self.menu_open == (true, Some(GuiMenuState::SettingsMenu)))) {
+                self.rebuild_interface();
+            }
+            if self.menu_open == (true, Some(GuiMenuState::SettingsMenu)) && previous != self.menu_open {
+                self.animate_settings_menu_in();
+            }
+            pending.redraw = true;
+        }
 
-        event_loop.run_app(&mut app)?;
+        if pending.rebuild {
+            self.rebuild_interface();
+            pending.redraw = true;
+        } else if pending.page_rebuild {
+            self.rebuild_page();
+            pending.redraw = true;
+        }
 
-        Ok(())
+        if pending.redraw && let Some(rs) = self.render_state.as_ref() {
+            rs.request_redraw();
+        }
     }
 
-    fn rebuild_interface(&mut self) {
-        println!("Rebuilding interface for layout: {:?}", self.layout);
-        let atlas = self.atlas.clone().unwrap();
-
-        let page_interface_data = match self.layout {
-            GuiPageState::ProjectView => Self::build_project_view_interface(atlas),
-            GuiPageState::FileExplorer => Self::build_file_explorer_interface(atlas),
-        };
-
-        let modified_interface_data = match self.menu_open {
-            (true, Some(GuiMenuState::SettingsMenu)) => Self::display_settings_menu(page_interface_data),
-            _ => page_interface_data
+    /// Routes an event for a detached window to its interface and handles what its elements send like the main
+    /// window's. Closing one only closes that window.
+    fn detached_window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+        match &event {
+            WindowEvent::CloseRequested => {
+                if let Some(detached) = self.detached.remove(&window_id) {
+                    self.session.remember_tool_window(&detached.page, &detached.window);
+                }
+                return;
+            }
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            _ => {}
+        }
+        let Some(detached) = self.detached.get_mut(&window_id) else {
+            return;
         };
+        let mut input = detached.handle_event(&event, &self.shortcuts, self.modifiers);
+        if let Some(edit) = input.edit {
+            match clipboard::edit_focused_input(self.clipboard.as_mut(), &mut detached.interface, edit) {
+                Ok(changed) => input.text_changed |= changed,
+                Err(e) => warn_clipboard_failed(&mut detached.interface, e),
+            }
+            detached.render_state.update_interface(&mut detached.interface);
+        }
 
-        if let Some(rs) = self.render_state.as_mut() {
-            let mut interface_guard = self.interface.lock().unwrap();
-            *interface_guard = modified_interface_data;
-
-            interface_guard.init_gpu_buffers(&rs.device, &rs.queue, rs.size, &rs.config);
+        let mut pending = PendingChanges::default();
+        if detached.page == pages::FILE_EXPLORER {
+            if let Some(offset) = input.scrolled {
+                self.explorer.set_scroll_offset(offset);
+            }
+            let interface = &detached.interface;
+            if input.text_changed
+                && interface.focused_element().is_some()
+                && interface.focused_element() == interface.element_index(EXPLORER_FILTER_INPUT)
+                && let Some(text) = interface.focused_text() {
+                self.explorer.set_filter(text);
+                pending.page_rebuild = true;
+            }
+            let rename_input = interface.element_index(EXPLORER_RENAME_INPUT);
+            if rename_input.is_some() {
+                if input.text_changed
+                    && interface.focused_element() == rename_input
+                    && let Some(text) = interface.focused_text() {
+                    self.explorer.set_rename_text(text);
+                }
+                if input.submitted == rename_input {
+                    input.events.push(GuiEvent::CommitRename);
+                } else if input.dismissed == rename_input {
+                    input.events.push(GuiEvent::CancelRename);
+                }
+            }
+            // Icons are sized in native pixels, so their panel fractions depend on the window size
+            pending.page_rebuild |= input.resized;
+        }
+        let close_menu_event = self.close_menu_event();
+        let events = input.events.into_iter()
+            .filter_map(|event| match event {
+                GuiEvent::CloseMenu => close_menu_event.clone(),
+                event => Some(event),
+            })
+            .collect();
+        self.handle_gui_events(event_loop, events, &mut pending);
+        self.apply_pending(pending);
+    }
 
-            interface_guard.update_vertices_and_queue_text(rs.size, &rs.queue, &rs.device);
+    /// Opens `page` in a window of its own, placed where it was last detached. The main window leaves the page
+    /// for the project view if it was showing it.
+    fn detach_page(&mut self, event_loop: &ActiveEventLoop, page: &str, pending: &mut PendingChanges) -> anyhow::Result<()> {
+        let (Some(main), Some(rs), Some(atlas)) = (self.window_ref.as_ref(), self.render_state.as_ref(), self.atlas.clone()) else {
+            return Ok(());
+        };
+        let attributes = placement::tool_window_attributes(main, self.session.tool_windows.get(page), WINDOW_TITLE);
+        // Built into by `rebuild_detached_windows` once the window knows its size
+        let detached = WindowContext::open(event_loop, attributes, rs.gpu().clone(), page, UiTree::new(atlas), &self.render_options)?;
+        self.detached.insert(detached.id(), detached);
+        if self.page == page {
+            pending.page_change = Some(pages::PROJECT_VIEW.to_string());
         } else {
-            log::warn!("Attempted to rebuild interface but render_state was None. Cannot initialize GPU buffers.");
-            let mut interface_guard = self.interface.lock().unwrap();
-            *interface_guard = modified_interface_data;
+            pending.rebuild = true;
         }
+        Ok(())
     }
 
-    fn build_project_view_interface(atlas: UiAtlas) -> Interface {
-        let mut interface = Interface::new(atlas);
-        let mut header = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.02))
-            .with_color("#0d1117ff");
-        
-        let element1 = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.025, 1.0), "solid")
-            .with_color("#0d1117ff")
-            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "File", 0.7)
-            .with_fn(|| Some(GuiEvent::Highlight), InteractionStyle::OnHover)
-            .with_fn(|| Some(GuiEvent::DisplaySettingsMenu), InteractionStyle::OnClick);
-
-        header.add_element(element1);
+    /// Opens the project at `path` and shows it, or says why it couldn't be opened.
+    fn open_project(&mut self, path: &Path, pending: &mut PendingChanges) {
+        log::info!("Opening project {:?}", path);
+        match Project::open(path) {
+            Ok(project) => {
+                log::info!("Opened project {:?} from {:?}", project.name, project.root);
+                self.recent_projects.record(&project.root, &project.name, SystemTime::now());
+                self.save_recent_projects();
+                self.current_project = Some(project);
+                self.start_level();
+                self.menu_open = (false, None);
+                pending.page_change = Some(pages::PROJECT_VIEW.to_string());
+            }
+            Err(e) => {
+                log::error!("Unable to open project {:?}: {}", path, e);
+                self.show_toast(ToastLevel::Error, format!("Unable to open project: {}", e));
+            }
+        }
+    }
 
-        interface.add_panel(header);
-        interface
+    /// Writes the recent projects list after a change. Safe mode leaves the saved one alone, like the session.
+    fn save_recent_projects(&self) {
+        if self.startup.safe_mode {
+            return;
+        }
+        if let Err(e) = self.recent_projects.save() {
+            log::error!("Unable to save the recent projects: {}", e);
+        }
     }
 
-    fn build_file_explorer_interface(atlas: UiAtlas) -> Interface {
-        let entries = fs::read_dir(r".\projects").unwrap()
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, io::Error>>().unwrap();
+    /// Whether a dialog waiting for an answer is open. The settings menu floats beside the page and doesn't count.
+    fn dialog_open(&self) -> bool {
+        self.menu_open.0 && self.menu_open.1 != Some(GuiMenuState::SettingsMenu)
+    }
 
-        let mut panel = Panel::new(Coordinate::new(0.2, 0.1), Coordinate::new(0.8, 0.9))
-            .with_color("#161b22ff");
-        let mut last_coordinate = Coordinate::new(0.0, 0.0);
-        for file in entries {
-            println!("{} {}", last_coordinate.x, last_coordinate.y);
-            let file_image = Element::new(Coordinate::new(0.01 + 0.005, last_coordinate.y + 0.005), Coordinate::new(0.04 - 0.005, last_coordinate.y + 0.03 - 0.005), "folder-1484");
-            let buffer_space = Element::new(Coordinate::new(0.0, last_coordinate.y), Coordinate::new(0.04, last_coordinate.y + 0.03), "solid")
-                .with_color("#0d1117ff");
-
-            let element = Element::new(Coordinate::new(0.04, last_coordinate.y), Coordinate::new(1.0, last_coordinate.y + 0.03), "solid")
-                .with_color("#0d1117ff")
-                .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left}, file.file_name().unwrap().to_str().unwrap(), 0.8);
-
-            panel.add_element(element);
-            panel.add_element(buffer_space);
-            panel.add_element(file_image);
-            last_coordinate.y = last_coordinate.y + 0.04
+    /// What the overlay shown while files are dragged over the window says dropping them will do.
+    fn drop_message(&self) -> String {
+        if self.dialog_open() {
+            return "Close the dialog to drop files".to_string();
         }
-        
-        let mut interface = Interface::new(atlas);
-
-        let mut header = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.02))
-            .with_color("#0d1117ff");
-        
-        let element1 = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.025, 1.0), "solid")
-            .with_color("#0d1117ff")
-            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Test", 0.7)
-            .with_fn(|| Some(GuiEvent::ChangeLayoutToProjectView), InteractionStyle::OnClick);
+        match DropAction::for_paths(self.file_drop.hovered()) {
+            Some(DropAction::OpenProject { .. }) | None => "Drop to open project".to_string(),
+            Some(DropAction::Import { images, .. }) if images.is_empty() => "Only images can be imported".to_string(),
+            Some(DropAction::Import { images, .. }) => match self.current_project.as_ref() {
+                None => "Open a project to import assets".to_string(),
+                // The explorer doesn't show which project is open
+                Some(project) if self.page == pages::FILE_EXPLORER => format!("Drop to import {} into {}", drop::count_of(images.len(), "asset"), project.name),
+                Some(_) => format!("Drop to import {}", drop::count_of(images.len(), "asset")),
+            },
+        }
+    }
 
-        header.add_element(element1);
+    /// Opens a dropped folder as a project, or imports dropped images into the open project. A drop while a dialog is
+    /// open is turned away, the dialog is waiting for an answer.
+    fn handle_file_drop(&mut self, paths: Vec<PathBuf>) {
+        log::info!("Dropped {}: {:?}", drop::count_of(paths.len(), "file"), paths);
+        let mut pending = PendingChanges { redraw: true, ..Default::default() };
+        if self.dialog_open() {
+            self.show_toast(ToastLevel::Warning, "Close the dialog before dropping files".to_string());
+        } else {
+            match DropAction::for_paths(&paths) {
+                Some(DropAction::OpenProject { path, ignored }) => {
+                    if ignored > 0 {
+                        log::warn!("Only one project opens at a time, ignoring the other {} dropped", drop::count_of(ignored, "path"));
+                    }
+                    self.open_project(&path, &mut pending);
+                }
+                Some(DropAction::Import { images, unsupported }) => self.import_dropped(&images, unsupported, &mut pending),
+                None => {}
+            }
+        }
+        self.apply_pending(pending);
+    }
 
-        interface.add_panel(header);
+    /// Copies dropped images into the open project's asset folder and packs its tiles again. Every image is tried,
+    /// one toast sums up how many were imported, which failed and why, and how many files were skipped.
+    fn import_dropped(&mut self, images: &[PathBuf], unsupported: usize, pending: &mut PendingChanges) {
+        let Some(asset_dir) = self.current_project.as_ref().map(Project::asset_path) else {
+            self.show_toast(ToastLevel::Warning, "Open a project before importing assets".to_string());
+            return;
+        };
+        let mut imported = 0;
+        let mut failures = Vec::new();
+        for image in images {
+            match import::import_asset(image, &asset_dir) {
+                Ok(target) => {
+                    log::info!("Imported {:?} as {:?}", image, target);
+                    imported += 1;
+                }
+                Err(e) => {
+                    log::error!("Unable to import {:?}: {}", image, e);
+                    failures.push(format!("{}: {}", drop::display_name(image), e));
+                }
+            }
+        }
+        if imported > 0 {
+            self.load_project_tiles();
+            pending.page_rebuild = true;
+        }
 
-        interface.add_panel(panel);
+        let mut summary = vec![match imported {
+            0 => "Nothing imported".to_string(),
+            count => format!("Imported {}", drop::count_of(count, "asset")),
+        }];
+        if !failures.is_empty() {
+            summary.push(format!("{} failed ({})", failures.len(), failures.join("; ")));
+        }
+        if unsupported > 0 {
+            summary.push(format!("skipped {}", drop::count_of(unsupported, "unsupported file")));
+        }
+        let toast_level = if !failures.is_empty() {
+            ToastLevel::Error
+        } else if imported == 0 || unsupported > 0 {
+            ToastLevel::Warning
+        } else {
+            ToastLevel::Info
+        };
+        self.show_toast(toast_level, summary.join(", "));
+    }
 
-        interface
+    /// Whether a left click would paint a tile where the cursor is: it's over the preview with a tile selected, and not
+    /// over a panel drawn on top of the preview.
+    fn paints_under_cursor(&self) -> bool {
+        !self.cursor_over_interface
+            && self.selected_tile.is_some()
+            && self.cursor_position.zip(self.render_state.as_ref()).is_some_and(|(cursor, rs)| rs.is_cursor_over_preview(cursor))
     }
 
-    fn display_settings_menu(mut interface: Interface) -> Interface {
-        let element = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
-            .with_color("#0d1117ff")
-            .with_fn(|| Some(GuiEvent::Highlight), InteractionStyle::OnHover)
-            .with_fn(|| Some(GuiEvent::ChangeLayoutToFileExplorer), InteractionStyle::OnClick)
-            .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "New", 0.7);
+    /// Interface of the window showing `page`, the main one or a detached one.
+    fn page_interface(&self, page: &str) -> Option<&UiTree<GuiEvent>> {
+        if self.page == page {
+            return Some(&self.interface);
+        }
+        self.detached.values().find(|detached| detached.page == page).map(|detached| &detached.interface)
+    }
 
-        let mut settings_panel = Panel::new(Coordinate::new(0.0, 0.02), Coordinate::new(0.1, 0.04));
-        settings_panel.add_element(element);
-        interface.add_panel(settings_panel);
-        interface
+    /// Whether `page` is on screen, in the main window or a detached one.
+    fn shows_page(&self, page: &str) -> bool {
+        self.page == page || self.detached.values().any(|detached| detached.page == page)
     }
 }
 
-impl ApplicationHandler<RenderState> for EditorApp {
+impl ApplicationHandler<UserEvent> for EditorApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.render_state.is_none() {
-            let window_attributes = Window::default_attributes().with_maximized(true);
+            let window_attributes = placement::main_window_attributes(event_loop, self.session.main_window.as_ref(), self.session.maximized, WINDOW_TITLE);
             let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
             self.window_ref = Some(window.clone());
             
 
-            let interface_arc = Arc::clone(&self.interface);
+            let atlas_image = self.atlas_image.take().expect("atlas image is only taken when the render state is created");
 
-            self.render_state = Some(pollster::block_on(RenderState::new(window, interface_arc)).unwrap());
+            self.render_state = Some(pollster::block_on(RenderState::new(window, &mut self.interface, &atlas_image, &self.render_options)).unwrap());
+            if let Some(rs) = self.render_state.as_mut() {
+                rs.show_preview = self.pages.shows_preview(&self.page);
+                let adapter = &rs.gpu().adapter_info;
+                crash::set_adapter(format!("{} ({:?}, driver {} {})", adapter.name, adapter.backend, adapter.driver, adapter.driver_info));
+            }
+            // The project reopened from the session needs the renderer for its tile atlas
+            self.start_level();
 
             self.rebuild_interface();
+        }
+    }
 
-            if let Some(rs) = self.render_state.as_mut() {
-                let mut interface_guard = self.interface.lock().unwrap();
-                interface_guard.init_gpu_buffers(&rs.device, &rs.queue, rs.size, &rs.config);
-            }
+    /// Saves the session for the next launch. Safe mode neither restores nor overwrites it.
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if self.startup.safe_mode {
+            return;
+        }
+        if let Some(window) = self.window_ref.as_ref() {
+            self.session.remember_main_window(window);
+        }
+        for detached in self.detached.values() {
+            self.session.remember_tool_window(&detached.page, &detached.window);
+        }
+        self.session.page = Some(self.page.clone());
+        self.session.project = self.current_project.as_ref().map(|project| project.root.clone());
+        self.session.splits.extend(self.interface.split_ratios().map(|(id, ratio)| (id.to_string(), ratio)));
+        if let Err(e) = self.session.save() {
+            log::error!("Unable to save the session: {}", e);
+        }
+    }
+
+    /// Asks for a frame if anything handled since the last one invalidated the window, then sleeps until
+    /// the interface's next wakeup, or indefinitely when nothing is pending.
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Every file of a drop has arrived by now, they're handled as one batch
+        let dropped = self.file_drop.take_dropped();
+        if !dropped.is_empty() {
+            self.handle_file_drop(dropped);
         }
+        let loading = self.step_explorer_loading();
+        self.refresh_debug_overlay(Instant::now());
+
+        self.step_asset_reload();
+
+        let now = Instant::now();
+        if self.interface.tick(now)
+            && let Some(rs) = self.render_state.as_mut() {
+            rs.update_interface(&mut self.interface);
+        }
+        // Spinners only rewrite their own quads each turn
+        let turned = self.interface.step_spinners(now);
+        if let Some(rs) = self.render_state.as_mut()
+            && !turned.iter().all(|&index| rs.update_interface_element(&self.interface, index)) {
+            rs.update_interface(&mut self.interface);
+        }
+        for detached in self.detached.values_mut() {
+            detached.tick(now);
+        }
+
+        let loading_deadline = loading.then(|| Instant::now() + LOADING_POLL_INTERVAL);
+        let detached_wakeups = self.detached.values().filter_map(WindowContext::next_wakeup);
+        match self.interface.next_wakeup().into_iter().chain(detached_wakeups).chain(loading_deadline).chain(self.debug_overlay_due).min() {
+            Some(deadline) => event_loop.set_control_flow(ControlFlow::WaitUntil(deadline)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
+        self.request_pending_redraw();
     }
 
-    #[allow(unused_mut)]
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, mut event: RenderState) {
-        self.render_state = Some(event);
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            // Only wake the loop, `about_to_wait` runs after every batch of events and applies the scan
+            // through `step_explorer_loading` and the reload through `step_asset_reload`
+            UserEvent::ExplorerScanned | UserEvent::AssetsReloading => {}
+        }
     }
 
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
-        _window_id: winit::window::WindowId,
+        window_id: WindowId,
         event: WindowEvent,
     ) {
-        let mut needs_layout_change: Option<GuiPageState> = None;
-        let mut needs_menu_change: Option<(bool, Option<GuiMenuState>)> = None;
-        let mut needs_redraw = false;
+        if self.window_ref.as_ref().is_some_and(|window| window.id() != window_id) {
+            self.detached_window_event(event_loop, window_id, event);
+            return;
+        }
+        let mut pending = PendingChanges::default();
+        // Event returned by an element, handled once the input has been routed
+        let mut gui_event: Option<GuiEvent> = None;
+
+        // Input is offered to the interface first and only reaches the viewport if nothing consumed it.
+        let is_input = matches!(event,
+            WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. } | WindowEvent::KeyboardInput { .. });
+        let mut input_consumed = false;
+        let routed_event = event.clone();
 
-        let current_window_size = if let Some(rs) = self.render_state.as_ref() {
-            rs.window.inner_size()
+        let current_window_size = if let Some(window) = self.render_state.as_ref().and_then(RenderState::window) {
+            window.inner_size()
         } else {
             log::warn!("Window event received before render_state is initialized.");
             return;
         };
 
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
+            // Asks first when there's something to lose, see `GuiEvent::RequestExit`
+            WindowEvent::CloseRequested => gui_event = Some(GuiEvent::RequestExit),
             WindowEvent::Resized(size) => {
                 if let Some(rs) = self.render_state.as_mut() {
-                    rs.resize(size.width, size.height);
+                    rs.resize(size.width, size.height, &mut self.interface);
                 }
-                needs_redraw = true;
+                // Icons are sized in native pixels, so their panel fractions depend on the window size
+                if self.page == pages::FILE_EXPLORER {
+                    pending.rebuild = true;
+                }
+                pending.redraw = true;
             }
-            WindowEvent::RedrawRequested => {
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                // The window keeps the size winit suggests, whose `Resized` follows
                 if let Some(rs) = self.render_state.as_mut() {
-                    match rs.render() {
-                        Ok(_) => {}
-                        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                            rs.resize(current_window_size.width, current_window_size.height);
-                        }
-                        Err(e) => {
-                            log::error!("Unable to render {}", e);
-                        }
-                    }
+                    rs.set_scale_factor(scale_factor, &mut self.interface);
                 }
+                if self.page == pages::FILE_EXPLORER {
+                    pending.rebuild = true;
+                }
+                pending.redraw = true;
             }
-            WindowEvent::CursorMoved { position, .. } => {
+            WindowEvent::RedrawRequested => {
+                // Lost and outdated surfaces are already retried inside render
+                if let Some(rs) = self.render_state.as_mut()
+                    && let Err(e) = rs.render(&mut self.interface) {
+                    log::error!("Unable to render {}", e);
+                }
+            }
+            // A preview pan or paint stroke keeps the cursor even over panels, it's routed to the viewport below
+            WindowEvent::CursorMoved { position, .. } if self.preview_drag.is_some() || self.level_stroke.is_some() => {
+                self.cursor_position = Some(position);
+            }
+            WindowEvent::CursorMoved { position, .. } if self.interface.captured_element().is_some() => {
                 self.cursor_position = Some(position);
-                let mut needs_state_update = false;
+                let interface = &mut self.interface;
+                input_consumed = true;
+                self.cursor_over_interface = true;
+                if interface.handle_captured_move(position, current_window_size)
+                    && let Some(rs) = self.render_state.as_mut() {
+                    rs.update_interface(interface);
+                    pending.redraw = true;
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let previous_position = self.cursor_position.replace(position);
 
-                let mut interface_guard = self.interface.lock().unwrap();
+                let interface = &mut self.interface;
 
-                let current_hovered = interface_guard.handle_interaction(position, current_window_size, InteractionStyle::OnHover);
+                // Moving while an element is held down drags it
+                if let Some(previous) = previous_position
+                    && interface.pressed_element().is_some() {
+                    let delta = ((position.x - previous.x) as f32, (position.y - previous.y) as f32);
+                    interface.handle_interaction(position, current_window_size, InteractionStyle::OnDrag { delta });
+                }
 
-                let current_index= if let Some((_, index)) = current_hovered {
-                    Some(index)
-                } else {
-                    None
-                };
+                let current_hovered = interface.handle_interaction(position, current_window_size, InteractionStyle::OnHover);
 
-                if self.menu_open != (false, None) && !interface_guard.is_cursor_within_menu_panel_bounds(position, current_window_size) {
-                    self.menu_open = (false, None);
-                    needs_menu_change = Some((false, None))
+                input_consumed = current_hovered.consumed;
+                self.cursor_over_interface = current_hovered.consumed;
+
+                if !current_hovered.hover_changed.is_empty()
+                    && let Some(rs) = self.render_state.as_mut() {
+                    // Only the elements entering and leaving hover change, everything else keeps its vertices
+                    let updated = current_hovered.hover_changed.iter()
+                        .all(|&(panel_idx, element_idx)| rs.update_interface_element(interface, (panel_idx, element_idx)));
+                    if !updated {
+                        rs.update_interface(interface);
+                    }
+                    pending.redraw = true;
                 }
+                // A tooltip waits for the cursor to rest, `about_to_wait` ticks the interface when it's due
+                if interface.track_tooltip(position, Instant::now())
+                    && let Some(rs) = self.render_state.as_mut() {
+                    rs.update_interface(interface);
+                    pending.redraw = true;
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                if let Some(scroll) = self.scroll_event(delta) {
+                    let interface = &mut self.interface;
+                    // Whatever isn't over a panel is left for the viewport, which zooms the preview with it
+                    let result = interface.handle_scroll(scroll.position, current_window_size, scroll.delta_lines);
+                    input_consumed = result.consumed;
 
-                if self.last_hovered_element_index != current_index {
-                    if let Some((panel_idx, element_idx)) = self.last_hovered_element_index {
-                        if panel_idx < interface_guard.panels.len() && element_idx < interface_guard.panels[panel_idx].elements.len() {
-                            let element = &mut interface_guard.panels[panel_idx].elements[element_idx];
-                            element.color = element.original_color.clone();
+                    if let Some((_, offset)) = result.scrolled {
+                        if self.page == pages::FILE_EXPLORER {
+                            self.explorer.set_scroll_offset(offset);
+                        }
+                        if let Some(rs) = self.render_state.as_mut() {
+                            rs.update_interface(interface);
+                            pending.redraw = true;
                         }
                     }
+                }
+            }
+            WindowEvent::KeyboardInput { event: key_event, .. } if key_event.state.is_pressed()
+                && key_event.logical_key == Key::Named(NamedKey::Escape)
+                && self.interface.is_dropdown_open() => {
+                input_consumed = true;
+                let interface = &mut self.interface;
+                interface.close_dropdown();
+                if let Some(rs) = self.render_state.as_mut() {
+                    rs.update_interface(interface);
+                    pending.redraw = true;
+                }
+            }
+            WindowEvent::KeyboardInput { event: key_event, .. } if self.interface.focused_element().is_some() => {
+                input_consumed = true;
+                if key_event.state.is_pressed() {
+                    let input_edit = self.shortcuts.input_edit_for(self.modifiers, key_event.physical_key);
+                    let mut clipboard_error = None;
+                    let interface = &mut self.interface;
+                    let renaming = interface.focused_element() == interface.element_index(EXPLORER_RENAME_INPUT);
+                    let changed = match key_event.logical_key {
+                        Key::Named(NamedKey::Escape) => {
+                            if renaming {
+                                gui_event = Some(GuiEvent::CancelRename);
+                            }
+                            interface.focus(None)
+                        }
+                        Key::Named(NamedKey::Backspace) => interface.delete_back(),
+                        Key::Named(NamedKey::Tab) => interface.focus_next(self.modifiers.shift_key()),
+                        Key::Named(NamedKey::Enter) => {
+                            let naming_project = interface.focused_element().is_some()
+                                && interface.focused_element() == interface.element_index(NEW_PROJECT_NAME);
+                            let settings_input = [FONT_SCALE_INPUT, ASSETS_DIR_INPUT].into_iter()
+                                .any(|id| interface.element_index(id) == interface.focused_element());
+                            if naming_project {
+                                gui_event = Some(GuiEvent::CreateProject);
+                            } else if renaming {
+                                gui_event = Some(GuiEvent::CommitRename);
+                            } else if settings_input {
+                                gui_event = Some(GuiEvent::ApplySettingsInput);
+                            }
+                            interface.focus(None)
+                        }
+                        _ => match input_edit {
+                            Some(edit) => clipboard::edit_focused_input(self.clipboard.as_mut(), interface, edit)
+                                .unwrap_or_else(|e| {
+                                    clipboard_error = Some(e);
+                                    false
+                                }),
+                            None => key_event.text.as_deref().is_some_and(|text| interface.insert_text(text)),
+                        },
+                    };
 
-                    if let Some((_event, (panel_idx, element_idx))) = current_hovered {
-                        let element = &mut interface_guard.panels[panel_idx].elements[element_idx];
-                        element.with_temp_color("#999999ff");
+                    // The search box filters the listing it already has, rebuilding the page keeps it focused
+                    if changed
+                        && interface.focused_element().is_some()
+                        && interface.focused_element() == interface.element_index(EXPLORER_FILTER_INPUT)
+                        && let Some(text) = interface.focused_text() {
+                        self.explorer.set_filter(text);
+                        pending.page_rebuild = true;
+                    }
+                    if changed && renaming
+                        && let Some(text) = interface.focused_text() {
+                        self.explorer.set_rename_text(text);
                     }
 
-                    self.last_hovered_element_index = current_index;
-                    needs_state_update = true;
+                    if interface.focused_element().is_none()
+                        && let Some(window) = self.window_ref.as_ref() {
+                        window.set_ime_allowed(false);
+                    }
+                    if changed && let Some(rs) = self.render_state.as_mut() {
+                        rs.update_interface(interface);
+                        pending.redraw = true;
+                    }
+                    if let Some(e) = clipboard_error {
+                        self.clipboard_failed(e);
+                        pending.redraw = true;
+                    }
                 }
-
-                if needs_state_update {
-                    if let Some(rs) = self.render_state.as_mut() {
-                        interface_guard.update_vertices_and_queue_text(rs.size, &rs.queue, &rs.device);
-                        needs_redraw = true;
-                    }
-                }
-            }
-            WindowEvent::MouseInput { state, button, .. } => {
-                if button == MouseButton::Left && state.is_pressed() {
-                    if let Some(cursor_pos) = self.cursor_position {
-                        let gui_event = {
-                            let mut interface_guard = self.interface.lock().unwrap();
-                            interface_guard.handle_interaction(cursor_pos, current_window_size, InteractionStyle::OnClick)
-                        };
-
-                        if let Some((event, _index)) = gui_event {
-                            println!("Received GUI event: {:?}", event);
-                            match event {
-                                GuiEvent::ChangeLayoutToFileExplorer => {
-                                    if self.layout != GuiPageState::FileExplorer {
-                                        self.menu_open = (false, None);
-                                        needs_layout_change = Some(GuiPageState::FileExplorer);
-                                    }
-                                }
-                                GuiEvent::ChangeLayoutToProjectView => {
-                                    if self.layout != GuiPageState::ProjectView {
-                                        needs_layout_change = Some(GuiPageState::ProjectView);
-                                    }
-                                }
-                                GuiEvent::DisplaySettingsMenu => {
-                                    if self.menu_open != (true, Some(GuiMenuState::SettingsMenu)) {
-                                        needs_menu_change = Some((true, Some(GuiMenuState::SettingsMenu)));
-                                    }
-                                }
-                                GuiEvent::Highlight => {
-
-                                }
-                            }
-                            needs_redraw = true;
+            }
+            // A focused input takes every key above, so typing never sets off a shortcut
+            // and Escape in one only unfocuses it
+            WindowEvent::KeyboardInput { event: key_event, .. } if key_event.state.is_pressed() => {
+                let close_menu_event = self.close_menu_event();
+                let interface = &mut self.interface;
+                let focus_changed = match key_event.logical_key {
+                    Key::Named(NamedKey::Tab) => {
+                        input_consumed = true;
+                        interface.focus_next(self.modifiers.shift_key())
+                    }
+                    Key::Named(NamedKey::Enter) if interface.tab_focused_element().is_some() => {
+                        input_consumed = true;
+                        interface.activate_tab_focus();
+                        true
+                    }
+                    _ => {
+                        if !key_event.repeat
+                            && let PhysicalKey::Code(key) = key_event.physical_key
+                            && let Some(event) = self.shortcuts.event_for(self.modifiers, key) {
+                            gui_event = match event {
+                                GuiEvent::CloseMenu => close_menu_event,
+                                event => Some(event),
+                            };
+                            input_consumed = gui_event.is_some();
                         }
-                    } else {
-                        log::warn!("Mouse click detected but cursor position is None.")
+                        false
+                    }
+                };
+                if focus_changed {
+                    // Tab onto a text input focuses it for typing
+                    if let Some(window) = self.window_ref.as_ref() {
+                        window.set_ime_allowed(interface.focused_element().is_some());
+                    }
+                    if let Some(rs) = self.render_state.as_mut() {
+                        rs.update_interface(interface);
+                        pending.redraw = true;
+                    }
+                }
+            }
+            WindowEvent::Ime(Ime::Commit(text)) => {
+                let interface = &mut self.interface;
+                if interface.insert_text(&text)
+                    && let Some(rs) = self.render_state.as_mut() {
+                    rs.update_interface(interface);
+                    pending.redraw = true;
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            // winit sends one of these per file, the overlay is rebuilt to count each
+            WindowEvent::HoveredFile(path) => {
+                self.file_drop.hover(path);
+                pending.page_rebuild = true;
+            }
+            WindowEvent::HoveredFileCancelled => {
+                pending.page_rebuild = self.file_drop.cancel_hover();
+            }
+            // Handled in `about_to_wait`, once the rest of the drop has arrived too
+            WindowEvent::DroppedFile(path) => {
+                self.file_drop.drop_file(path);
+                pending.page_rebuild = true;
+            }
+            // Opens context menus, a right press the interface doesn't take erases tiles in the preview
+            WindowEvent::MouseInput { state, button: MouseButton::Right, .. } if state.is_pressed() => {
+                let interface = &mut self.interface;
+                if let Some(cursor_pos) = self.cursor_position {
+                    let result = interface.handle_interaction(cursor_pos, current_window_size, InteractionStyle::OnRightClick);
+                    if let Some(rs) = self.render_state.as_mut() {
+                        rs.update_interface(interface);
+                        pending.redraw = true;
+                    }
+                    input_consumed = result.consumed;
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                let interface = &mut self.interface;
+                if interface.hide_tooltip()
+                    && let Some(rs) = self.render_state.as_mut() {
+                    rs.update_interface(interface);
+                    pending.redraw = true;
+                }
+            }
+            WindowEvent::Focused(false) => {
+                let interface = &mut self.interface;
+                interface.end_capture();
+                interface.cancel_press();
+                self.preview_drag = None;
+                self.level_stroke = None;
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                let interface = &mut self.interface;
+                let interaction = if state.is_pressed() {
+                    InteractionStyle::OnPress
+                } else {
+                    // A release ends a slider drag wherever the cursor is
+                    input_consumed = interface.captured_element().is_some();
+                    interface.end_capture();
+                    InteractionStyle::OnRelease
+                };
+                if let Some(cursor_pos) = self.cursor_position {
+                    // Ctrl and Shift decide what a click does to the selection
+                    interface.set_modifiers(self.modifiers);
+                    let result = interface.handle_interaction(cursor_pos, current_window_size, interaction);
+                    // Presses move keyboard focus, IME composition is only wanted while a text input has it
+                    if let Some(window) = self.window_ref.as_ref() {
+                        window.set_ime_allowed(interface.focused_element().is_some());
+                    }
+                    // Checkboxes and sliders change their own state on press, and the pressed color comes and goes
+                    if let Some(rs) = self.render_state.as_mut() {
+                        rs.update_interface(interface);
+                        pending.redraw = true;
                     }
+                    input_consumed |= result.consumed;
+                } else {
+                    log::warn!("Mouse click detected but cursor position is None.")
                 }
             }
             _ => {}
         }
 
-        if let Some(new_layout) = needs_layout_change {
-            self.render_state.as_mut().unwrap().gui_state = new_layout.clone();
-            self.layout = new_layout;
-            self.rebuild_interface();
-            needs_redraw = true;
-        }
+        // Element callbacks queue their events on the interface, taken once the input has been routed
+        let mut gui_events: Vec<GuiEvent> = gui_event.into_iter().collect();
+        gui_events.extend(self.interface.drain_events());
+        self.handle_gui_events(event_loop, gui_events, &mut pending);
 
-        if let Some(menu_opened) = needs_menu_change {
-            self.menu_open = menu_opened;
-            self.rebuild_interface();
-            needs_redraw = true;
+        if is_input && !input_consumed {
+            self.handle_viewport_input(&routed_event);
         }
 
-        if needs_redraw {
-            if let Some(window_arc) = self.window_ref.as_ref() {
-                window_arc.request_redraw();
-            }
+        self.apply_pending(pending);
+
+        // Split dividers ask for a resize arrow while hovered or dragged, the preview for a crosshair while it can be painted
+        let cursor_icon = match self.interface.cursor_icon() {
+            CursorIcon::Default if self.paints_under_cursor() => CursorIcon::Crosshair,
+            icon => icon,
+        };
+        if cursor_icon != self.cursor_icon
+            && let Some(window) = self.window_ref.as_ref() {
+            window.set_cursor(cursor_icon);
+            self.cursor_icon = cursor_icon;
         }
     }
 }
\ No newline at end of file
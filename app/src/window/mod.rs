@@ -1 +1,12 @@
-pub(crate) mod gui;
\ No newline at end of file
+pub(crate) mod gui;
+pub(crate) mod assets;
+pub(crate) mod clipboard;
+pub(crate) mod detached;
+pub(crate) mod drop;
+pub(crate) mod explorer;
+pub(crate) mod event;
+pub(crate) mod pages;
+pub(crate) mod placement;
+pub(crate) mod recent;
+pub(crate) mod session;
+pub(crate) mod shortcuts;
\ No newline at end of file
@@ -0,0 +1,246 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use gfx::{definitions::{InteractionStyle, UiAtlas}, gui::{interface::{Alignment, ColorRole, Coordinate, Dimension, Element, HorizontalAlignment, Panel, UiTree, VerticalAlignment}, layout_file::LayoutFile}};
+use winit::dpi::PhysicalSize;
+
+use crate::{config::EditorConfig, level::{TileId, TileSet}, project::Project, theme::Theme, window::{event::GuiEvent, explorer::ExplorerState, recent::RecentProjects}};
+
+pub(crate) const PROJECT_VIEW: &str = "project_view";
+pub(crate) const FILE_EXPLORER: &str = "file_explorer";
+pub(crate) const ACCESSIBILITY_SETTINGS: &str = "accessibility_settings";
+pub(crate) const PIXEL_GRID: &str = "pixel_grid";
+
+/// Lines in each direction on the pixel grid page, each an eighth of a pixel further off the pixel grid than the last.
+const PIXEL_GRID_LINES: usize = 8;
+const PIXEL_GRID_SPACING: f32 = 24.0;
+const PIXEL_GRID_LINE_COLOR: &str = "#ffffff";
+const PIXEL_GRID_BACKGROUND: &str = "#000000";
+
+/// Where page layout files are looked for, relative to the working directory, see `layout_path`.
+const LAYOUT_DIR: &str = "layouts";
+
+/// Everything a page builder may read while building its interface.
+#[derive(Clone, Copy)]
+pub(crate) struct PageCtx<'a> {
+    pub(crate) atlas: &'a UiAtlas,
+    pub(crate) config: &'a EditorConfig,
+    pub(crate) theme: &'a Theme,
+    pub(crate) explorer: &'a ExplorerState,
+    /// How many of the explorer's entries to build rows for, the rest are still filling in.
+    pub(crate) explorer_rows: usize,
+    /// The project open in the editor, if any.
+    pub(crate) project: Option<&'a Project>,
+    /// Projects opened before, listed on the project view while none is open.
+    pub(crate) recent_projects: &'a RecentProjects,
+    /// The open project's tiles, empty without one.
+    pub(crate) tile_set: &'a TileSet,
+    /// The tile left-clicking in the preview paints.
+    pub(crate) selected_tile: Option<TileId>,
+    /// Whether the level has edits that aren't saved yet.
+    pub(crate) level_modified: bool,
+    /// `None` until the window exists.
+    pub(crate) screen_size: Option<PhysicalSize<u32>>,
+    /// Physical pixels per logical one, 1.0 until the window exists.
+    pub(crate) scale_factor: f32,
+    /// The page's layout file, already checked against `layout_actions`. Builders that support one build from it
+    /// instead of their own layout.
+    pub(crate) layout: Option<&'a LayoutFile>,
+    /// Whether the page is being built for a detached window rather than the main one.
+    pub(crate) detached: bool,
+}
+
+pub(crate) type PageBuilder = fn(&PageCtx) -> UiTree<GuiEvent>;
+
+pub(crate) struct Page {
+    builder: PageBuilder,
+    shows_preview: bool,
+    backdrop: Option<ColorRole>,
+}
+
+impl Page {
+    pub(crate) fn new(builder: PageBuilder) -> Self {
+        Self { builder, shows_preview: false, backdrop: None }
+    }
+
+    /// Draws the level preview under the page's panels.
+    pub(crate) fn with_preview(mut self) -> Self {
+        self.shows_preview = true;
+        self
+    }
+
+    /// Clears the window to the theme's color for `role` behind the page, rather than its background color.
+    pub(crate) fn with_backdrop(mut self, role: ColorRole) -> Self {
+        self.backdrop = Some(role);
+        self
+    }
+}
+
+/// Pages the editor can navigate to with `GuiEvent::Navigate`, keyed by id.
+#[derive(Default)]
+pub(crate) struct PageRegistry {
+    pages: HashMap<String, Page>,
+}
+
+impl PageRegistry {
+    /// Replaces any page already registered as `id`.
+    pub(crate) fn register(&mut self, id: &str, page: Page) {
+        self.pages.insert(id.to_string(), page);
+    }
+
+    /// Builds the page registered as `id`, or a page saying there's no such page.
+    pub(crate) fn build(&self, id: &str, ctx: &PageCtx) -> UiTree<GuiEvent> {
+        match self.pages.get(id) {
+            Some(page) => (page.builder)(ctx),
+            None => {
+                log::error!("No page is registered as {:?}", id);
+                unknown_page(id, ctx)
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, id: &str) -> bool {
+        self.pages.contains_key(id)
+    }
+
+    pub(crate) fn shows_preview(&self, id: &str) -> bool {
+        self.pages.get(id).is_some_and(|page| page.shows_preview)
+    }
+
+    /// What the page registered as `id` is drawn over, see `Page::with_backdrop`.
+    pub(crate) fn backdrop(&self, id: &str) -> Option<ColorRole> {
+        self.pages.get(id).and_then(|page| page.backdrop)
+    }
+}
+
+/// The layout file for page `id`, which needn't exist.
+pub(crate) fn layout_path(id: &str) -> PathBuf {
+    Path::new(LAYOUT_DIR).join(format!("{}.ron", id))
+}
+
+/// Events layout files can name in `on_click` and dropdown items.
+pub(crate) fn layout_actions() -> HashMap<String, GuiEvent> {
+    [
+        ("open_project", GuiEvent::Navigate(FILE_EXPLORER.to_string())),
+        ("open_settings", GuiEvent::DisplaySettingsMenu),
+        ("save", GuiEvent::SaveLevel),
+        ("undo", GuiEvent::Undo),
+        ("redo", GuiEvent::Redo),
+        ("reload_assets", GuiEvent::ReloadAssets),
+        ("dump_diagnostics", GuiEvent::DumpDiagnostics),
+        ("exit", GuiEvent::RequestExit),
+    ]
+    .into_iter()
+    .map(|(action, event)| (action.to_string(), event))
+    .collect()
+}
+
+fn unknown_page(id: &str, ctx: &PageCtx) -> UiTree<GuiEvent> {
+    let mut interface = UiTree::new(ctx.atlas.clone());
+    let mut panel = Panel::new(Coordinate::new(0.35, 0.4), Coordinate::new(0.65, 0.5))
+        .with_color_role(ColorRole::Panel)
+        .with_border(1.0, "#30363dff");
+
+    let message = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.5), "solid")
+        .with_color_role(ColorRole::Panel)
+        .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, &format!("There's no page called {:?}", id), 0.7);
+    let back = Element::new(Coordinate::new(0.35, 0.6), Coordinate::new(0.65, 0.9), "solid")
+        .with_color_role(ColorRole::Header)
+        .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Back", 0.7)
+        .with_hover_color_role(ColorRole::Hover)
+        .with_fn(|| Some(GuiEvent::Navigate(PROJECT_VIEW.to_string())), InteractionStyle::OnClick);
+
+    panel.add_element(message);
+    panel.add_element(back);
+    interface.add_panel(panel);
+    interface
+}
+
+/// A test page for pixel snapping: 1px lines, vertical ones down the left half of the window and horizontal ones
+/// across the right half, at offsets from 0 to 7/8 of a pixel. Every line should come out one physical pixel wide
+/// and equally sharp.
+pub(crate) fn pixel_grid_page(ctx: &PageCtx) -> UiTree<GuiEvent> {
+    pixel_grid(ctx.atlas, ctx.scale_factor)
+}
+
+fn pixel_grid(atlas: &UiAtlas, scale_factor: f32) -> UiTree<GuiEvent> {
+    let mut interface = UiTree::new(atlas.clone());
+    let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)).with_color(PIXEL_GRID_BACKGROUND);
+
+    let back = Element::new(Coordinate::px(8.0, 8.0), Coordinate::px(88.0, 32.0), "solid")
+        .with_color_role(ColorRole::Header)
+        .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "Back", 0.7)
+        .with_hover_color_role(ColorRole::Hover)
+        .with_fn(|| Some(GuiEvent::Navigate(PROJECT_VIEW.to_string())), InteractionStyle::OnClick);
+    let caption = Element::new(Coordinate::px(100.0, 8.0), Coordinate::mixed(1.0, Dimension::Pixels(32.0)), "solid")
+        .with_color(PIXEL_GRID_BACKGROUND)
+        .with_text(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Every line should be 1px and equally sharp", 0.6);
+    panel.add_element(back);
+    panel.add_element(caption);
+
+    // Pixels are scaled by the scale factor, a line of one physical pixel is less than one of them on HiDPI displays
+    let thickness = 1.0 / scale_factor;
+    for line in 0..PIXEL_GRID_LINES {
+        let offset = 40.0 + (PIXEL_GRID_SPACING + 1.0 / PIXEL_GRID_LINES as f32) * line as f32;
+        let vertical = Element::new(Coordinate::px(offset, 48.0), Coordinate::mixed(Dimension::Pixels(offset + thickness), 1.0), "solid")
+            .with_color(PIXEL_GRID_LINE_COLOR);
+        let horizontal = Element::new(Coordinate::mixed(0.5, Dimension::Pixels(offset + 40.0)), Coordinate::mixed(1.0, Dimension::Pixels(offset + 40.0 + thickness)), "solid")
+            .with_color(PIXEL_GRID_LINE_COLOR);
+        panel.add_element(vertical);
+        panel.add_element(horizontal);
+    }
+    interface.add_panel(panel);
+    interface
+}
+
+#[cfg(test)]
+mod tests {
+    use gfx::{definitions::UiAtlasTexture, RenderOptions, RenderState};
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    const LINE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    /// The widths of the runs of line pixels along `pixels`, after checking nothing is blurred between line and background.
+    fn line_widths(pixels: impl Iterator<Item = Rgba<u8>>) -> Vec<usize> {
+        let mut widths = Vec::new();
+        let mut run = 0;
+        for pixel in pixels {
+            assert!(pixel == LINE || pixel == BACKGROUND, "{:?} is neither line nor background", pixel);
+            if pixel == LINE {
+                run += 1;
+            } else if run > 0 {
+                widths.push(run);
+                run = 0;
+            }
+        }
+        widths
+    }
+
+    #[test]
+    fn every_grid_line_is_one_crisp_pixel() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+        let mut interface = pixel_grid(&atlas, 1.0);
+        let atlas_image = RgbaImage::from_pixel(1, 1, Rgba([255; 4]));
+        // Without multisampling a line off the pixel grid still fills one whole pixel, just maybe the wrong one.
+        // With it, the line would be split over two half covered pixels.
+        let options = RenderOptions { sample_count: 4, ..RenderOptions::default() };
+        let mut state = match pollster::block_on(RenderState::new_headless(640, 480, &mut interface, &atlas_image, &options)) {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Skipping, no adapter to render with: {}", e);
+                return;
+            }
+        };
+        state.update_interface(&mut interface);
+        let frame = state.render_to_image(&mut interface).unwrap();
+
+        // Along a row under the vertical lines, and down a column through the horizontal ones
+        let vertical = line_widths((0..320).map(|x| *frame.get_pixel(x, 400)));
+        let horizontal = line_widths((48..480).map(|y| *frame.get_pixel(480, y)));
+        assert_eq!(vertical, vec![1; PIXEL_GRID_LINES]);
+        assert_eq!(horizontal, vec![1; PIXEL_GRID_LINES]);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, event_loop::ActiveEventLoop, monitor::MonitorHandle, window::{Window, WindowAttributes}};
+
+/// Where a window was last open: monitor name, outer position and inner size in physical pixels.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct SavedPlacement {
+    pub(crate) monitor: Option<String>,
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+/// Puts the main window back where `saved` says, if that monitor is still connected.
+/// Otherwise, or when it was last `maximized`, it opens maximized.
+pub(crate) fn main_window_attributes(event_loop: &ActiveEventLoop, saved: Option<&SavedPlacement>, maximized: bool, title: &str) -> WindowAttributes {
+    let attributes = Window::default_attributes().with_title(title);
+
+    if !maximized
+        && let Some(saved) = saved
+        && let Some(monitor) = event_loop.available_monitors().find(|monitor| monitor.name() == saved.monitor)
+        && overlaps(&monitor, saved) {
+        return attributes
+            .with_position(PhysicalPosition::new(saved.x, saved.y))
+            .with_inner_size(PhysicalSize::new(saved.width, saved.height));
+    }
+    attributes.with_maximized(true)
+}
+
+/// Attributes for a detached panel's window. It goes back where it was last time if that monitor is still
+/// connected, otherwise it takes the right third of the monitor `main` is on. When the platform can't say
+/// which monitor that is (Wayland), it's only sized relative to `main` and the compositor places it.
+pub(crate) fn tool_window_attributes(main: &Window, saved: Option<&SavedPlacement>, title: &str) -> WindowAttributes {
+    let attributes = Window::default_attributes().with_title(title);
+
+    if let Some(saved) = saved
+        && let Some(monitor) = main.available_monitors().find(|monitor| monitor.name() == saved.monitor)
+        && overlaps(&monitor, saved) {
+        return attributes
+            .with_position(PhysicalPosition::new(saved.x, saved.y))
+            .with_inner_size(PhysicalSize::new(saved.width, saved.height));
+    }
+
+    match main.current_monitor() {
+        Some(monitor) => {
+            let (position, size) = default_rect(&monitor);
+            attributes.with_position(position).with_inner_size(size)
+        }
+        None => {
+            let main_size = main.inner_size();
+            attributes.with_inner_size(PhysicalSize::new((main_size.width / 3).max(1), main_size.height.max(1)))
+        }
+    }
+}
+
+/// Right third of the monitor. winit doesn't expose the work area, so this is the full monitor height
+/// and the OS may nudge it clear of taskbars.
+fn default_rect(monitor: &MonitorHandle) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let (origin, size) = (monitor.position(), monitor.size());
+    let width = size.width / 3;
+    (
+        PhysicalPosition::new(origin.x + (size.width - width) as i32, origin.y),
+        PhysicalSize::new(width.max(1), size.height.max(1)),
+    )
+}
+
+/// Whether any of the saved rect is on `monitor`, so a window isn't restored somewhere it can't be reached
+/// after the monitor layout changed.
+fn overlaps(monitor: &MonitorHandle, saved: &SavedPlacement) -> bool {
+    let (origin, size) = (monitor.position(), monitor.size());
+    let (monitor_right, monitor_bottom) = (origin.x + size.width as i32, origin.y + size.height as i32);
+    let (saved_right, saved_bottom) = (saved.x + saved.width as i32, saved.y + saved.height as i32);
+    saved.x < monitor_right && saved_right > origin.x && saved.y < monitor_bottom && saved_bottom > origin.y
+}
+
+/// `None` where windows can't read their own position (Wayland).
+pub(crate) fn placement_of(window: &Window) -> Option<SavedPlacement> {
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size();
+    Some(SavedPlacement {
+        monitor: window.current_monitor().and_then(|monitor| monitor.name()),
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    })
+}
@@ -0,0 +1,90 @@
+use std::{fs, io, path::{Path, PathBuf}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config, storage::SaveError};
+
+/// Most projects the landing page lists, the oldest fall off once there are more.
+const MAX_RECENT: usize = 10;
+
+/// A project opened before, as the project view's landing page lists it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct RecentProject {
+    pub(crate) path: PathBuf,
+    /// The project's name when it was last opened, shown even once its folder is gone.
+    pub(crate) name: String,
+    /// Unix time in seconds.
+    last_opened: u64,
+}
+
+impl RecentProject {
+    pub(crate) fn last_opened(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.last_opened)
+    }
+
+    /// Whether the project is still there to open. Missing ones stay listed until removed, the drive may only be
+    /// unplugged.
+    pub(crate) fn exists(&self) -> bool {
+        self.path.is_dir()
+    }
+}
+
+/// Projects opened in the editor, most recent first. Stored in `config::RECENT_PROJECTS_FILE` next to the session,
+/// see `config::state_path`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct RecentProjects {
+    projects: Vec<RecentProject>,
+}
+
+impl RecentProjects {
+    /// The saved list, or an empty one when the file is missing or unreadable. Like the session it's only logged,
+    /// the list fills in again as projects are opened.
+    pub(crate) fn load() -> Self {
+        let path = config::state_path(config::RECENT_PROJECTS_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                log::warn!("Unable to read {:?}, starting without recent projects: {}", path, e);
+                return Self::default();
+            }
+        };
+        match toml::from_str::<RecentProjects>(&contents) {
+            Ok(mut recent) => {
+                recent.projects.truncate(MAX_RECENT);
+                recent
+            }
+            Err(e) => {
+                log::warn!("{:?} is malformed, starting without recent projects: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub(crate) fn save(&self) -> Result<(), SaveError> {
+        config::save_state(config::RECENT_PROJECTS_FILE, self)
+    }
+
+    pub(crate) fn entries(&self) -> &[RecentProject] {
+        &self.projects
+    }
+
+    /// Moves the project at `path` to the top of the list, or adds it there, dropping the oldest past `MAX_RECENT`.
+    pub(crate) fn record(&mut self, path: &Path, name: &str, now: SystemTime) {
+        self.projects.retain(|project| project.path != path);
+        self.projects.insert(0, RecentProject {
+            path: path.to_path_buf(),
+            name: name.to_string(),
+            last_opened: now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        });
+        self.projects.truncate(MAX_RECENT);
+    }
+
+    /// Takes the project at `path` off the list, returns whether it was on it.
+    pub(crate) fn remove(&mut self, path: &Path) -> bool {
+        let count = self.projects.len();
+        self.projects.retain(|project| project.path != path);
+        self.projects.len() != count
+    }
+}
@@ -0,0 +1,109 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use winit::window::Window;
+
+use crate::{config, storage::SaveError, window::placement::{self, SavedPlacement}};
+
+/// Schema of `config::SESSION_FILE`. Bump it when a field changes meaning, and convert older files in `Session::load`.
+/// Files from before the field existed count as version 1, they only held `tool_windows`.
+const SESSION_VERSION: u32 = 1;
+
+/// What the editor was doing when it last closed, restored on the next launch unless in safe mode.
+/// Stored in `config::SESSION_FILE`, see `config::state_path`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct Session {
+    pub(crate) version: u32,
+    /// Where the main window was, unless it was maximized.
+    pub(crate) main_window: Option<SavedPlacement>,
+    pub(crate) maximized: bool,
+    /// Id of the page on screen, one of `pages`.
+    pub(crate) page: Option<String>,
+    /// Root of the open project, reopened if its manifest is still there.
+    pub(crate) project: Option<PathBuf>,
+    /// Divider ratios by split id, see `UiTree::add_split`.
+    pub(crate) splits: BTreeMap<String, f32>,
+    /// Detached panel placements keyed by panel name, see `placement::tool_window_attributes`.
+    pub(crate) tool_windows: BTreeMap<String, SavedPlacement>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            version: SESSION_VERSION,
+            main_window: None,
+            maximized: true,
+            page: None,
+            project: None,
+            splits: BTreeMap::new(),
+            tool_windows: BTreeMap::new(),
+        }
+    }
+}
+
+/// Just the version, read before the rest so a newer schema isn't misread.
+#[derive(Deserialize)]
+struct SessionVersion {
+    #[serde(default = "first_version")]
+    version: u32,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+impl Session {
+    /// The last session, or a fresh one when the file is missing, unreadable or from a newer editor.
+    /// Unlike the config a broken session isn't worth a banner, it's only logged and replaced on exit.
+    pub(crate) fn load() -> Self {
+        let path = config::state_path(config::SESSION_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                log::warn!("Unable to read {:?}, starting a new session: {}", path, e);
+                return Self::default();
+            }
+        };
+        match toml::from_str::<SessionVersion>(&contents) {
+            Ok(header) if header.version > SESSION_VERSION => {
+                log::warn!("{:?} is version {}, newer than this editor's {}, starting a new session", path, header.version, SESSION_VERSION);
+                return Self::default();
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("{:?} is malformed, starting a new session: {}", path, e);
+                return Self::default();
+            }
+        }
+        match toml::from_str::<Session>(&contents) {
+            Ok(session) => Session { version: SESSION_VERSION, ..session },
+            Err(e) => {
+                log::warn!("{:?} is malformed, starting a new session: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub(crate) fn save(&self) -> Result<(), SaveError> {
+        config::save_state(config::SESSION_FILE, self)
+    }
+
+    /// Takes the main window's geometry. A maximized window keeps the placement it had before, so unmaximizing
+    /// next time goes back there.
+    pub(crate) fn remember_main_window(&mut self, window: &Window) {
+        self.maximized = window.is_maximized();
+        if !self.maximized
+            && let Some(placement) = placement::placement_of(window) {
+            self.main_window = Some(placement);
+        }
+    }
+
+    /// Remembers where `window` is, for the next time `panel` is detached.
+    pub(crate) fn remember_tool_window(&mut self, panel: &str, window: &Window) {
+        if let Some(placement) = placement::placement_of(window) {
+            self.tool_windows.insert(panel.to_string(), placement);
+        }
+    }
+}
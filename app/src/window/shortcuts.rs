@@ -0,0 +1,68 @@
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+
+use crate::window::{clipboard::InputEdit, event::GuiEvent, pages};
+
+/// Keys that send a `GuiEvent` from anywhere in the editor. They're looked up by physical key, so they stay
+/// in the same place whatever the keyboard layout, and never while a text input has focus, apart from the
+/// clipboard's, see `input_edit_for`.
+pub(crate) struct Shortcuts {
+    bindings: Vec<Shortcut>,
+}
+
+struct Shortcut {
+    modifiers: ModifiersState,
+    key: KeyCode,
+    event: GuiEvent,
+}
+
+impl Shortcuts {
+    pub(crate) fn new() -> Self {
+        Self { bindings: Vec::new() }
+    }
+
+    /// Sends `event` when `key` is pressed with exactly `modifiers` held, replacing whatever those keys sent before.
+    pub(crate) fn bind(&mut self, modifiers: ModifiersState, key: KeyCode, event: GuiEvent) {
+        self.bindings.retain(|shortcut| (shortcut.modifiers, shortcut.key) != (modifiers, key));
+        self.bindings.push(Shortcut { modifiers, key, event });
+    }
+
+    pub(crate) fn event_for(&self, modifiers: ModifiersState, key: KeyCode) -> Option<GuiEvent> {
+        self.bindings.iter()
+            .find(|shortcut| shortcut.modifiers == modifiers && shortcut.key == key)
+            .map(|shortcut| shortcut.event.clone())
+    }
+
+    /// The clipboard edit `key` with `modifiers` makes in a focused text input, when it's bound to one of the
+    /// clipboard's events.
+    pub(crate) fn input_edit_for(&self, modifiers: ModifiersState, key: PhysicalKey) -> Option<InputEdit> {
+        let PhysicalKey::Code(key) = key else {
+            return None;
+        };
+        self.event_for(modifiers, key).as_ref().and_then(InputEdit::for_event)
+    }
+}
+
+impl Default for Shortcuts {
+    /// The editor's own shortcuts.
+    fn default() -> Self {
+        let mut shortcuts = Self::new();
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyO, GuiEvent::Navigate(pages::FILE_EXPLORER.to_string()));
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::Comma, GuiEvent::DisplaySettingsMenu);
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyS, GuiEvent::SaveLevel);
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyZ, GuiEvent::Undo);
+        shortcuts.bind(ModifiersState::CONTROL | ModifiersState::SHIFT, KeyCode::KeyZ, GuiEvent::Redo);
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyY, GuiEvent::Redo);
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyC, GuiEvent::Copy);
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyX, GuiEvent::Cut);
+        shortcuts.bind(ModifiersState::CONTROL, KeyCode::KeyV, GuiEvent::Paste);
+        shortcuts.bind(ModifiersState::empty(), KeyCode::Escape, GuiEvent::CloseMenu);
+        shortcuts.bind(ModifiersState::empty(), KeyCode::Enter, GuiEvent::OpenSelection);
+        shortcuts.bind(ModifiersState::empty(), KeyCode::Delete, GuiEvent::RequestDeleteSelection);
+        shortcuts.bind(ModifiersState::empty(), KeyCode::F11, GuiEvent::ToggleFullscreen);
+        shortcuts.bind(ModifiersState::empty(), KeyCode::F3, GuiEvent::ToggleDebugOverlay);
+        if cfg!(debug_assertions) {
+            shortcuts.bind(ModifiersState::CONTROL | ModifiersState::SHIFT, KeyCode::KeyR, GuiEvent::ReloadShaders);
+        }
+        shortcuts
+    }
+}
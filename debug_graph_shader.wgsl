@@ -1,3 +1,6 @@
+// Bars of the debug overlay's frame-time graph. Positions are in clip space of the viewport, which the graph sets
+// to its element's rect, so they don't depend on the window size.
+
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) color: vec4<f32>,
@@ -9,19 +12,14 @@ struct VertexOutput {
 };
 
 @vertex
-fn vs_main(
-    in: VertexInput,
-) -> VertexOutput {
+fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-
     out.clip_position = vec4<f32>(in.position, 0.0, 1.0);
     out.color = in.color;
     return out;
 }
 
-// Fragment shader
-
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color);
-}
\ No newline at end of file
+    return in.color;
+}
@@ -0,0 +1,33 @@
+//! A window with one button, run with `cargo run -p gfx --example simple`. Escape quits, F11 toggles fullscreen.
+
+use gfx::{definitions::{InteractionStyle, UiAtlas, UiAtlasTexture}, gui::prelude::*};
+use image::{Rgba, RgbaImage};
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    Clicked,
+}
+
+fn main() -> anyhow::Result<()> {
+    // A single white pixel is all a solid color interface samples
+    let atlas_image = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
+    let mut atlas = UiAtlas::new(1, 1);
+    atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+
+    let mut interface = UiTree::new(atlas);
+    let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0))
+        .with_color("#0d1117ff");
+    panel.add_element(Element::new(Coordinate::new(0.4, 0.45), Coordinate::new(0.6, 0.55), "solid")
+        .with_color("#238636ff")
+        .with_hover_color("#2ea043ff")
+        .with_fn(|| Some(Event::Clicked), InteractionStyle::OnClick));
+    interface.add_panel(panel);
+
+    let mut clicks = 0;
+    gfx::run_simple("gfx example", interface, atlas_image, move |event, _interface| match event {
+        Event::Clicked => {
+            clicks += 1;
+            println!("Clicked {} times", clicks);
+        }
+    })
+}
@@ -1,14 +1,43 @@
-use std::{env::current_dir, fs};
+use std::{borrow::Cow, fmt, fs, path::PathBuf};
 
 use wgpu::{
     BindGroupLayout, BlendState, ColorTargetState, ColorWrites, DepthBiasState, DepthStencilState, Device, Face, FragmentState, FrontFace, MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderSource, StencilState, TextureFormat, VertexBufferLayout, VertexState
 };
 
+/// A pipeline's shader didn't compile, or the pipeline built with it didn't validate.
+#[derive(Debug)]
+pub struct ShaderError {
+    /// The shader's file name, or the path it was read from.
+    pub file: String,
+    /// What wgpu reported, naga's diagnostic when the shader itself is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shader {} failed to build: {}", self.file, self.message)
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
 pub(crate) struct PipeLineBuilder<'a> {
     shader_filename: String,
+    /// The shader as compiled into the binary, used unless `shader_path` is set and readable.
+    shader_source: &'static str,
+    /// Read when the pipeline is built instead of `shader_source`, see `set_shader_file`.
+    shader_path: Option<PathBuf>,
     vertex_entry: String,
     fragment_entry: String,
     pixel_format: wgpu::TextureFormat,
+    /// How fragments combine with the target, `BlendState::REPLACE` (opaque) unless set.
+    blend_state: BlendState,
+    topology: PrimitiveTopology,
+    cull_mode: Option<Face>,
+    /// No depth test unless set, the pass then must not have a depth attachment.
+    depth_stencil: Option<DepthStencilState>,
+    /// Has to match the sample count of the pass's attachments.
+    sample_count: u32,
     vertex_buffer_layouts: Vec<VertexBufferLayout<'static>>,
     bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
     device: &'a Device,
@@ -18,9 +47,16 @@ impl <'a> PipeLineBuilder <'a> {
     pub(crate) fn new(device: &'a Device) -> Self {
         PipeLineBuilder {
             shader_filename: "empty".to_string(),
+            shader_source: "",
+            shader_path: None,
             vertex_entry: "empty".to_string(),
             fragment_entry: "empty".to_string(),
             pixel_format: TextureFormat::Rgba8Unorm,
+            blend_state: BlendState::REPLACE,
+            topology: PrimitiveTopology::TriangleList,
+            cull_mode: Some(Face::Back),
+            depth_stencil: None,
+            sample_count: 1,
             vertex_buffer_layouts: Vec::new(),
             bind_group_layouts: Vec::new(),
             device: device,
@@ -31,14 +67,23 @@ impl <'a> PipeLineBuilder <'a> {
         self.vertex_buffer_layouts.clear();
     }
 
-    pub(crate) fn set_shader_module(&mut self, shader_filename: &str, vertex_entry: &str, fragment_entry: &str) -> &mut Self {
+    /// `shader_source` is the shader compiled in with `include_str!`, `shader_filename` names it in errors.
+    pub(crate) fn set_shader_module(&mut self, shader_filename: &str, shader_source: &'static str, vertex_entry: &str, fragment_entry: &str) -> &mut Self {
         self.shader_filename = shader_filename.to_string();
+        self.shader_source = shader_source;
         self.vertex_entry = vertex_entry.to_string();
         self.fragment_entry = fragment_entry.to_string();
 
         self
     }
 
+    /// Reads the shader from `path` when the pipeline is built, so edits show without recompiling. Falls back to
+    /// the compiled in shader, with a warning, if the file can't be read.
+    pub(crate) fn set_shader_file(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.shader_path = Some(path.into());
+        self
+    }
+
     pub(crate) fn add_vertex_buffer_layout(&mut self, layout: VertexBufferLayout<'static>) -> &mut Self {
         self.vertex_buffer_layouts.push(layout);
         self
@@ -54,42 +99,72 @@ impl <'a> PipeLineBuilder <'a> {
         self
     }
 
-    pub(crate) fn build(&mut self, label: &str) -> RenderPipeline {
+    /// `BlendState::ALPHA_BLENDING` for straight alpha colors, `BlendState::PREMULTIPLIED_ALPHA_BLENDING` when the
+    /// shader outputs color already multiplied by alpha.
+    pub(crate) fn set_blend_state(&mut self, blend_state: BlendState) -> &mut Self {
+        self.blend_state = blend_state;
+        self
+    }
+
+    pub(crate) fn set_topology(&mut self, topology: PrimitiveTopology) -> &mut Self {
+        self.topology = topology;
+        self
+    }
+
+    /// `None` draws both faces, the default culls back faces.
+    pub(crate) fn set_cull_mode(&mut self, cull_mode: Option<Face>) -> &mut Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    /// Tests fragments against a `format` depth attachment with `compare`, writing the depth of those that pass.
+    pub(crate) fn set_depth_stencil(&mut self, format: TextureFormat, compare: wgpu::CompareFunction) -> &mut Self {
+        self.depth_stencil = Some(DepthStencilState {
+            format,
+            depth_write_enabled: true,
+            depth_compare: compare,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        });
+        self
+    }
+
+    pub(crate) fn set_sample_count(&mut self, sample_count: u32) -> &mut Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Fails if the shader doesn't compile or the pipeline doesn't validate against it, naming the file the
+    /// shader came from. Nothing is left behind for wgpu to report as an uncaptured error.
+    pub(crate) fn build(&mut self, label: &str) -> Result<RenderPipeline, ShaderError> {
 
+        let layout_label = format!("{} Layout", label);
         let pipeline_layout_descriptor = PipelineLayoutDescriptor {
-            label: Some("Pipeline Layout"),
+            label: Some(&layout_label),
             bind_group_layouts: &self.bind_group_layouts,
             push_constant_ranges: &[],
         };
 
-        let mut filepath = current_dir().unwrap();
-        filepath.push(self.shader_filename.as_str());
-        let filepath = filepath.into_os_string().into_string().unwrap();
-        let source_code = fs::read_to_string(filepath).expect("Can't read source code!");
+        let (shader_file, source_code) = self.read_shader();
 
         let shader_module_descriptor = ShaderModuleDescriptor {
-            label: Some("Shader Module"),
-            source: ShaderSource::Wgsl(source_code.into()),
+            label: Some(&shader_file),
+            source: ShaderSource::Wgsl(source_code),
         };
 
+        // Compile errors are validation errors, caught here rather than panicking in wgpu's default handler
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+
         let shader_module = self.device.create_shader_module(shader_module_descriptor);
 
         let pipeline_layout = self.device.create_pipeline_layout(&pipeline_layout_descriptor);
 
         let render_targets = [Some(ColorTargetState {
             format: self.pixel_format,
-            blend: Some(BlendState::ALPHA_BLENDING),
+            blend: Some(self.blend_state),
             write_mask: ColorWrites::ALL,
         })];
 
-        let _depth_stencil = DepthStencilState {
-            format: TextureFormat::Depth32Float,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: StencilState::default(),
-            bias: DepthBiasState::default(),
-        };
-
         let pipeline_descriptor = RenderPipelineDescriptor {
             label: Some(label),
             layout: Some(&pipeline_layout),
@@ -109,17 +184,17 @@ impl <'a> PipeLineBuilder <'a> {
             }),
 
             primitive: PrimitiveState {
-                topology: PrimitiveTopology::TriangleList,
+                topology: self.topology,
                 strip_index_format: None,
                 front_face: FrontFace::Ccw,
-                cull_mode: Some(Face::Back),
+                cull_mode: self.cull_mode,
                 unclipped_depth: false,
                 polygon_mode: PolygonMode::Fill,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: self.depth_stencil.clone(),
             multisample: MultisampleState {
-                count: 1,
+                count: self.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -128,9 +203,24 @@ impl <'a> PipeLineBuilder <'a> {
         };
 
         let pipeline = self.device.create_render_pipeline(&pipeline_descriptor);
+        let error = pollster::block_on(self.device.pop_error_scope());
 
         self.reset();
 
-        pipeline
+        match error {
+            Some(error) => Err(ShaderError { file: shader_file, message: error.to_string() }),
+            None => Ok(pipeline),
+        }
     }
-}
\ No newline at end of file
+
+    /// Name the shader goes by in errors and its source, from `shader_path` when that's set and readable.
+    fn read_shader(&self) -> (String, Cow<'static, str>) {
+        if let Some(path) = &self.shader_path {
+            match fs::read_to_string(path) {
+                Ok(source) => return (path.display().to_string(), source.into()),
+                Err(e) => log::warn!("Unable to read {}, using the built in {}: {}", path.display(), self.shader_filename, e),
+            }
+        }
+        (self.shader_filename.clone(), self.shader_source.into())
+    }
+}
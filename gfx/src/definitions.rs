@@ -1,4 +1,7 @@
 use core::f64;
+use std::collections::HashMap;
+
+use crate::gui::color::{parse_hex, ColorParseError};
 
 #[allow(dead_code)]
 
@@ -8,6 +11,12 @@ pub(crate) struct Vertex {
     pub(crate) position: [f32; 2],
     pub(crate) color: [f32; 4],
     pub(crate) tex_coords: [f32; 2],
+    /// `RenderMode::shader_value` of the quad the vertex belongs to.
+    pub(crate) mode: u32,
+    /// Unclipped rect of the quad as `(x_min, y_bottom, x_max, y_top)`, which rounded corners are cut from.
+    pub(crate) bounds: [f32; 4],
+    /// `(radius, ring thickness)` in pixels. A radius of 0 leaves the quad square, a thickness of 0 fills it.
+    pub(crate) corner: [f32; 2],
 }
 
 unsafe impl bytemuck::Pod for Vertex {}
@@ -33,15 +42,109 @@ impl Vertex {
                     offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress * 2 + std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress * 2 + std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress
+                        + std::mem::size_of::<u32>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress * 2 + std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress * 2
+                        + std::mem::size_of::<u32>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x2,
                 }
             ]
         }
     }
 }
 
+/// How the UI shader colors a quad. Untextured quads don't sample the atlas at all, so they come out in exactly
+/// their color whatever the atlas holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// The vertex color as it is.
+    #[default]
+    SolidColor,
+    /// The texture as it is, the vertex color is ignored.
+    Texture,
+    /// The texture multiplied by the vertex color.
+    TextureTinted,
+}
+
+impl RenderMode {
+    /// What elements and panels built with `texture_name` default to, `SolidColor` for `"solid"` or no texture.
+    pub(crate) fn for_texture(texture_name: &str) -> Self {
+        match texture_name {
+            "" | "solid" => RenderMode::SolidColor,
+            _ => RenderMode::TextureTinted,
+        }
+    }
+
+    /// Value of `Vertex::mode`, matched in `ui_shader.wgsl`'s `fs_main`.
+    pub(crate) fn shader_value(self) -> u32 {
+        match self {
+            RenderMode::SolidColor => 0,
+            RenderMode::Texture => 1,
+            RenderMode::TextureTinted => 2,
+        }
+    }
+}
+
+/// One occupied cell of the level, drawn as a quad showing part of the tile atlas. See `RenderState::set_tile_chunk`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TileInstance {
+    /// The cell at `[0, 0]` has its bottom-left corner on the world origin, y points up.
+    pub cell: [i32; 2],
+    /// `(u, v, width, height)` of the tile's image in the tile atlas, in texture coordinates.
+    /// A zero width draws the placeholder instead, see `TileInstance::placeholder`.
+    pub uv_rect: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for TileInstance {}
+unsafe impl bytemuck::Zeroable for TileInstance {}
+
+impl TileInstance {
+    /// A magenta and black checkerboard in `cell`, for a tile the level names but the atlas doesn't have.
+    pub fn placeholder(cell: [i32; 2]) -> Self {
+        Self { cell, uv_rect: [0.0; 4] }
+    }
+
+    pub(crate) fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TileInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Sint32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[i32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ]
+        }
+    }
+}
+
+/// Where each named texture is in the atlas image.
 #[derive(Debug, Clone)]
 pub struct UiAtlas {
-    pub entries: Vec<UiAtlasTexture>,
+    entries: HashMap<String, UiAtlasTexture>,
+    /// Entry names in the order they were added, which is the order `entries` and `names` list them in.
+    order: Vec<String>,
+    /// Drawn in place of names the atlas has no entry for, see `set_missing`.
+    missing: Option<UiAtlasTexture>,
     width: u32,
     height: u32,
 }
@@ -49,14 +152,81 @@ pub struct UiAtlas {
 impl UiAtlas {
     pub fn new(width: u32, height: u32) -> Self {
         Self {
-            entries: Vec::new(),
+            entries: HashMap::new(),
+            order: Vec::new(),
+            missing: None,
             width,
             height
         }
     }
 
+    /// Adds `entry`, replacing any entry of the same name but keeping that one's place in the order.
     pub fn add_entry(&mut self, entry: UiAtlasTexture) {
-        self.entries.push(entry.generate_tex_coords(self.width, self.height));
+        let entry = entry.generate_tex_coords(self.width, self.height);
+        if !self.entries.contains_key(&entry.name) {
+            self.order.push(entry.name.clone());
+        }
+        self.entries.insert(entry.name.clone(), entry);
+    }
+
+    /// Sets the texture drawn for names the atlas has no entry for. It isn't an entry itself, `get` doesn't
+    /// return it and `names` doesn't list it.
+    pub fn set_missing(&mut self, entry: UiAtlasTexture) {
+        self.missing = Some(entry.generate_tex_coords(self.width, self.height));
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The entry named `name`, without falling back to the missing texture like `resolve` does.
+    ///
+    /// ```
+    /// use gfx::definitions::{UiAtlas, UiAtlasTexture};
+    ///
+    /// let mut atlas = UiAtlas::new(64, 32);
+    /// atlas.add_entry(UiAtlasTexture::new("button".to_string(), 32, 0, 32, 32));
+    /// assert_eq!(atlas.get("button").unwrap().pixel_rect(), (32, 0, 32, 32));
+    /// assert!(atlas.get("missing").is_none());
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&UiAtlasTexture> {
+        self.entries.get(name)
+    }
+
+    /// The entry named `name`, or the missing texture if there's no such entry.
+    pub fn resolve(&self, name: &str) -> Option<&UiAtlasTexture> {
+        self.entries.get(name).or(self.missing.as_ref())
+    }
+
+    /// Texture coordinates of the corners of `name`'s entry, top-left, top-right, bottom-right then bottom-left.
+    /// Falls back to the missing texture like `resolve`, `None` only if the atlas has neither.
+    pub fn uv_quad(&self, name: &str) -> Option<[[f32; 2]; 4]> {
+        let ((x_0, y_0), (x_1, y_1)) = self.resolve(name).and_then(|entry| entry.start_coord.zip(entry.end_coord))?;
+        Some([[x_0, y_0], [x_1, y_0], [x_1, y_1], [x_0, y_1]])
+    }
+
+    /// Every entry, in the order they were added.
+    pub fn entries(&self) -> impl Iterator<Item = &UiAtlasTexture> {
+        self.order.iter().filter_map(|name| self.entries.get(name))
+    }
+
+    /// Names of the entries, in the order they were added. Replacing an entry keeps its place.
+    ///
+    /// ```
+    /// use gfx::definitions::{UiAtlas, UiAtlasTexture};
+    ///
+    /// let mut atlas = UiAtlas::new(64, 32);
+    /// atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 32, 32));
+    /// atlas.add_entry(UiAtlasTexture::new("button".to_string(), 32, 0, 32, 32));
+    /// atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 16, 16));
+    /// assert_eq!(atlas.names().collect::<Vec<_>>(), ["solid", "button"]);
+    /// ```
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
     }
 }
 
@@ -84,11 +254,23 @@ impl UiAtlasTexture {
         }
     }
 
+    /// `(x, y, width, height)` of the texture inside the atlas image, in pixels.
+    pub fn pixel_rect(&self) -> (u32, u32, u32, u32) {
+        (self.x_start, self.y_start, self.image_width, self.image_height)
+    }
+
+    /// Native `(width, height)` of the texture in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.image_width, self.image_height)
+    }
+
+    /// Texture coordinates of the entry, inset by half a texel so the outermost samples land on the centres of
+    /// its edge pixels. Linear filtering there never reaches past the entry, whatever is packed next to it.
     fn generate_tex_coords(mut self, width: u32, height: u32) -> Self {
-        let x0 = self.x_start as f32 / width as f32;
-        let y0 = self.y_start as f32 / height as f32;
-        let x1 = (self.x_start + self.image_width) as f32 / width as f32;
-        let y1 = (self.y_start + self.image_height) as f32 / height as f32;
+        let x0 = (self.x_start as f32 + 0.5) / width as f32;
+        let y0 = (self.y_start as f32 + 0.5) / height as f32;
+        let x1 = ((self.x_start + self.image_width) as f32 - 0.5) / width as f32;
+        let y1 = ((self.y_start + self.image_height) as f32 - 0.5) / height as f32;
 
         self.start_coord = Some((x0, y0));
         self.end_coord = Some((x1, y1));
@@ -96,38 +278,30 @@ impl UiAtlasTexture {
     }
 }
 
-pub trait ColorExt {
-    fn from_hex(hex: &str) -> Self;
+pub trait ColorExt: Sized {
+    /// Like `try_from_hex`, but panics on a malformed color.
+    fn from_hex(hex: &str) -> Self {
+        Self::try_from_hex(hex).unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Accepts the same `#RGB`, `#RGBA`, `#RRGGBB` and `#RRGGBBAA` forms as the UI `Color`.
+    fn try_from_hex(hex: &str) -> Result<Self, ColorParseError>;
     fn srgb_correction(x: f64, y: f64, z: f64) -> (f64, f64, f64);
 }
 
 impl ColorExt for wgpu::Color {
-    fn from_hex(hex_color: &str) -> Self {
-        if let Some(hex) = hex_color.strip_prefix("#") {
-            let mut chars = hex.chars();
-            let red: String = [chars.next().unwrap(), chars.next().unwrap()].iter().collect() ;
-            let green: String = [chars.next().unwrap(), chars.next().unwrap()].iter().collect();
-            let blue: String = [chars.next().unwrap(), chars.next().unwrap()].iter().collect();
-
-            let red_value = u32::from_str_radix(&red, 16).unwrap() as f64 / 255.0;
-            let green_value = u32::from_str_radix(&green, 16).unwrap() as f64 / 255.0;
-            let blue_value = u32::from_str_radix(&blue, 16).unwrap() as f64 / 255.0;
-
-            let (corrected_r, corrected_g, corrected_b) = Self::srgb_correction(red_value, green_value, blue_value);
-            
-            Self {
-                r: corrected_r,
-                g: corrected_g,
-                b: corrected_b,
-                a: 1.0,
-            }
-        } else {
-        log::error!("Provided parameter was not hex!");
-        panic!()
+    fn try_from_hex(hex_color: &str) -> Result<Self, ColorParseError> {
+        let [red, green, blue, alpha] = parse_hex(hex_color)?.map(|channel| channel as f64 / 255.0);
+        let (corrected_r, corrected_g, corrected_b) = Self::srgb_correction(red, green, blue);
+
+        Ok(Self {
+            r: corrected_r,
+            g: corrected_g,
+            b: corrected_b,
+            a: alpha,
+        })
     }
-}
 
-        fn srgb_correction(x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    fn srgb_correction(x: f64, y: f64, z: f64) -> (f64, f64, f64) {
         let mut linear_color = (0.0, 0.0, 0.0);
 
         if x <= 0.04045 {
@@ -152,27 +326,86 @@ impl ColorExt for wgpu::Color {
     }
 }
 
-#[derive(PartialEq, Debug, Clone)]
-pub enum GuiEvent {
-    ChangeLayoutToFileExplorer,
-    ChangeLayoutToProjectView,
-    DisplaySettingsMenu,
-    Highlight
-}
-
-#[derive(PartialEq, Debug, Clone)]
-pub enum GuiPageState {
-    ProjectView,
-    FileExplorer,
-}
-
 #[derive(PartialEq, Debug, Clone)]
 pub enum GuiMenuState {
-    SettingsMenu
+    SettingsMenu,
+    ConfirmDelete,
+    CrashReport,
+    NewProjectMenu,
+    ConfirmExit,
 }
 
+/// Both what an element's `with_fn` callback responds to and the input handed to `UiTree::handle_interaction`.
 #[derive(PartialEq, Debug, Clone)]
 pub enum InteractionStyle {
+    /// A press and release on the same element, the usual button. Handed to `handle_interaction`,
+    /// it stands for a press immediately followed by a release.
     OnClick,
-    OnHover
-}
\ No newline at end of file
+    /// The cursor entering the element, once per entry rather than on every move.
+    OnHover,
+    /// The cursor leaving an element it entered, or the element losing hover some other way, e.g. a scroll.
+    OnHoverExit,
+    /// The button going down over the element.
+    OnPress,
+    /// The button coming up over the element, wherever the press started.
+    OnRelease,
+    /// A second click on the same element within `DOUBLE_CLICK_INTERVAL` of the first.
+    OnDoubleClick,
+    /// The secondary button going down over the element. Handed to `handle_interaction` for a right press,
+    /// which also opens the element's context menu, see `Element::with_context_menu`.
+    OnRightClick,
+    /// The cursor moving by `delta` physical pixels while the button is held after a press on the element,
+    /// whether or not it's still over it.
+    OnDrag { delta: (f32, f32) },
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_attributes_match_the_struct_layout() {
+        let layout = Vertex::desc();
+        let fields = [
+            (std::mem::offset_of!(Vertex, position), wgpu::VertexFormat::Float32x2),
+            (std::mem::offset_of!(Vertex, color), wgpu::VertexFormat::Float32x4),
+            (std::mem::offset_of!(Vertex, tex_coords), wgpu::VertexFormat::Float32x2),
+            (std::mem::offset_of!(Vertex, mode), wgpu::VertexFormat::Uint32),
+            (std::mem::offset_of!(Vertex, bounds), wgpu::VertexFormat::Float32x4),
+            (std::mem::offset_of!(Vertex, corner), wgpu::VertexFormat::Float32x2),
+        ];
+        assert_eq!(layout.array_stride, std::mem::size_of::<Vertex>() as wgpu::BufferAddress);
+        assert_eq!(layout.attributes.len(), fields.len());
+        for (location, (attribute, (offset, format))) in layout.attributes.iter().zip(fields).enumerate() {
+            assert_eq!(attribute.shader_location, location as u32);
+            assert_eq!(attribute.offset, offset as wgpu::BufferAddress, "offset of location {}", location);
+            assert_eq!(attribute.format, format, "format of location {}", location);
+        }
+        // Nothing past the last attribute but padding
+        let last = layout.attributes.last().unwrap();
+        assert!(last.offset + last.format.size() <= layout.array_stride);
+    }
+
+    #[test]
+    fn the_shader_reads_each_attribute_at_its_location() {
+        let shader = include_str!("../../ui_shader.wgsl");
+        let input = &shader[shader.find("struct VertexInput").unwrap()..];
+        let input = &input[..input.find("};").unwrap()];
+        for (location, declaration) in ["position: vec2<f32>", "color: vec4<f32>", "tex_coords: vec2<f32>", "mode: u32", "bounds: vec4<f32>", "corner: vec2<f32>"].iter().enumerate() {
+            assert!(input.contains(&format!("@location({}) {}", location, declaration)), "location {} isn't {}", location, declaration);
+        }
+    }
+
+    #[test]
+    fn untextured_quads_are_solid_colors() {
+        assert_eq!(RenderMode::for_texture(""), RenderMode::SolidColor);
+        assert_eq!(RenderMode::for_texture("solid"), RenderMode::SolidColor);
+        assert_eq!(RenderMode::for_texture("button"), RenderMode::TextureTinted);
+        assert_eq!(RenderMode::default(), RenderMode::SolidColor);
+    }
+
+    #[test]
+    fn render_modes_have_distinct_shader_values() {
+        let values = [RenderMode::SolidColor, RenderMode::Texture, RenderMode::TextureTinted].map(RenderMode::shader_value);
+        assert_eq!(values, [0, 1, 2]);
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+
+use winit::dpi::PhysicalSize;
+
+/// What a `RenderState` is drawing with and how much it holds on the GPU, from `RenderState::diagnostics`.
+/// Displayed as one `name: value` line each, for pasting into bug reports.
+#[derive(Debug, Clone)]
+pub struct RenderDiagnostics {
+    /// Adapter the device was requested from, its backend among the rest.
+    pub adapter: wgpu::AdapterInfo,
+    pub surface_format: wgpu::TextureFormat,
+    pub present_mode: wgpu::PresentMode,
+    pub size: PhysicalSize<u32>,
+    /// Samples per pixel of both passes, 1 without MSAA.
+    pub sample_count: u32,
+    /// Size of the interface's vertex buffer in bytes.
+    pub vertex_buffer_bytes: u64,
+    /// Size of the preview's tile instance buffer in bytes.
+    pub tile_buffer_bytes: u64,
+    /// Estimated bytes of every texture the state and its `GpuContext` hold: the atlases, render targets and
+    /// MSAA textures. The surface's own textures aren't counted, the platform owns those.
+    pub texture_bytes: u64,
+    /// `draw_indexed` calls the interface issued for the last frame.
+    pub draw_calls: u32,
+}
+
+impl RenderDiagnostics {
+    /// The adapter, backend, last frame's draw calls and memory held on one line, short enough for a toast.
+    pub fn summary(&self) -> String {
+        let bytes = self.vertex_buffer_bytes + self.tile_buffer_bytes + self.texture_bytes;
+        format!("{} on {}, {} draw calls, ~{} MiB", self.adapter.name, self.adapter.backend, self.draw_calls, bytes.div_ceil(1024 * 1024))
+    }
+}
+
+impl fmt::Display for RenderDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "adapter: {} ({:?})", self.adapter.name, self.adapter.device_type)?;
+        writeln!(f, "backend: {}", self.adapter.backend)?;
+        writeln!(f, "driver: {} {}", self.adapter.driver, self.adapter.driver_info)?;
+        writeln!(f, "surface: {}x{} {:?}, {:?}", self.size.width, self.size.height, self.surface_format, self.present_mode)?;
+        writeln!(f, "msaa: {}x", self.sample_count)?;
+        writeln!(f, "vertex buffer: {} KiB", self.vertex_buffer_bytes / 1024)?;
+        writeln!(f, "tile buffer: {} KiB", self.tile_buffer_bytes / 1024)?;
+        writeln!(f, "textures: ~{} KiB", self.texture_bytes / 1024)?;
+        write!(f, "draw calls: {}", self.draw_calls)
+    }
+}
@@ -0,0 +1,48 @@
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+/// Frames the rolling FPS average is taken over.
+const FPS_WINDOW: usize = 60;
+
+/// Times the frames `RenderState::render` draws. Frames are only drawn on demand, so the time between two
+/// includes however long the editor sat idle, and the FPS is how often it drew rather than how often it could.
+#[derive(Debug, Default)]
+pub struct FrameClock {
+    last_frame: Option<Instant>,
+    /// Time between each of the latest frames and the one before, oldest first, at most `FPS_WINDOW` of them.
+    frame_times: VecDeque<Duration>,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a frame drawn at `now`.
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame.replace(now) {
+            if self.frame_times.len() == FPS_WINDOW {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(now.saturating_duration_since(last));
+        }
+    }
+
+    /// Time between the last two frames, zero until there have been two.
+    pub fn last_frame_time(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    /// Time between each of the latest frames and the one before, oldest first.
+    pub fn frame_times(&self) -> impl ExactSizeIterator<Item = Duration> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    /// Frames per second averaged over the latest frames, 0.0 until there have been two.
+    pub fn fps(&self) -> f32 {
+        let total: Duration = self.frame_times.iter().sum();
+        if total.is_zero() {
+            return 0.0;
+        }
+        self.frame_times.len() as f32 / total.as_secs_f32()
+    }
+}
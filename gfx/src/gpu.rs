@@ -0,0 +1,266 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::definitions::UiAtlas;
+
+/// The device and what every window draws with on it: the bind group layouts the pipelines are built against
+/// and the UI atlas texture. Each `RenderState` holds it through an `Arc`, a window opened with
+/// `RenderState::new_window` shares the device, queue and atlas of the one it came from.
+pub struct GpuContext {
+    /// Kept for creating the surfaces of windows opened later.
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub adapter_info: wgpu::AdapterInfo,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    /// Layout of every camera's bind group, the UI cameras and the previews'.
+    pub(crate) camera_bind_group_layout: wgpu::BindGroupLayout,
+    /// Layout of the texture and sampler bind groups, the atlases' and the render targets'.
+    pub(crate) material_bind_group_layout: wgpu::BindGroupLayout,
+    pub(crate) sampler: wgpu::Sampler,
+    /// Replaced by `set_atlas`, each frame binds whichever is current.
+    atlas: RwLock<SharedAtlas>,
+    /// Label of the pass last recorded by any window, named by errors the device reports outside an error scope.
+    last_pass: Arc<Mutex<&'static str>>,
+}
+
+struct SharedAtlas {
+    bind_group: wgpu::BindGroup,
+    dimensions: (u32, u32),
+}
+
+impl GpuContext {
+    /// Requests a device from `adapter` and uploads `atlas_image` as the UI atlas. Errors the device raises
+    /// outside an error scope are logged rather than panicking, see `log_uncaptured_error`.
+    pub(crate) async fn new(instance: wgpu::Instance, adapter: wgpu::Adapter, atlas_image: &image::RgbaImage) -> anyhow::Result<GpuContext> {
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("Editor Device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: if cfg!(target_arch = "wasm32") {
+                    wgpu::Limits::downlevel_webgl2_defaults()
+                } else {
+                    wgpu::Limits::default()
+                },
+                memory_hints: Default::default(),
+                trace: wgpu::Trace::Off,
+            })
+            .await?;
+
+        let last_pass = Arc::new(Mutex::new("none"));
+        let handler_pass = last_pass.clone();
+        device.on_uncaptured_error(Box::new(move |error| {
+            let pass = *handler_pass.lock().unwrap_or_else(|e| e.into_inner());
+            log_uncaptured_error(&error, pass);
+        }));
+
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None
+                        },
+                        count: None,
+                    }
+                ],
+                label: Some("Camera 2D Bind Group Layout"),
+            });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let material_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None
+                    }
+                ],
+                label: Some("Material Bind Group Layout"),
+            });
+
+        let bind_group = create_atlas_bind_group(&device, &queue, &material_bind_group_layout, &sampler, atlas_image, "UI Atlas")?;
+
+        Ok(Self {
+            adapter_info: adapter.get_info(),
+            instance,
+            adapter,
+            device,
+            queue,
+            camera_bind_group_layout,
+            material_bind_group_layout,
+            sampler,
+            atlas: RwLock::new(SharedAtlas { bind_group, dimensions: atlas_image.dimensions() }),
+            last_pass,
+        })
+    }
+
+    /// Swaps in a newly generated UI atlas for every window. The texture is allocated again rather than
+    /// overwritten, so the new atlas may be a different size. Each window's interface still needs the matching
+    /// `UiAtlas`, see `RenderState::reload_atlas`.
+    pub fn set_atlas(&self, atlas_image: &image::RgbaImage) -> anyhow::Result<()> {
+        let bind_group = create_atlas_bind_group(&self.device, &self.queue, &self.material_bind_group_layout, &self.sampler, atlas_image, "UI Atlas")?;
+        *self.atlas.write().unwrap_or_else(|e| e.into_inner()) = SharedAtlas { bind_group, dimensions: atlas_image.dimensions() };
+        Ok(())
+    }
+
+    /// Fails unless `atlas` describes the atlas texture currently uploaded.
+    pub(crate) fn check_atlas(&self, atlas: &UiAtlas) -> anyhow::Result<()> {
+        let dimensions = self.atlas.read().unwrap_or_else(|e| e.into_inner()).dimensions;
+        check_atlas_size(dimensions, atlas)
+    }
+
+    /// The atlas bind group as of now, a handle to the same GPU object.
+    pub(crate) fn atlas_bind_group(&self) -> wgpu::BindGroup {
+        self.atlas.read().unwrap_or_else(|e| e.into_inner()).bind_group.clone()
+    }
+
+    /// Size of the UI atlas texture in pixels.
+    pub(crate) fn atlas_dimensions(&self) -> (u32, u32) {
+        self.atlas.read().unwrap_or_else(|e| e.into_inner()).dimensions
+    }
+
+    /// Records `pass` as the last one recorded, for the uncaptured error log. Called before beginning it, so errors
+    /// raised while recording it or submitting the frame name it.
+    pub(crate) fn set_last_pass(&self, pass: &'static str) {
+        *self.last_pass.lock().unwrap_or_else(|e| e.into_inner()) = pass;
+    }
+}
+
+/// Logs an error no scope caught, with the kind of error apart from wgpu's description, which names the
+/// resource by its label. wgpu's default handler panics instead.
+fn log_uncaptured_error(error: &wgpu::Error, last_pass: &str) {
+    let (kind, description) = match error {
+        wgpu::Error::OutOfMemory { .. } => ("out of memory", error.to_string()),
+        wgpu::Error::Validation { description, .. } => ("validation", description.clone()),
+        wgpu::Error::Internal { description, .. } => ("internal", description.clone()),
+    };
+    log::error!("Uncaptured {} error on the GPU, last pass {:?}: {}", kind, last_pass, description);
+}
+
+/// Runs `create` inside validation and out of memory error scopes in debug builds, logging anything they catch
+/// as a failure to create `label`. The resource `create` returns is still handed back, invalid, and using it raises
+/// uncaptured errors later. Release builds skip the scopes and leave errors to `log_uncaptured_error`.
+pub(crate) fn create_scoped<T>(device: &wgpu::Device, label: &str, create: impl FnOnce() -> T) -> T {
+    if !cfg!(debug_assertions) {
+        return create();
+    }
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let resource = create();
+    let validation = pollster::block_on(device.pop_error_scope());
+    let out_of_memory = pollster::block_on(device.pop_error_scope());
+    for error in [validation, out_of_memory].into_iter().flatten() {
+        log::error!("Unable to create {}: {}", label, error);
+    }
+    resource
+}
+
+/// Roughly the bytes a `width` x `height` texture of `format` with `sample_count` samples per pixel takes, for
+/// `RenderState::diagnostics`. Formats without a single texel size are counted as 4 bytes.
+pub(crate) fn texture_bytes(format: wgpu::TextureFormat, width: u32, height: u32, sample_count: u32) -> u64 {
+    let texel = format.block_copy_size(None).unwrap_or(4);
+    width as u64 * height as u64 * texel as u64 * sample_count as u64
+}
+
+pub(crate) fn check_atlas_size(dimensions: (u32, u32), atlas: &UiAtlas) -> anyhow::Result<()> {
+    if dimensions != (atlas.width(), atlas.height()) {
+        anyhow::bail!(
+            "The atlas image is {}x{} but its atlas data expects {}x{}",
+            dimensions.0, dimensions.1, atlas.width(), atlas.height(),
+        );
+    }
+    Ok(())
+}
+
+/// Uploads `atlas_image` into a new texture labeled `label` and binds it with `layout`, the material layout.
+pub(crate) fn create_atlas_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    atlas_image: &image::RgbaImage,
+    label: &str,
+) -> anyhow::Result<wgpu::BindGroup> {
+    let dimensions = atlas_image.dimensions();
+    let max_dimension = device.limits().max_texture_dimension_2d;
+    if dimensions.0 > max_dimension || dimensions.1 > max_dimension {
+        anyhow::bail!("The {}x{} texture atlas is larger than this GPU's {}px texture limit", dimensions.0, dimensions.1, max_dimension);
+    }
+
+    let texture_size = wgpu::Extent3d {
+        width: dimensions.0,
+        height: dimensions.1,
+        depth_or_array_layers: 1,
+    };
+    let diffuse_texture = create_scoped(device, label, || device.create_texture(
+        &wgpu::TextureDescriptor {
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: Some(label),
+            view_formats: &[],
+        }
+    ));
+
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &diffuse_texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        atlas_image,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * dimensions.0),
+            rows_per_image: Some(dimensions.1),
+        },
+        texture_size,
+    );
+
+    let diffuse_texture_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    Ok(device.create_bind_group(
+        &wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                }
+            ],
+        }
+    ))
+}
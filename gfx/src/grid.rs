@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use wgpu::util::DeviceExt;
+
+use crate::{builder::{PipeLineBuilder, ShaderError}, gui::color::Color};
+
+const SHADER_FILE: &str = "grid_shader.wgsl";
+
+/// Smallest spacing drawn, see `GridConfig::spacing`.
+const MIN_SPACING: f32 = 0.001;
+
+/// Spacing and colors of the grid drawn behind the level in the preview, see `RenderState::set_grid`.
+#[derive(Clone)]
+pub struct GridConfig {
+    /// World units between minor lines. Anything below a thousandth of a unit is drawn at that.
+    pub spacing: f32,
+    /// Every this many minor lines is a major one, at least 1.
+    pub major_every: u32,
+    pub color_major: Color,
+    pub color_minor: Color,
+}
+
+impl GridConfig {
+    /// `spacing` as it's drawn, never below `MIN_SPACING`.
+    pub(crate) fn cell_size(&self) -> f32 {
+        self.spacing.max(MIN_SPACING)
+    }
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            spacing: 32.0,
+            major_every: 8,
+            color_major: Color::from_hex("#484f58"),
+            color_minor: Color::from_hex("#30363d"),
+        }
+    }
+}
+
+/// `GridConfig` as `grid_shader.wgsl` reads it.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct GridUniform {
+    spacing: f32,
+    major_every: f32,
+    /// The colors are 16-byte aligned in the shader.
+    _padding: [f32; 2],
+    color_major: [f32; 4],
+    color_minor: [f32; 4],
+}
+
+impl GridUniform {
+    fn new(config: &GridConfig) -> Self {
+        Self {
+            spacing: config.cell_size(),
+            major_every: config.major_every.max(1) as f32,
+            _padding: [0.0; 2],
+            color_major: config.color_major.into_vec4(),
+            color_minor: config.color_minor.into_vec4(),
+        }
+    }
+}
+
+/// Infinite grid over the preview, one triangle covering the pass whose fragments work out their world position
+/// from the preview camera. Lines are a pixel wide on screen at any zoom, minor ones fade out as they close up.
+pub(crate) struct Grid {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Layout of `bind_group`, kept for building the pipeline again in `reload_shader`.
+    bind_group_layout: wgpu::BindGroupLayout,
+    config: GridConfig,
+}
+
+impl Grid {
+    /// Draws in the preview pass, into a `format` target with `sample_count` samples and a `depth_format` depth
+    /// attachment. `camera_bind_group_layout` is the layout of the preview camera's bind group, bound at group 0.
+    pub(crate) fn new(device: &wgpu::Device, camera_bind_group_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat, depth_format: wgpu::TextureFormat, sample_count: u32) -> Result<Self, ShaderError> {
+        let config = GridConfig::default();
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[GridUniform::new(&config)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("Grid Bind Group Layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grid Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                }
+            ]
+        });
+
+        let pipeline = Self::build_pipeline(device, [camera_bind_group_layout, &bind_group_layout], format, depth_format, sample_count, None)?;
+
+        Ok(Self { pipeline, uniform_buffer, bind_group, bind_group_layout, config })
+    }
+
+    /// Builds the pipeline again with the shader read from `shader_dir`, for the same target `new` was given.
+    /// The old pipeline stays if the shader doesn't build.
+    pub(crate) fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        shader_dir: &Path,
+    ) -> Result<(), ShaderError> {
+        let layouts = [camera_bind_group_layout, &self.bind_group_layout];
+        self.pipeline = Self::build_pipeline(device, layouts, format, depth_format, sample_count, Some(shader_dir))?;
+        Ok(())
+    }
+
+    /// With the compiled in shader, or the one in `shader_dir` when given.
+    fn build_pipeline(
+        device: &wgpu::Device,
+        layouts: [&wgpu::BindGroupLayout; 2],
+        format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        shader_dir: Option<&Path>,
+    ) -> Result<wgpu::RenderPipeline, ShaderError> {
+        let mut builder = PipeLineBuilder::new(device);
+        // Always passes the depth test and writes the far plane, the depth the pass clears to, so level content
+        // drawn after it tests as if it weren't there
+        builder
+            .set_pixel_format(format)
+            .set_depth_stencil(depth_format, wgpu::CompareFunction::Always)
+            .set_sample_count(sample_count)
+            .set_blend_state(wgpu::BlendState::ALPHA_BLENDING)
+            .add_bind_group_layout(layouts[0])
+            .add_bind_group_layout(layouts[1])
+            .set_shader_module(SHADER_FILE, include_str!("../../grid_shader.wgsl"), "vs_main", "fs_main");
+        if let Some(shader_dir) = shader_dir {
+            builder.set_shader_file(shader_dir.join(SHADER_FILE));
+        }
+        builder.build("Grid Pipeline")
+    }
+
+    pub(crate) fn config(&self) -> &GridConfig {
+        &self.config
+    }
+
+    pub(crate) fn set_config(&mut self, queue: &wgpu::Queue, config: GridConfig) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[GridUniform::new(&config)]));
+        self.config = config;
+    }
+
+    /// Covers the whole pass. `camera_bind_group` holds the preview camera, whose view the grid follows.
+    pub(crate) fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, camera_bind_group: &'a wgpu::BindGroup) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
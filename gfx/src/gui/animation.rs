@@ -0,0 +1,177 @@
+use std::{sync::OnceLock, time::{Duration, Instant}};
+
+use crate::definitions::Vertex;
+
+use super::{color::Color, interface::ElementHandle, layout::QuadRect};
+
+/// How long the hover highlight takes to fade between colors, unless `UiTree::set_hover_transition` changes it.
+pub const HOVER_TRANSITION: Duration = Duration::from_millis(100);
+
+/// How often a running animation wakes the event loop to draw its next step.
+pub(crate) const ANIMATION_FRAME: Duration = Duration::from_millis(16);
+
+/// How long a spinner's dot takes to circle once.
+pub(crate) const SPINNER_TURN: Duration = Duration::from_secs(1);
+
+/// Where every spinner's dot is at `now`, in radians. It goes by time since the first call rather than since the
+/// interface was built, so a spinner carries on from the same place when its page is rebuilt.
+pub(crate) fn spinner_angle(now: Instant) -> f32 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    let epoch = *EPOCH.get_or_init(|| now);
+    let turns = now.saturating_duration_since(epoch).as_secs_f32() / SPINNER_TURN.as_secs_f32();
+    turns.fract() * std::f32::consts::TAU
+}
+
+/// How an animation's progress maps onto the distance covered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    Linear,
+    /// Starts fast and slows into the end value, for things coming to rest.
+    #[default]
+    EaseOutCubic,
+}
+
+impl Easing {
+    /// Share of the distance covered at `t`, both from 0.0 to 1.0.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+        }
+    }
+}
+
+/// A render-time property of an element or panel, and the value `UiTree::animate` moves it to.
+#[derive(Clone)]
+pub enum AnimProperty {
+    /// Fill color. Wins over the styled colors and `Element::with_temp_color` until `Element::reset_color`,
+    /// though not over the pressed and disabled colors.
+    Color(Color),
+    /// Offset from where layout puts it, in pixels scaled like `Dimension::Pixels`, x right and y down.
+    Offset(f32, f32),
+    /// Size relative to the laid out rect, scaled about its center. Text keeps its size.
+    Scale(f32),
+    /// Multiplies the alpha of the fill, border and text. A panel's opacity applies to its elements too.
+    Opacity(f32),
+}
+
+impl AnimProperty {
+    fn same_property(&self, other: &AnimProperty) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    fn lerp(&self, to: &AnimProperty, t: f32) -> AnimProperty {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        match (self, to) {
+            (AnimProperty::Color(from), AnimProperty::Color(to)) => AnimProperty::Color(from.lerp(to, t)),
+            (AnimProperty::Offset(from_x, from_y), AnimProperty::Offset(to_x, to_y)) => AnimProperty::Offset(mix(*from_x, *to_x), mix(*from_y, *to_y)),
+            (AnimProperty::Scale(from), AnimProperty::Scale(to)) => AnimProperty::Scale(mix(*from, *to)),
+            (AnimProperty::Opacity(from), AnimProperty::Opacity(to)) => AnimProperty::Opacity(mix(*from, *to)),
+            _ => to.clone(),
+        }
+    }
+}
+
+/// Values animations write on top of an element's or panel's layout and style. Only drawing uses them,
+/// input still goes by the laid out rect.
+#[derive(Clone)]
+pub(crate) struct Animated {
+    pub(crate) color: Option<Color>,
+    pub(crate) offset: (f32, f32),
+    pub(crate) scale: f32,
+    pub(crate) opacity: f32,
+}
+
+impl Default for Animated {
+    fn default() -> Self {
+        Self { color: None, offset: (0.0, 0.0), scale: 1.0, opacity: 1.0 }
+    }
+}
+
+impl Animated {
+    /// The current value of `property`'s kind, what an animation of it starts from. Colors have no value of their own
+    /// without an override, so `fallback` is used.
+    pub(crate) fn current(&self, property: &AnimProperty, fallback: Color) -> AnimProperty {
+        match property {
+            AnimProperty::Color(_) => AnimProperty::Color(self.color.clone().unwrap_or(fallback)),
+            AnimProperty::Offset(..) => AnimProperty::Offset(self.offset.0, self.offset.1),
+            AnimProperty::Scale(_) => AnimProperty::Scale(self.scale),
+            AnimProperty::Opacity(_) => AnimProperty::Opacity(self.opacity),
+        }
+    }
+
+    pub(crate) fn set(&mut self, property: AnimProperty) {
+        match property {
+            AnimProperty::Color(color) => self.color = Some(color),
+            AnimProperty::Offset(x, y) => self.offset = (x, y),
+            AnimProperty::Scale(scale) => self.scale = scale.max(0.0),
+            AnimProperty::Opacity(opacity) => self.opacity = opacity.clamp(0.0, 1.0),
+        }
+    }
+
+    /// `rect` moved by the offset and scaled about its center, with pixel sizes at `pixel_scale`.
+    pub(crate) fn apply(&self, rect: QuadRect, pixel_scale: f32) -> QuadRect {
+        if self.offset == (0.0, 0.0) && self.scale == 1.0 {
+            return rect;
+        }
+        let (center_x, center_y) = ((rect.x_min + rect.x_max) / 2.0 + self.offset.0 * pixel_scale, (rect.y_top + rect.y_bottom) / 2.0 - self.offset.1 * pixel_scale);
+        let (half_width, half_height) = ((rect.x_max - rect.x_min) / 2.0 * self.scale, (rect.y_top - rect.y_bottom) / 2.0 * self.scale);
+        QuadRect {
+            x_min: center_x - half_width,
+            x_max: center_x + half_width,
+            y_top: center_y + half_height,
+            y_bottom: center_y - half_height,
+        }
+    }
+}
+
+/// Multiplies the alpha of every vertex by `opacity`.
+pub(crate) fn fade(vertices: &mut [Vertex], opacity: f32) {
+    if opacity < 1.0 {
+        for vertex in vertices {
+            vertex.color[3] *= opacity;
+        }
+    }
+}
+
+/// What an animation writes to. Panels are kept by index, which `UiTree::remove_overlay` keeps up to date.
+#[derive(Clone)]
+pub(crate) enum AnimTarget {
+    Element(ElementHandle),
+    Panel(usize),
+}
+
+/// One property moving from `from` to `to`, see `UiTree::animate`.
+pub(crate) struct Animation {
+    pub(crate) target: AnimTarget,
+    pub(crate) from: AnimProperty,
+    pub(crate) to: AnimProperty,
+    pub(crate) start: Instant,
+    pub(crate) duration: Duration,
+    pub(crate) easing: Easing,
+    /// Drops the color override once done, handing the element back to its styled color. Set for hover transitions.
+    pub(crate) settle: bool,
+}
+
+impl Animation {
+    /// Whether `self` animates the same property of the same thing, which a new animation replaces.
+    pub(crate) fn replaced_by(&self, target: &AnimTarget, property: &AnimProperty) -> bool {
+        let same_target = match (&self.target, target) {
+            (AnimTarget::Element(own), AnimTarget::Element(other)) => own == other,
+            (AnimTarget::Panel(own), AnimTarget::Panel(other)) => own == other,
+            _ => false,
+        };
+        same_target && self.to.same_property(property)
+    }
+
+    /// The value at `now`, and whether the animation has finished.
+    pub(crate) fn sample(&self, now: Instant) -> (AnimProperty, bool) {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return (self.to.clone(), true);
+        }
+        let t = self.easing.apply(elapsed.as_secs_f32() / self.duration.as_secs_f32());
+        (self.from.lerp(&self.to, t), false)
+    }
+}
@@ -1,20 +1,39 @@
+use std::{ops::{Deref, DerefMut}, sync::atomic::{AtomicBool, Ordering}};
+
 use glam::{Mat4, Vec2, Vec3};
 use winit::dpi::PhysicalSize;
 
+pub(crate) const MIN_ZOOM: f32 = 0.1;
+pub(crate) const MAX_ZOOM: f32 = 64.0;
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub(crate) struct Camera2DUniform {
     pub(crate) view_proj: [[f32; 4]; 4],
+    /// Clip space back to world space, for shaders that work out world positions themselves, such as the grid's.
+    pub(crate) inv_view_proj: [[f32; 4]; 4],
 }
 
-pub(crate) struct Camera2D {
+impl Camera2DUniform {
+    pub(crate) fn new(camera: &Camera2D) -> Self {
+        let view_proj = camera.build_view_projection_matrix();
+        Self {
+            view_proj: view_proj.to_cols_array_2d(),
+            inv_view_proj: view_proj.inverse().to_cols_array_2d(),
+        }
+    }
+}
+
+/// Orthographic camera in physical pixels: at zoom 1.0 one world unit is one pixel, y pointing up.
+pub struct Camera2D {
     position: Vec2,
     zoom: f32,
     screen_size: PhysicalSize<u32>,
 }
 
 impl Camera2D {
-    pub(crate) fn new(screen_width: u32, screen_height: u32) -> Self {
+    /// Centered on the origin at zoom 1.0, for a viewport of `screen_width` by `screen_height` pixels.
+    pub fn new(screen_width: u32, screen_height: u32) -> Self {
         Self { 
             position: Vec2::new(0.0, 0.0), 
             zoom: 1.0,
@@ -47,4 +66,78 @@ impl Camera2D {
     pub(crate) fn update_screen_size(&mut self, new_size: PhysicalSize<u32>) {
         self.screen_size = new_size;
     }
-}
\ No newline at end of file
+
+    /// Moves the view so the content follows a cursor dragged `delta` physical pixels, screen y pointing down.
+    pub fn pan(&mut self, delta: Vec2) {
+        self.position -= Vec2::new(delta.x, -delta.y) / self.zoom;
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// World position at the center of the viewport.
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
+        self.position = position;
+    }
+
+    /// Clamped to 0.1-64.0.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the world point under `point` where it is on screen.
+    /// `point` is in physical pixels from the top-left of the camera's viewport.
+    pub fn zoom_at(&mut self, point: Vec2, factor: f32) {
+        let anchor = self.screen_to_world(point);
+        self.set_zoom(self.zoom * factor);
+        self.position += anchor - self.screen_to_world(point);
+    }
+
+    /// World position shown at `point`, in physical pixels from the top-left of the camera's viewport.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let from_center = Vec2::new(
+            point.x - self.screen_size.width as f32 / 2.0,
+            self.screen_size.height as f32 / 2.0 - point.y,
+        );
+        self.position + from_center / self.zoom
+    }
+}
+
+/// Mutable access to a camera whose uniform is uploaded, and a redraw requested, when this is dropped.
+/// See `RenderState::preview_camera_mut`.
+pub struct CameraMut<'a> {
+    pub(crate) camera: &'a mut Camera2D,
+    pub(crate) queue: &'a wgpu::Queue,
+    pub(crate) buffer: &'a wgpu::Buffer,
+    pub(crate) needs_redraw: &'a AtomicBool,
+}
+
+impl Deref for CameraMut<'_> {
+    type Target = Camera2D;
+
+    fn deref(&self) -> &Camera2D {
+        self.camera
+    }
+}
+
+impl DerefMut for CameraMut<'_> {
+    fn deref_mut(&mut self) -> &mut Camera2D {
+        self.camera
+    }
+}
+
+impl Drop for CameraMut<'_> {
+    fn drop(&mut self) {
+        write_camera(self.queue, self.buffer, self.camera);
+        self.needs_redraw.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn write_camera(queue: &wgpu::Queue, buffer: &wgpu::Buffer, camera: &Camera2D) {
+    queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[Camera2DUniform::new(camera)]));
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Written as a `#RRGGBBAA` hex string in layout files, see `to_hex`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color {
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32
+}
+
+impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub(crate) fn into_vec4(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// The color as a render pass clear value, it's linear already.
+    pub(crate) fn to_wgpu(&self) -> wgpu::Color {
+        let [r, g, b, a] = self.into_vec4().map(f64::from);
+        wgpu::Color { r, g, b, a }
+    }
+
+    /// The color `t` of the way from `self` to `other`, mixed in linear space.
+    pub(crate) fn lerp(&self, other: &Color, t: f32) -> Color {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.a, other.a))
+    }
+
+    /// Like `try_from_hex`, but panics on a malformed color. Meant for colors written in the source.
+    pub fn from_hex(hex_color: &str) -> Self {
+        Self::try_from_hex(hex_color).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parses `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA` in either case. Alpha defaults to opaque.
+    pub fn try_from_hex(hex_color: &str) -> Result<Self, ColorParseError> {
+        let [red, green, blue, alpha] = parse_hex(hex_color)?.map(|channel| channel as f32 / 255.0);
+        let (corrected_r, corrected_g, corrected_b) = Self::srgb_correction(red, green, blue);
+
+        Ok(Self {
+            r: corrected_r,
+            g: corrected_g,
+            b: corrected_b,
+            a: alpha
+        })
+    }
+
+    /// `#RRGGBBAA`, undoing the sRGB correction `try_from_hex` applies, so parsing it gives this color back.
+    pub fn to_hex(&self) -> String {
+        let encode = |linear: f32| {
+            let srgb = if linear <= 0.0031308 { linear * 12.92 } else { 1.055 * linear.powf(1.0 / 2.4) - 0.055 };
+            (srgb.clamp(0.0, 1.0) * 255.0).round() as u8
+        };
+        let alpha = (self.a.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!("#{:02x}{:02x}{:02x}{:02x}", encode(self.r), encode(self.g), encode(self.b), alpha)
+    }
+
+    fn srgb_correction(x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let mut linear_color = (0.0, 0.0, 0.0);
+
+        if x <= 0.04045 {
+            linear_color.0 = x / 12.92;
+        } else {
+            linear_color.0 = ((x + 0.055) / 1.055).powf(2.4);
+        }
+
+        if y <= 0.04045 {
+            linear_color.1 = y / 12.92;
+        } else {
+            linear_color.1 = ((y + 0.055) / 1.055).powf(2.4);
+        }
+
+        if z <= 0.04045 {
+            linear_color.2 = z / 12.92;
+        } else {
+            linear_color.2 = ((z + 0.055) / 1.055).powf(2.4);
+        }
+
+        linear_color
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorParseError {
+    MissingHash(String),
+    /// Not 3, 4, 6 or 8 digits after the `#`.
+    BadLength(String),
+    InvalidDigit(String),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorParseError::MissingHash(color) => write!(f, "Color {:?} doesn't start with '#'", color),
+            ColorParseError::BadLength(color) => write!(f, "Color {:?} should be #RGB, #RGBA, #RRGGBB or #RRGGBBAA", color),
+            ColorParseError::InvalidDigit(color) => write!(f, "Color {:?} has a character that isn't a hex digit", color),
+        }
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+impl TryFrom<String> for Color {
+    type Error = ColorParseError;
+
+    fn try_from(hex_color: String) -> Result<Self, Self::Error> {
+        Self::try_from_hex(&hex_color)
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_hex()
+    }
+}
+
+/// Red, green, blue and alpha of a `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA` color, as written (no sRGB correction).
+pub(crate) fn parse_hex(hex_color: &str) -> Result<[u8; 4], ColorParseError> {
+    let Some(hex) = hex_color.strip_prefix('#') else {
+        return Err(ColorParseError::MissingHash(hex_color.to_string()));
+    };
+    // from_str_radix alone would let a leading '+' through
+    let digits = hex.chars()
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| ColorParseError::InvalidDigit(hex_color.to_string()))?;
+
+    match digits[..] {
+        // A short digit stands for itself repeated, F is FF
+        [r, g, b] => Ok([r * 17, g * 17, b * 17, 255]),
+        [r, g, b, a] => Ok([r * 17, g * 17, b * 17, a * 17]),
+        [r1, r0, g1, g0, b1, b0] => Ok([r1 << 4 | r0, g1 << 4 | g0, b1 << 4 | b0, 255]),
+        [r1, r0, g1, g0, b1, b0, a1, a0] => Ok([r1 << 4 | r0, g1 << 4 | g0, b1 << 4 | b0, a1 << 4 | a0]),
+        _ => Err(ColorParseError::BadLength(hex_color.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_length_parses() {
+        assert_eq!(parse_hex("#f80"), Ok([0xff, 0x88, 0x00, 0xff]));
+        assert_eq!(parse_hex("#f808"), Ok([0xff, 0x88, 0x00, 0x88]));
+        assert_eq!(parse_hex("#0d1117"), Ok([0x0d, 0x11, 0x17, 0xff]));
+        assert_eq!(parse_hex("#0d111780"), Ok([0x0d, 0x11, 0x17, 0x80]));
+    }
+
+    #[test]
+    fn uppercase_is_the_same_color() {
+        assert_eq!(parse_hex("#ABCDEF"), parse_hex("#abcdef"));
+        assert_eq!(parse_hex("#FA0C"), Ok([0xff, 0xaa, 0x00, 0xcc]));
+        assert_eq!(Color::try_from_hex("#0D1117").unwrap().to_hex(), "#0d1117ff");
+    }
+
+    #[test]
+    fn alpha_defaults_to_opaque() {
+        assert_eq!(Color::try_from_hex("#123").unwrap().into_vec4()[3], 1.0);
+        assert_eq!(Color::try_from_hex("#123456").unwrap().into_vec4()[3], 1.0);
+        assert_eq!(Color::try_from_hex("#12345600").unwrap().into_vec4()[3], 0.0);
+    }
+
+    #[test]
+    fn wgpu_colors_keep_their_alpha() {
+        use crate::definitions::ColorExt;
+
+        assert_eq!(wgpu::Color::try_from_hex("#0d1117").unwrap().a, 1.0);
+        assert_eq!(wgpu::Color::try_from_hex("#0d111780").unwrap().a, 128.0 / 255.0);
+        assert_eq!(wgpu::Color::try_from_hex("0d1117").unwrap_err(), ColorParseError::MissingHash("0d1117".to_string()));
+    }
+
+    #[test]
+    fn a_missing_hash_is_an_error() {
+        assert_eq!(Color::try_from_hex("0d1117").unwrap_err(), ColorParseError::MissingHash("0d1117".to_string()));
+    }
+
+    #[test]
+    fn invalid_characters_are_an_error() {
+        for color in ["#0d11g7", "#+fff", "#ff ", "#ééé"] {
+            assert_eq!(Color::try_from_hex(color).unwrap_err(), ColorParseError::InvalidDigit(color.to_string()));
+        }
+    }
+
+    #[test]
+    fn other_lengths_are_an_error() {
+        for color in ["#", "#ff", "#fffff", "#fffffffff"] {
+            assert_eq!(Color::try_from_hex(color).unwrap_err(), ColorParseError::BadLength(color.to_string()));
+        }
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let color = Color::try_from_hex("#30363d80").unwrap();
+        assert_eq!(color.to_hex(), "#30363d80");
+        assert_eq!(Color::try_from_hex(&color.to_hex()).unwrap().to_hex(), "#30363d80");
+    }
+}
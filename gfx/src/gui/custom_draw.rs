@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use wgpu::{BindGroup, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::render_target::RenderTarget;
+
+use super::layout::PixelRect;
+
+/// Everything a custom draw hook gets while the UI pass is being recorded.
+/// The scissor is already set to `pixel_rect`; pipeline, bind groups and buffers are restored by the renderer afterwards.
+pub struct CustomDrawCtx<'a, 'pass> {
+    pub render_pass: &'a mut RenderPass<'pass>,
+    pub pixel_rect: PixelRect,
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    /// Samples per pixel of the UI pass, pipelines drawn with in it must be built for the same count.
+    pub sample_count: u32,
+}
+
+/// What a custom draw hook gets when the interface is uploaded, to create or update what it draws with.
+pub struct CustomPrepareCtx<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pub pixel_rect: PixelRect,
+    /// Format of the UI pass's color target, pipelines drawn with in it must be built for it.
+    pub format: TextureFormat,
+    /// Samples per pixel of the UI pass, as in `CustomDrawCtx`.
+    pub sample_count: u32,
+}
+
+/// Non-quad content drawn inside an element, see `Element::with_custom_draw`.
+///
+/// `prepare` runs whenever the renderer uploads the interface and may create or upload its own
+/// buffers; `draw` only records commands with them, so no mutable borrow is needed while the pass is open.
+pub trait CustomDraw: Send {
+    fn prepare(&mut self, _ctx: &CustomPrepareCtx<'_>) {}
+
+    fn draw(&self, ctx: &mut CustomDrawCtx<'_, '_>);
+}
+
+impl<F: Fn(&mut CustomDrawCtx<'_, '_>) + Send> CustomDraw for F {
+    fn draw(&self, ctx: &mut CustomDrawCtx<'_, '_>) {
+        self(ctx)
+    }
+}
+
+pub(crate) struct CustomDrawSlot {
+    pub(crate) hook: Box<dyn CustomDraw>,
+    pub(crate) pixel_rect: PixelRect,
+}
+
+/// The UI pipeline state custom draws may clobber, so it can be put back after each one.
+pub(crate) struct UiPassState<'a> {
+    pub(crate) pipeline: &'a RenderPipeline,
+    pub(crate) camera_bind_group: &'a BindGroup,
+    pub(crate) material_bind_group: &'a BindGroup,
+    /// Bound in place of `material_bind_group` for elements with a render target.
+    pub(crate) render_targets: &'a HashMap<String, RenderTarget>,
+}
+
+impl UiPassState<'_> {
+    pub(crate) fn apply(&self, render_pass: &mut RenderPass<'_>) {
+        render_pass.set_pipeline(self.pipeline);
+        render_pass.set_bind_group(0, self.camera_bind_group, &[]);
+        render_pass.set_bind_group(1, self.material_bind_group, &[]);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{sync::{Arc, Mutex}, time::Duration};
+
+use wgpu::util::DeviceExt;
+
+use crate::builder::PipeLineBuilder;
+
+use super::{color::Color, custom_draw::{CustomDraw, CustomDrawCtx, CustomPrepareCtx}};
+
+const SHADER_FILE: &str = "debug_graph_shader.wgsl";
+
+/// The debug overlay's layer, above anything else. Other overlays are placed below it, see `UiTree::show_debug_overlay`.
+pub(crate) const DEBUG_OVERLAY_LAYER: i32 = i32::MAX;
+
+/// Size of the overlay's lines, its padding and its gap to the window corner, in pixels scaled like `Dimension::Pixels`.
+pub(crate) const DEBUG_OVERLAY_WIDTH: f32 = 220.0;
+pub(crate) const DEBUG_OVERLAY_LINE_HEIGHT: f32 = 18.0;
+pub(crate) const DEBUG_OVERLAY_PADDING: f32 = 6.0;
+pub(crate) const DEBUG_OVERLAY_MARGIN: f32 = 8.0;
+/// Height of the frame-time graph under the lines, scaled the same way.
+pub(crate) const DEBUG_OVERLAY_GRAPH_HEIGHT: f32 = 40.0;
+
+pub(crate) const DEBUG_OVERLAY_COLOR: &str = "#000000b0";
+
+/// Frame time a bar of the full graph height stands for, longer frames are cut off at the top.
+const GRAPH_MAX_FRAME_TIME: Duration = Duration::from_millis(50);
+/// Frames slower than 60 and than 30 fps, drawn in `GRAPH_SLOW_COLOR` and `GRAPH_DROPPED_COLOR`.
+const GRAPH_SLOW_FRAME_TIME: Duration = Duration::from_micros(16_667);
+const GRAPH_DROPPED_FRAME_TIME: Duration = Duration::from_micros(33_334);
+/// Share of its slot a bar covers, the rest is the gap to the next.
+const GRAPH_BAR_FILL: f32 = 0.75;
+
+const GRAPH_COLOR: &str = "#3fb950";
+const GRAPH_SLOW_COLOR: &str = "#d29922";
+const GRAPH_DROPPED_COLOR: &str = "#f85149";
+
+/// Pipeline the frame-time graph draws with, shared by the graphs of successive overlays so a refresh doesn't
+/// build it again. Kept with the format and sample count it was built for.
+pub(crate) type GraphPipeline = Arc<Mutex<Option<(wgpu::TextureFormat, u32, wgpu::RenderPipeline)>>>;
+
+/// A corner of a graph bar, in clip space of the graph's rect.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GraphVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl GraphVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GraphVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Bar chart of the latest frame times, oldest on the left, drawn in the debug overlay with `Element::with_custom_draw`.
+pub(crate) struct FrameTimeGraph {
+    vertices: Vec<GraphVertex>,
+    shared_pipeline: GraphPipeline,
+    /// Taken from `shared_pipeline` in `prepare`, `None` if it didn't build.
+    pipeline: Option<wgpu::RenderPipeline>,
+    /// Written once in `prepare`, the bars don't change for the graph's lifetime.
+    vertex_buffer: Option<wgpu::Buffer>,
+}
+
+impl FrameTimeGraph {
+    pub(crate) fn new(frame_times: &[Duration], shared_pipeline: GraphPipeline) -> Self {
+        Self { vertices: graph_vertices(frame_times), shared_pipeline, pipeline: None, vertex_buffer: None }
+    }
+
+    fn build_pipeline(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Option<wgpu::RenderPipeline> {
+        PipeLineBuilder::new(device)
+            .set_pixel_format(format)
+            .set_sample_count(sample_count)
+            .set_blend_state(wgpu::BlendState::ALPHA_BLENDING)
+            .set_cull_mode(None)
+            .add_vertex_buffer_layout(GraphVertex::desc())
+            .set_shader_module(SHADER_FILE, include_str!("../../../debug_graph_shader.wgsl"), "vs_main", "fs_main")
+            .build("Frame Time Graph Pipeline")
+            .inspect_err(|e| log::warn!("The frame-time graph won't be drawn: {}", e))
+            .ok()
+    }
+}
+
+impl CustomDraw for FrameTimeGraph {
+    fn prepare(&mut self, ctx: &CustomPrepareCtx<'_>) {
+        if self.pipeline.is_none() {
+            let mut shared = self.shared_pipeline.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let built_for = shared.as_ref().map(|(format, sample_count, _)| (*format, *sample_count));
+            if built_for != Some((ctx.format, ctx.sample_count)) {
+                *shared = Self::build_pipeline(ctx.device, ctx.format, ctx.sample_count)
+                    .map(|pipeline| (ctx.format, ctx.sample_count, pipeline));
+            }
+            self.pipeline = shared.as_ref().map(|(_, _, pipeline)| pipeline.clone());
+        }
+        if self.vertex_buffer.is_none() && !self.vertices.is_empty() {
+            self.vertex_buffer = Some(ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Frame Time Graph Vertex Buffer"),
+                contents: bytemuck::cast_slice(&self.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }));
+        }
+    }
+
+    fn draw(&self, ctx: &mut CustomDrawCtx<'_, '_>) {
+        let (Some(pipeline), Some(vertex_buffer)) = (&self.pipeline, &self.vertex_buffer) else {
+            return;
+        };
+        let rect = ctx.pixel_rect;
+        if rect.width <= 0.0 || rect.height <= 0.0 {
+            return;
+        }
+        // The renderer puts the full window viewport back afterwards
+        ctx.render_pass.set_viewport(rect.x, rect.y, rect.width, rect.height, 0.0, 1.0);
+        ctx.render_pass.set_pipeline(pipeline);
+        ctx.render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        ctx.render_pass.draw(0..self.vertices.len() as u32, 0..1);
+    }
+}
+
+/// Two triangles per frame, each bar as tall as its frame took against `GRAPH_MAX_FRAME_TIME` and colored by
+/// how slow it was.
+fn graph_vertices(frame_times: &[Duration]) -> Vec<GraphVertex> {
+    let slot = 2.0 / frame_times.len().max(1) as f32;
+    frame_times.iter().enumerate().flat_map(|(i, frame_time)| {
+        let share = (frame_time.as_secs_f32() / GRAPH_MAX_FRAME_TIME.as_secs_f32()).min(1.0);
+        let color = Color::from_hex(match *frame_time {
+            time if time > GRAPH_DROPPED_FRAME_TIME => GRAPH_DROPPED_COLOR,
+            time if time > GRAPH_SLOW_FRAME_TIME => GRAPH_SLOW_COLOR,
+            _ => GRAPH_COLOR,
+        }).into_vec4();
+        let (left, right) = (-1.0 + slot * i as f32, -1.0 + slot * (i as f32 + GRAPH_BAR_FILL));
+        let (bottom, top) = (-1.0, -1.0 + 2.0 * share);
+        [[left, bottom], [right, bottom], [left, top], [left, top], [right, bottom], [right, top]]
+            .map(|position| GraphVertex { position, color })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (left, right, bottom, top) of each bar.
+    fn bars(vertices: &[GraphVertex]) -> Vec<(f32, f32, f32, f32)> {
+        vertices.chunks(6).map(|bar| {
+            let xs = bar.iter().map(|vertex| vertex.position[0]);
+            let ys = bar.iter().map(|vertex| vertex.position[1]);
+            (xs.clone().fold(f32::MAX, f32::min), xs.fold(f32::MIN, f32::max), ys.clone().fold(f32::MAX, f32::min), ys.fold(f32::MIN, f32::max))
+        }).collect()
+    }
+
+    fn assert_bar(bar: (f32, f32, f32, f32), expected: (f32, f32, f32, f32)) {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+        assert!(close(bar.0, expected.0) && close(bar.1, expected.1) && close(bar.2, expected.2) && close(bar.3, expected.3),
+            "bar {:?} isn't {:?}", bar, expected);
+    }
+
+    #[test]
+    fn bars_are_as_tall_as_their_frames_and_side_by_side() {
+        let vertices = graph_vertices(&[Duration::from_millis(10), Duration::from_millis(25)]);
+        assert_eq!(vertices.len(), 12);
+        let bars = bars(&vertices);
+        assert_bar(bars[0], (-1.0, -1.0 + GRAPH_BAR_FILL, -1.0, -0.6));
+        assert_bar(bars[1], (0.0, GRAPH_BAR_FILL, -1.0, 0.0));
+    }
+
+    #[test]
+    fn frames_past_the_top_are_cut_off() {
+        let bars = bars(&graph_vertices(&[Duration::from_secs(2)]));
+        assert_bar(bars[0], (-1.0, -1.0 + 2.0 * GRAPH_BAR_FILL, -1.0, 1.0));
+    }
+
+    #[test]
+    fn slow_frames_are_colored_by_how_slow() {
+        let vertices = graph_vertices(&[Duration::from_millis(10), Duration::from_millis(20), Duration::from_millis(40)]);
+        let colors: Vec<_> = vertices.chunks(6).map(|bar| bar[0].color).collect();
+        assert_eq!(colors, [GRAPH_COLOR, GRAPH_SLOW_COLOR, GRAPH_DROPPED_COLOR].map(|hex| Color::from_hex(hex).into_vec4()));
+    }
+
+    #[test]
+    fn no_frames_draw_nothing() {
+        assert!(graph_vertices(&[]).is_empty());
+    }
+}
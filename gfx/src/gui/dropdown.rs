@@ -0,0 +1,87 @@
+use super::element::ElementCallback;
+
+/// Height of each item in an open dropdown, in pixels scaled like `Dimension::Pixels`.
+pub const DROPDOWN_ITEM_HEIGHT: f32 = 24.0;
+
+/// Narrowest a dropdown opens, in pixels scaled like `Dimension::Pixels`. It's otherwise as wide as the element that opened it.
+pub const DROPDOWN_MIN_WIDTH: f32 = 160.0;
+
+pub(crate) const DROPDOWN_BORDER_COLOR: &str = "#30363dff";
+
+/// Follows the label of an item that opens a submenu.
+pub(crate) const SUBMENU_MARKER: &str = "  >";
+
+/// One entry of a dropdown or context menu, see `Element::with_dropdown` and `Element::with_context_menu`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuItem<E> {
+    pub label: String,
+    /// Returned from `UiTree::handle_interaction` when the item is clicked. Without one, clicking the item only
+    /// closes the menu, or opens `submenu`.
+    pub event: Option<E>,
+    /// Listed beside the menu when the item is clicked, which keeps the menu open. Submenus don't nest further.
+    pub submenu: Vec<MenuItem<E>>,
+}
+
+impl<E> MenuItem<E> {
+    pub fn new(label: &str, event: E) -> Self {
+        Self { label: label.to_string(), event: Some(event), submenu: Vec::new() }
+    }
+
+    /// An item that opens `items` beside the menu.
+    pub fn submenu(label: &str, items: Vec<MenuItem<E>>) -> Self {
+        Self { label: label.to_string(), event: None, submenu: items }
+    }
+
+    /// An item that only shows `label`, such as a note that a submenu has nothing to list.
+    pub fn text(label: &str) -> Self {
+        Self { label: label.to_string(), event: None, submenu: Vec::new() }
+    }
+}
+
+/// A `MenuItem` as the element that lists it keeps it, its event turned into a callback.
+pub(crate) struct MenuEntry<E> {
+    pub(crate) label: String,
+    pub(crate) callback: ElementCallback<E>,
+    pub(crate) submenu: Vec<MenuEntry<E>>,
+}
+
+impl<E: Clone + Send + 'static> MenuEntry<E> {
+    pub(crate) fn from_item(MenuItem { label, event, submenu }: MenuItem<E>) -> Self {
+        Self {
+            label,
+            callback: Box::new(move || event.clone()),
+            submenu: submenu.into_iter().map(MenuEntry::from_item).collect(),
+        }
+    }
+}
+
+impl<E> MenuEntry<E> {
+    /// The label as the open menu shows it, marked when the entry opens a submenu.
+    pub(crate) fn display_label(&self) -> String {
+        if self.submenu.is_empty() {
+            self.label.clone()
+        } else {
+            format!("{}{}", self.label, SUBMENU_MARKER)
+        }
+    }
+}
+
+/// The dropdown on screen, opened by clicking `source`, or its context menu, opened by right-clicking it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OpenDropdown {
+    /// Panel and element index of the element built `with_dropdown` or `with_context_menu`.
+    pub(crate) source: (usize, usize),
+    /// Lists the source's context menu rather than its dropdown.
+    pub(crate) context: bool,
+    /// Index of the overlay panel listing the items, kept up to date as other overlays come and go.
+    pub(crate) panel: usize,
+    /// The item whose submenu is open beside the menu and the overlay panel listing it, kept up to date like `panel`.
+    pub(crate) submenu: Option<(usize, usize)>,
+}
+
+impl OpenDropdown {
+    /// Whether `panel` is the menu or its open submenu.
+    pub(crate) fn lists_in(&self, panel: usize) -> bool {
+        panel == self.panel || self.submenu.is_some_and(|(_, submenu)| submenu == panel)
+    }
+}
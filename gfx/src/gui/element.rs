@@ -0,0 +1,894 @@
+use wgpu_text::glyph_brush::{ab_glyph::PxScale, Section, Text};
+use winit::dpi::PhysicalSize;
+
+use crate::definitions::{InteractionStyle, RenderMode, UiAtlas, Vertex};
+
+use super::{animation::Animated, color::Color, custom_draw::{CustomDraw, CustomDrawSlot}, dropdown::{MenuEntry, MenuItem}, group::GroupState, layout::{round_corners, Alignment, Coordinate, Dimension, HorizontalAlignment, IconFit, PixelRect, QuadRect, VerticalAlignment, Wrap, BORDER_QUADS}, style::{Border, Style, TextStyle}, theme::ColorRole, text::{FONT_SIZE, GLYPH_WIDTH}, font::FontId};
+
+pub(crate) type ElementCallback<E> = Box<dyn Fn() -> Option<E> + Send + 'static>;
+type DragCallback<E> = Box<dyn Fn((f32, f32)) -> Option<E> + Send + 'static>;
+
+/// A piece of a panel. `E` is the event type the application's callbacks return.
+pub struct Element<E> {
+    /// Stable name for the element, used by groups to find it again after a rebuild.
+    pub(crate) id: Option<String>,
+    pub(crate) start_coordinate: Coordinate,
+    pub(crate) end_coordinate: Coordinate,
+    /// Overrides on top of the panel's style.
+    pub(crate) style: Style,
+    /// Set by `with_temp_color`, wins over every style.
+    pub(crate) temp_color: Option<Color>,
+    /// Set by the interface while the cursor is over the element, see `UiTree::hovered_element`.
+    pub(crate) hovered: bool,
+    /// Set by the interface between a press on the element and the release.
+    pub(crate) pressed: bool,
+    /// Set by the interface while Tab has keyboard focus on the element, see `UiTree::focus_next`.
+    pub(crate) focus_ring: bool,
+    /// Whether a click selects the element, see `with_selected_color`.
+    pub(crate) selectable: bool,
+    /// Set by the interface while the element is in its panel's selection, see `UiTree::selection`.
+    pub(crate) selected: bool,
+    pub(crate) border: Option<Border>,
+    /// Radius the fill and border corners are rounded by, in pixels before scaling, see `with_corner_radius`.
+    pub(crate) corner_radius: f32,
+    pub(crate) text: Option<String>,
+    pub(crate) text_alignment: Option<Alignment>,
+    pub(crate) text_wrap: Option<Wrap>,
+    /// `text` as cut down by `text_wrap` in the last upload, when it didn't fit.
+    pub(crate) fitted_text: Option<String>,
+    /// Color the text was queued in by the last upload. A hover or group change that would draw it in another
+    /// needs the text queued again, which `UiRenderer::upload_element` can't do.
+    pub(crate) queued_text_color: Option<[f32; 4]>,
+    /// Font the text is drawn in, the interface's default when unset. See `with_font`.
+    pub(crate) font: Option<FontId>,
+    /// Callbacks registered with `with_fn`, in registration order, several per style allowed.
+    pub(crate) handlers: Vec<(InteractionStyle, ElementCallback<E>)>,
+    pub(crate) on_drag: Option<DragCallback<E>>,
+    pub(crate) texture_name: String,
+    /// How the fill uses `texture_name`, see `with_render_mode`.
+    pub(crate) render_mode: RenderMode,
+    /// How the texture is fitted to the element's rect, see `with_icon`.
+    pub(crate) icon_fit: IconFit,
+    /// Part of the atlas entry drawn, `(x0, y0, x1, y1)` from 0.0 to 1.0 across it, see `with_uv_rect`.
+    pub(crate) uv_rect: (f32, f32, f32, f32),
+    pub(crate) kind: ElementKind,
+    pub(crate) custom_draw: Option<CustomDrawSlot>,
+    pub(crate) snap: bool,
+    /// Orders the element against the others in its panel, see `with_layer`.
+    pub(crate) layer: i32,
+    /// Name of the render target the fill samples instead of the atlas, see `with_render_target`.
+    pub(crate) render_target: Option<String>,
+    /// Share of the free space the element's cell gets in a `Layout::Column` or `Layout::Row` panel.
+    pub(crate) flex: f32,
+    pub(crate) fixed_width: Option<Dimension>,
+    pub(crate) fixed_height: Option<Dimension>,
+    /// Placed in the previous element's layout cell rather than a new one, see `with_shared_cell`.
+    pub(crate) shares_cell: bool,
+    /// Set with `UiTree::set_element_visible`, on top of whatever the element's groups allow.
+    pub(crate) visible: bool,
+    /// Set with `UiTree::set_element_enabled`, on top of whatever the element's groups allow.
+    pub(crate) enabled: bool,
+    /// Items a click opens, see `with_dropdown`.
+    pub(crate) dropdown: Vec<MenuEntry<E>>,
+    /// Items a right-click opens, see `with_context_menu`.
+    pub(crate) context_menu: Vec<MenuEntry<E>>,
+    /// Index of the item this element lists in an open dropdown, which the interface builds.
+    pub(crate) menu_item: Option<usize>,
+    /// Shown near the cursor after it rests on the element, see `with_tooltip`.
+    pub(crate) tooltip: Option<String>,
+    /// Written by the interface's animations, see `UiTree::animate`.
+    pub(crate) animated: Animated,
+}
+
+impl<E> Element<E> {
+    pub fn new(start_coordinate: Coordinate, end_coordinate: Coordinate, texture_name: &str) -> Self {
+        Self {
+            id: None,
+            start_coordinate,
+            end_coordinate,
+            style: Style::default(),
+            temp_color: None,
+            hovered: false,
+            pressed: false,
+            focus_ring: false,
+            selectable: false,
+            selected: false,
+            border: None,
+            corner_radius: 0.0,
+            text: None,
+            text_alignment: None,
+            text_wrap: None,
+            fitted_text: None,
+            queued_text_color: None,
+            font: None,
+            handlers: Vec::new(),
+            on_drag: None,
+            texture_name: texture_name.to_string(),
+            render_mode: RenderMode::for_texture(texture_name),
+            icon_fit: IconFit::Stretch,
+            uv_rect: (0.0, 0.0, 1.0, 1.0),
+            kind: ElementKind::Label,
+            custom_draw: None,
+            snap: true,
+            layer: 0,
+            render_target: None,
+            flex: 1.0,
+            fixed_width: None,
+            fixed_height: None,
+            shares_cell: false,
+            visible: true,
+            enabled: true,
+            dropdown: Vec::new(),
+            context_menu: Vec::new(),
+            menu_item: None,
+            tooltip: None,
+            animated: Animated::default(),
+        }
+    }
+
+    pub fn image(start_coordinate: Coordinate, end_coordinate: Coordinate, texture_name: &str) -> Self {
+        Self::new(start_coordinate, end_coordinate, texture_name).with_kind(ElementKind::Image)
+    }
+
+    /// Shows `texture_name` fitted to the element by `fit`, so an icon can keep its shape in a rect of any size.
+    /// Plain elements become images, widgets draw it as their fill.
+    pub fn with_icon(mut self, texture_name: &str, fit: IconFit) -> Self {
+        if self.kind == ElementKind::Label {
+            self.kind = ElementKind::Image;
+        }
+        self.texture_name = texture_name.to_string();
+        self.render_mode = RenderMode::for_texture(texture_name);
+        self.icon_fit = fit;
+        self
+    }
+
+    /// Draws only part of the texture, from `(x0, y0)` to `(x1, y1)` in fractions of its atlas entry with (0, 0)
+    /// at the top left, for sprite sheets and icon strips. The part counts as the texture for `with_icon`'s fit.
+    pub fn with_uv_rect(mut self, x0: f32, y0: f32, x1: f32, y1: f32) -> Self {
+        self.uv_rect = (x0.clamp(0.0, 1.0), y0.clamp(0.0, 1.0), x1.clamp(0.0, 1.0), y1.clamp(0.0, 1.0));
+        self
+    }
+
+    pub fn checkbox(start_coordinate: Coordinate, end_coordinate: Coordinate, checked: bool) -> Self {
+        Self::new(start_coordinate, end_coordinate, "solid").with_kind(ElementKind::Checkbox { checked })
+    }
+
+    pub fn slider(start_coordinate: Coordinate, end_coordinate: Coordinate, value: f32) -> Self {
+        Self::new(start_coordinate, end_coordinate, "solid").with_kind(ElementKind::Slider { value: value.clamp(0.0, 1.0) })
+    }
+
+    /// A track filled from the left edge as far as `progress`, 0.0 to 1.0, see `set_progress`.
+    pub fn progress_bar(start_coordinate: Coordinate, end_coordinate: Coordinate, progress: f32) -> Self {
+        Self::new(start_coordinate, end_coordinate, "solid").with_kind(ElementKind::ProgressBar { progress: progress.clamp(0.0, 1.0) })
+    }
+
+    /// A dot circling the element's center for as long as it's shown, for work of unknown length.
+    /// It turns in `UiTree::step_spinners`, or stays a still ring, see `UiTree::set_spinners_turn`.
+    pub fn spinner(start_coordinate: Coordinate, end_coordinate: Coordinate) -> Self {
+        Self::new(start_coordinate, end_coordinate, "solid").with_kind(ElementKind::Spinner { angle: Some(0.0) })
+    }
+
+    pub fn text_input(start_coordinate: Coordinate, end_coordinate: Coordinate) -> Self {
+        Self::new(start_coordinate, end_coordinate, "solid").with_text_input()
+    }
+
+    /// Makes the element focusable and editable from the keyboard. Typed text is left aligned, scrolling to keep the end in view.
+    pub fn with_text_input(mut self) -> Self {
+        self.kind = ElementKind::TextInput { focused: false };
+        if self.text.is_none() {
+            self.text = Some(String::new());
+        }
+        self.text_alignment = Some(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left });
+        self
+    }
+
+    pub fn with_kind(mut self, kind: ElementKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Draws `hook` after this element's quads, clipped to the element's rect.
+    pub fn with_custom_draw(mut self, hook: impl CustomDraw + 'static) -> Self {
+        self.custom_draw = Some(CustomDrawSlot { hook: Box::new(hook), pixel_rect: PixelRect::default() });
+        self
+    }
+
+    /// Draws a `thickness_px` frame inside the element's edges. Hovering only recolors the fill.
+    pub fn with_border(mut self, thickness_px: f32, color: &str) -> Self {
+        self.border = Some(Border::new(thickness_px, color));
+        self
+    }
+
+    /// Rounds the corners of the fill and border by `radius_px`, anti-aliased. A radius of more than half the
+    /// element's shorter side makes a capsule. Clicks still land anywhere in the element's rect.
+    pub fn with_corner_radius(mut self, radius_px: f32) -> Self {
+        self.corner_radius = radius_px.max(0.0);
+        self
+    }
+
+    /// Quads the element occupies in the vertex buffer: its widget's, then its border's.
+    pub(crate) fn quad_count(&self) -> usize {
+        self.kind.quad_count() + if self.border.is_some() { BORDER_QUADS } else { 0 }
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Starts the element hidden or shown. Hidden elements keep their place in the vertex buffer and their layout cell.
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Starts the element disabled or enabled. Disabled elements are drawn in their style's disabled color
+    /// and ignore hover, clicks and focus.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn own_state(&self) -> GroupState {
+        GroupState { enabled: self.enabled, visible: self.visible }
+    }
+
+    /// Draws the element above lower-layered elements of the same panel and hit-tests it before them.
+    /// Only orders elements within their panel, panels are ordered against each other by `Panel::with_layer`.
+    pub fn with_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Fills the element with the render target registered on `RenderState` as `name`, such as `PREVIEW_TARGET`,
+    /// stretched over it and tinted by the fill color. The target is resized to the element's pixel size.
+    /// Input over the element isn't consumed, so it reaches the viewport behind it.
+    pub fn with_render_target(mut self, name: &str) -> Self {
+        self.render_target = Some(name.to_string());
+        self
+    }
+
+    /// Overrides how the fill is drawn. Elements on `"solid"` or no texture default to `RenderMode::SolidColor`,
+    /// any other texture to `RenderMode::TextureTinted`. Render targets are always tinted.
+    pub fn with_render_mode(mut self, mode: RenderMode) -> Self {
+        self.render_mode = mode;
+        self
+    }
+
+    /// How the fill is drawn, a render target has to be sampled whatever the element was built with.
+    pub(crate) fn fill_render_mode(&self) -> RenderMode {
+        match self.render_target {
+            Some(_) => RenderMode::TextureTinted,
+            None => self.render_mode,
+        }
+    }
+
+    /// In a `Layout::Column` or `Layout::Row` panel, gives the element's cell `flex` shares of the space
+    /// the fixed-size cells leave. Cells are flex 1.0 unless they have a fixed size.
+    pub fn with_flex(mut self, flex: f32) -> Self {
+        self.flex = flex.max(0.0);
+        self
+    }
+
+    /// Makes the element's cell `height` tall in a `Layout::Column` panel, fractions are of the panel's height
+    /// inside its padding. Ignored by other layouts.
+    pub fn with_fixed_height(mut self, height: Dimension) -> Self {
+        self.fixed_height = Some(height);
+        self
+    }
+
+    /// Makes the element's cell `width` wide in a `Layout::Row` panel. Ignored by other layouts.
+    pub fn with_fixed_width(mut self, width: Dimension) -> Self {
+        self.fixed_width = Some(width);
+        self
+    }
+
+    /// Puts the element in the cell opened by the element added before it, sized by that element,
+    /// so an icon and a badge can sit on the same row as a label. Its coordinates are fractions of the cell
+    /// like every element's in a layout.
+    pub fn with_shared_cell(mut self) -> Self {
+        self.shares_cell = true;
+        self
+    }
+
+    /// Whether vertices and text are rounded to whole physical pixels. On by default;
+    /// turn it off for animated or scaled content where rounding would make motion jitter.
+    pub fn with_snap(mut self, snap: bool) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    pub fn kind(&self) -> &ElementKind {
+        &self.kind
+    }
+
+    /// Calls `func` on `style`'s interaction, returning its event from `UiTree::handle_interaction`.
+    /// Any number of callbacks can be registered, for different styles or the same one.
+    /// `OnDrag`'s delta is ignored, see `with_drag_fn`.
+    pub fn with_fn(mut self, func: impl Fn() -> Option<E> + Send + 'static, style: InteractionStyle) -> Self {
+        let is_button = matches!(style,
+            InteractionStyle::OnClick | InteractionStyle::OnPress | InteractionStyle::OnRelease | InteractionStyle::OnDoubleClick);
+        if is_button && self.kind == ElementKind::Label {
+            self.kind = ElementKind::Button;
+        }
+        self.handlers.push((style, Box::new(func)));
+        self
+    }
+
+    /// Calls `func` with the cursor's movement in physical pixels each time it moves while a press on the element
+    /// is held, e.g. to resize a splitter.
+    pub fn with_drag_fn(mut self, func: impl Fn((f32, f32)) -> Option<E> + Send + 'static) -> Self {
+        self.on_drag = Some(Box::new(func));
+        self
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.style = self.style.with_color(color);
+        self
+    }
+
+    /// Fills the element with the theme's color for `role`, which follows `UiTree::set_theme`.
+    pub fn with_color_role(mut self, role: ColorRole) -> Self {
+        self.style = self.style.with_color_role(role);
+        self
+    }
+
+    /// Draws the element in `color` while the cursor is over it. Elements with an `OnHover` or `OnHoverExit`
+    /// callback highlight in the inherited hover color without this.
+    pub fn with_hover_color(mut self, color: &str) -> Self {
+        self.style = self.style.with_hover_color(color);
+        self
+    }
+
+    /// Like `with_hover_color`, with the theme's color for `role`.
+    pub fn with_hover_color_role(mut self, role: ColorRole) -> Self {
+        self.style = self.style.with_hover_color_role(role);
+        self
+    }
+
+    /// Makes the element selectable and draws it in `color` while selected. A click selects it alone, Ctrl+click
+    /// adds or removes it and Shift+click selects the run of selectable elements from the last one clicked, all within
+    /// its panel, see `UiTree::selection`. Hover and press colors still show over the selected color.
+    pub fn with_selected_color(mut self, color: &str) -> Self {
+        self.style = self.style.with_selected_color(color);
+        self.selectable = true;
+        self
+    }
+
+    /// Like `with_selected_color`, with the theme's color for `role`.
+    pub fn with_selected_color_role(mut self, role: ColorRole) -> Self {
+        self.style = self.style.with_selected_color_role(role);
+        self.selectable = true;
+        self
+    }
+
+    /// Makes the element selectable, in the inherited selected color, and builds it selected or not. Used to carry a
+    /// selection the application keeps into a rebuilt interface.
+    pub fn with_selected(mut self, selected: bool) -> Self {
+        self.selectable = true;
+        self.selected = selected;
+        self
+    }
+
+    /// Overrides the panel's style. Fields `style` leaves unset keep whatever this element already set.
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style.or(&self.style);
+        self
+    }
+
+    /// Gives the element `text`, drawn as `style` says: either just a scale, in the theme's text color, or a full
+    /// `TextStyle`. Hover and disabled colors come from `Style::hover_text_color` and `Style::disabled_text_color`.
+    pub fn with_text(mut self, alignment: Alignment, text: &str, style: impl Into<TextStyle>) -> Self {
+        self.style = self.style.with_text_style(style.into());
+        self.with_label(alignment, text)
+    }
+
+    /// Like `with_text`, but fits the text to the element's width as `wrap` says instead of letting it run past the edge.
+    /// Wrapped lines are aligned as one block, so a centered two-line label is centered as a whole.
+    pub fn with_text_wrapped(self, alignment: Alignment, text: &str, style: impl Into<TextStyle>, wrap: Wrap) -> Self {
+        let mut element = self.with_text(alignment, text, style);
+        element.text_wrap = Some(wrap);
+        element
+    }
+
+    /// Draws the element's text in `font`, one of the interface's `Fonts`. Ids from another set fall back to the default.
+    pub fn with_font(mut self, font: FontId) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// The element's text, or what's been typed into it for a text input.
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Replaces the text of an element that has some, showing from the next upload. Returns false for one without.
+    pub fn set_text(&mut self, text: &str) -> bool {
+        let Some(current) = self.text.as_mut() else {
+            return false;
+        };
+        *current = text.to_string();
+        self.fitted_text = None;
+        true
+    }
+
+    /// Moves a progress bar to `progress`, clamped to 0.0..=1.0, showing from the next upload or
+    /// `RenderState::update_interface_element`. Returns false for any other kind of element.
+    pub fn set_progress(&mut self, progress: f32) -> bool {
+        let ElementKind::ProgressBar { progress: current } = &mut self.kind else {
+            return false;
+        };
+        *current = progress.clamp(0.0, 1.0);
+        true
+    }
+
+    /// Like `with_text`, but the scale comes from the panel or interface style.
+    pub fn with_label(mut self, alignment: Alignment, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self.text_alignment = Some(alignment);
+        self
+    }
+
+    /// Shows `text` in a small overlay near the cursor once it has rested on the element for the interface's
+    /// tooltip delay, see `UiTree::set_tooltip_delay`. Moving away or clicking hides it.
+    pub fn with_tooltip(mut self, text: &str) -> Self {
+        self.tooltip = Some(text.to_string());
+        self
+    }
+
+    /// Runs every callback registered for `interaction_type`'s style, returning the first event one of them produced.
+    pub fn handle_click(&self, interaction_type: InteractionStyle) -> Option<E> {
+        let style = std::mem::discriminant(&interaction_type);
+        let mut event = None;
+        for (_, func) in self.handlers.iter().filter(|(registered, _)| std::mem::discriminant(registered) == style) {
+            let fired = func();
+            event = event.or(fired);
+        }
+        match interaction_type {
+            InteractionStyle::OnDrag { delta } => self.on_drag.as_ref().and_then(|func| func(delta)).or(event),
+            _ => event,
+        }
+    }
+
+    pub(crate) fn has_handler(&self, interaction_type: &InteractionStyle) -> bool {
+        let style = std::mem::discriminant(interaction_type);
+        self.handlers.iter().any(|(registered, _)| std::mem::discriminant(registered) == style)
+            || (matches!(interaction_type, InteractionStyle::OnDrag { .. }) && self.on_drag.is_some())
+    }
+
+    pub fn with_temp_color(&mut self, color: &str) {
+        self.temp_color = Some(Color::from_hex(color));
+    }
+
+    /// Undoes `with_temp_color` and any finished color animation, going back to the element's styled color.
+    pub fn reset_color(&mut self) {
+        self.temp_color = None;
+        self.animated.color = None;
+    }
+
+    /// Whether `UiTree::focus_next` stops at the element: it takes text, a click or opens a dropdown.
+    pub(crate) fn is_tab_stop(&self) -> bool {
+        self.kind.is_focusable()
+            || self.menu_item.is_some()
+            || !self.dropdown.is_empty()
+            || self.has_handler(&InteractionStyle::OnClick)
+    }
+
+    /// Whether the interface tracks the cursor entering and leaving the element.
+    pub(crate) fn reacts_to_hover(&self) -> bool {
+        self.style.hover_color.is_some()
+            || self.style.hover_color_role.is_some()
+            || self.menu_item.is_some()
+            || self.tooltip.is_some()
+            || self.has_handler(&InteractionStyle::OnHover)
+            || self.has_handler(&InteractionStyle::OnHoverExit)
+    }
+
+    /// Whether a right-click on the element does anything, see `UiTree::handle_interaction`.
+    pub(crate) fn reacts_to_right_click(&self) -> bool {
+        !self.context_menu.is_empty() || self.has_handler(&InteractionStyle::OnRightClick)
+    }
+
+    /// Items of the element's context menu, or of its dropdown.
+    pub(crate) fn menu(&self, context: bool) -> &[MenuEntry<E>] {
+        if context { &self.context_menu } else { &self.dropdown }
+    }
+
+    /// Fill color given the element's resolved style, an animated color included.
+    pub(crate) fn fill_color(&self, style: &Style, state: GroupState) -> Color {
+        if state.enabled && !self.pressed && let Some(color) = &self.animated.color {
+            return color.clone();
+        }
+        self.styled_fill_color(style, state)
+    }
+
+    /// Fill color from the style and the element's flags alone, what a hover transition settles on.
+    pub(crate) fn styled_fill_color(&self, style: &Style, state: GroupState) -> Color {
+        if !state.enabled {
+            return style.disabled_color();
+        }
+        // Later states win: base < selected < hover < pressed, with Tab's focus ring over hover
+        match (&self.temp_color, self.pressed, self.hovered) {
+            (Some(color), _, _) => color.clone(),
+            (None, true, _) => style.pressed_color(),
+            (None, false, _) if self.focus_ring => style.focus_color(),
+            (None, false, true) => style.hover_color(),
+            (None, false, false) if self.selected => style.selected_color(),
+            (None, false, false) => style.color(),
+        }
+    }
+
+    /// Text color from the style and the element's flags: its disabled color in a disabled group, its hover color
+    /// under the cursor until pressed.
+    pub(crate) fn text_color(&self, style: &Style, state: GroupState) -> Color {
+        if !state.enabled {
+            style.disabled_text_color()
+        } else if self.hovered && !self.pressed {
+            style.hover_text_color()
+        } else {
+            style.text_color()
+        }
+    }
+
+    /// The element's quad inside `frame`, its entry in the panel's `element_frames`, with pixel sizes at `pixel_scale`.
+    pub(crate) fn layout_rect(&self, frame: QuadRect, screen_size: PhysicalSize<u32>, pixel_scale: f32) -> QuadRect {
+        let rect = self.calculate_rect_relative_to_panel(frame.x_min, frame.y_bottom, frame.x_max, frame.y_top, pixel_scale);
+        if self.snap { rect.snapped(screen_size) } else { rect }
+    }
+
+    /// Where the fill goes inside `rect` and the texture coordinates it samples, after `uv_rect` and `icon_fit`.
+    /// Render targets fill the rect with the whole target. Textures missing from the atlas get its missing texture,
+    /// stretched.
+    pub(crate) fn fill(&self, atlas: &UiAtlas, rect: QuadRect) -> (QuadRect, [[f32; 2]; 4]) {
+        if self.render_target.is_some() {
+            return (rect, [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        }
+        let entry = atlas.get(&self.texture_name);
+        let Some(((u_0, v_0), (u_1, v_1))) = atlas.resolve(&self.texture_name).and_then(|entry| entry.start_coord.zip(entry.end_coord)) else {
+            return (rect, [[0.0, 0.0]; 4]);
+        };
+        let (x_0, y_0, x_1, y_1) = if entry.is_some() { self.uv_rect } else { (0.0, 0.0, 1.0, 1.0) };
+        let (mut u_0, mut v_0, mut u_1, mut v_1) = (u_0 + (u_1 - u_0) * x_0, v_0 + (v_1 - v_0) * y_0, u_0 + (u_1 - u_0) * x_1, v_0 + (v_1 - v_0) * y_1);
+        let mut rect = rect;
+
+        let (rect_width, rect_height) = (rect.x_max - rect.x_min, rect.y_top - rect.y_bottom);
+        let texture_aspect = entry.map(|entry| {
+            let (width, height) = entry.size();
+            (width as f32 * (x_1 - x_0)) / (height as f32 * (y_1 - y_0))
+        });
+        if let Some(texture_aspect) = texture_aspect.filter(|aspect| aspect.is_finite() && *aspect > 0.0)
+            && rect_width > 0.0 && rect_height > 0.0 {
+            let rect_aspect = rect_width / rect_height;
+            match self.icon_fit {
+                IconFit::Stretch => {}
+                // Letterboxed: the texture's long side spans the rect, the short side is centered
+                IconFit::Contain if texture_aspect > rect_aspect => {
+                    let inset = (rect_height - rect_width / texture_aspect) / 2.0;
+                    rect = QuadRect { y_top: rect.y_top - inset, y_bottom: rect.y_bottom + inset, ..rect };
+                }
+                IconFit::Contain => {
+                    let inset = (rect_width - rect_height * texture_aspect) / 2.0;
+                    rect = QuadRect { x_min: rect.x_min + inset, x_max: rect.x_max - inset, ..rect };
+                }
+                // Cropped: the texture's short side spans the rect, the long side loses as much at either end
+                IconFit::Cover if texture_aspect > rect_aspect => {
+                    let crop = (u_1 - u_0) * (1.0 - rect_aspect / texture_aspect) / 2.0;
+                    (u_0, u_1) = (u_0 + crop, u_1 - crop);
+                }
+                IconFit::Cover => {
+                    let crop = (v_1 - v_0) * (1.0 - texture_aspect / rect_aspect) / 2.0;
+                    (v_0, v_1) = (v_0 + crop, v_1 - crop);
+                }
+            }
+        }
+        (rect, [[u_0, v_0], [u_1, v_0], [u_1, v_1], [u_0, v_1]])
+    }
+
+    /// Fill, widget and border vertices given the element's resolved style, clipped to `clip` when there is one.
+    /// The fill samples `atlas` as `fill` places it, the border is `pixel_scale` times as thick as it was built with.
+    /// Hidden elements still fill their share of the vertex buffer, with empty quads.
+    pub(crate) fn vertices(&self, rect: QuadRect, style: &Style, state: GroupState, atlas: &UiAtlas, pixel_scale: f32, clip: Option<QuadRect>) -> Vec<Vertex> {
+        if !state.visible {
+            return vec![<Vertex as bytemuck::Zeroable>::zeroed(); self.quad_count() * 4];
+        }
+        let scale = style.text_scale();
+        let visible_text = self.text.as_ref()
+            .map(|content| (self.kind.visible_text(content, scale, rect.x_max - rect.x_min), scale));
+        let (fill_rect, tex_coords) = self.fill(atlas, rect);
+        let mut vertices = self.kind.generate_vertices(fill_rect, &self.fill_color(style, state), (tex_coords, self.fill_render_mode()), visible_text);
+        let corner_radius = self.corner_radius * pixel_scale;
+        if corner_radius > 0.0 {
+            round_corners(&mut vertices[..4], corner_radius, 0.0);
+        }
+        if let Some(border) = &self.border {
+            vertices.extend(rect.border_vertices(border, pixel_scale, corner_radius));
+        }
+        if let Some(clip) = clip {
+            clip.clip_vertices(&mut vertices);
+        }
+        vertices
+    }
+
+    pub(crate) fn calculate_rect_relative_to_panel(
+        &self,
+        panel_x_min_center_origin: f32,
+        panel_y_min_center_origin: f32,
+        panel_x_max_center_origin: f32,
+        panel_y_max_center_origin: f32,
+        pixel_scale: f32,
+    ) -> QuadRect {
+
+        let panel_width = panel_x_max_center_origin - panel_x_min_center_origin;
+        let panel_height = panel_y_max_center_origin - panel_y_min_center_origin;
+
+        // Convert element's local coordinates to panel's absolute coordinates (center-origin)
+        let element_abs_x_min_center_origin = panel_x_min_center_origin + self.start_coordinate.x.resolve(panel_width, pixel_scale);
+        let element_abs_x_max_center_origin = panel_x_min_center_origin + self.end_coordinate.x.resolve(panel_width, pixel_scale);
+
+        // Y-axis is inverted here: y_max_center_origin is top, y_min_center_origin is bottom,
+        // while element coordinates are measured down from the panel's top
+        let element_abs_y_top_center_origin = panel_y_max_center_origin - self.start_coordinate.y.resolve(panel_height, pixel_scale);
+        let element_abs_y_bottom_center_origin = panel_y_max_center_origin - self.end_coordinate.y.resolve(panel_height, pixel_scale);
+
+        QuadRect {
+            x_min: element_abs_x_min_center_origin,
+            x_max: element_abs_x_max_center_origin,
+            y_top: element_abs_y_top_center_origin,
+            y_bottom: element_abs_y_bottom_center_origin,
+        }
+    }
+}
+
+impl<E: Clone + Send + 'static> Element<E> {
+    /// Opens a menu of `items` directly beneath the element when it's clicked. Clicking an item returns its event
+    /// and closes the menu, or opens its submenu beside it; a press anywhere else or `UiTree::close_dropdown` closes
+    /// it without one.
+    /// Items highlight in the inherited hover color.
+    pub fn with_dropdown(mut self, items: Vec<MenuItem<E>>) -> Self {
+        if self.kind == ElementKind::Label {
+            self.kind = ElementKind::Button;
+        }
+        self.dropdown = items.into_iter().map(MenuEntry::from_item).collect();
+        self
+    }
+
+    /// Opens a menu of `items` at the cursor when the element is right-clicked, kept inside the window. It picks and
+    /// closes like a dropdown, and only one of the two is open at a time.
+    pub fn with_context_menu(mut self, items: Vec<MenuItem<E>>) -> Self {
+        self.context_menu = items.into_iter().map(MenuEntry::from_item).collect();
+        self
+    }
+}
+
+/// What an element is, along with any state that only that kind of element needs.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ElementKind {
+    Label,
+    Button,
+    Image,
+    Checkbox { checked: bool },
+    /// `value` runs from 0.0 at the left edge to 1.0 at the right edge.
+    Slider { value: f32 },
+    TextInput { focused: bool },
+    /// `progress` runs from 0.0, an empty track, to 1.0, a full one.
+    ProgressBar { progress: f32 },
+    /// `angle` is where the dot is around the center in radians, counterclockwise from the right.
+    /// `None` draws a still ring instead.
+    Spinner { angle: Option<f32> },
+}
+
+pub(crate) enum WidgetInput {
+    /// `x_fraction` is the cursor position across the element, 0.0 at the left edge.
+    Click { x_fraction: f32 },
+    /// Cursor movement while this element holds pointer capture, `x_fraction` may fall outside 0.0..=1.0.
+    Drag { x_fraction: f32 },
+    /// Keyboard focus moved onto (`true`) or away from this element.
+    Focus(bool),
+}
+
+/// Per-kind behaviour the interface dispatches to when building buffers and handling input.
+pub(crate) trait Widget {
+    /// Number of quads written to the vertex buffer, the background quad included.
+    fn quad_count(&self) -> usize;
+
+    /// Whether a press on this widget should capture the pointer until release.
+    fn captures_pointer(&self) -> bool;
+
+    /// Whether clicking the widget gives it keyboard focus.
+    fn is_focusable(&self) -> bool;
+
+    /// Returns exactly `quad_count() * 4` vertices, background quad first. `fill` is how the background is textured,
+    /// the widget's own quads are solid colors. `text` is the visible part of the element's text with its scale,
+    /// as returned by `visible_text`.
+    fn generate_vertices(&self, rect: QuadRect, color: &Color, fill: ([[f32; 2]; 4], RenderMode), text: Option<(&str, f32)>) -> Vec<Vertex>;
+
+    /// The part of `text` that is drawn in an element `width` pixels wide.
+    fn visible_text<'a>(&self, text: &'a str, scale: f32, width: f32) -> &'a str;
+
+    /// Returns true when the widget's state changed and its vertices need regenerating.
+    fn handle_input(&mut self, input: WidgetInput) -> bool;
+
+    fn queue_text<'a>(&self, text: &'a str, font: FontId, scale: f32, color: [f32; 4], position: (f32, f32)) -> Option<Section<'a>>;
+}
+
+impl Widget for ElementKind {
+    fn quad_count(&self) -> usize {
+        match self {
+            ElementKind::Checkbox { .. } | ElementKind::Slider { .. } | ElementKind::TextInput { .. }
+            | ElementKind::ProgressBar { .. } | ElementKind::Spinner { .. } => 2,
+            _ => 1,
+        }
+    }
+
+    fn captures_pointer(&self) -> bool {
+        matches!(self, ElementKind::Slider { .. })
+    }
+
+    fn is_focusable(&self) -> bool {
+        matches!(self, ElementKind::TextInput { .. })
+    }
+
+    fn generate_vertices(&self, rect: QuadRect, color: &Color, (tex_coords, mode): ([[f32; 2]; 4], RenderMode), text: Option<(&str, f32)>) -> Vec<Vertex> {
+        let mut vertices = rect.vertices(color.into_vec4(), tex_coords, mode).to_vec();
+        match self {
+            ElementKind::Checkbox { checked } => {
+                // The mark is always written so the buffer layout doesn't change when toggled.
+                let mark_alpha = if *checked { 1.0 } else { 0.0 };
+                vertices.extend(rect.inset(0.25).vertices([1.0, 1.0, 1.0, mark_alpha], tex_coords, RenderMode::SolidColor));
+            }
+            ElementKind::Slider { value } => {
+                let fill = QuadRect { x_max: rect.x_min + (rect.x_max - rect.x_min) * value, ..rect };
+                vertices.extend(fill.vertices([1.0, 1.0, 1.0, 0.35], tex_coords, RenderMode::SolidColor));
+            }
+            ElementKind::TextInput { focused } => {
+                // Like the checkbox mark, the caret is always written and hidden with alpha when unfocused.
+                // Left aligned text starts one glyph in from the edge.
+                let (visible, scale) = text.unwrap_or(("", 0.0));
+                let glyph_width = GLYPH_WIDTH * scale;
+                let caret_x = (rect.x_min + glyph_width * (visible.chars().count() + 1) as f32).min(rect.x_max - 2.0);
+                let inset = (rect.y_top - rect.y_bottom) * 0.2;
+                let caret = QuadRect { x_min: caret_x, x_max: caret_x + 2.0, y_top: rect.y_top - inset, y_bottom: rect.y_bottom + inset };
+                let caret_alpha = if *focused { 1.0 } else { 0.0 };
+                vertices.extend(caret.vertices([1.0, 1.0, 1.0, caret_alpha], tex_coords, RenderMode::SolidColor));
+            }
+            ElementKind::ProgressBar { progress } => {
+                // Written even when empty, so moving the bar only rewrites this element's quads
+                let fill = QuadRect { x_max: rect.x_min + (rect.x_max - rect.x_min) * progress, ..rect };
+                vertices.extend(fill.vertices([1.0, 1.0, 1.0, 0.6], tex_coords, RenderMode::SolidColor));
+            }
+            ElementKind::Spinner { angle } => {
+                let radius = (rect.x_max - rect.x_min).min(rect.y_top - rect.y_bottom) / 2.0;
+                let dot_radius = radius * 0.25;
+                let center_x = (rect.x_min + rect.x_max) / 2.0;
+                let center_y = (rect.y_bottom + rect.y_top) / 2.0;
+                let start = vertices.len();
+                match angle {
+                    // The dot stays axis aligned as it circles, so panel clipping still works on it
+                    Some(angle) => {
+                        let dot_x = center_x + (radius - dot_radius) * angle.cos();
+                        let dot_y = center_y + (radius - dot_radius) * angle.sin();
+                        let dot = QuadRect { x_min: dot_x - dot_radius, x_max: dot_x + dot_radius, y_top: dot_y + dot_radius, y_bottom: dot_y - dot_radius };
+                        vertices.extend(dot.vertices([1.0, 1.0, 1.0, 0.9], tex_coords, RenderMode::SolidColor));
+                        round_corners(&mut vertices[start..], dot_radius, 0.0);
+                    }
+                    // The whole track the dot would circle, so a still spinner doesn't look stuck
+                    None => {
+                        let ring = QuadRect { x_min: center_x - radius, x_max: center_x + radius, y_top: center_y + radius, y_bottom: center_y - radius };
+                        vertices.extend(ring.vertices([1.0, 1.0, 1.0, 0.9], tex_coords, RenderMode::SolidColor));
+                        round_corners(&mut vertices[start..], radius, dot_radius);
+                    }
+                }
+            }
+            _ => {}
+        }
+        vertices
+    }
+
+    fn handle_input(&mut self, input: WidgetInput) -> bool {
+        match (self, input) {
+            (ElementKind::Checkbox { checked }, WidgetInput::Click { .. }) => {
+                *checked = !*checked;
+                true
+            }
+            (ElementKind::Slider { value }, WidgetInput::Click { x_fraction } | WidgetInput::Drag { x_fraction }) => {
+                *value = x_fraction.clamp(0.0, 1.0);
+                true
+            }
+            (ElementKind::TextInput { focused }, WidgetInput::Focus(focus)) => {
+                *focused = focus;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn visible_text<'a>(&self, text: &'a str, scale: f32, width: f32) -> &'a str {
+        if !matches!(self, ElementKind::TextInput { .. }) {
+            return text;
+        }
+
+        // Keep the end of the text in view, leaving a glyph of padding on each side for the caret
+        let fits = ((width / (GLYPH_WIDTH * scale)).floor() as usize).saturating_sub(2);
+        let overflow = text.chars().count().saturating_sub(fits);
+        match text.char_indices().nth(overflow) {
+            Some((start, _)) => &text[start..],
+            None => "",
+        }
+    }
+
+    fn queue_text<'a>(&self, text: &'a str, font: FontId, scale: f32, color: [f32; 4], position: (f32, f32)) -> Option<Section<'a>> {
+        if *self == ElementKind::Image {
+            return None;
+        }
+
+        Some(Section::builder()
+            .with_screen_position([position.0, position.1])
+            .with_text(vec![
+                Text::new(text)
+                    .with_scale(PxScale {x: FONT_SIZE * scale, y: FONT_SIZE * scale})
+                    .with_font_id(font)
+                    .with_color(color),
+            ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::{panel::Panel, quad_layout::QuadLayout};
+
+    const KINDS: [ElementKind; 9] = [
+        ElementKind::Label,
+        ElementKind::Button,
+        ElementKind::Image,
+        ElementKind::Checkbox { checked: true },
+        ElementKind::Slider { value: 0.25 },
+        ElementKind::TextInput { focused: true },
+        ElementKind::ProgressBar { progress: 0.5 },
+        ElementKind::Spinner { angle: Some(1.0) },
+        ElementKind::Spinner { angle: None },
+    ];
+
+    fn labelled(kind: ElementKind, border: bool) -> Element<()> {
+        let mut element = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+            .with_kind(kind)
+            .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, "Label");
+        if border {
+            element = element.with_border(2.0, "#ffffff");
+        }
+        element
+    }
+
+    #[test]
+    fn every_kind_writes_as_many_vertices_as_it_is_given_room_for() {
+        let rect = QuadRect { x_min: 10.0, x_max: 110.0, y_top: 10.0, y_bottom: 40.0 };
+        let atlas = UiAtlas::new(1, 1);
+        for kind in KINDS {
+            for border in [false, true] {
+                let element = labelled(kind.clone(), border);
+                let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0));
+                panel.add_element(labelled(kind.clone(), border));
+                let layout = QuadLayout::new(&[panel], &[0]);
+                let run = layout.element(0, 0).unwrap();
+
+                assert_eq!(run.quads, element.quad_count(), "{:?}", kind);
+                for state in [GroupState::default(), GroupState { enabled: true, visible: false }] {
+                    let vertices = element.vertices(rect, &Style::default(), state, &atlas, 1.0, None);
+                    assert_eq!(vertices.len(), run.quads * 4, "{:?} with border {}, {:?}", kind, border, state);
+                }
+            }
+        }
+    }
+}
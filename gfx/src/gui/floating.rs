@@ -0,0 +1,284 @@
+use winit::{dpi::PhysicalSize, window::CursorIcon};
+
+use crate::definitions::InteractionStyle;
+
+use super::{element::Element, layout::{Alignment, Coordinate, Dimension, HorizontalAlignment, PixelRect, VerticalAlignment}, panel::Panel, split::Edge, theme::ColorRole};
+
+/// Height of a floating window's title bar, in pixels scaled like `Dimension::Pixels`.
+pub const TITLE_BAR_HEIGHT: f32 = 24.0;
+
+/// Width of the grab zones along a floating window's edges, in pixels scaled like `Dimension::Pixels`.
+/// The content is inset from the sides and bottom by as much, so the zones never cover it.
+pub const RESIZE_GRIP: f32 = 4.0;
+
+/// Side of the grab zones at a floating window's corners, in pixels scaled like `Dimension::Pixels`.
+pub const RESIZE_CORNER: f32 = 12.0;
+
+/// Layer of the lowest floating window, above the panels of a page. Each window raised above it goes a layer higher,
+/// so dropdowns and tooltips, which open above every panel, still open above them.
+pub const FLOATING_LAYER: i32 = 16;
+
+/// Smallest a floating window can be resized to without `FloatingWindow::with_min_size`, in pixels scaled like
+/// `Dimension::Pixels`.
+pub const DEFAULT_MIN_SIZE: (f32, f32) = (120.0, 80.0);
+
+pub(crate) const FRAME_BORDER_COLOR: &str = "#30363dff";
+const GRIP_COLOR: &str = "#00000000";
+
+/// A panel in a frame that can be moved by its title bar and resized by its edges, above every ordinary panel,
+/// see `UiTree::add_floating_window`. Clicking anywhere on one raises it above the other floating windows.
+pub struct FloatingWindow<E> {
+    pub(crate) id: String,
+    title: String,
+    start_coordinate: Coordinate,
+    end_coordinate: Coordinate,
+    content: Panel<E>,
+    close_button: Option<Element<E>>,
+    min_size: (f32, f32),
+    max_size: (f32, f32),
+    visible: bool,
+}
+
+impl<E> FloatingWindow<E> {
+    /// A window `title`d over the area from `start_coordinate` to `end_coordinate`, title bar included.
+    /// `content` fills the rest of it and is given `id`, so its elements and `UiTree::panel_index` find it as usual;
+    /// its own coordinates are replaced.
+    pub fn new(id: &str, title: &str, start_coordinate: Coordinate, end_coordinate: Coordinate, content: Panel<E>) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            start_coordinate,
+            end_coordinate,
+            content: content.with_id(id),
+            close_button: None,
+            min_size: DEFAULT_MIN_SIZE,
+            max_size: (f32::INFINITY, f32::INFINITY),
+            visible: true,
+        }
+    }
+
+    /// Smallest the window can be resized to, in pixels scaled like `Dimension::Pixels`.
+    pub fn with_min_size(mut self, width: f32, height: f32) -> Self {
+        self.min_size = (width.max(0.0), height.max(TITLE_BAR_HEIGHT));
+        self
+    }
+
+    /// Largest the window can be resized to, in pixels scaled like `Dimension::Pixels`.
+    pub fn with_max_size(mut self, width: f32, height: f32) -> Self {
+        self.max_size = (width, height);
+        self
+    }
+
+    /// Starts the window hidden or shown, see `UiTree::set_floating_visible`.
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// The frame and content panels, and what the interface keeps to move and resize them.
+    pub(crate) fn build(self, layer: i32) -> (Panel<E>, Panel<E>, Floating) {
+        let mut frame = Panel::new(self.start_coordinate, self.end_coordinate)
+            .with_id(&Floating::frame_id(&self.id))
+            .with_color_role(ColorRole::Panel)
+            .with_border(1.0, FRAME_BORDER_COLOR)
+            .with_layer(layer)
+            .with_visible(self.visible);
+
+        frame.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::mixed(1.0, Dimension::Pixels(TITLE_BAR_HEIGHT)), "solid")
+            .with_id(&format!("{}:title", self.id))
+            .with_color_role(ColorRole::Header)
+            .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, &self.title));
+        let title = frame.elements.len() - 1;
+        if let Some(close_button) = self.close_button {
+            frame.add_element(close_button);
+        }
+        // Added last so they're hit before the title bar where they overlap it
+        let mut grips = Vec::new();
+        for grip in ResizeGrip::ALL {
+            let (start, end) = grip.zone();
+            frame.add_element(Element::new(start, end, "solid")
+                .with_color(GRIP_COLOR)
+                .with_hover_color_role(ColorRole::Accent));
+            grips.push((frame.elements.len() - 1, grip));
+        }
+
+        let content = self.content
+            .with_layer(layer)
+            .with_visible(self.visible);
+        let floating = Floating {
+            id: self.id,
+            start_coordinate: self.start_coordinate,
+            end_coordinate: self.end_coordinate,
+            min_size: self.min_size,
+            max_size: self.max_size,
+            title,
+            grips,
+        };
+        (frame, content, floating)
+    }
+}
+
+impl<E: Clone + Send + 'static> FloatingWindow<E> {
+    /// Puts a close button at the right of the title bar, returning `event` when clicked. The window stays open
+    /// until the caller hides it or builds the interface without it.
+    pub fn with_close_event(mut self, event: E) -> Self {
+        self.close_button = Some(Element::new(Coordinate::mixed(Dimension::from_end(TITLE_BAR_HEIGHT), 0.0), Coordinate::mixed(1.0, Dimension::Pixels(TITLE_BAR_HEIGHT)), "solid")
+            .with_id(&format!("{}:close", self.id))
+            .with_color_role(ColorRole::Header)
+            .with_hover_color_role(ColorRole::Error)
+            .with_fn(move || Some(event.clone()), InteractionStyle::OnClick)
+            .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, "X"));
+        self
+    }
+}
+
+/// A floating window in the interface: where it is, how far it can be resized, and which of its frame's elements
+/// are the title bar and the grab zones.
+pub(crate) struct Floating {
+    /// The content panel's id, the frame panel's is `frame_id` of it.
+    pub(crate) id: String,
+    pub(crate) start_coordinate: Coordinate,
+    pub(crate) end_coordinate: Coordinate,
+    min_size: (f32, f32),
+    max_size: (f32, f32),
+    /// Element index of the title bar in the frame panel.
+    title: usize,
+    /// Element index of each grab zone in the frame panel.
+    grips: Vec<(usize, ResizeGrip)>,
+}
+
+impl Floating {
+    pub(crate) fn frame_id(id: &str) -> String {
+        format!("{}:frame", id)
+    }
+
+    /// What pressing the frame's element at `element_idx` drags: the whole window for the title bar,
+    /// edges for a grab zone, nothing for anything else.
+    pub(crate) fn drag_part(&self, element_idx: usize) -> Option<Option<ResizeGrip>> {
+        if element_idx == self.title {
+            return Some(None);
+        }
+        self.grips.iter().find(|(index, _)| *index == element_idx).map(|(_, grip)| Some(*grip))
+    }
+
+    /// The grab zone the frame's element at `element_idx` is, if it is one.
+    pub(crate) fn grip(&self, element_idx: usize) -> Option<ResizeGrip> {
+        self.drag_part(element_idx).flatten()
+    }
+
+    /// Where the frame goes, the window's whole area.
+    pub(crate) fn frame_coordinates(&self) -> (Coordinate, Coordinate) {
+        (self.start_coordinate, self.end_coordinate)
+    }
+
+    /// Where the content goes, below the title bar and inside the grab zones.
+    pub(crate) fn content_coordinates(&self) -> (Coordinate, Coordinate) {
+        let start = Coordinate { x: self.start_coordinate.x.offset(RESIZE_GRIP), y: self.start_coordinate.y.offset(TITLE_BAR_HEIGHT) };
+        let end = Coordinate { x: self.end_coordinate.x.offset(-RESIZE_GRIP), y: self.end_coordinate.y.offset(-RESIZE_GRIP) };
+        (start, end)
+    }
+
+    /// Start and end coordinates for the window dragged as `drag` says with the cursor at `(x, y)`.
+    /// Moves keep the title bar on screen; resizes keep to the window's size limits and don't take an edge past
+    /// the screen's.
+    pub(crate) fn dragged(&self, drag: &WindowDrag, (x, y): (f32, f32), screen_size: PhysicalSize<u32>, scale: f32) -> (Coordinate, Coordinate) {
+        let origin = drag.origin;
+        let (dx, dy) = (x - drag.grab.0, y - drag.grab.1);
+        let (screen_width, screen_height) = (screen_size.width as f32, screen_size.height as f32);
+        let (mut start, mut end) = drag.origin_coordinates;
+
+        let Some(grip) = drag.grip else {
+            let title_height = TITLE_BAR_HEIGHT * scale;
+            let left = (origin.x + dx).min(screen_width - origin.width).max(0.0);
+            let top = (origin.y + dy).min(screen_height - title_height).max(0.0);
+            let (shift_x, shift_y) = ((left - origin.x) / scale, (top - origin.y) / scale);
+            start = Coordinate { x: start.x.offset(shift_x), y: start.y.offset(shift_y) };
+            end = Coordinate { x: end.x.offset(shift_x), y: end.y.offset(shift_y) };
+            return (start, end);
+        };
+
+        let (min_width, min_height) = (self.min_size.0 * scale, self.min_size.1 * scale);
+        let (max_width, max_height) = (self.max_size.0 * scale, self.max_size.1 * scale);
+        match grip.x {
+            Some(Edge::Left) => {
+                let width = (origin.width - dx).clamp(min_width, max_width.max(min_width)).min(origin.x + origin.width);
+                start.x = start.x.offset((origin.width - width) / scale);
+            }
+            Some(_) => {
+                let width = (origin.width + dx).clamp(min_width, max_width.max(min_width)).min(screen_width - origin.x);
+                end.x = end.x.offset((width - origin.width) / scale);
+            }
+            None => {}
+        }
+        match grip.y {
+            Some(Edge::Top) => {
+                let height = (origin.height - dy).clamp(min_height, max_height.max(min_height)).min(origin.y + origin.height);
+                start.y = start.y.offset((origin.height - height) / scale);
+            }
+            Some(_) => {
+                let height = (origin.height + dy).clamp(min_height, max_height.max(min_height)).min(screen_height - origin.y);
+                end.y = end.y.offset((height - origin.height) / scale);
+            }
+            None => {}
+        }
+        (start, end)
+    }
+}
+
+/// A floating window being moved or resized, from the press on its title bar or a grab zone until release.
+pub(crate) struct WindowDrag {
+    /// Index in `UiTree::floating`.
+    pub(crate) window: usize,
+    /// `None` while moving the window.
+    pub(crate) grip: Option<ResizeGrip>,
+    /// Where the press was, in window pixels.
+    pub(crate) grab: (f32, f32),
+    /// The window's rect in window pixels when it was pressed.
+    pub(crate) origin: PixelRect,
+    pub(crate) origin_coordinates: (Coordinate, Coordinate),
+}
+
+/// The edges a grab zone moves, one for a side and two for a corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ResizeGrip {
+    /// `Left` or `Right`.
+    pub(crate) x: Option<Edge>,
+    /// `Top` or `Bottom`.
+    pub(crate) y: Option<Edge>,
+}
+
+impl ResizeGrip {
+    /// Sides before corners, so the corners are hit first where they overlap.
+    const ALL: [ResizeGrip; 8] = [
+        ResizeGrip { x: Some(Edge::Left), y: None },
+        ResizeGrip { x: Some(Edge::Right), y: None },
+        ResizeGrip { x: None, y: Some(Edge::Top) },
+        ResizeGrip { x: None, y: Some(Edge::Bottom) },
+        ResizeGrip { x: Some(Edge::Left), y: Some(Edge::Top) },
+        ResizeGrip { x: Some(Edge::Right), y: Some(Edge::Top) },
+        ResizeGrip { x: Some(Edge::Left), y: Some(Edge::Bottom) },
+        ResizeGrip { x: Some(Edge::Right), y: Some(Edge::Bottom) },
+    ];
+
+    /// Where the zone sits in the frame: along the whole side for a side, a square for a corner.
+    fn zone(self) -> (Coordinate, Coordinate) {
+        let size = if self.x.is_some() && self.y.is_some() { RESIZE_CORNER } else { RESIZE_GRIP };
+        let axis = |edge: Option<Edge>| match edge {
+            Some(Edge::Left | Edge::Top) => (Dimension::Fraction(0.0), Dimension::Pixels(size)),
+            Some(_) => (Dimension::from_end(size), Dimension::Fraction(1.0)),
+            None => (Dimension::Fraction(0.0), Dimension::Fraction(1.0)),
+        };
+        let ((x_start, x_end), (y_start, y_end)) = (axis(self.x), axis(self.y));
+        (Coordinate { x: x_start, y: y_start }, Coordinate { x: x_end, y: y_end })
+    }
+
+    /// Resize arrow shown over the zone and while dragging it.
+    pub(crate) fn cursor_icon(self) -> CursorIcon {
+        match (self.x, self.y) {
+            (Some(_), None) => CursorIcon::EwResize,
+            (None, _) => CursorIcon::NsResize,
+            (Some(Edge::Left), Some(Edge::Top)) | (Some(Edge::Right), Some(Edge::Bottom)) => CursorIcon::NwseResize,
+            _ => CursorIcon::NeswResize,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashMap, sync::atomic::{AtomicU64, Ordering}};
+
+use wgpu_text::glyph_brush::ab_glyph::{FontArc, InvalidFont};
+
+use super::style::FontWeight;
+
+pub use wgpu_text::glyph_brush::FontId;
+
+/// Source of `Fonts::version`, 0 is left for the empty set.
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+/// Fonts an interface's text can be drawn in. The first one added is the default for elements without
+/// `Element::with_font`. Clones share the loaded fonts, so an app can load them once and hand the set
+/// to every interface it builds.
+#[derive(Clone, Default)]
+pub struct Fonts {
+    fonts: Vec<FontArc>,
+    names: HashMap<String, FontId>,
+    /// Other weights of a font, see `set_weight`.
+    weights: HashMap<(FontId, FontWeight), FontId>,
+    /// Changes with every font added and is shared by clones, so two sets with the same version hold the same fonts.
+    version: u64,
+}
+
+impl Fonts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads `bytes` as a TrueType or OpenType font called `name`. Adding a name again points it at the new font,
+    /// the old one keeps its id.
+    pub fn add(&mut self, name: &str, bytes: Vec<u8>) -> Result<FontId, InvalidFont> {
+        let font = FontArc::try_from_vec(bytes)?;
+        let id = FontId(self.fonts.len());
+        self.fonts.push(font);
+        self.names.insert(name.to_string(), id);
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+        Ok(id)
+    }
+
+    /// Id of the font last added as `name`.
+    pub fn id(&self, name: &str) -> Option<FontId> {
+        self.names.get(name).copied()
+    }
+
+    /// Draws text in `font` that asks for `weight`, with `TextStyle::with_weight_hint`, in `variant` instead.
+    /// Both are ids from this set.
+    pub fn set_weight(&mut self, font: FontId, weight: FontWeight, variant: FontId) {
+        self.weights.insert((font, weight), variant);
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    /// Whether text laid out or drawn with `other` looks the same, because they're clones of one set.
+    pub(crate) fn same_as(&self, other: &Fonts) -> bool {
+        self.version == other.version
+    }
+
+    pub(crate) fn fonts(&self) -> &[FontArc] {
+        &self.fonts
+    }
+
+    /// `id` if it's one of these fonts, otherwise the default. Ids from another set would index past the brush's fonts.
+    pub(crate) fn resolve(&self, id: Option<FontId>) -> FontId {
+        id.filter(|id| id.0 < self.fonts.len()).unwrap_or_default()
+    }
+
+    /// Like `resolve`, with the variant registered for `weight` if there is one.
+    pub(crate) fn resolve_weight(&self, id: Option<FontId>, weight: FontWeight) -> FontId {
+        let id = self.resolve(id);
+        self.weights.get(&(id, weight)).copied().unwrap_or(id)
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+/// Element ids whose enabled and visible state is switched together.
+struct ElementGroup {
+    members: Vec<String>,
+    enabled: bool,
+    visible: bool,
+}
+
+/// What an element's groups allow, an element in several groups gets the most restrictive of them.
+/// The interface narrows it further by the element's and its panel's own flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GroupState {
+    pub(crate) enabled: bool,
+    pub(crate) visible: bool,
+}
+
+impl Default for GroupState {
+    fn default() -> Self {
+        Self { enabled: true, visible: true }
+    }
+}
+
+impl GroupState {
+    /// Whether the element takes hover, clicks and focus.
+    pub(crate) fn interactive(&self) -> bool {
+        self.enabled && self.visible
+    }
+
+    /// Enabled and visible only where both states are.
+    pub(crate) fn and(self, other: GroupState) -> GroupState {
+        GroupState { enabled: self.enabled && other.enabled, visible: self.visible && other.visible }
+    }
+}
+
+/// Every group of an interface, keyed by name. Membership is by element id rather than index,
+/// so it still applies after the elements are rebuilt.
+#[derive(Default)]
+pub(crate) struct Groups(BTreeMap<String, ElementGroup>);
+
+impl Groups {
+    pub(crate) fn define(&mut self, name: &str, members: Vec<String>) {
+        self.0.insert(name.to_string(), ElementGroup { members, enabled: true, visible: true });
+    }
+
+    /// Returns true when the group exists and its state changed.
+    pub(crate) fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.0.get_mut(name) {
+            Some(group) if group.enabled != enabled => {
+                group.enabled = enabled;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true when the group exists and its state changed.
+    pub(crate) fn set_visible(&mut self, name: &str, visible: bool) -> bool {
+        match self.0.get_mut(name) {
+            Some(group) if group.visible != visible => {
+                group.visible = visible;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn members(&self, name: &str) -> &[String] {
+        self.0.get(name).map(|group| group.members.as_slice()).unwrap_or_default()
+    }
+
+    pub(crate) fn state(&self, id: Option<&str>) -> GroupState {
+        let Some(id) = id else {
+            return GroupState::default();
+        };
+        self.0.values()
+            .filter(|group| group.members.iter().any(|member| member == id))
+            .fold(GroupState::default(), |state, group| GroupState {
+                enabled: state.enabled && group.enabled,
+                visible: state.visible && group.visible,
+            })
+    }
+
+    /// Adds `previous`'s groups that aren't defined here, keeping their state.
+    pub(crate) fn inherit(&mut self, previous: Groups) {
+        for (name, group) in previous.0 {
+            self.0.entry(name).or_insert(group);
+        }
+    }
+}
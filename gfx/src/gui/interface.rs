@@ -1,666 +1,2535 @@
-use wgpu::{Device, Queue, util::DeviceExt};
+use std::{collections::{HashMap, HashSet, VecDeque}, ops::Range, sync::atomic::{AtomicBool, AtomicU64, Ordering}, time::{Duration, Instant}};
+
+use wgpu_text::glyph_brush::{ab_glyph::{FontArc, InvalidFont}, Extra, GlyphBrush};
+use glam::Vec2;
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, keyboard::ModifiersState, window::CursorIcon};
+
+use crate::definitions::{InteractionStyle, UiAtlas};
+
+use super::{animation::{self, AnimProperty, AnimTarget, Animated, Animation, Easing}, debug_overlay, dropdown::{self, MenuEntry, OpenDropdown}, element::{Widget, WidgetInput}, floating::{self, Floating, WindowDrag}, group::{GroupState, Groups}, layout::PixelRect, scroll::SCROLL_LINE, split::{self, Edge, Split}, text, toast::{self, Toast}, tooltip::{self, PendingTooltip}};
+
+pub use super::{color::Color, dropdown::MenuItem, element::{Element, ElementKind}, floating::FloatingWindow, font::{FontId, Fonts}, layout::{Alignment, Coordinate, Dimension, HorizontalAlignment, Layout, VerticalAlignment, Wrap}, panel::{Panel, ScrollDirection}, split::SplitDirection, toast::ToastLevel, style::{Border, FontWeight, Style, TextStyle}, theme::{ColorRole, Theme}};
+
+#[derive(Debug, Default)]
+pub struct InteractionResult {
+    /// True when a callback produced an event, which is queued for `UiTree::drain_events`.
+    pub fired: bool,
+    /// Panel and element index of the element whose callback fired.
+    pub element: Option<(usize, usize)>,
+    /// The same element as `element`, in a form that can be kept past a rebuild.
+    pub handle: Option<ElementHandle>,
+    /// True when the interface handled the input and it must not reach editor tools.
+    pub consumed: bool,
+    /// Panel index and new offset when `handle_scroll` moved a panel.
+    pub scrolled: Option<(usize, f32)>,
+    /// Elements that lost or gained hover, whose vertices need updating for the color change.
+    pub hover_changed: Vec<(usize, usize)>,
+}
 
-use wgpu_text::{glyph_brush::{ab_glyph::{FontRef, PxScale}, Section, Text}, BrushBuilder, TextBrush};
-use winit::dpi::{PhysicalPosition, PhysicalSize};
+/// Refers to an element in a way that can't end up at the wrong one after a rebuild. In the interface that made it,
+/// it resolves by index; in any other, by the element's id, so elements without an id resolve to nothing there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementHandle {
+    interface: u64,
+    index: (usize, usize),
+    id: Option<String>,
+}
 
-use crate::definitions::{GuiEvent, InteractionStyle, UiAtlas, Vertex};
+impl ElementHandle {
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
 
-pub struct Interface {
-    pub panels: Vec<Panel>,
-    pub(crate) vertex_buffer: Option<wgpu::Buffer>,
-    pub(crate) index_buffer: Option<wgpu::Buffer>,
-    brush: Option<TextBrush<FontRef<'static>>>,
-    atlas: UiAtlas,
+/// Longest gap between two clicks on an element for the second to fire `InteractionStyle::OnDoubleClick`.
+pub const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Source of `UiTree::instance`, so handles and the renderer can tell trees apart.
+static NEXT_INSTANCE: AtomicU64 = AtomicU64::new(0);
+
+/// Everything the interface shows and how it reacts to input, as plain data: panels, elements and their callbacks,
+/// the atlas and fonts they're drawn with, and the state input leaves behind. It holds nothing on the GPU, so the
+/// application owns it outright and can build one anywhere. It is `Send` as long as the event type is, element
+/// callbacks are boxed as `Send`.
+///
+/// Nothing reaches the screen until a `UiRenderer`, the one `RenderState` owns, uploads the tree with
+/// `RenderState::update_interface`; changes made since show from the next upload.
+///
+/// `E` is the application's event type. Element callbacks return it, and the tree queues it for `drain_events`.
+pub struct UiTree<E> {
+    pub panels: Vec<Panel<E>>,
+    pub(crate) fonts: Fonts,
+    /// Lays text out without drawing it, so alignment can use the real glyph bounds.
+    /// Built from `fonts` by the first upload after they change.
+    pub(crate) measure: Option<GlyphBrush<(), Extra, FontArc>>,
+    pub(crate) atlas: UiAtlas,
+    /// Textures elements asked for that the atlas doesn't have, each warned about once.
+    pub(crate) missing_textures: HashSet<String>,
+    captured_element: Option<(usize, usize)>,
+    focused_element: Option<(usize, usize)>,
+    /// Element Tab moved keyboard focus to, drawn in the focus color, see `focus_next`.
+    tab_focus: Option<(usize, usize)>,
+    /// Bottom of the style cascade, used for anything neither the panel nor the element sets.
+    pub(crate) style: Style,
+    /// Colors behind `ColorRole`s, and under `style` for anything it leaves unset.
+    pub(crate) theme: Theme,
+    /// What the window is cleared to behind the panels, the theme's background when unset, see `set_clear_color`.
+    clear_color: Option<Color>,
+    /// Multiplies every resolved text scale, see `set_text_scale_factor`.
+    pub(crate) text_scale_factor: f32,
+    /// Window scale factor every pixel size and text scale is multiplied by, see `set_scale_factor`.
+    pub(crate) scale_factor: f32,
+    pub(crate) groups: Groups,
+    /// Screen size of the last upload, which single-element uploads lay out against.
+    pub(crate) screen_size: PhysicalSize<u32>,
+    /// Pixel rect of each visible element showing a render target, keyed by target name, from the last upload.
+    pub(crate) render_target_rects: HashMap<String, PixelRect>,
+    wakeups: Vec<(WakeupId, Instant)>,
+    next_wakeup_id: u64,
+    /// Unique to this tree, handles made by others resolve by id instead of index. The renderer also uses it
+    /// to tell whether it last uploaded this tree or the one it replaced.
+    pub(crate) instance: u64,
+    /// Element the held button went down on, see `pressed_element`.
+    pressed_element: Option<(usize, usize)>,
+    /// Element and time of the last click, which a second one soon after on the same element makes a double-click.
+    /// Kept as a handle so `inherit_clicks` can carry it over a rebuild.
+    last_click: Option<(ElementHandle, Instant)>,
+    /// Element the cursor is over, see `hovered_element`.
+    hovered: Option<(usize, usize)>,
+    /// Modifier keys held, which decide what a click on a selectable element does, see `set_modifiers`.
+    modifiers: ModifiersState,
+    /// Per panel index, the selectable element the last click without Shift landed on, which Shift+click selects
+    /// a run from. Kept as handles so `inherit_selection` can carry them over a rebuild.
+    selection_anchors: HashMap<usize, ElementHandle>,
+    /// Set whenever vertices or text are uploaded, cleared once `RenderState` has requested a frame for them.
+    /// Atomic so single-element uploads, which only borrow the tree, can set it too.
+    needs_redraw: AtomicBool,
+    /// In the order they were added, so a split comes after the splits nested in it.
+    splits: Vec<Split>,
+    /// Split whose divider is held, and how far from the boundary it was grabbed, in pixels along the split.
+    dragged_split: Option<(usize, f32)>,
+    /// Floating windows, bottom of the stack first, see `add_floating_window`.
+    floating: Vec<Floating>,
+    /// Floating window whose title bar or grab zone is held.
+    dragged_window: Option<WindowDrag>,
+    /// Dropdown opened by a click on an element built `with_dropdown`, see `close_dropdown`.
+    open_dropdown: Option<OpenDropdown>,
+    /// Element the cursor is resting on for a tooltip, and the tooltip once it's up, see `track_tooltip`.
+    tooltip: Option<PendingTooltip>,
+    /// See `set_tooltip_delay`.
+    tooltip_delay: Duration,
+    /// Oldest first, see `push_toast`.
+    toasts: Vec<Toast>,
+    /// Index of the overlay panel stacking the toasts, there while any are left.
+    toast_panel: Option<usize>,
+    /// Lines and frame times shown by the debug overlay and the index of its panel, see `show_debug_overlay`.
+    debug_overlay: Option<(Vec<String>, Vec<Duration>, usize)>,
+    /// Kept over rebuilds with the overlay, see `debug_overlay::GraphPipeline`.
+    debug_graph_pipeline: debug_overlay::GraphPipeline,
+    /// Events element callbacks produced, oldest first, waiting for `drain_events`.
+    events: VecDeque<E>,
+    /// Running animations, at most one per property of an element or panel, see `animate`.
+    animations: Vec<Animation>,
+    /// When the next animation step is due, `None` while nothing animates so the event loop can idle.
+    animation_frame: Option<Instant>,
+    /// See `set_hover_transition`.
+    hover_transition: Duration,
+    /// When spinners next turn, `None` without any, see `step_spinners`.
+    spinner_frame: Option<Instant>,
+    /// See `set_spinners_turn`.
+    spinners_turn: bool,
 }
 
-impl Interface {
-    pub fn new(atlas: UiAtlas) -> Interface {
+/// Handle for a pending wakeup, used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WakeupId(u64);
+
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    // Callbacks are boxed as `Send`, so this holds for any event type that is itself `Send`
+    assert_send::<UiTree<()>>();
+};
+
+impl<E> UiTree<E> {
+    pub fn new(atlas: UiAtlas) -> UiTree<E> {
         Self {
             panels: Vec::new(),
-            vertex_buffer: None,
-            index_buffer: None,
-            brush: None,
+            fonts: Fonts::default(),
+            measure: None,
             atlas,
+            missing_textures: HashSet::new(),
+            captured_element: None,
+            focused_element: None,
+            tab_focus: None,
+            style: Style::default(),
+            theme: Theme::default(),
+            clear_color: None,
+            text_scale_factor: 1.0,
+            scale_factor: 1.0,
+            groups: Groups::default(),
+            screen_size: PhysicalSize::new(0, 0),
+            render_target_rects: HashMap::new(),
+            wakeups: Vec::new(),
+            next_wakeup_id: 0,
+            instance: NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed),
+            pressed_element: None,
+            last_click: None,
+            hovered: None,
+            modifiers: ModifiersState::default(),
+            selection_anchors: HashMap::new(),
+            needs_redraw: AtomicBool::new(false),
+            splits: Vec::new(),
+            dragged_split: None,
+            floating: Vec::new(),
+            dragged_window: None,
+            open_dropdown: None,
+            tooltip: None,
+            tooltip_delay: tooltip::TOOLTIP_DELAY,
+            toasts: Vec::new(),
+            toast_panel: None,
+            debug_overlay: None,
+            debug_graph_pipeline: Default::default(),
+            events: VecDeque::new(),
+            animations: Vec::new(),
+            animation_frame: None,
+            hover_transition: animation::HOVER_TRANSITION,
+            spinner_frame: None,
+            spinners_turn: true,
+        }
+    }
+
+    pub(crate) fn atlas(&self) -> &UiAtlas {
+        &self.atlas
+    }
+
+    /// Replaces the atlas elements look their textures up in, takes effect on the next upload.
+    pub fn set_atlas(&mut self, atlas: UiAtlas) {
+        self.atlas = atlas;
+        self.missing_textures.clear();
+    }
+
+    /// Replaces the fonts text is drawn in. Takes effect on the next upload,
+    /// until there are fonts the interface draws no text.
+    pub fn set_fonts(&mut self, fonts: Fonts) {
+        self.fonts = fonts;
+        self.measure = None;
+    }
+
+    /// Loads `bytes` into the interface's fonts as `name`, see `Fonts::add`.
+    pub fn add_font(&mut self, name: &str, bytes: Vec<u8>) -> Result<FontId, InvalidFont> {
+        let id = self.fonts.add(name, bytes)?;
+        self.measure = None;
+        Ok(id)
+    }
+
+    pub fn fonts(&self) -> &Fonts {
+        &self.fonts
+    }
+
+    /// Replaces the colors `ColorRole`s resolve to. Takes effect on the next upload, nothing needs rebuilding.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Clears the window to `color` behind the panels instead of the theme's background, e.g. so each page can have
+    /// a backdrop of its own. The level preview is still cleared to the theme's background.
+    pub fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = Some(color);
+        self.request_redraw();
+    }
+
+    /// What the window is cleared to behind the panels, see `set_clear_color`.
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color.as_ref().map_or_else(|| self.theme.clear_color(), Color::to_wgpu)
+    }
+
+    /// Replaces the interface-wide style. Takes effect on the next upload.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Scales all text by `factor` on top of whatever scale the cascade resolves to, for a user font size preference.
+    /// Takes effect on the next upload.
+    pub fn set_text_scale_factor(&mut self, factor: f32) {
+        self.text_scale_factor = factor;
+    }
+
+    /// Lays the interface out for a window at `factor` physical pixels per logical one, as winit reports it.
+    /// Pixel dimensions, borders, padding and text all grow with it, so the UI keeps its size relative to the window
+    /// on HiDPI displays. Hit-testing stays in physical pixels like everything else, it resolves the same layout.
+    /// Takes effect on the next upload.
+    pub fn set_scale_factor(&mut self, factor: f32) {
+        self.scale_factor = factor;
+        for panel in &mut self.panels {
+            panel.pixel_scale = factor;
         }
     }
 
-    pub fn add_panel(&mut self, panel: Panel) {
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Panics if the panel or one of its elements has an id already used in the interface,
+    /// since id lookups, groups and handles would silently pick one of them.
+    pub fn add_panel(&mut self, mut panel: Panel<E>) {
+        if let Some(id) = panel.id() {
+            assert!(self.panel_index(id).is_none(), "panel id {:?} is already used in this interface", id);
+        }
+        for id in panel.elements.iter().filter_map(Element::id) {
+            assert!(self.element_index(id).is_none(), "element id {:?} is already used in this interface", id);
+        }
+        panel.pixel_scale = self.scale_factor;
+        if set_spinner_angles(&mut panel, self.spinners_turn) && self.spinners_turn {
+            self.spinner_frame.get_or_insert_with(Instant::now);
+        }
         self.panels.push(panel);
     }
 
-    pub fn handle_interaction(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>, interaction_type: InteractionStyle) -> Option<(GuiEvent, (usize, usize))> {
-        let x_position = position.x as f32 / screen_size.width as f32;
-        let y_position = position.y as f32 / screen_size.height as f32;
-
-        for (panel_idx, panel) in self.panels.iter().enumerate() {
-            if x_position >= panel.start_coordinate.x && x_position <= panel.end_coordinate.x &&
-            y_position >= panel.start_coordinate.y && y_position <= panel.end_coordinate.y {
-                let rel_cursor_x = x_position - panel.start_coordinate.x;
-                let rel_cursor_y = y_position - panel.start_coordinate.y;
-                
-                for (element_idx, element) in panel.elements.iter().enumerate() {
-                    if rel_cursor_x >= element.start_coordinate.x && rel_cursor_x <= element.end_coordinate.x &&
-                    rel_cursor_y >= element.start_coordinate.y && rel_cursor_y <= element.end_coordinate.y {
-                        
-                        if interaction_type == InteractionStyle::OnClick && element.on_click.is_some() {
-                            if let Some(event) = element.handle_click(interaction_type.clone()) {
-                                return Some((event, (panel_idx, element_idx)));
-                            }
-                        } else if interaction_type == InteractionStyle::OnHover && element.on_hover.is_some() {
-                            if let Some(event) = element.handle_click(interaction_type.clone()) {
-                                return Some((event, (panel_idx, element_idx)));
-                            }
-                        }
+    /// Adds `elements` after the last of the panel at `panel_idx`, returning their element indices. Nothing already
+    /// in the tree moves, so focus, hover and presses stay on their elements and `RenderState::update_interface_appended`
+    /// can upload just the new ones. Panics on an id already in use, as `add_panel` does.
+    pub fn append_elements(&mut self, panel_idx: usize, elements: Vec<Element<E>>) -> Range<usize> {
+        // One pass over the tree rather than one per id, long lists are appended to a chunk at a time
+        let mut ids = HashSet::new();
+        for id in elements.iter().filter_map(Element::id) {
+            assert!(ids.insert(id), "element id {:?} is already used in this interface", id);
+        }
+        if let Some(id) = self.panels.iter().flat_map(|panel| &panel.elements).filter_map(Element::id).find(|id| ids.contains(id)) {
+            panic!("element id {:?} is already used in this interface", id);
+        }
+        let panel = &mut self.panels[panel_idx];
+        let start = panel.elements.len();
+        panel.elements.extend(elements);
+        if set_spinner_angles(panel, self.spinners_turn) && self.spinners_turn {
+            self.spinner_frame.get_or_insert_with(Instant::now);
+        }
+        start..self.panels[panel_idx].elements.len()
+    }
+
+    /// Offers a pointer interaction to the interface. `consumed` is set whenever the cursor is over a panel with a
+    /// fill or over one of a panel's elements, even if no element fired, so the caller knows not to pass the input on
+    /// to editor tools. Empty space in a panel without a fill, and panels built `with_input_passthrough`, leave it
+    /// for them. Panels are tried from the top layer down, stopping at the first one that isn't `with_click_through`.
+    ///
+    /// The caller hands over `OnPress` and `OnRelease` for the button and `OnDrag` for cursor moves while it's held,
+    /// the interface works out `OnClick` and `OnDoubleClick` from them. A release firing several styles
+    /// returns the event of the most specific: double-click, then click, then release.
+    ///
+    /// `OnHover` moves the hover to the topmost element under the cursor that reacts to it, firing `OnHoverExit`
+    /// and `OnHover` only when that changes. An entry's event wins over a leave's.
+    ///
+    /// `OnRightClick` fires on the topmost element under the cursor that reacts to it and opens its context menu.
+    ///
+    /// While a dropdown or context menu is open, a press of either button outside it closes it and goes no further.
+    /// Scrolling goes through `handle_scroll`.
+    pub fn handle_interaction(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>, interaction_type: InteractionStyle) -> InteractionResult {
+        let mut result = InteractionResult::default();
+        let (x_position, y_position) = (position.x as f32, position.y as f32);
+
+        let hit_panels = self.panels_under(x_position, y_position, screen_size);
+        let over_viewport = self.is_viewport_at(x_position, y_position, screen_size);
+
+        // A release ends the press wherever it happens, even over the viewport
+        let mut released_from = None;
+        match interaction_type {
+            // Goes to the pressed element wherever the cursor is, like a capture
+            InteractionStyle::OnDrag { .. } => {
+                return match self.pressed_element {
+                    Some(index) => {
+                        result.consumed = true;
+                        self.fire(index, &[interaction_type], result)
                     }
+                    None => result,
+                };
+            }
+            InteractionStyle::OnClick => {
+                let pressed = self.handle_interaction(position, screen_size, InteractionStyle::OnPress);
+                let released = self.handle_interaction(position, screen_size, InteractionStyle::OnRelease);
+                return if released.fired { released } else { pressed };
+            }
+            // Worked out from releases, there's no input to hand over for it
+            InteractionStyle::OnDoubleClick => return result,
+            InteractionStyle::OnPress | InteractionStyle::OnRightClick => {
+                self.hide_tooltip();
+                // A press outside an open menu only closes it, even over the element that opened it
+                if let Some(open) = self.open_dropdown
+                    && !hit_panels.iter().any(|&panel| open.lists_in(panel)) {
+                    self.close_dropdown();
+                    result.consumed = true;
+                    return result;
                 }
+                // Pressing anywhere but a focusable element drops keyboard focus
+                if interaction_type == InteractionStyle::OnPress {
+                    let target = self.hit_test(position, screen_size)
+                        .filter(|&index| self.element_at(index).is_some_and(|element| element.kind.is_focusable()));
+                    self.focus(target);
+                }
+            }
+            InteractionStyle::OnRelease => {
+                released_from = self.pressed_element;
+                self.cancel_press();
+            }
+            InteractionStyle::OnHover => {}
+            // Worked out from moves, there's no input to hand over for it
+            InteractionStyle::OnHoverExit => return result,
+        }
+        if over_viewport {
+            return match interaction_type {
+                InteractionStyle::OnHover => self.set_hovered(None, result),
+                _ => result,
+            };
+        }
+        result.consumed = hit_panels.iter().any(|&panel_idx| self.blocks_input(panel_idx, x_position, y_position, screen_size));
+
+        match interaction_type {
+            InteractionStyle::OnPress => self.press(position, screen_size, result),
+            InteractionStyle::OnRelease => self.release(position, screen_size, released_from, result),
+            InteractionStyle::OnRightClick => self.right_click(x_position, y_position, &hit_panels, screen_size, result),
+            _ => {
+                let target = self.hover_target(x_position, y_position, &hit_panels, screen_size);
+                self.set_hovered(target, result)
             }
         }
-        None
     }
 
-    pub fn is_cursor_within_menu_panel_bounds(&self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>) -> bool {
-        let x_position = position.x as f32 / screen_size.width as f32;
-        let y_position = position.y as f32 / screen_size.height as f32;
+    /// Presses the element under `position`, which then gets the drags and decides what the release fires.
+    /// A press anywhere on a floating window raises it first.
+    fn press(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>, result: InteractionResult) -> InteractionResult {
+        if let Some(window_idx) = self.panels_under(position.x as f32, position.y as f32, screen_size).first()
+            .and_then(|&panel_idx| self.floating_window_of(panel_idx)) {
+            self.raise_floating(window_idx);
+        }
+        let Some(index) = self.hit_test(position, screen_size) else {
+            return result;
+        };
+        let rect = self.panels[index.0].element_pixel_rects(screen_size)[index.1];
+        let element = &mut self.panels[index.0].elements[index.1];
+        let x_fraction = (position.x as f32 - rect.x) / rect.width;
+        element.kind.handle_input(WidgetInput::Click { x_fraction });
+        if element.kind.captures_pointer() {
+            self.captured_element = Some(index);
+        }
+        element.pressed = true;
+        self.pressed_element = Some(index);
+        if let Some(split_idx) = self.divider_split(index.0) {
+            let boundary = self.split_boundary_px(split_idx, screen_size);
+            let along = self.split_axis_px(split_idx, position);
+            self.dragged_split = Some((split_idx, along - boundary));
+            self.captured_element = Some(index);
+        }
+        if let Some(drag) = self.window_drag(index, position, screen_size) {
+            self.dragged_window = Some(drag);
+            self.captured_element = Some(index);
+        }
+        self.fire(index, &[InteractionStyle::OnPress], result)
+    }
 
-        for panel in self.panels.iter() {
-            if x_position >= panel.start_coordinate.x && x_position <= panel.end_coordinate.x &&
-            y_position >= panel.start_coordinate.y && y_position <= panel.end_coordinate.y {
-                return true;
+    /// Fires the release on the element under `position`, and the click and double-click when `pressed`,
+    /// the element the press started on, is the same one.
+    fn release(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>, pressed: Option<(usize, usize)>, result: InteractionResult) -> InteractionResult {
+        let Some(index) = self.hit_test(position, screen_size) else {
+            return result;
+        };
+        if pressed == Some(index) && Some(index.0) == self.toast_panel {
+            self.dismiss_toast(index.1);
+            return result;
+        }
+        if pressed == Some(index)
+            && let Some(open) = self.open_dropdown
+            && open.lists_in(index.0) {
+            return self.pick_dropdown_item(index, open, screen_size, result);
+        }
+        let now = Instant::now();
+        let mut styles = Vec::new();
+        if pressed == Some(index) {
+            let double = self.last_click.as_ref()
+                .is_some_and(|(last, at)| self.resolve(last) == Some(index) && now.duration_since(*at) <= DOUBLE_CLICK_INTERVAL);
+            // A third click starts a new pair rather than making another double-click
+            self.last_click = if double { None } else { self.handle(index).map(|handle| (handle, now)) };
+            if double {
+                styles.push(InteractionStyle::OnDoubleClick);
             }
-        } false
+            styles.push(InteractionStyle::OnClick);
+        }
+        styles.push(InteractionStyle::OnRelease);
+        // Selected before the callbacks fire, so a click handler reading `selection` sees the click in it
+        if pressed == Some(index) {
+            self.click_select(index);
+        }
+        let result = self.fire(index, &styles, result);
+        if pressed == Some(index) && self.element_at(index).is_some_and(|element| !element.dropdown.is_empty()) {
+            self.open_dropdown(index, screen_size);
+        }
+        result
     }
 
-    pub fn reset_all_element_colors(&mut self) {
-        for panel in &mut self.panels {
-            for element in &mut panel.elements {
-                element.color = element.original_color.clone();
+    /// Fires `OnRightClick` on the topmost element under the cursor that has a context menu or a handler for it,
+    /// then opens the menu at the cursor.
+    fn right_click(&mut self, x: f32, y: f32, hit_panels: &[usize], screen_size: PhysicalSize<u32>, result: InteractionResult) -> InteractionResult {
+        let target = hit_panels.iter().find_map(|&panel_idx| {
+            let panel = &self.panels[panel_idx];
+            let rects = panel.element_pixel_rects(screen_size);
+            let panel_state = panel.own_state();
+            panel.element_draw_order().into_iter().rev()
+                .find(|&element_idx| {
+                    let element = &panel.elements[element_idx];
+                    rects[element_idx].contains(x, y) && element_state(&self.groups, panel_state, element).interactive()
+                        && element.reacts_to_right_click()
+                })
+                .map(|element_idx| (panel_idx, element_idx))
+        });
+        let Some(index) = target else {
+            return result;
+        };
+        let result = self.fire(index, &[InteractionStyle::OnRightClick], result);
+        if self.element_at(index).is_some_and(|element| !element.context_menu.is_empty()) {
+            self.open_menu(index, true, (x, y), dropdown::DROPDOWN_MIN_WIDTH * self.scale_factor, screen_size);
+        }
+        result
+    }
+
+    /// Opens the dropdown of the element at `source` beneath it, at least as wide as the element.
+    fn open_dropdown(&mut self, source: (usize, usize), screen_size: PhysicalSize<u32>) {
+        let rect = self.panels[source.0].element_pixel_rects(screen_size)[source.1];
+        let width = rect.width.max(dropdown::DROPDOWN_MIN_WIDTH * self.scale_factor);
+        self.open_menu(source, false, (rect.x, rect.y + rect.height), width, screen_size);
+    }
+
+    /// Adds the overlay panel listing the items of the element at `source`, its context menu or its dropdown, above
+    /// every panel. `corner` is where its top left goes and `width` how wide it is, both in physical pixels.
+    fn open_menu(&mut self, source: (usize, usize), context: bool, corner: (f32, f32), width: f32, screen_size: PhysicalSize<u32>) {
+        self.close_dropdown();
+        let labels: Vec<String> = self.panels[source.0].elements[source.1].menu(context).iter().map(MenuEntry::display_label).collect();
+        let menu = self.menu_panel(&labels, corner, width, screen_size);
+        self.panels.push(menu);
+        self.open_dropdown = Some(OpenDropdown { source, context, panel: self.panels.len() - 1, submenu: None });
+    }
+
+    /// Adds the overlay panel listing the submenu of `item` in the open menu beside the item, on its right unless
+    /// there's no room there, replacing any submenu already open.
+    fn open_submenu(&mut self, item: usize, screen_size: PhysicalSize<u32>) {
+        if let Some((_, panel)) = self.open_dropdown.as_mut().and_then(|open| open.submenu.take()) {
+            self.remove_overlay(panel);
+        }
+        let Some(open) = self.open_dropdown else {
+            return;
+        };
+        let Some(entry) = self.panels[open.source.0].elements[open.source.1].menu(open.context).get(item) else {
+            return;
+        };
+        let labels: Vec<String> = entry.submenu.iter().map(MenuEntry::display_label).collect();
+        let rect = self.panels[open.panel].element_pixel_rects(screen_size)[item];
+        let x = if rect.x + 2.0 * rect.width <= screen_size.width as f32 { rect.x + rect.width } else { rect.x - rect.width };
+        let submenu = self.menu_panel(&labels, (x, rect.y), rect.width, screen_size);
+        self.panels.push(submenu);
+        if let Some(open) = self.open_dropdown.as_mut() {
+            open.submenu = Some((item, self.panels.len() - 1));
+        }
+    }
+
+    /// Overlay panel listing `labels` above every panel, with the top left at `corner` and `width` wide, both in
+    /// physical pixels.
+    fn menu_panel(&self, labels: &[String], corner: (f32, f32), width: f32, screen_size: PhysicalSize<u32>) -> Panel<E> {
+        let scale = self.scale_factor;
+        let height = dropdown::DROPDOWN_ITEM_HEIGHT * scale * labels.len() as f32;
+        // Kept on screen when opened near the right or bottom edge
+        let x = corner.0.min(screen_size.width as f32 - width).max(0.0);
+        let y = corner.1.min(screen_size.height as f32 - height).max(0.0);
+        let layer = self.overlay_layer();
+        // The menu's coordinates are scaled back up to physical pixels when it's laid out
+        let (x, y, width) = (x / scale, y / scale, width / scale);
+        let mut menu = Panel::new(Coordinate::px(x, y), Coordinate::px(x + width, y + dropdown::DROPDOWN_ITEM_HEIGHT * labels.len() as f32))
+            .with_layer(layer)
+            .with_layout(Layout::Column { spacing: 0.0, padding: 0.0 })
+            .with_border(1.0, dropdown::DROPDOWN_BORDER_COLOR);
+        for (item, label) in labels.iter().enumerate() {
+            let mut element = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(dropdown::DROPDOWN_ITEM_HEIGHT))
+                .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, label);
+            element.menu_item = Some(item);
+            menu.add_element(element);
+        }
+        menu.pixel_scale = scale;
+        menu
+    }
+
+    /// Runs the callback of the item at `index` in the `open` menu or its submenu, queuing its event, and closes the
+    /// menu. An item of the menu that has a submenu opens it instead.
+    fn pick_dropdown_item(&mut self, index: (usize, usize), open: OpenDropdown, screen_size: PhysicalSize<u32>, result: InteractionResult) -> InteractionResult {
+        let Some(item) = self.element_at(index).and_then(|element| element.menu_item) else {
+            self.close_dropdown();
+            return result;
+        };
+        let entries = self.element_at(open.source).map(|source| source.menu(open.context)).unwrap_or_default();
+        let entry = match open.submenu {
+            Some((parent, panel)) if panel == index.0 => entries.get(parent).and_then(|parent| parent.submenu.get(item)),
+            _ => entries.get(item),
+        };
+        if entry.is_some_and(|entry| !entry.submenu.is_empty()) {
+            self.open_submenu(item, screen_size);
+            return result;
+        }
+        let event = entry.and_then(|entry| (entry.callback)());
+        self.close_dropdown();
+        match event {
+            Some(event) => {
+                self.events.push_back(event);
+                InteractionResult { fired: true, element: Some(open.source), handle: self.handle(open.source), ..result }
             }
+            None => result,
         }
     }
 
-    pub fn init_gpu_buffers(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
-        screen_size: PhysicalSize<u32>,
-        config: &wgpu::SurfaceConfiguration
-    ) {
-        let indices: &[u16] = &[0, 2, 1, 1, 2, 3];
-
-        let font_bytes = include_bytes!("../../../ComicMono.ttf");
-        self.brush = Some(BrushBuilder::using_font_bytes(font_bytes)
-            .unwrap()
-            .build(device, config.width, config.height, config.format));
-
-        let total_vertices_needed =
-            (self.panels.iter().flat_map(|panel| &panel.elements).count() * 4) + (self.panels.iter().count() * 4);
-        let vertex_buffer_size =
-            (total_vertices_needed * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
-
-        self.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Vertex Buffer"),
-            size: vertex_buffer_size,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        }));
-
-        self.index_buffer = Some(
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(&indices),
-                usage: wgpu::BufferUsages::INDEX,
-            }),
-        );
-
-        self.update_vertices_and_queue_text(screen_size, queue, device);
-    }
-
-    pub fn update_vertices_and_queue_text(
-        &mut self,
-        screen_size: PhysicalSize<u32>,
-        queue: &Queue,
-        device: &Device,
-    ) {
-        let mut sections_to_queue: Vec<Section> = Vec::new();
-        let mut vertex_offset = 0; // Keep track of the current offset in bytes
-        self.brush.as_ref().unwrap().resize_view(screen_size.width as f32, screen_size.height as f32, queue);
+    /// Closes the open dropdown or context menu without an event, e.g. on Escape. Returns true if one was open.
+    /// Rebuilding the interface drops it too, the new interface starts with none.
+    pub fn close_dropdown(&mut self) -> bool {
+        let Some(open) = self.open_dropdown.take() else {
+            return false;
+        };
+        // Opened after the menu, so removing it first leaves the menu's index as it is
+        if let Some((_, submenu)) = open.submenu {
+            self.remove_overlay(submenu);
+        }
+        self.remove_overlay(open.panel);
+        true
+    }
 
-        for panel in &mut self.panels {
-            let (panel_x_min_co, panel_y_min_co, panel_x_max_co, panel_y_max_co) =
-                panel.calculate_absolute_coordinates(screen_size);
-
-            let mut panel_tex_coords: [[f32; 2]; 4] = [
-                [0.0, 0.0],
-                [0.0, 0.0],
-                [0.0, 0.0],
-                [0.0, 0.0],
-            ];
-
-            for entry in &self.atlas.entries {
-                if entry.name == panel.texture_name {
-                    panel_tex_coords = [
-                        [entry.start_coord.unwrap().0, entry.start_coord.unwrap().1],
-                        [entry.end_coord.unwrap().0, entry.start_coord.unwrap().1],
-                        [entry.end_coord.unwrap().0, entry.end_coord.unwrap().1],
-                        [entry.start_coord.unwrap().0, entry.end_coord.unwrap().1]
-                    ];
-                }
+    pub fn is_dropdown_open(&self) -> bool {
+        self.open_dropdown.is_some()
+    }
+
+    /// Topmost interactive element under the cursor that reacts to hover, see `Element::with_hover_color`.
+    fn hover_target(&self, x: f32, y: f32, hit_panels: &[usize], screen_size: PhysicalSize<u32>) -> Option<(usize, usize)> {
+        hit_panels.iter().find_map(|&panel_idx| {
+            let panel = &self.panels[panel_idx];
+            let rects = panel.element_pixel_rects(screen_size);
+            let panel_state = panel.own_state();
+            panel.element_draw_order().into_iter().rev()
+                .find(|&element_idx| {
+                    let element = &panel.elements[element_idx];
+                    rects[element_idx].contains(x, y) && element_state(&self.groups, panel_state, element).interactive()
+                        && element.reacts_to_hover()
+                })
+                .map(|element_idx| (panel_idx, element_idx))
+        })
+    }
+
+    /// Moves the hover to `target`, firing the leave and the entry when it changes.
+    fn set_hovered(&mut self, target: Option<(usize, usize)>, mut result: InteractionResult) -> InteractionResult {
+        if target == self.hovered {
+            return result;
+        }
+        if let Some(index) = self.hovered.take()
+            && self.set_element_hovered(index, false) {
+            result.hover_changed.push(index);
+            result = self.fire(index, &[InteractionStyle::OnHoverExit], result);
+        }
+        if let Some(index) = target
+            && self.set_element_hovered(index, true) {
+            self.hovered = Some(index);
+            result.hover_changed.push(index);
+            result = self.fire(index, &[InteractionStyle::OnHover], result);
+        }
+        result
+    }
+
+    /// Sets the element's hover flag, fading its fill from the color it shows now to its new styled color over
+    /// `hover_transition`. Returns false when there's no element at `index`.
+    fn set_element_hovered(&mut self, index: (usize, usize), hovered: bool) -> bool {
+        let Some((style, state)) = self.element_look(index) else {
+            return false;
+        };
+        let Some(element) = self.element_at_mut(index) else {
+            return false;
+        };
+        let from = element.fill_color(&style, state);
+        element.hovered = hovered;
+        let to = element.styled_fill_color(&style, state);
+        if !self.hover_transition.is_zero()
+            && from.into_vec4() != to.into_vec4()
+            && let Some(handle) = self.handle(index) {
+            self.start_animation(AnimTarget::Element(handle), AnimProperty::Color(from), AnimProperty::Color(to), self.hover_transition, Easing::EaseOutCubic, true);
+        }
+        true
+    }
+
+    /// How long hover highlights fade between colors, `animation::HOVER_TRANSITION` by default.
+    /// `Duration::ZERO` switches them instantly.
+    pub fn set_hover_transition(&mut self, duration: Duration) {
+        self.hover_transition = duration;
+    }
+
+    /// Resolved style and state of the element at `index`, as drawing sees them.
+    fn element_look(&self, (panel_idx, element_idx): (usize, usize)) -> Option<(Style, GroupState)> {
+        let panel = self.panels.get(panel_idx)?;
+        let element = panel.elements.get(element_idx)?;
+        Some((element.style.or(&panel.style.or(&self.style)).themed(&self.theme), element_state(&self.groups, panel.own_state(), element)))
+    }
+
+    /// Moves `property` of the element `target` refers to from its current value to the one it holds, over `duration`.
+    /// An animation already running on the same property of the element is replaced, starting from wherever it got to.
+    /// A zero `duration` sets the value at once. Values stay once the animation finishes and only change how the
+    /// element is drawn, input still goes by its laid out rect. Animations step in `tick` and don't survive a rebuild.
+    /// Returns false when the element isn't in this interface.
+    pub fn animate(&mut self, target: &ElementHandle, property: AnimProperty, duration: Duration, easing: Easing) -> bool {
+        let Some(index) = self.resolve(target) else {
+            return false;
+        };
+        let Some(((style, state), element)) = self.element_look(index).zip(self.element_at(index)) else {
+            return false;
+        };
+        let from = element.animated.current(&property, element.fill_color(&style, state));
+        self.start_animation(AnimTarget::Element(target.clone()), from, property, duration, easing, false);
+        true
+    }
+
+    /// Like `animate`, for the panel at `panel_idx`. Its offset, scale and opacity carry its elements along,
+    /// a color only changes its own fill. Returns false when there's no such panel.
+    pub fn animate_panel(&mut self, panel_idx: usize, property: AnimProperty, duration: Duration, easing: Easing) -> bool {
+        let Some(panel) = self.panels.get(panel_idx) else {
+            return false;
+        };
+        let from = panel.animated.current(&property, panel.fill_color(&self.theme));
+        self.start_animation(AnimTarget::Panel(panel_idx), from, property, duration, easing, false);
+        true
+    }
+
+    /// Whether any animation is running, in which case `next_wakeup` asks for the next step a frame away.
+    pub fn is_animating(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    fn start_animation(&mut self, target: AnimTarget, from: AnimProperty, to: AnimProperty, duration: Duration, easing: Easing, settle: bool) {
+        self.animations.retain(|animation| !animation.replaced_by(&target, &to));
+        if duration.is_zero() {
+            self.write_animated(&target, to, settle);
+            return;
+        }
+        let now = Instant::now();
+        self.write_animated(&target, from.clone(), false);
+        self.animations.push(Animation { target, from, to, start: now, duration, easing, settle });
+        self.animation_frame.get_or_insert(now + animation::ANIMATION_FRAME);
+    }
+
+    fn animated_mut(&mut self, target: &AnimTarget) -> Option<&mut Animated> {
+        match target {
+            AnimTarget::Element(handle) => {
+                let index = self.resolve(handle)?;
+                self.element_at_mut(index).map(|element| &mut element.animated)
             }
+            AnimTarget::Panel(panel_idx) => self.panels.get_mut(*panel_idx).map(|panel| &mut panel.animated),
+        }
+    }
 
-            if panel.renderable == true {
-                let panel_vertices = [
-                    Vertex {
-                        position: [panel_x_min_co, panel_y_max_co],
-                        color: panel.color.into_vec4(),
-                        tex_coords: panel_tex_coords[0]
-                    }, // Top-Left
-                    Vertex {
-                        position: [panel_x_max_co, panel_y_max_co],
-                        color: panel.color.into_vec4(),
-                        tex_coords: panel_tex_coords[1]
-                    }, // Top-Right
-                    Vertex {
-                        position: [panel_x_min_co, panel_y_min_co],
-                        color: panel.color.into_vec4(),
-                        tex_coords: panel_tex_coords[3]
-                    }, // Bottom-Left
-                    Vertex {
-                        position: [panel_x_max_co, panel_y_min_co],
-                        color: panel.color.into_vec4(),
-                        tex_coords: panel_tex_coords[2]
-                    }, // Bottom-Right
-                ];
-
-                let vertex_data_slice = bytemuck::cast_slice(&panel_vertices);
-                let vertex_data_size = vertex_data_slice.len() as wgpu::BufferAddress;
-
-                queue.write_buffer(
-                    self.vertex_buffer.as_ref().unwrap(),
-                    vertex_offset,
-                    vertex_data_slice,
-                );
-
-                vertex_offset += vertex_data_size;
+    /// Writes an animated value, or with `settle` drops a color override. Returns false when the target is gone.
+    fn write_animated(&mut self, target: &AnimTarget, value: AnimProperty, settle: bool) -> bool {
+        let Some(animated) = self.animated_mut(target) else {
+            return false;
+        };
+        match value {
+            AnimProperty::Color(_) if settle => animated.color = None,
+            value => animated.set(value),
+        }
+        true
+    }
+
+    /// Writes every animation's value at `now`, dropping the finished ones and those whose target is gone.
+    fn step_animations(&mut self, now: Instant) {
+        let animations = std::mem::take(&mut self.animations);
+        for animation in animations {
+            let (value, finished) = animation.sample(now);
+            if self.write_animated(&animation.target, value, finished && animation.settle) && !finished {
+                self.animations.push(animation);
             }
+        }
+        self.animation_frame = (!self.animations.is_empty()).then(|| now + animation::ANIMATION_FRAME);
+    }
 
-            let mut tex_coords: [[f32; 2]; 4] = [
-                        [0.0, 0.0],
-                        [0.0, 0.0],
-                        [0.0, 0.0],
-                        [0.0, 0.0],
-                    ];
-
-            
-            for element in &mut panel.elements {
-                for entry in &self.atlas.entries {
-                    if entry.name == element.texture_name {
-                        tex_coords = [
-                         [entry.start_coord.unwrap().0, entry.start_coord.unwrap().1],
-                         [entry.end_coord.unwrap().0, entry.start_coord.unwrap().1],
-                         [entry.end_coord.unwrap().0, entry.end_coord.unwrap().1],
-                         [entry.start_coord.unwrap().0, entry.end_coord.unwrap().1]
-                        ];
-                    }
-                }
+    /// How long the cursor has to rest on an element before its tooltip shows, `tooltip::TOOLTIP_DELAY` by default.
+    pub fn set_tooltip_delay(&mut self, delay: Duration) {
+        self.tooltip_delay = delay;
+    }
+
+    /// Starts or restarts the tooltip countdown for a cursor move to `position` at `now`, call it after the move's
+    /// `OnHover`. The hovered element's tooltip shows from `tick` once the cursor has stayed within
+    /// `tooltip::TOOLTIP_MOVE_TOLERANCE` of where it came to rest for the tooltip delay; moving further hides it.
+    /// Returns true when a tooltip was hidden, meaning the vertices need a full update.
+    pub fn track_tooltip(&mut self, position: PhysicalPosition<f64>, now: Instant) -> bool {
+        let (x, y) = (position.x as f32, position.y as f32);
+        let target = self.hovered.filter(|&index| self.element_at(index).is_some_and(|element| element.tooltip.is_some()));
+        let tolerance = tooltip::TOOLTIP_MOVE_TOLERANCE * self.scale_factor;
+        if let Some(pending) = &self.tooltip
+            && Some(pending.source) == target
+            && (pending.anchor.0 - x).hypot(pending.anchor.1 - y) <= tolerance {
+            return false;
+        }
+        let hidden = self.hide_tooltip();
+        if let Some(source) = target {
+            let due = now + self.tooltip_delay;
+            let wakeup = self.schedule_wakeup(due);
+            self.tooltip = Some(PendingTooltip { source, anchor: (x, y), due, wakeup, panel: None });
+        }
+        hidden
+    }
+
+    /// Drops the tooltip and its countdown, e.g. when the cursor leaves the window. Returns true if one was showing.
+    /// Rebuilding the interface drops it too, the new interface starts with none.
+    pub fn hide_tooltip(&mut self) -> bool {
+        let Some(pending) = self.tooltip.take() else {
+            return false;
+        };
+        self.cancel_wakeup(pending.wakeup);
+        match pending.panel {
+            Some(panel_idx) => {
+                self.remove_overlay(panel_idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_tooltip_visible(&self) -> bool {
+        self.tooltip.is_some_and(|pending| pending.panel.is_some())
+    }
+
+    /// Adds the overlay panel showing the pending tooltip near where the cursor rests, above every panel
+    /// and kept inside the window. It's sized to the measured text, so nothing shows before fonts are set.
+    fn show_tooltip(&mut self) {
+        let Some(pending) = self.tooltip else {
+            return;
+        };
+        let Some(label) = self.element_at(pending.source).and_then(|element| element.tooltip.clone()) else {
+            return;
+        };
+        let Some(measure) = self.measure.as_mut() else {
+            return;
+        };
+        let scale = self.scale_factor;
+        let style = self.style.clone().themed(&self.theme).with_text_scale_factor(self.text_scale_factor).with_scale_factor(scale);
+        let (text_width, text_height) = text::text_size(measure, &label, self.fonts.resolve(None), style.text_scale());
+        let padding = tooltip::TOOLTIP_PADDING * scale;
+        let (width, height) = (text_width + padding * 2.0, text_height + padding * 2.0);
+
+        let screen = (self.screen_size.width as f32, self.screen_size.height as f32);
+        let (offset_x, offset_y) = (tooltip::TOOLTIP_OFFSET.0 * scale, tooltip::TOOLTIP_OFFSET.1 * scale);
+        let x = (pending.anchor.0 + offset_x).min(screen.0 - width).max(0.0);
+        // Flipped above the cursor rather than covering it when there's no room below
+        let below = pending.anchor.1 + offset_y;
+        let y = if below + height > screen.1 { pending.anchor.1 - height - padding } else { below }.min(screen.1 - height).max(0.0);
+        let layer = self.overlay_layer();
+        // Laid out in physical pixels, the panel's coordinates are scaled back up
+        let (x, y, width, height) = (x / scale, y / scale, width / scale, height / scale);
+        let mut panel = Panel::new(Coordinate::px(x, y), Coordinate::px(x + width, y + height))
+            .with_layer(layer)
+            .with_color(tooltip::TOOLTIP_COLOR)
+            .with_border(1.0, tooltip::TOOLTIP_BORDER_COLOR)
+            .with_click_through();
+        panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+            .with_color("#00000000")
+            .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center }, &label));
+        panel.pixel_scale = scale;
+        self.panels.push(panel);
+        self.tooltip = Some(PendingTooltip { panel: Some(self.panels.len() - 1), ..pending });
+    }
+
+    /// Shows `message` in a small panel stacked in the bottom-right corner, colored by `level`, until `duration`
+    /// has passed or it's clicked. Messages too long for it are cut short with an ellipsis. Pushing the message of
+    /// a toast still showing restarts that one's time instead of stacking another. Only the newest
+    /// `toast::TOAST_MAX_VISIBLE` show, the rest collapse into a "+N more" entry that dismisses them when clicked.
+    ///
+    /// Toasts expire from `tick` and stay over rebuilds with `inherit_toasts`. Like any panel change,
+    /// they show from the next upload.
+    pub fn push_toast(&mut self, level: ToastLevel, message: String, duration: Duration) {
+        let expires = Instant::now() + duration;
+        if let Some(position) = self.toasts.iter().position(|toast| toast.level == level && toast.message == message) {
+            let previous = self.toasts.remove(position);
+            self.cancel_wakeup(previous.wakeup);
+        }
+        let wakeup = self.schedule_wakeup(expires);
+        self.toasts.push(Toast { level, message, expires, wakeup });
+        self.layout_toasts();
+    }
+
+    /// Number of toasts waiting to expire, those collapsed into "+N more" included.
+    pub fn toast_count(&self) -> usize {
+        self.toasts.len()
+    }
+
+    /// Takes over the toasts of the interface this one replaces, with the time they had left.
+    pub fn inherit_toasts(&mut self, previous: &mut UiTree<E>) {
+        for toast in previous.toasts.drain(..) {
+            let wakeup = self.schedule_wakeup(toast.expires);
+            self.toasts.push(Toast { wakeup, ..toast });
+        }
+        self.layout_toasts();
+    }
+
+    /// Dismisses the toast shown by element `element_idx` of the toast panel, or every collapsed one for "+N more".
+    fn dismiss_toast(&mut self, element_idx: usize) {
+        let hidden = self.toasts.len().saturating_sub(toast::TOAST_MAX_VISIBLE);
+        let dismissed: Vec<Toast> = match (hidden, element_idx) {
+            (0, index) => self.toasts.drain(index..=index).collect(),
+            (_, 0) => self.toasts.drain(..hidden).collect(),
+            (_, index) => self.toasts.drain(hidden + index - 1..hidden + index).collect(),
+        };
+        for toast in dismissed {
+            self.cancel_wakeup(toast.wakeup);
+        }
+        self.layout_toasts();
+    }
+
+    /// Replaces the toast panel with one listing the current toasts, above every other panel.
+    fn layout_toasts(&mut self) {
+        if let Some(panel_idx) = self.toast_panel.take() {
+            self.remove_overlay(panel_idx);
+        }
+        if self.toasts.is_empty() {
+            return;
+        }
+        let hidden = self.toasts.len().saturating_sub(toast::TOAST_MAX_VISIBLE);
+        let rows = self.toasts.len() - hidden + usize::from(hidden > 0);
+        let height = rows as f32 * (toast::TOAST_HEIGHT + toast::TOAST_SPACING) - toast::TOAST_SPACING;
+        let layer = self.overlay_layer();
+        // Anchored to the corner, so it stays there as the window resizes
+        let mut panel = Panel::new(
+            Coordinate::mixed(Dimension::from_end(toast::TOAST_MARGIN + toast::TOAST_WIDTH), Dimension::from_end(toast::TOAST_MARGIN + height)),
+            Coordinate::mixed(Dimension::from_end(toast::TOAST_MARGIN), Dimension::from_end(toast::TOAST_MARGIN)),
+        )
+            .with_layer(layer)
+            .with_layout(Layout::Column { spacing: toast::TOAST_SPACING, padding: 0.0 })
+            // The gaps between toasts let clicks through
+            .with_click_through();
+        let row = |color: &str, text: &str| {
+            let mut element = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_fixed_height(Dimension::Pixels(toast::TOAST_HEIGHT))
+                .with_color(color)
+                .with_border(1.0, toast::TOAST_BORDER_COLOR)
+                .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, text);
+            element.text_wrap = Some(Wrap::TruncateWithEllipsis);
+            element
+        };
+        if hidden > 0 {
+            panel.add_element(row(toast::TOAST_MORE_COLOR, &format!("+{} more", hidden)));
+        }
+        for toast in &self.toasts[hidden..] {
+            panel.add_element(row(toast.level.color(), &toast.message));
+        }
+        panel.pixel_scale = self.scale_factor;
+        self.panels.push(panel);
+        self.toast_panel = Some(self.panels.len() - 1);
+    }
+
+    /// Shows `lines` in a small panel in the top-left corner, over everything else including the other overlays,
+    /// replacing what it showed before. Under them a bar graph plots `frame_times`, oldest first, unless there are
+    /// none. It's never hit-tested and stays over rebuilds with `inherit_debug_overlay`.
+    /// Like any panel change, it shows from the next upload.
+    pub fn show_debug_overlay(&mut self, lines: Vec<String>, frame_times: Vec<Duration>) {
+        self.hide_debug_overlay();
+        let graph_height = if frame_times.is_empty() { 0.0 } else { debug_overlay::DEBUG_OVERLAY_GRAPH_HEIGHT };
+        let height = debug_overlay::DEBUG_OVERLAY_LINE_HEIGHT * lines.len() as f32 + graph_height + debug_overlay::DEBUG_OVERLAY_PADDING * 2.0;
+        let (x, y) = (debug_overlay::DEBUG_OVERLAY_MARGIN, debug_overlay::DEBUG_OVERLAY_MARGIN);
+        let mut panel = Panel::new(Coordinate::px(x, y), Coordinate::px(x + debug_overlay::DEBUG_OVERLAY_WIDTH, y + height))
+            .with_layer(debug_overlay::DEBUG_OVERLAY_LAYER)
+            .with_color(debug_overlay::DEBUG_OVERLAY_COLOR)
+            .with_layout(Layout::Column { spacing: 0.0, padding: debug_overlay::DEBUG_OVERLAY_PADDING })
+            .with_click_through();
+        for line in &lines {
+            let mut element = Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_color("#00000000")
+                .with_fixed_height(Dimension::Pixels(debug_overlay::DEBUG_OVERLAY_LINE_HEIGHT))
+                .with_label(Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Left }, line);
+            element.text_wrap = Some(Wrap::Truncate);
+            panel.add_element(element);
+        }
+        if !frame_times.is_empty() {
+            let graph = debug_overlay::FrameTimeGraph::new(&frame_times, self.debug_graph_pipeline.clone());
+            panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+                .with_color("#00000000")
+                .with_fixed_height(Dimension::Pixels(graph_height))
+                .with_custom_draw(graph));
+        }
+        panel.pixel_scale = self.scale_factor;
+        self.panels.push(panel);
+        self.debug_overlay = Some((lines, frame_times, self.panels.len() - 1));
+    }
+
+    /// Takes the debug overlay down. Returns true if it was up.
+    pub fn hide_debug_overlay(&mut self) -> bool {
+        let Some((_, _, panel_idx)) = self.debug_overlay.take() else {
+            return false;
+        };
+        self.remove_overlay(panel_idx);
+        true
+    }
+
+    pub fn is_debug_overlay_visible(&self) -> bool {
+        self.debug_overlay.is_some()
+    }
 
-                let new_vertices = element.calculate_vertices_relative_to_panel(
-                    panel_x_min_co,
-                    panel_y_min_co,
-                    panel_x_max_co,
-                    panel_y_max_co,
-                    tex_coords
-                );
-                let vertex_data_slice = bytemuck::cast_slice(&new_vertices);
-                let vertex_data_size = vertex_data_slice.len() as wgpu::BufferAddress;
-
-
-
-                queue.write_buffer(
-                    self.vertex_buffer.as_ref().unwrap(),
-                    vertex_offset,
-                    vertex_data_slice,
-                );
-
-                vertex_offset += vertex_data_size; // Increment offset for the next element
-
-                if let (Some(text_content), Some(text_align)) = (
-                    &element.text,
-                    &element.text_alignment,
-                ) {
-                    let ((adjusted_x, adjusted_y), _scale) = Self::text_alignment(
-                        element.start_coordinate.x, 
-                        element.start_coordinate.y, 
-                        element.end_coordinate.x, 
-                        element.end_coordinate.y, 
-                        panel_x_min_co, 
-                        panel_y_min_co, 
-                        panel_x_max_co, 
-                        panel_y_max_co, 
-                        screen_size,
-                        text_align,
-                        text_content,
-                    );
-                    let text_content_str = text_content.0.as_str();
-
-                    let section = Section::builder()
-                        .with_screen_position([adjusted_x, adjusted_y])
-                        .with_text(vec![
-                            Text::new(text_content_str)
-                                .with_scale(PxScale {x: 30.0 * text_content.1, y: 30.0 * text_content.1})
-                                .with_color([1.0, 1.0, 1.0, 1.0]),
-                        ]);
-                    sections_to_queue.push(section);
+    /// Carries the debug overlay of the interface this one replaces over, see `inherit_toasts`.
+    pub fn inherit_debug_overlay(&mut self, previous: &mut UiTree<E>) {
+        self.debug_graph_pipeline = previous.debug_graph_pipeline.clone();
+        if let Some((lines, frame_times, _)) = previous.debug_overlay.take() {
+            self.show_debug_overlay(lines, frame_times);
+        }
+    }
+
+    /// Removes a panel the interface added on top of the built ones, moving every index it keeps past it down by one
+    /// and dropping those into it without events.
+    fn remove_overlay(&mut self, panel_idx: usize) {
+        self.panels.remove(panel_idx);
+        let shift = |index: Option<(usize, usize)>| index.and_then(|(panel, element)| match panel.cmp(&panel_idx) {
+            std::cmp::Ordering::Less => Some((panel, element)),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some((panel - 1, element)),
+        });
+        self.hovered = shift(self.hovered);
+        self.pressed_element = shift(self.pressed_element);
+        self.captured_element = shift(self.captured_element);
+        self.focused_element = shift(self.focused_element);
+        self.tab_focus = shift(self.tab_focus);
+        let shift_panel = |panel: usize| if panel > panel_idx { panel - 1 } else { panel };
+        if let Some(open) = self.open_dropdown.as_mut() {
+            open.panel = shift_panel(open.panel);
+            open.source.0 = shift_panel(open.source.0);
+            open.submenu = open.submenu.map(|(item, panel)| (item, shift_panel(panel)));
+        }
+        if let Some(pending) = self.tooltip.as_mut() {
+            pending.panel = pending.panel.map(shift_panel);
+            pending.source.0 = shift_panel(pending.source.0);
+        }
+        self.toast_panel = self.toast_panel.map(shift_panel);
+        if let Some((_, _, panel)) = self.debug_overlay.as_mut() {
+            *panel = shift_panel(*panel);
+        }
+        self.animations.retain_mut(|animation| match &mut animation.target {
+            AnimTarget::Panel(panel) => {
+                let kept = *panel != panel_idx;
+                *panel = shift_panel(*panel);
+                kept
+            }
+            AnimTarget::Element(handle) => match shift(Some(handle.index)) {
+                Some(index) => {
+                    handle.index = index;
+                    true
                 }
+                None => false,
+            },
+        });
+    }
+
+    /// Element the cursor was over at the last `OnHover`, when it reacts to hover.
+    pub fn hovered_element(&self) -> Option<(usize, usize)> {
+        self.hovered
+    }
+
+    /// Drops the hover, e.g. when the cursor leaves the window or the hovered element's panel is hidden.
+    /// Returns the leave like `handle_interaction` would.
+    pub fn clear_hover(&mut self) -> InteractionResult {
+        self.set_hovered(None, InteractionResult::default())
+    }
+
+    /// Runs the element's callbacks for each of `styles` and queues the first event they produced for `drain_events`.
+    /// Only the one element is looked up, so a callback never runs while the panels are being walked.
+    fn fire(&mut self, index: (usize, usize), styles: &[InteractionStyle], result: InteractionResult) -> InteractionResult {
+        let Some(element) = self.element_at(index) else {
+            return result;
+        };
+        let mut event = None;
+        for style in styles {
+            let fired = element.handle_click(style.clone());
+            event = event.or(fired);
+        }
+        match event {
+            Some(event) => {
+                self.events.push_back(event);
+                InteractionResult { fired: true, element: Some(index), handle: self.handle(index), ..result }
             }
+            None => result,
         }
-        if !sections_to_queue.is_empty() {
-            self.brush.as_mut().unwrap().queue(device, queue, sections_to_queue).unwrap();
+    }
+
+    /// Takes every event element callbacks produced since the last call, oldest first. Call it once input handling
+    /// is done with the tree, so handling an event is free to change it again.
+    pub fn drain_events(&mut self) -> Vec<E> {
+        self.events.drain(..).collect()
+    }
+
+    /// Element the held button was pressed on, set between `OnPress` and `OnRelease`.
+    pub fn pressed_element(&self) -> Option<(usize, usize)> {
+        self.pressed_element
+    }
+
+    /// Forgets the held press without firing anything, e.g. when the window loses focus mid-press.
+    pub fn cancel_press(&mut self) {
+        if let Some(index) = self.pressed_element.take()
+            && let Some(element) = self.element_at_mut(index) {
+            element.pressed = false;
         }
     }
 
-    fn text_alignment(ex_0: f32, ey_0: f32, ex_1: f32, ey_1: f32, px_0: f32, py_0: f32, px_1: f32, py_1: f32, screen_size: PhysicalSize<u32>, alignment: &Alignment, text: &(String, f32)) -> ((f32, f32), f32){
-        let screen_x_center = screen_size.width as f32 / 2.0;
-        let screen_y_center = screen_size.height as f32 / 2.0;
-        let scale = 1.0;
+    /// Panel and element index of the interactive element under `position`, in the same order `handle_interaction`
+    /// visits them: top panel layer first, then the highest element layer, then the last added.
+    /// Element coordinates are resolved within their panel exactly as they're laid out for drawing.
+    pub fn hit_test(&self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>) -> Option<(usize, usize)> {
+        let (x, y) = (position.x as f32, position.y as f32);
+        self.panels_under(x, y, screen_size).into_iter().find_map(|panel_idx| {
+            let panel = &self.panels[panel_idx];
+            let rects = panel.element_pixel_rects(screen_size);
+            panel.element_draw_order().into_iter().rev()
+                .find(|&element_idx| {
+                    let element = &panel.elements[element_idx];
+                    rects[element_idx].contains(x, y) && element_state(&self.groups, panel.own_state(), element).interactive()
+                })
+                .map(|element_idx| (panel_idx, element_idx))
+        })
+    }
+
+    /// Whether the topmost element under a window position shows a render target, which leaves input
+    /// unconsumed for the viewport it displays.
+    fn is_viewport_at(&self, x: f32, y: f32, screen_size: PhysicalSize<u32>) -> bool {
+        self.panels_under(x, y, screen_size).first().is_some_and(|&panel_idx| {
+            let panel = &self.panels[panel_idx];
+            let rects = panel.element_pixel_rects(screen_size);
+            panel.element_draw_order().into_iter().rev()
+                .find(|&element_idx| rects[element_idx].contains(x, y) && element_state(&self.groups, panel.own_state(), &panel.elements[element_idx]).visible)
+                .is_some_and(|element_idx| panel.elements[element_idx].render_target.is_some())
+        })
+    }
+
+    /// Where the element showing render target `name` was laid out by the last upload,
+    /// `None` when no visible element shows it.
+    pub fn render_target_rect(&self, name: &str) -> Option<PixelRect> {
+        self.render_target_rects.get(name).copied()
+    }
+
+    /// Panel indices in the order they're drawn, by layer and then insertion order.
+    pub(crate) fn draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.panels.len()).collect();
+        order.sort_by_key(|&index| self.panels[index].layer);
+        order
+    }
 
-        match (&alignment.horizontal, &alignment.vertical) {
-            (HorizontalAlignment::Left, VerticalAlignment::Top) => {
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_0 * (py_1 - py_0));
-                return ((x, y), scale);
+    /// Offers a scroll of `delta` lines to the panels under the cursor, topmost first, see `ScrollEvent` for
+    /// the units. The first that scrolls along the delta moves by `SCROLL_LINE` of its height per line.
+    /// `consumed` is set like `handle_interaction` sets it, or when a panel scrolled, so only scrolling over the
+    /// viewport or nothing that blocks input is left for the caller, e.g. to zoom the preview.
+    pub fn handle_scroll(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>, delta: Vec2) -> InteractionResult {
+        let mut result = InteractionResult::default();
+        let (x_position, y_position) = (position.x as f32, position.y as f32);
+        self.hide_tooltip();
+        if self.is_viewport_at(x_position, y_position, screen_size) {
+            return result;
+        }
+        let hit_panels = self.panels_under(x_position, y_position, screen_size);
+        result.consumed = hit_panels.iter().any(|&panel_idx| self.blocks_input(panel_idx, x_position, y_position, screen_size));
+
+        // Panels only scroll vertically, a sideways scroll over them does nothing
+        let scrollable = hit_panels.into_iter()
+            .find(|&panel_idx| matches!(self.panels[panel_idx].scroll, Some(ScrollDirection::Vertical)));
+        if let Some(panel_idx) = scrollable
+            && delta.y != 0.0 {
+            let panel = &mut self.panels[panel_idx];
+            result.consumed = true;
+            if panel.scroll_by(delta.y * SCROLL_LINE, screen_size) {
+                result.scrolled = Some((panel_idx, panel.scroll_offset));
+                // The hovered element has moved out from under the cursor, the next move finds the new one
+                result = self.set_hovered(None, result);
             }
-            (HorizontalAlignment::Left, VerticalAlignment::Center) => {
-                let half_y_length = ((py_1 - ey_0 * (py_1 - py_0)) - (py_1 - ey_1 * (py_1 - py_0))) / 2.0;
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_0 * (py_1 - py_0));
-                return ((x + (15.0 * text.1), y + half_y_length - (15.0 * text.1)), scale);
+        }
+        result
+    }
+
+    /// Visible panels under a window position in physical pixels, topmost first, down to and including the first one
+    /// that isn't `with_click_through`.
+    fn panels_under(&self, x: f32, y: f32, screen_size: PhysicalSize<u32>) -> Vec<usize> {
+        let mut hit = Vec::new();
+        for panel_idx in self.draw_order().into_iter().rev() {
+            let panel = &self.panels[panel_idx];
+            // The tooltip and debug overlay only show text, the cursor reaches whatever is beneath them
+            if self.is_display_only(panel_idx) || panel.input_passthrough {
+                continue;
             }
-            (HorizontalAlignment::Left, VerticalAlignment::Bottom) => {
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_1 * (py_1 - py_0));
-                return ((x, y - (30.0 * text.1)), scale);
+            if panel.visible && panel.contains(x, y, screen_size) {
+                hit.push(panel_idx);
+                if !panel.click_through {
+                    break;
+                }
             }
+        }
+        hit
+    }
+
+    /// Whether input at `(x, y)` stops at the panel rather than reaching editor tools beneath the interface: the panel
+    /// draws a fill there, or one of its visible elements covers the point.
+    fn blocks_input(&self, panel_idx: usize, x: f32, y: f32, screen_size: PhysicalSize<u32>) -> bool {
+        let panel = &self.panels[panel_idx];
+        if panel.renderable {
+            return true;
+        }
+        let rects = panel.element_pixel_rects(screen_size);
+        panel.elements.iter().zip(rects)
+            .any(|(element, rect)| rect.contains(x, y) && element_state(&self.groups, panel.own_state(), element).visible)
+    }
+
+    /// Whether the panel is the tooltip or the debug overlay, which are never hit-tested.
+    fn is_display_only(&self, panel_idx: usize) -> bool {
+        self.tooltip.is_some_and(|tooltip| tooltip.panel == Some(panel_idx))
+            || self.debug_overlay.as_ref().is_some_and(|(_, _, panel)| *panel == panel_idx)
+    }
 
+    /// Layer for a new overlay panel, above every panel but the debug overlay.
+    fn overlay_layer(&self) -> i32 {
+        self.panels.iter().enumerate()
+            .filter(|(panel_idx, _)| self.debug_overlay.as_ref().is_none_or(|(_, _, panel)| panel != panel_idx))
+            .map(|(_, panel)| panel.layer)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    fn element_at(&self, (panel_idx, element_idx): (usize, usize)) -> Option<&Element<E>> {
+        self.panels.get(panel_idx).and_then(|panel| panel.elements.get(element_idx))
+    }
 
+    fn element_at_mut(&mut self, (panel_idx, element_idx): (usize, usize)) -> Option<&mut Element<E>> {
+        self.panels.get_mut(panel_idx).and_then(|panel| panel.elements.get_mut(element_idx))
+    }
 
-            (HorizontalAlignment::Center, VerticalAlignment::Top) => {
-                let text_offset = (text.0.chars().count() as f32 * (15.0 * text.1)) / 2.0;
+    /// The element built `with_id(id)`.
+    pub fn element(&self, id: &str) -> Option<&Element<E>> {
+        self.element_index(id).and_then(|index| self.element_at(index))
+    }
 
-                let half_x_length = ((px_0 + ex_1 * (px_1 - px_0)) - (px_0 + ex_0 * (px_1 - px_0))) / 2.0;
+    pub fn element_mut(&mut self, id: &str) -> Option<&mut Element<E>> {
+        self.element_index(id).and_then(|index| self.element_at_mut(index))
+    }
 
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_0 * (py_1 - py_0));
-                return ((x + half_x_length - text_offset, y), scale);
-            }
-            (HorizontalAlignment::Center, VerticalAlignment::Center) => {
-                let text_offset = (text.0.chars().count() as f32 * (15.0 * text.1)) / 2.0;
+    /// A handle to the element at `index` that stays safe to use after the interface is rebuilt, see `ElementHandle`.
+    pub fn handle(&self, index: (usize, usize)) -> Option<ElementHandle> {
+        self.element_at(index).map(|element| ElementHandle { interface: self.instance, index, id: element.id.clone() })
+    }
 
-                let half_x_length = ((px_0 + ex_1 * (px_1 - px_0)) - (px_0 + ex_0 * (px_1 - px_0))) / 2.0;
-                let half_y_length = ((py_1 - ey_0 * (py_1 - py_0)) - (py_1 - ey_1 * (py_1 - py_0))) / 2.0;
+    /// Panel and element index `handle` refers to in this interface, `None` when the element isn't in it.
+    pub fn resolve(&self, handle: &ElementHandle) -> Option<(usize, usize)> {
+        if handle.interface == self.instance {
+            // `panels` is public, so check the element at the index is still the one the handle was made for
+            return self.element_at(handle.index)
+                .filter(|element| element.id() == handle.id())
+                .map(|_| handle.index);
+        }
+        handle.id().and_then(|id| self.element_index(id))
+    }
+
+    /// Index of the panel built `with_id(id)`.
+    pub fn panel_index(&self, id: &str) -> Option<usize> {
+        self.panels.iter().position(|panel| panel.id() == Some(id))
+    }
+
+    /// Panel and element index of the element built `with_id(id)`.
+    pub fn element_index(&self, id: &str) -> Option<(usize, usize)> {
+        self.panels.iter().enumerate().find_map(|(panel_idx, panel)| {
+            panel.elements.iter().position(|element| element.id() == Some(id)).map(|element_idx| (panel_idx, element_idx))
+        })
+    }
+
+    /// Moves keyboard focus to `element` (panel index, element index), or clears it with `None`.
+    /// Elements that aren't focusable clear it too. Returns true when focus changed.
+    ///
+    /// Focus anywhere but the element Tab is on, a press included, takes it off that element.
+    pub fn focus(&mut self, element: Option<(usize, usize)>) -> bool {
+        let element = element.filter(|&index| self.element_at(index).is_some_and(|element| element.kind.is_focusable()));
+        if self.tab_focus.is_some() && self.tab_focus != element {
+            self.set_focus_ring(None);
+        }
+        if element == self.focused_element {
+            return false;
+        }
+
+        if let Some(previous) = self.focused_element.take()
+            && let Some(previous) = self.element_at_mut(previous) {
+            previous.kind.handle_input(WidgetInput::Focus(false));
+        }
+        if let Some(index) = element
+            && let Some(next) = self.element_at_mut(index) {
+            next.kind.handle_input(WidgetInput::Focus(true));
+            self.focused_element = Some(index);
+        }
+        true
+    }
+
+    pub fn focused_element(&self) -> Option<(usize, usize)> {
+        self.focused_element
+    }
+
+    /// Moves keyboard focus to the next element Tab stops at, or the previous one with `backwards`, wrapping around.
+    /// Tab stops at interactive elements that take text, a click or open a dropdown, in the topmost layer that has
+    /// any, so it stays inside an open dialog or dropdown. Within the layer it follows panel and element order.
+    /// Text inputs also get text focus, see `focus`. Returns true when focus moved.
+    pub fn focus_next(&mut self, backwards: bool) -> bool {
+        let stops = self.tab_stops();
+        let Some(&first) = (if backwards { stops.last() } else { stops.first() }) else {
+            return false;
+        };
+        let next = match self.tab_focus.and_then(|current| stops.iter().position(|&stop| stop == current)) {
+            Some(position) if backwards => stops[(position + stops.len() - 1) % stops.len()],
+            Some(position) => stops[(position + 1) % stops.len()],
+            None => first,
+        };
+        if Some(next) == self.tab_focus {
+            return false;
+        }
+        // Text focus follows onto an input and off anything else
+        self.focus(Some(next));
+        self.set_focus_ring(Some(next));
+        true
+    }
+
+    /// Element Tab moved keyboard focus to, `None` once a press or `focus` moves it elsewhere.
+    pub fn tab_focused_element(&self) -> Option<(usize, usize)> {
+        self.tab_focus
+    }
+
+    /// Activates the element Tab is on as if it were clicked, e.g. on Enter: fires its `OnClick`, toggles a checkbox,
+    /// opens its dropdown or picks the dropdown item. The click's event is queued like `handle_interaction` queues it.
+    pub fn activate_tab_focus(&mut self) -> InteractionResult {
+        let Some(index) = self.tab_focus else {
+            return InteractionResult::default();
+        };
+        let result = InteractionResult { consumed: true, ..InteractionResult::default() };
+        if let Some(open) = self.open_dropdown
+            && open.lists_in(index.0) {
+            return self.pick_dropdown_item(index, open, self.screen_size, result);
+        }
+        if let Some(element) = self.element_at_mut(index)
+            && matches!(element.kind, ElementKind::Checkbox { .. }) {
+            element.kind.handle_input(WidgetInput::Click { x_fraction: 0.5 });
+        }
+        let result = self.fire(index, &[InteractionStyle::OnClick], result);
+        if self.element_at(index).is_some_and(|element| !element.dropdown.is_empty()) {
+            self.open_dropdown(index, self.screen_size);
+        }
+        result
+    }
 
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_0 * (py_1 - py_0));
-                return ((x + half_x_length - text_offset, y + half_y_length - (15.0 * text.1)), scale);
+    /// Every element `focus_next` can stop at, in the order it visits them.
+    fn tab_stops(&self) -> Vec<(usize, usize)> {
+        let mut stops = Vec::new();
+        for panel_idx in self.draw_order() {
+            let panel = &self.panels[panel_idx];
+            if self.is_display_only(panel_idx) {
+                continue;
             }
-            (HorizontalAlignment::Center, VerticalAlignment::Bottom) => {
-                let text_offset = (text.0.chars().count() as f32 * (15.0 * text.1)) / 2.0;
-                
-                let half_x_length = ((px_0 + ex_1 * (px_1 - px_0)) - (px_0 + ex_0 * (px_1 - px_0))) / 2.0;
-
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_1 * (py_1 - py_0));
-                return ((x + half_x_length - text_offset, y - 15.0), scale);
+            for (element_idx, element) in panel.elements.iter().enumerate() {
+                if element.is_tab_stop() && element_state(&self.groups, panel.own_state(), element).interactive() {
+                    stops.push((panel_idx, element_idx));
+                }
             }
+        }
+        let top = stops.iter().map(|&(panel_idx, _)| self.panels[panel_idx].layer).max();
+        stops.retain(|&(panel_idx, _)| Some(self.panels[panel_idx].layer) == top);
+        stops
+    }
 
+    /// Moves the focus color onto `element`, leaving `focused_element` alone.
+    fn set_focus_ring(&mut self, element: Option<(usize, usize)>) {
+        if let Some(previous) = self.tab_focus.take()
+            && let Some(previous) = self.element_at_mut(previous) {
+            previous.focus_ring = false;
+        }
+        if let Some(index) = element
+            && let Some(next) = self.element_at_mut(index) {
+            next.focus_ring = true;
+            self.tab_focus = Some(index);
+        }
+    }
+
+    pub fn focused_text(&self) -> Option<&str> {
+        self.focused_element
+            .and_then(|index| self.element_at(index))
+            .and_then(|element| element.text.as_deref())
+    }
 
-            
-            (HorizontalAlignment::Right, VerticalAlignment::Top) => {
-                let text_offset = text.0.chars().count() as f32 * (15.0 * text.1);
+    /// Appends typed text to the focused element, skipping control characters. Returns true when the text changed.
+    pub fn insert_text(&mut self, typed: &str) -> bool {
+        let Some(index) = self.focused_element else {
+            return false;
+        };
+        let Some(text) = self.element_at_mut(index).and_then(|element| element.text.as_mut()) else {
+            return false;
+        };
 
-                let half_x_length = (px_0 + ex_1 * (px_1 - px_0)) - (px_0 + ex_0 * (px_1 - px_0));
+        let length = text.len();
+        text.extend(typed.chars().filter(|c| !c.is_control()));
+        text.len() != length
+    }
 
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_0 * (py_1 - py_0));
-                return ((x + half_x_length - text_offset, y), scale);
-            }
-            (HorizontalAlignment::Right, VerticalAlignment::Center) => {
-                let text_offset = text.0.chars().count() as f32 * (15.0 * text.1);
+    /// Removes the last character of the focused element's text. Returns true when the text changed.
+    pub fn delete_back(&mut self) -> bool {
+        let Some(index) = self.focused_element else {
+            return false;
+        };
+        self.element_at_mut(index)
+            .and_then(|element| element.text.as_mut())
+            .is_some_and(|text| text.pop().is_some())
+    }
+
+    /// Empties the focused element's text, returning what it held, e.g. to cut it. `None` without a focused element
+    /// or when it has no text.
+    pub fn take_focused_text(&mut self) -> Option<String> {
+        let index = self.focused_element?;
+        self.element_at_mut(index)
+            .and_then(|element| element.text.as_mut())
+            .map(std::mem::take)
+    }
 
-                let half_x_length = (px_0 + ex_1 * (px_1 - px_0)) - (px_0 + ex_0 * (px_1 - px_0));
-                let half_y_length = ((py_1 - ey_0 * (py_1 - py_0)) - (py_1 - ey_1 * (py_1 - py_0))) / 2.0;
+    /// Modifier keys held, from `WindowEvent::ModifiersChanged`. Ctrl and Shift change what a click on a selectable
+    /// element does to the selection, see `Element::with_selected_color`.
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
 
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_0 * (py_1 - py_0));
-                return ((x + half_x_length - text_offset, y + half_y_length - 15.0), scale);
+    /// Applies a click on the element at `index` to its panel's selection, as the held modifiers say.
+    /// Clicks on elements that aren't selectable leave it alone.
+    fn click_select(&mut self, (panel_idx, element_idx): (usize, usize)) {
+        if !self.element_at((panel_idx, element_idx)).is_some_and(|element| element.selectable) {
+            return;
+        }
+        let (ctrl, shift) = (self.modifiers.control_key(), self.modifiers.shift_key());
+        let anchor = self.selection_anchors.get(&panel_idx)
+            .and_then(|handle| self.resolve(handle))
+            .filter(|&(anchor_panel, _)| anchor_panel == panel_idx);
+        let elements = &mut self.panels[panel_idx].elements;
+        match anchor {
+            Some((_, anchor_idx)) if shift => {
+                let range = anchor_idx.min(element_idx)..=anchor_idx.max(element_idx);
+                for (idx, element) in elements.iter_mut().enumerate().filter(|(_, element)| element.selectable) {
+                    element.selected = range.contains(&idx) || (ctrl && element.selected);
+                }
+                // The anchor stays put so further Shift+clicks resize the same run
+                return;
             }
-            (HorizontalAlignment::Right, VerticalAlignment::Bottom) => {
-                let text_offset = text.0.chars().count() as f32 * (15.0 * text.1);
+            _ if ctrl => {
+                let element = &mut elements[element_idx];
+                element.selected = !element.selected;
+            }
+            _ => {
+                for (idx, element) in elements.iter_mut().enumerate().filter(|(_, element)| element.selectable) {
+                    element.selected = idx == element_idx;
+                }
+            }
+        }
+        if let Some(handle) = self.handle((panel_idx, element_idx)) {
+            self.selection_anchors.insert(panel_idx, handle);
+        }
+    }
 
-                let half_x_length = (px_0 + ex_1 * (px_1 - px_0)) - (px_0 + ex_0 * (px_1 - px_0));
+    /// Handles of the selected elements, in panel and element order. Each panel keeps a selection of its own,
+    /// clicks on a selectable element only change the one in its panel.
+    pub fn selection(&self) -> Vec<ElementHandle> {
+        self.panels.iter().enumerate()
+            .flat_map(|(panel_idx, panel)| {
+                panel.elements.iter().enumerate()
+                    .filter(|(_, element)| element.selected)
+                    .map(move |(element_idx, _)| (panel_idx, element_idx))
+            })
+            .filter_map(|index| self.handle(index))
+            .collect()
+    }
 
-                let x = screen_x_center + (px_0 + ex_0 * (px_1 - px_0));
-                let y = screen_y_center - (py_1 - ey_1 * (py_1 - py_0));
-                return ((x + half_x_length - text_offset, y - 15.0), scale);
+    /// Selects or deselects the element at `index`, leaving the rest of its panel's selection as it is.
+    /// Returns true when that changed it, false too for elements that aren't selectable.
+    pub fn set_selected(&mut self, index: (usize, usize), selected: bool) -> bool {
+        match self.element_at_mut(index) {
+            Some(element) if element.selectable && element.selected != selected => {
+                element.selected = selected;
+                true
             }
+            _ => false,
         }
     }
 
-    pub(crate)  fn draw_text_brush<'a>( &'a self, renderpass: &mut wgpu::RenderPass<'a>) {
-        if let Some(brush) = self.brush.as_ref() {
-            brush.draw(renderpass);
-        } else {
-            eprintln!("Warning: Brush not initialized for drawing.");
+    /// Deselects everything in every panel. Returns true when anything was selected.
+    pub fn clear_selection(&mut self) -> bool {
+        self.selection_anchors.clear();
+        let mut changed = false;
+        for element in self.panels.iter_mut().flat_map(|panel| panel.elements.iter_mut()) {
+            changed |= std::mem::replace(&mut element.selected, false);
         }
+        changed
     }
 
-    pub(crate) fn render<'a>(&'a self, renderpass: &mut wgpu::RenderPass<'a>) {
-        let vertex_buffer = match &self.vertex_buffer {
-            Some(buffer) => buffer,
-            None => {
-                eprintln!("Warning: GUI vertex buffer not initialized. Skipping Render...");
-                return;
+    /// Asks the event loop to wake up at `at` even if no input arrives, e.g. for a caret blink or toast expiry.
+    pub fn schedule_wakeup(&mut self, at: Instant) -> WakeupId {
+        let id = WakeupId(self.next_wakeup_id);
+        self.next_wakeup_id += 1;
+        self.wakeups.push((id, at));
+        id
+    }
+
+    pub fn cancel_wakeup(&mut self, id: WakeupId) {
+        self.wakeups.retain(|(pending, _)| *pending != id);
+    }
+
+    /// Earliest pending wakeup, the next animation step and spinner turn included, `None` when the interface is idle.
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        self.wakeups.iter().map(|(_, at)| *at).chain(self.animation_frame).chain(self.spinner_frame).min()
+    }
+
+    /// Marks the interface as changed, so the next `RenderState::take_redraw_request` asks for a frame.
+    /// Uploads do this themselves.
+    pub fn request_redraw(&self) {
+        self.needs_redraw.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether anything changed since the last call, clearing the flag.
+    pub(crate) fn take_redraw_request(&self) -> bool {
+        self.needs_redraw.swap(false, Ordering::Relaxed)
+    }
+
+    /// Drops every wakeup that is due at `now`, shows a tooltip whose delay has passed and steps running animations.
+    /// Returns true if any were due or anything animated, meaning the interface should be redrawn.
+    /// Call it once per frame, from `about_to_wait`; with nothing animating it costs nothing.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let animated = !self.animations.is_empty();
+        if animated {
+            self.step_animations(now);
+        }
+        let pending = self.wakeups.len();
+        self.wakeups.retain(|(_, at)| *at > now);
+        if self.tooltip.is_some_and(|tooltip| tooltip.panel.is_none() && tooltip.due <= now) {
+            self.show_tooltip();
+        }
+        let toasts = self.toasts.len();
+        self.toasts.retain(|toast| toast.expires > now);
+        if self.toasts.len() != toasts {
+            self.layout_toasts();
+        }
+        self.wakeups.len() != pending || animated
+    }
+
+    /// Turns every spinner to where it should be at `now` once a frame has passed since the last turn.
+    /// Returns the visible ones that moved, for `RenderState::update_interface_element` to rewrite on their own;
+    /// nothing else needs uploading for them. Call it from `about_to_wait` alongside `tick`.
+    ///
+    /// Once no spinner is visible they stop waking the event loop, until hiding something is undone.
+    pub fn step_spinners(&mut self, now: Instant) -> Vec<(usize, usize)> {
+        if self.spinner_frame.is_none_or(|due| due > now) {
+            return Vec::new();
+        }
+        let angle = animation::spinner_angle(now);
+        let mut turned = Vec::new();
+        for (panel_idx, panel) in self.panels.iter_mut().enumerate() {
+            let panel_state = panel.own_state();
+            for (element_idx, element) in panel.elements.iter_mut().enumerate() {
+                if let ElementKind::Spinner { angle: current } = &mut element.kind {
+                    *current = Some(angle);
+                    if element_state(&self.groups, panel_state, element).visible {
+                        turned.push((panel_idx, element_idx));
+                    }
+                }
             }
+        }
+        self.spinner_frame = (!turned.is_empty()).then(|| now + animation::ANIMATION_FRAME);
+        turned
+    }
+
+    /// Shown again, a hidden spinner may need to turn, `step_spinners` works out whether one does.
+    fn wake_spinners(&mut self) {
+        if self.spinners_turn {
+            self.spinner_frame.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Whether spinners circle, on by default. Still ones draw a ring and never wake the event loop, for reduced
+    /// motion. Spinners already in the tree change on the next `RenderState::update_interface`.
+    pub fn set_spinners_turn(&mut self, turn: bool) {
+        self.spinners_turn = turn;
+        let mut spinners = false;
+        for panel in &mut self.panels {
+            spinners |= set_spinner_angles(panel, turn);
+        }
+        self.spinner_frame = (turn && spinners).then(Instant::now);
+    }
+
+    /// Routes every cursor move to `element` (panel index, element index) until `end_capture`,
+    /// even when the cursor leaves the element or the window. Capturing widgets begin this on press.
+    pub fn begin_capture(&mut self, element: (usize, usize)) {
+        self.captured_element = Some(element);
+    }
+
+    pub fn end_capture(&mut self) {
+        self.captured_element = None;
+        self.dragged_split = None;
+        self.dragged_window = None;
+    }
+
+    pub fn captured_element(&self) -> Option<(usize, usize)> {
+        self.captured_element
+    }
+
+    /// Delivers a cursor move to the captured element. Returns true when the element changed.
+    pub fn handle_captured_move(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>) -> bool {
+        let Some((panel_idx, element_idx)) = self.captured_element else {
+            return false;
         };
-        let index_buffer = match &self.index_buffer {
-            Some(buffer) => buffer,
-            None => {
-                eprintln!("Warning: GUI index buffer not initialized. Skipping Render...");
-                return;
-            }
+        if let Some((split_idx, grab_offset)) = self.dragged_split {
+            let boundary = self.split_axis_px(split_idx, position) - grab_offset;
+            return self.drag_split(split_idx, boundary, screen_size);
+        }
+        if self.dragged_window.is_some() {
+            return self.drag_window(position, screen_size);
+        }
+        let Some(panel) = self.panels.get_mut(panel_idx) else {
+            self.captured_element = None;
+            return false;
         };
-        renderpass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-    
-        let mut vertex_offset_in_buffer = 0;
-        let vertex_size_bytes = std::mem::size_of::<Vertex>() as wgpu::BufferAddress;
-        let quad_vertices_count = 4;
-        let quad_indices_count = 6;
-        let quad_buffer_size = quad_vertices_count * vertex_size_bytes;
-    
-        for panel in &self.panels {
-            if panel.renderable {
-                renderpass.set_vertex_buffer(
-                    0,
-                    vertex_buffer.slice(vertex_offset_in_buffer..(vertex_offset_in_buffer + quad_buffer_size)),
-                );
-                renderpass.draw_indexed(0..quad_indices_count, 0, 0..1);
-                vertex_offset_in_buffer += quad_buffer_size;
-            }
-    
-            for _element in &panel.elements {
-                renderpass.set_vertex_buffer(
-                    0,
-                    vertex_buffer.slice(vertex_offset_in_buffer..(vertex_offset_in_buffer + quad_buffer_size)),
-                );
-                renderpass.draw_indexed(0..quad_indices_count, 0, 0..1);
-                vertex_offset_in_buffer += quad_buffer_size;
+        let Some(rect) = panel.element_pixel_rects(screen_size).get(element_idx).copied() else {
+            self.captured_element = None;
+            return false;
+        };
+
+        let x_fraction = (position.x as f32 - rect.x) / rect.width;
+        let element = &mut panel.elements[element_idx];
+        element.kind.handle_input(WidgetInput::Drag { x_fraction })
+    }
+
+    /// Whether the cursor is over any visible panel, an open dropdown included and a tooltip not.
+    pub fn is_cursor_within_menu_panel_bounds(&self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>) -> bool {
+        self.panels.iter().enumerate()
+            .any(|(panel_idx, panel)| !self.is_display_only(panel_idx) && panel.visible && panel.contains(position.x as f32, position.y as f32, screen_size))
+    }
+
+    /// Groups the elements with these ids, replacing any group called `name`. New groups start enabled and visible.
+    pub fn define_group<'a>(&mut self, name: &str, members: impl IntoIterator<Item = &'a str>) {
+        self.groups.define(name, members.into_iter().map(str::to_string).collect());
+    }
+
+    /// Disabled elements are drawn in their style's disabled color and ignore hover, clicks and focus.
+    /// An element in several groups is disabled while any of them is. Returns true when the group's state changed,
+    /// after which `UiRenderer::upload_group` redraws just the members.
+    pub fn set_group_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let changed = self.groups.set_enabled(name, enabled);
+        if changed {
+            self.drop_focus_if_inactive();
+        }
+        changed
+    }
+
+    /// Hidden elements draw nothing and ignore input, but keep their place in the vertex buffer.
+    /// Their text has to come off the text brush too, so unlike `set_group_enabled` a change needs a full
+    /// upload. Returns true when the group's state changed.
+    pub fn set_group_visible(&mut self, name: &str, visible: bool) -> bool {
+        let changed = self.groups.set_visible(name, visible);
+        if changed {
+            self.drop_focus_if_inactive();
+            if visible {
+                self.wake_spinners();
             }
         }
+        changed
     }
-}
 
-pub struct Panel {
-    pub elements: Vec<Element>,
-    start_coordinate: Coordinate,
-    end_coordinate: Coordinate,
-    renderable: bool,
-    texture_name: String,
-    color: Color,
-}
+    /// Hides or shows a panel and everything in it without rebuilding the interface. Hidden panels keep their place
+    /// in the vertex buffer but aren't drawn and take no input, so panels below get it. Like `set_group_visible`,
+    /// a change needs a full upload. Returns true when the panel's visibility changed.
+    pub fn set_panel_visible(&mut self, panel_idx: usize, visible: bool) -> bool {
+        match self.panels.get_mut(panel_idx) {
+            Some(panel) if panel.visible != visible => panel.visible = visible,
+            _ => return false,
+        }
+        self.drop_focus_if_inactive();
+        if visible {
+            self.wake_spinners();
+        }
+        true
+    }
 
-impl Panel {
-    pub fn new(start_coordinate: Coordinate, end_coordinate: Coordinate) -> Self {
-        Self {
-            elements: Vec::new(),
-            start_coordinate,
-            end_coordinate,
-            renderable: false,
-            texture_name: "solid".to_string(),
-            color: Color::from_hex("#ffffffff"),
+    /// Disables or enables every element of a panel, as `set_group_enabled` does for a group.
+    /// The panel still blocks input to the panels below. Returns true when the panel's state changed.
+    pub fn set_panel_enabled(&mut self, panel_idx: usize, enabled: bool) -> bool {
+        match self.panels.get_mut(panel_idx) {
+            Some(panel) if panel.enabled != enabled => panel.enabled = enabled,
+            _ => return false,
         }
+        self.drop_focus_if_inactive();
+        true
     }
 
-    pub fn add_element(&mut self, element: Element) {
-        self.elements.push(element);
+    /// Hides or shows one element, see `set_group_visible`. Returns true when the element's visibility changed.
+    pub fn set_element_visible(&mut self, index: (usize, usize), visible: bool) -> bool {
+        match self.element_at_mut(index) {
+            Some(element) if element.visible != visible => element.visible = visible,
+            _ => return false,
+        }
+        self.drop_focus_if_inactive();
+        if visible {
+            self.wake_spinners();
+        }
+        true
     }
 
-    pub fn with_color(mut self, color: &str) -> Self {
-        self.renderable = true;
-        self.color = Color::from_hex(color);
-        self
+    /// Disables or enables one element, see `set_group_enabled`. Returns true when the element's state changed,
+    /// after which `UiRenderer::upload_element` redraws it.
+    pub fn set_element_enabled(&mut self, index: (usize, usize), enabled: bool) -> bool {
+        match self.element_at_mut(index) {
+            Some(element) if element.enabled != enabled => element.enabled = enabled,
+            _ => return false,
+        }
+        self.drop_focus_if_inactive();
+        true
     }
 
-    fn calculate_absolute_coordinates(
-        &self,
-        screen_size: PhysicalSize<u32>,
-    ) -> (f32, f32, f32, f32) {
-        let screen_width_full = screen_size.width as f32;
-        let screen_height_full = screen_size.height as f32;
+    /// Takes over the groups of the interface this one replaces, except those this one already defines.
+    /// Membership is by element id, so the groups and their state apply to the rebuilt elements.
+    pub fn inherit_groups(&mut self, previous: &mut UiTree<E>) {
+        self.groups.inherit(std::mem::take(&mut previous.groups));
+    }
 
-        let x_min_px = self.start_coordinate.x * screen_width_full;
-        let x_max_px = self.end_coordinate.x * screen_width_full;
-        let y_min_px = self.start_coordinate.y * screen_height_full;
-        let y_max_px = self.end_coordinate.y * screen_height_full;
+    /// Takes over where the dividers were dragged to in the interface this one replaces, for splits with the same id.
+    /// Splits this one doesn't have are dropped.
+    pub fn inherit_splits(&mut self, previous: &mut UiTree<E>) {
+        let mut changed = false;
+        for split in &mut self.splits {
+            if let Some(old) = previous.splits.iter().find(|old| old.id == split.id) {
+                changed |= split.ratio != old.ratio;
+                split.ratio = old.ratio;
+            }
+        }
+        // Outermost first, each layout passes the new edges down to the splits inside it
+        if changed {
+            for split_idx in (0..self.splits.len()).rev() {
+                self.layout_split(split_idx);
+            }
+        }
+    }
 
-        let half_screen_width = screen_width_full / 2.0;
-        let half_screen_height = screen_height_full / 2.0;
+    /// Takes over the last click of the interface this one replaces, so a click that rebuilds the interface can still
+    /// be the first half of a double-click. The clicked element needs an id to be found again.
+    pub fn inherit_clicks(&mut self, previous: &mut UiTree<E>) {
+        self.last_click = previous.last_click.take();
+    }
 
-        let x_min_ndc = x_min_px - half_screen_width;
-        let x_max_ndc = x_max_px - half_screen_width;
+    /// Focuses the element the interface this one replaces had focused, so typing into an input that rebuilds the
+    /// interface as it changes keeps going. The element needs an id to be found again.
+    /// Tab's place is carried over the same way, so Tab after activating an element with Enter carries on from it.
+    pub fn inherit_focus(&mut self, previous: &mut UiTree<E>) {
+        let focused = previous.focused_element.and_then(|index| previous.handle(index));
+        let tab_focused = previous.tab_focus.and_then(|index| previous.handle(index));
+        self.focus(focused.and_then(|handle| self.resolve(&handle)));
+        if let Some(index) = tab_focused.and_then(|handle| self.resolve(&handle)) {
+            self.focus(Some(index));
+            self.set_focus_ring(Some(index));
+        }
+    }
 
-        let y_max_ndc = half_screen_height - y_min_px;
-        let y_min_ndc = half_screen_height - y_max_px;
+    /// Takes over where Shift+click ranges start from in the interface this one replaces, so a click that rebuilds the
+    /// interface can still be extended. The anchors need an id to be found again. What's selected isn't carried over,
+    /// the rebuilt elements are selected as they were built, see `Element::with_selected`.
+    pub fn inherit_selection(&mut self, previous: &mut UiTree<E>) {
+        self.selection_anchors = std::mem::take(&mut previous.selection_anchors).into_values()
+            .filter_map(|handle| self.resolve(&handle))
+            .filter_map(|index| self.handle(index).map(|handle| (index.0, handle)))
+            .collect();
+    }
 
-        (x_min_ndc, y_min_ndc, x_max_ndc, y_max_ndc)
+    /// Adds `window` above every other panel and floating window, its frame and content as two panels on a layer of
+    /// their own from `floating::FLOATING_LAYER` up. Dragging its title bar or edges moves them, after which the caller
+    /// uploads the tree again as for any `handle_captured_move`. Panics like `add_panel` on an id already in use.
+    pub fn add_floating_window(&mut self, window: FloatingWindow<E>) {
+        let layer = floating::FLOATING_LAYER + self.floating.len() as i32;
+        let (frame, content, floating) = window.build(layer);
+        self.add_panel(frame);
+        self.add_panel(content);
+        self.floating.push(floating);
+        self.layout_floating(self.floating.len() - 1);
     }
-}
 
-pub struct Element {
-    start_coordinate: Coordinate,
-    end_coordinate: Coordinate,
-    pub color: Color,
-    pub original_color: Color,
-    text: Option<(String, f32)>,
-    text_alignment: Option<Alignment>,
-    on_click: Option<Box<dyn Fn() -> Option<GuiEvent> + 'static>>,
-    on_hover: Option<Box<dyn Fn() -> Option<GuiEvent> + 'static>>,
-    texture_name: String
-}
+    /// Panel indices of the floating window `id`'s frame and content, e.g. to animate them together.
+    pub fn floating_window_panels(&self, id: &str) -> Option<(usize, usize)> {
+        self.panel_index(&Floating::frame_id(id)).zip(self.panel_index(id))
+    }
 
-impl Element {
-    pub fn new(start_coordinate: Coordinate, end_coordinate: Coordinate, texture_name: &str) -> Self {
-        Self {
-            start_coordinate,
-            end_coordinate,
-            color: Color::from_hex("#ffffffff"),
-            original_color: Color::from_hex("#ffffffff"),
-            text: None,
-            text_alignment: None,
-            on_click: None,
-            on_hover: None,
-            texture_name: texture_name.to_string(),
+    /// Shows or hides the floating window `id`. Returns true when that changed anything.
+    pub fn set_floating_visible(&mut self, id: &str, visible: bool) -> bool {
+        let Some((frame, content)) = self.floating_window_panels(id) else {
+            return false;
+        };
+        let changed = self.set_panel_visible(frame, visible);
+        self.set_panel_visible(content, visible) || changed
+    }
+
+    /// Moves the floating window `id` above the others, as clicking it does. Returns true when it wasn't already on top,
+    /// in which case the tree needs uploading again.
+    pub fn raise_floating_window(&mut self, id: &str) -> bool {
+        match self.floating.iter().position(|window| window.id == id) {
+            Some(window_idx) => self.raise_floating(window_idx),
+            None => false,
         }
     }
 
-    pub fn with_fn(mut self, func: impl Fn() -> Option<GuiEvent> + 'static, style: InteractionStyle) -> Self {
-        if style == InteractionStyle::OnClick {
-            self.on_click = Some(Box::new(func));
-        } else if style == InteractionStyle::OnHover {
-            self.on_hover = Some(Box::new(func));
+    /// Takes over where the interface this one replaces had its floating windows moved or resized to, and their order,
+    /// for windows with the same id.
+    pub fn inherit_floating_windows(&mut self, previous: &mut UiTree<E>) {
+        let rank = |id: &str| previous.floating.iter().position(|old| old.id == id).unwrap_or(usize::MAX);
+        // Stable, so windows the previous interface didn't have keep their order above the ones it did
+        self.floating.sort_by_key(|window| rank(&window.id));
+        for window in &mut self.floating {
+            if let Some(old) = previous.floating.iter().find(|old| old.id == window.id) {
+                window.start_coordinate = old.start_coordinate;
+                window.end_coordinate = old.end_coordinate;
+            }
+        }
+        self.restack_floating();
+        for window_idx in 0..self.floating.len() {
+            self.layout_floating(window_idx);
         }
-        self
     }
 
-    pub fn with_color(mut self, color: &str) -> Self {
-        let new_color = Color::from_hex(color);
-        self.color = new_color.clone();
-        self.original_color = new_color;
-        self
+    /// Index in `floating` of the window the panel at `panel_idx` is the frame or content of.
+    fn floating_window_of(&self, panel_idx: usize) -> Option<usize> {
+        let id = self.panels.get(panel_idx)?.id()?;
+        self.floating.iter().position(|window| window.id == id || Floating::frame_id(&window.id) == id)
     }
 
-    pub fn with_text(mut self, alignment: Alignment, text: &str, scale: f32) -> Self {
-        self.text = Some((text.to_string(), scale));
-        self.text_alignment = Some(alignment);
-        self
+    /// The grab zone the element at `index` is, if it's one of a floating window's.
+    fn floating_grip(&self, (panel_idx, element_idx): (usize, usize)) -> Option<floating::ResizeGrip> {
+        let window = &self.floating[self.floating_window_of(panel_idx)?];
+        let frame = self.panels[panel_idx].id().is_some_and(|id| id == Floating::frame_id(&window.id));
+        frame.then(|| window.grip(element_idx)).flatten()
     }
 
-    pub fn handle_click(&self, interaction_type: InteractionStyle) -> Option<GuiEvent> {
-        let function_src = if interaction_type == InteractionStyle::OnClick {
-            &self.on_click
-        } else {
-            &self.on_hover
+    /// Moves the window at `window_idx` to the top of the stack. Returns false when it already was.
+    fn raise_floating(&mut self, window_idx: usize) -> bool {
+        if window_idx + 1 >= self.floating.len() {
+            return false;
+        }
+        let window = self.floating.remove(window_idx);
+        self.floating.push(window);
+        self.restack_floating();
+        true
+    }
+
+    /// Puts each floating window's panels on the layer for its place in the stack.
+    fn restack_floating(&mut self) {
+        for window_idx in 0..self.floating.len() {
+            let layer = floating::FLOATING_LAYER + window_idx as i32;
+            if let Some((frame, content)) = self.floating_window_panels(&self.floating[window_idx].id.clone()) {
+                self.panels[frame].layer = layer;
+                self.panels[content].layer = layer;
+            }
+        }
+    }
+
+    /// Places the frame and content of the window at `window_idx` where the window is.
+    fn layout_floating(&mut self, window_idx: usize) {
+        let window = &self.floating[window_idx];
+        let (frame_coordinates, content_coordinates) = (window.frame_coordinates(), window.content_coordinates());
+        if let Some((frame, content)) = self.floating_window_panels(&window.id.clone()) {
+            (self.panels[frame].start_coordinate, self.panels[frame].end_coordinate) = frame_coordinates;
+            (self.panels[content].start_coordinate, self.panels[content].end_coordinate) = content_coordinates;
+        }
+    }
+
+    /// The drag a press on the element at `index` starts, when it's a floating window's title bar or grab zone.
+    fn window_drag(&self, (panel_idx, element_idx): (usize, usize), position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>) -> Option<WindowDrag> {
+        let window_idx = self.floating_window_of(panel_idx)?;
+        let window = &self.floating[window_idx];
+        let frame = &self.panels[panel_idx];
+        if frame.id() != Some(Floating::frame_id(&window.id).as_str()) {
+            return None;
+        }
+        let grip = window.drag_part(element_idx)?;
+        Some(WindowDrag {
+            window: window_idx,
+            grip,
+            grab: (position.x as f32, position.y as f32),
+            origin: frame.quad_rect(screen_size).to_pixel_rect(screen_size),
+            origin_coordinates: window.frame_coordinates(),
+        })
+    }
+
+    /// Moves or resizes the dragged floating window for the cursor at `position`. Returns true when it changed.
+    fn drag_window(&mut self, position: PhysicalPosition<f64>, screen_size: PhysicalSize<u32>) -> bool {
+        let Some(drag) = self.dragged_window.as_ref() else {
+            return false;
         };
-        if let Some(func) = function_src {
-            func()
-        } else {
-            None
-        }
-    }
-
-    pub fn with_temp_color(&mut self, color: &str) {
-        let new_color = Color::from_hex(color);
-        self.color = new_color;
-    }
-
-    fn calculate_vertices_relative_to_panel(
-        &mut self,
-        panel_x_min_center_origin: f32,
-        panel_y_min_center_origin: f32,
-        panel_x_max_center_origin: f32,
-        panel_y_max_center_origin: f32,
-        tex_coords: [[f32; 2]; 4]
-    ) -> [Vertex; 4] {
-
-        // Convert element's local coordinates to panel's absolute coordinates (center-origin)
-        let element_abs_x_min_center_origin = panel_x_min_center_origin
-            + self.start_coordinate.x * (panel_x_max_center_origin - panel_x_min_center_origin);
-        let element_abs_x_max_center_origin = panel_x_min_center_origin
-            + self.end_coordinate.x * (panel_x_max_center_origin - panel_x_min_center_origin);
-
-        // Y-axis is inverted here: y_max_center_origin is top, y_min_center_origin is bottom
-        // elem_local_y_min_rel corresponds to the top of the element relative to panel's top (0.0 to 1.0)
-        // elem_local_y_max_rel corresponds to the bottom of the element relative to panel's top (0.0 to 1.0)
-        let element_abs_y_top_center_origin = panel_y_max_center_origin
-            - self.start_coordinate.y * (panel_y_max_center_origin - panel_y_min_center_origin);
-        let element_abs_y_bottom_center_origin = panel_y_max_center_origin
-            - self.end_coordinate.y * (panel_y_max_center_origin - panel_y_min_center_origin);
-
-        let vtx_x_min = element_abs_x_min_center_origin;
-        let vtx_x_max = element_abs_x_max_center_origin;
-        let vtx_y_top = element_abs_y_top_center_origin; // The Y coordinate for the top edge of the element
-        let vtx_y_bottom = element_abs_y_bottom_center_origin; // The Y coordinate for the bottom edge of the element
-
-        [
-            Vertex {
-                position: [vtx_x_min, vtx_y_top],
-                color: self.color.into_vec4(),
-                tex_coords: tex_coords[0]
-            }, // Top-Left
-            Vertex {
-                position: [vtx_x_max, vtx_y_top],
-                color: self.color.into_vec4(),
-                tex_coords: tex_coords[1]
-            }, // Top-Right
-            Vertex {
-                position: [vtx_x_min, vtx_y_bottom],
-                color: self.color.into_vec4(),
-                tex_coords: tex_coords[3]
-            }, // Bottom-Left
-            Vertex {
-                position: [vtx_x_max, vtx_y_bottom],
-                color: self.color.into_vec4(),
-                tex_coords: tex_coords[2]
-            }, // Bottom-Right
-        ]
+        let window_idx = drag.window;
+        let Some(window) = self.floating.get(window_idx) else {
+            self.dragged_window = None;
+            return false;
+        };
+        let (start, end) = window.dragged(drag, (position.x as f32, position.y as f32), screen_size, self.scale_factor);
+        if (start, end) == window.frame_coordinates() {
+            return false;
+        }
+        let window = &mut self.floating[window_idx];
+        window.start_coordinate = start;
+        window.end_coordinate = end;
+        self.layout_floating(window_idx);
+        true
     }
-}
 
-pub struct Coordinate {
-    pub x: f32,
-    pub y: f32,
-}
+    /// Lays `first` and `second` out side by side (`Vertical`) or stacked (`Horizontal`) over the area from the start
+    /// of `first` to the end of `second`, with a draggable divider `ratio` of the way across. Each is a panel id or
+    /// the id returned by an earlier `add_split`, which is how splits nest: split the viewport from the inspector,
+    /// then the file tree from that split.
+    ///
+    /// Dragging the divider moves the panels' edges, after which the caller uploads the tree again
+    /// as for any `handle_captured_move`. Panics if either pane doesn't exist or the pair is already split.
+    pub fn add_split(&mut self, direction: SplitDirection, first: &str, second: &str, ratio: f32) -> String {
+        assert!(self.pane_exists(first), "no panel or split with id {:?} to split", first);
+        assert!(self.pane_exists(second), "no panel or split with id {:?} to split", second);
+        let id = split::split_id(first, second);
+        assert!(self.split_index(&id).is_none(), "{:?} and {:?} are already split", first, second);
+
+        let divider_id = format!("{}:divider", id);
+        // Over both panes, which it overlaps by half its thickness each
+        let mut divider = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.0))
+            .with_id(&divider_id)
+            .with_color(split::DIVIDER_COLOR)
+            .with_layer(self.pane_layer(first).max(self.pane_layer(second)));
+        divider.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid")
+            .with_color(split::DIVIDER_COLOR)
+            .with_hover_color(split::DIVIDER_HOVER_COLOR));
+        self.add_panel(divider);
+
+        self.splits.push(Split {
+            id: id.clone(),
+            direction,
+            first: first.to_string(),
+            second: second.to_string(),
+            ratio: ratio.clamp(0.0, 1.0),
+            divider: divider_id,
+        });
+        self.layout_split(self.splits.len() - 1);
+        id
+    }
 
-impl Coordinate {
-    pub fn new(x: f32, y: f32) -> Self {
-        Self { x, y }
+    /// Where the divider of split `id` sits between the outer edges of its panes, as passed to `add_split`
+    /// or dragged to since.
+    pub fn split_ratio(&self, id: &str) -> Option<f32> {
+        self.split_index(id).map(|split_idx| self.splits[split_idx].ratio)
     }
-}
 
-#[derive(Clone)]
-pub struct Color {
-    r: f32,
-    g: f32,
-    b: f32,
-    a: f32
-}
+    /// Id and ratio of every split, e.g. to save them for the next session.
+    pub fn split_ratios(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.splits.iter().map(|split| (split.id.as_str(), split.ratio))
+    }
 
-impl Color {
-    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
-        Self { r, g, b, a }
+    /// Moves the divider of split `id`, without the minimum pane sizes a drag keeps to. Returns false when there's
+    /// no such split. The caller uploads the tree again afterwards.
+    pub fn set_split_ratio(&mut self, id: &str, ratio: f32) -> bool {
+        let Some(split_idx) = self.split_index(id) else {
+            return false;
+        };
+        self.splits[split_idx].ratio = ratio.clamp(0.0, 1.0);
+        self.layout_split(split_idx);
+        true
     }
 
-    fn into_vec4(&self) -> [f32; 4] {
-        [self.r, self.g, self.b, self.a]
+    /// Cursor the application should show with `Window::set_cursor`: a resize arrow over or while dragging a divider
+    /// or a floating window's edge, otherwise the default.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        if let Some(grip) = self.dragged_window.as_ref().map_or_else(|| self.hovered.and_then(|index| self.floating_grip(index)), |drag| drag.grip) {
+            return grip.cursor_icon();
+        }
+        self.dragged_split.map(|(split_idx, _)| split_idx)
+            .or_else(|| self.hovered.and_then(|(panel_idx, _)| self.divider_split(panel_idx)))
+            .map_or(CursorIcon::Default, |split_idx| self.splits[split_idx].direction.cursor_icon())
+    }
+
+    fn split_index(&self, id: &str) -> Option<usize> {
+        self.splits.iter().position(|split| split.id == id)
+    }
+
+    /// Split whose divider is the panel at `panel_idx`.
+    fn divider_split(&self, panel_idx: usize) -> Option<usize> {
+        let id = self.panels.get(panel_idx)?.id()?;
+        self.splits.iter().position(|split| split.divider == id)
     }
 
-    pub fn from_hex(hex_color: &str) -> Self {
-        if let Some(hex) = hex_color.strip_prefix("#") {
-            let red = u32::from_str_radix(&hex[0..2], 16).unwrap() as f32 / 255.0;
-            let green = u32::from_str_radix(&hex[2..4], 16).unwrap() as f32 / 255.0;
-            let blue = u32::from_str_radix(&hex[4..6], 16).unwrap() as f32 / 255.0;
-            let alpha = u32::from_str_radix(&hex[6..8], 16).unwrap() as f32 / 255.0;
+    pub(crate) fn pane_exists(&self, id: &str) -> bool {
+        self.split_index(id).is_some() || self.panel_index(id).is_some()
+    }
 
-            let (corrected_r, corrected_g, corrected_b) = Self::srgb_correction(red, green, blue);
-            
-            Self {
-                r: corrected_r,
-                g: corrected_g,
-                b: corrected_b,
-                a: alpha
+    /// Highest layer of any panel in the pane.
+    fn pane_layer(&self, id: &str) -> i32 {
+        match self.split_index(id) {
+            Some(split_idx) => {
+                let split = &self.splits[split_idx];
+                self.pane_layer(&split.first).max(self.pane_layer(&split.second))
             }
-        } else {
-            log::error!("Provided parameter was not hex!");
-            panic!()
+            None => self.panel_index(id).map_or(0, |panel_idx| self.panels[panel_idx].layer),
         }
     }
 
-    fn srgb_correction(x: f32, y: f32, z: f32) -> (f32, f32, f32) {
-        let mut linear_color = (0.0, 0.0, 0.0);
+    /// Left and top edges of a split are its first pane's, right and bottom its second's.
+    fn pane_edge(&self, id: &str, edge: Edge) -> Dimension {
+        match self.split_index(id) {
+            Some(split_idx) => {
+                let split = &self.splits[split_idx];
+                self.pane_edge(if edge.is_start() { &split.first } else { &split.second }, edge)
+            }
+            None => self.panel_index(id).map_or(Dimension::Fraction(0.0), |panel_idx| self.panels[panel_idx].edge(edge)),
+        }
+    }
 
-        if x <= 0.04045 {
-            linear_color.0 = x / 12.92;
+    /// Moves one edge of a pane. For a split that is the outer edge of the pane on that side, or of both panes
+    /// and the divider across it, and the divider is laid out again at the same ratio.
+    fn set_pane_edge(&mut self, id: &str, edge: Edge, position: Dimension) {
+        let Some(split_idx) = self.split_index(id) else {
+            if let Some(panel_idx) = self.panel_index(id) {
+                self.panels[panel_idx].set_edge(edge, position);
+            }
+            return;
+        };
+        let split = &self.splits[split_idx];
+        let (first, second) = (split.first.clone(), split.second.clone());
+        if split.direction.cross_edges().contains(&edge) {
+            self.set_pane_edge(&first, edge, position);
+            self.set_pane_edge(&second, edge, position);
+        } else if edge.is_start() {
+            self.set_pane_edge(&first, edge, position);
         } else {
-            linear_color.0 = ((x + 0.055) / 1.055).powf(2.4);
+            self.set_pane_edge(&second, edge, position);
         }
+        self.layout_split(split_idx);
+    }
 
-        if y <= 0.04045 {
-            linear_color.1 = y / 12.92;
-        } else {
-            linear_color.1 = ((y + 0.055) / 1.055).powf(2.4);
+    /// Places the boundary between the panes at the split's ratio, and the divider over it.
+    fn layout_split(&mut self, split_idx: usize) {
+        let split = &self.splits[split_idx];
+        let (first, second, ratio, direction) = (split.first.clone(), split.second.clone(), split.ratio, split.direction);
+        let divider = self.panel_index(&split.divider);
+        let (first_end, second_start) = direction.inner_edges();
+
+        let boundary = split::lerp_dimension(self.pane_edge(&first, second_start), self.pane_edge(&second, first_end), ratio);
+        self.set_pane_edge(&first, first_end, boundary);
+        self.set_pane_edge(&second, second_start, boundary);
+
+        if let Some(panel_idx) = divider {
+            let cross = direction.cross_edges().map(|edge| (edge, self.pane_edge(&first, edge)));
+            let panel = &mut self.panels[panel_idx];
+            panel.set_edge(second_start, boundary.offset(-split::DIVIDER_THICKNESS / 2.0));
+            panel.set_edge(first_end, boundary.offset(split::DIVIDER_THICKNESS / 2.0));
+            for (edge, position) in cross {
+                panel.set_edge(edge, position);
+            }
+        }
+    }
+
+    /// Size along `direction` the pane can't be dragged below.
+    fn pane_min_size(&self, id: &str, direction: SplitDirection) -> f32 {
+        let Some(split_idx) = self.split_index(id) else {
+            return split::MIN_PANE_SIZE * self.scale_factor;
+        };
+        let split = &self.splits[split_idx];
+        let (first, second) = (self.pane_min_size(&split.first, direction), self.pane_min_size(&split.second, direction));
+        if split.direction == direction { first + second } else { first.max(second) }
+    }
+
+    /// Window position along the split's axis, x for side by side panes and y for stacked ones.
+    fn split_axis_px(&self, split_idx: usize, position: PhysicalPosition<f64>) -> f32 {
+        match self.splits[split_idx].direction {
+            SplitDirection::Vertical => position.x as f32,
+            SplitDirection::Horizontal => position.y as f32,
         }
+    }
+
+    /// Outer edges of the split's panes and the boundary between them, in pixels along its axis.
+    fn split_extent_px(&self, split_idx: usize, screen_size: PhysicalSize<u32>) -> (f32, f32) {
+        let split = &self.splits[split_idx];
+        let (first_end, second_start) = split.direction.inner_edges();
+        let extent = if first_end.is_x() { screen_size.width } else { screen_size.height } as f32;
+        (self.pane_edge(&split.first, second_start).resolve(extent, self.scale_factor), self.pane_edge(&split.second, first_end).resolve(extent, self.scale_factor))
+    }
 
-        if z <= 0.04045 {
-            linear_color.2 = z / 12.92;
+    fn split_boundary_px(&self, split_idx: usize, screen_size: PhysicalSize<u32>) -> f32 {
+        let (start, end) = self.split_extent_px(split_idx, screen_size);
+        start + (end - start) * self.splits[split_idx].ratio
+    }
+
+    /// Moves the split's boundary as close to `boundary` (pixels along its axis) as the minimum pane sizes allow.
+    /// Returns true when it moved.
+    fn drag_split(&mut self, split_idx: usize, boundary: f32, screen_size: PhysicalSize<u32>) -> bool {
+        let (start, end) = self.split_extent_px(split_idx, screen_size);
+        let span = end - start;
+        if span <= 0.0 {
+            return false;
+        }
+        let split = &self.splits[split_idx];
+        let first_min = self.pane_min_size(&split.first, split.direction);
+        let second_min = self.pane_min_size(&split.second, split.direction);
+        let boundary = if first_min + second_min <= span {
+            boundary.clamp(start + first_min, end - second_min)
         } else {
-            linear_color.2 = ((z + 0.055) / 1.055).powf(2.4);
+            // Too small for both minimums, share the shortfall in proportion to them
+            start + span * first_min / (first_min + second_min)
+        };
+        let ratio = (boundary - start) / span;
+        if ratio == split.ratio {
+            return false;
         }
+        self.splits[split_idx].ratio = ratio;
+        self.layout_split(split_idx);
+        true
+    }
 
-        linear_color
+    fn drop_focus_if_inactive(&mut self) {
+        if let Some(index) = self.focused_element
+            && !self.panels.get(index.0).zip(self.element_at(index))
+                .is_some_and(|(panel, element)| element_state(&self.groups, panel.own_state(), element).interactive()) {
+            self.focus(None);
+        }
+        if let Some(index) = self.tab_focus
+            && !self.panels.get(index.0).zip(self.element_at(index))
+                .is_some_and(|(panel, element)| element_state(&self.groups, panel.own_state(), element).interactive()) {
+            self.set_focus_ring(None);
+        }
     }
 }
 
-pub struct Alignment {
-    pub vertical: VerticalAlignment,
-    pub horizontal: HorizontalAlignment
+/// What an element's groups, its own flags and those of `panel_state`'s panel allow together.
+pub(crate) fn element_state<E>(groups: &Groups, panel_state: GroupState, element: &Element<E>) -> GroupState {
+    groups.state(element.id()).and(panel_state).and(element.own_state())
 }
 
-pub enum VerticalAlignment {
-    Top,
-    Center,
-    Bottom
+/// Stills `panel`'s spinners, or sets still ones turning again. Returns whether it has any.
+fn set_spinner_angles<E>(panel: &mut Panel<E>, turn: bool) -> bool {
+    let mut spinners = false;
+    for element in &mut panel.elements {
+        if let ElementKind::Spinner { angle } = &mut element.kind {
+            *angle = if turn { angle.or(Some(0.0)) } else { None };
+            spinners = true;
+        }
+    }
+    spinners
 }
 
-pub enum HorizontalAlignment {
-    Left,
-    Center,
-    Right
-}
\ No newline at end of file
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+    fn click(interface: &mut UiTree<&'static str>, x: f64, y: f64) -> InteractionResult {
+        interface.handle_interaction(PhysicalPosition::new(x, y), SCREEN, InteractionStyle::OnClick)
+    }
+
+    /// A full-screen panel whose only element, 200 by 60 pixels in the top left, opens `items`.
+    fn menu_tree(items: Vec<MenuItem<&'static str>>) -> UiTree<&'static str> {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0));
+        panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.25, 0.1), "solid").with_dropdown(items));
+        interface.add_panel(panel);
+        interface
+    }
+
+    /// Window y of the middle of `item` in a menu opened beneath the element of `menu_tree`.
+    fn item_y(item: usize) -> f64 {
+        60.0 + dropdown::DROPDOWN_ITEM_HEIGHT as f64 * (item as f64 + 0.5)
+    }
+
+    #[test]
+    fn submenu_opens_beside_its_item_and_picks_from_it() {
+        let mut interface = menu_tree(vec![
+            MenuItem::new("Save", "save"),
+            MenuItem::submenu("Revert", vec![MenuItem::new("First", "first"), MenuItem::new("Second", "second")]),
+        ]);
+        click(&mut interface, 10.0, 10.0);
+        assert!(interface.is_dropdown_open());
+
+        let opened = click(&mut interface, 10.0, item_y(1));
+        assert!(!opened.fired);
+        assert!(interface.is_dropdown_open());
+        assert_eq!(interface.panels.len(), 3);
+
+        // The submenu's items line up with the one that opened it, to the right of the 200 pixel wide menu
+        let picked = click(&mut interface, 210.0, item_y(2));
+        assert!(picked.fired);
+        assert_eq!(interface.drain_events(), vec!["second"]);
+        assert!(!interface.is_dropdown_open());
+        assert_eq!(interface.panels.len(), 1);
+    }
+
+    #[test]
+    fn text_items_close_the_menu_without_an_event() {
+        let mut interface = menu_tree(vec![MenuItem::submenu("Revert", vec![MenuItem::text("Nothing here")])]);
+        click(&mut interface, 10.0, 10.0);
+        click(&mut interface, 10.0, item_y(0));
+        let picked = click(&mut interface, 210.0, item_y(0));
+        assert!(!picked.fired);
+        assert!(interface.drain_events().is_empty());
+        assert!(!interface.is_dropdown_open());
+    }
+
+    #[test]
+    fn pressing_outside_closes_the_menu_and_its_submenu() {
+        let mut interface = menu_tree(vec![MenuItem::submenu("Revert", vec![MenuItem::new("First", "first")])]);
+        click(&mut interface, 10.0, 10.0);
+        click(&mut interface, 10.0, item_y(0));
+        assert_eq!(interface.panels.len(), 3);
+
+        let outside = click(&mut interface, 700.0, 500.0);
+        assert!(outside.consumed);
+        assert!(!interface.is_dropdown_open());
+        assert_eq!(interface.panels.len(), 1);
+    }
+
+    /// A panel over the middle quarter of the screen, 400 by 300 pixels from (200, 150), with an element in its top
+    /// left and bottom right quarters and a third over its middle, overlapping both.
+    fn centered_panel_tree() -> UiTree<&'static str> {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let mut panel = Panel::new(Coordinate::new(0.25, 0.25), Coordinate::new(0.75, 0.75));
+        panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.5, 0.5), "solid"));
+        panel.add_element(Element::new(Coordinate::new(0.5, 0.5), Coordinate::new(1.0, 1.0), "solid"));
+        panel.add_element(Element::new(Coordinate::new(0.25, 0.25), Coordinate::new(0.75, 0.75), "solid"));
+        interface.add_panel(panel);
+        interface
+    }
+
+    fn hit(interface: &UiTree<&'static str>, x: f64, y: f64) -> Option<(usize, usize)> {
+        interface.hit_test(PhysicalPosition::new(x, y), SCREEN)
+    }
+
+    #[test]
+    fn hit_test_resolves_positions_inside_the_panel() {
+        let interface = centered_panel_tree();
+        assert_eq!(hit(&interface, 250.0, 200.0), Some((0, 0)));
+        assert_eq!(hit(&interface, 550.0, 400.0), Some((0, 1)));
+        // Element coordinates are fractions of the panel, not the screen
+        assert_eq!(hit(&interface, 450.0, 200.0), None);
+        assert_eq!(hit(&interface, 250.0, 400.0), None);
+    }
+
+    #[test]
+    fn hit_test_picks_the_last_added_of_overlapping_elements() {
+        let interface = centered_panel_tree();
+        assert_eq!(hit(&interface, 400.0, 300.0), Some((0, 2)));
+        assert_eq!(hit(&interface, 320.0, 240.0), Some((0, 2)));
+        assert_eq!(hit(&interface, 480.0, 360.0), Some((0, 2)));
+    }
+
+    #[test]
+    fn hit_test_misses_outside_the_panel() {
+        let interface = centered_panel_tree();
+        assert_eq!(hit(&interface, 100.0, 100.0), None);
+        assert_eq!(hit(&interface, 700.0, 500.0), None);
+        assert_eq!(hit(&interface, 400.0, 100.0), None);
+    }
+
+    #[test]
+    fn hit_test_includes_element_edges() {
+        let interface = centered_panel_tree();
+        assert_eq!(hit(&interface, 200.0, 150.0), Some((0, 0)));
+        assert_eq!(hit(&interface, 400.0, 160.0), Some((0, 0)));
+        assert_eq!(hit(&interface, 600.0, 450.0), Some((0, 1)));
+        assert_eq!(hit(&interface, 199.0, 150.0), None);
+        assert_eq!(hit(&interface, 601.0, 450.0), None);
+        assert_eq!(hit(&interface, 600.0, 451.0), None);
+    }
+
+    /// The preview filling the screen under a toolbar across the top 60 pixels, which has a button in its left
+    /// 200 pixels and is filled with `toolbar_color`, if any.
+    fn editor_tree(toolbar_color: Option<&str>) -> UiTree<&'static str> {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let mut viewport = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0));
+        viewport.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid").with_render_target("preview"));
+        interface.add_panel(viewport);
+        let mut toolbar = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 0.1)).with_layer(1);
+        if let Some(color) = toolbar_color {
+            toolbar = toolbar.with_color(color);
+        }
+        toolbar.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.25, 1.0), "solid").with_fn(|| Some("button"), InteractionStyle::OnClick));
+        interface.add_panel(toolbar);
+        interface
+    }
+
+    /// Presses and releases the left button at (`x`, `y`), as the app hands a click over. Returns whether either
+    /// was consumed, in which case the click never reaches the preview's tools.
+    fn press_and_release(interface: &mut UiTree<&'static str>, x: f64, y: f64) -> bool {
+        let position = PhysicalPosition::new(x, y);
+        let pressed = interface.handle_interaction(position, SCREEN, InteractionStyle::OnPress);
+        let released = interface.handle_interaction(position, SCREEN, InteractionStyle::OnRelease);
+        pressed.consumed || released.consumed
+    }
+
+    #[test]
+    fn clicking_a_button_over_the_preview_never_reaches_it() {
+        let mut interface = editor_tree(None);
+        assert!(press_and_release(&mut interface, 100.0, 30.0));
+        assert_eq!(interface.drain_events(), vec!["button"]);
+    }
+
+    #[test]
+    fn clicking_the_preview_is_left_for_the_tools() {
+        let mut interface = editor_tree(None);
+        assert!(!press_and_release(&mut interface, 400.0, 300.0));
+        assert!(interface.drain_events().is_empty());
+    }
+
+    #[test]
+    fn only_a_filled_panel_takes_clicks_on_its_empty_space() {
+        let mut see_through = editor_tree(None);
+        assert!(!press_and_release(&mut see_through, 600.0, 30.0));
+        let mut filled = editor_tree(Some("#202020"));
+        assert!(press_and_release(&mut filled, 600.0, 30.0));
+        assert!(filled.drain_events().is_empty());
+    }
+
+    /// `editor_tree` with a filled panel over the middle of the preview, a button in its top left quarter.
+    fn overlay_tree(passthrough: bool) -> UiTree<&'static str> {
+        let mut interface = editor_tree(None);
+        let mut overlay = Panel::new(Coordinate::new(0.25, 0.25), Coordinate::new(0.75, 0.75)).with_color("#202020").with_layer(1);
+        if passthrough {
+            overlay = overlay.with_input_passthrough();
+        }
+        overlay.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.5, 0.5), "solid").with_fn(|| Some("overlay"), InteractionStyle::OnClick));
+        interface.add_panel(overlay);
+        interface
+    }
+
+    fn hover(interface: &mut UiTree<&'static str>, x: f64, y: f64) -> InteractionResult {
+        interface.handle_interaction(PhysicalPosition::new(x, y), SCREEN, InteractionStyle::OnHover)
+    }
+
+    #[test]
+    fn a_panel_over_the_preview_keeps_clicks_and_hover_from_it() {
+        let mut interface = overlay_tree(false);
+        // Its empty space, and its button
+        assert!(press_and_release(&mut interface, 500.0, 400.0));
+        assert!(hover(&mut interface, 500.0, 400.0).consumed);
+        assert!(press_and_release(&mut interface, 250.0, 200.0));
+        assert_eq!(interface.drain_events(), vec!["overlay"]);
+        // Around it the preview still gets both
+        assert!(!press_and_release(&mut interface, 100.0, 500.0));
+        assert!(!hover(&mut interface, 100.0, 500.0).consumed);
+    }
+
+    #[test]
+    fn a_passthrough_panel_over_the_preview_lets_clicks_and_hover_reach_it() {
+        let mut interface = overlay_tree(true);
+        assert!(!press_and_release(&mut interface, 500.0, 400.0));
+        assert!(!hover(&mut interface, 500.0, 400.0).consumed);
+        // Not even its button reacts
+        assert!(!press_and_release(&mut interface, 250.0, 200.0));
+        assert!(interface.drain_events().is_empty());
+        assert_eq!(hover(&mut interface, 250.0, 200.0).handle, None);
+    }
+
+    /// A vertically scrolling panel over the right quarter of the screen below the toolbar, with twenty rows
+    /// each a tenth of its height.
+    fn scroll_list(layer: i32) -> Panel<&'static str> {
+        let mut list = Panel::new(Coordinate::new(0.75, 0.1), Coordinate::new(1.0, 1.0)).with_scroll(ScrollDirection::Vertical).with_layer(layer);
+        for row in 0..20 {
+            let y = row as f32 * 0.1;
+            list.add_element(Element::new(Coordinate::new(0.0, y), Coordinate::new(1.0, y + 0.1), "solid"));
+        }
+        list
+    }
+
+    fn scroll(interface: &mut UiTree<&'static str>, x: f64, y: f64, delta: Vec2) -> InteractionResult {
+        interface.handle_scroll(PhysicalPosition::new(x, y), SCREEN, delta)
+    }
+
+    #[test]
+    fn scrolling_a_panel_is_consumed_and_scrolling_the_preview_is_not() {
+        let mut interface = editor_tree(None);
+        interface.add_panel(scroll_list(1));
+
+        assert!(scroll(&mut interface, 700.0, 300.0, Vec2::new(0.0, -1.0)).consumed);
+        assert!(!scroll(&mut interface, 300.0, 300.0, Vec2::new(0.0, -1.0)).consumed);
+    }
+
+    #[test]
+    fn stacked_lists_scroll_only_the_topmost() {
+        let mut interface = editor_tree(None);
+        interface.add_panel(scroll_list(2));
+        interface.add_panel(scroll_list(1));
+
+        let result = scroll(&mut interface, 700.0, 300.0, Vec2::new(0.0, 2.0));
+        assert!(result.consumed);
+        assert_eq!(result.scrolled, Some((2, 2.0 * SCROLL_LINE)));
+        assert_eq!(interface.panels[3].scroll_offset, 0.0);
+    }
+
+    #[test]
+    fn scrolling_passes_through_click_through_panels_to_a_list_below() {
+        let mut interface = editor_tree(None);
+        interface.add_panel(scroll_list(1));
+        interface.add_panel(Panel::new(Coordinate::new(0.5, 0.0), Coordinate::new(1.0, 1.0)).with_color("#202020").with_click_through().with_layer(2));
+
+        let result = scroll(&mut interface, 700.0, 300.0, Vec2::new(0.0, 1.0));
+        assert!(result.consumed);
+        assert_eq!(result.scrolled, Some((2, SCROLL_LINE)));
+    }
+
+    #[test]
+    fn sideways_scrolls_over_a_list_are_consumed_without_moving_it() {
+        let mut interface = editor_tree(None);
+        interface.add_panel(scroll_list(1));
+
+        let result = scroll(&mut interface, 700.0, 300.0, Vec2::new(1.0, 0.0));
+        assert!(result.consumed);
+        assert_eq!(result.scrolled, None);
+    }
+
+    #[test]
+    fn scrolling_over_only_passthrough_panels_falls_through() {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        interface.add_panel(scroll_list(0));
+        interface.add_panel(Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.5, 1.0)).with_color("#202020").with_input_passthrough());
+
+        assert!(!scroll(&mut interface, 100.0, 300.0, Vec2::new(0.0, 1.0)).consumed);
+        assert!(scroll(&mut interface, 700.0, 300.0, Vec2::new(0.0, 1.0)).consumed);
+    }
+
+    #[test]
+    fn debug_overlay_graphs_frame_times_under_its_lines() {
+        let mut interface: UiTree<&'static str> = UiTree::new(UiAtlas::new(1, 1));
+        interface.show_debug_overlay(vec!["60 fps".to_string()], vec![Duration::from_millis(16); 3]);
+        let overlay = interface.panels.last().unwrap();
+        assert_eq!(overlay.elements.len(), 2);
+        assert!(overlay.elements[0].custom_draw.is_none());
+        assert!(overlay.elements[1].custom_draw.is_some());
+
+        let mut rebuilt = UiTree::new(UiAtlas::new(1, 1));
+        rebuilt.inherit_debug_overlay(&mut interface);
+        assert!(std::sync::Arc::ptr_eq(&rebuilt.debug_graph_pipeline, &interface.debug_graph_pipeline));
+        assert_eq!(rebuilt.panels.last().unwrap().elements.len(), 2);
+
+        // Nothing to plot before two frames have been drawn
+        rebuilt.show_debug_overlay(vec!["0 fps".to_string()], Vec::new());
+        assert_eq!(rebuilt.panels.last().unwrap().elements.len(), 1);
+    }
+
+    /// The style drawing resolves for the only element of a panel, with each level of the cascade styled as given.
+    fn resolved_style(interface_style: Style, panel: Style, element: Style) -> Style {
+        let mut interface = UiTree::<()>::new(UiAtlas::new(1, 1));
+        interface.set_style(interface_style);
+        let mut styled = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)).with_style(panel);
+        styled.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid").with_style(element));
+        interface.add_panel(styled);
+        interface.element_look((0, 0)).unwrap().0
+    }
+
+    fn hex(color: Option<Color>) -> Option<String> {
+        color.map(|color| color.to_hex())
+    }
+
+    #[test]
+    fn fill_colors_resolve_element_over_panel_over_theme() {
+        let theme = Theme::default();
+        let by_role = Style::new().with_color_role(ColorRole::Header);
+        assert_eq!(hex(resolved_style(Style::new(), Style::new(), Style::new()).color), None);
+        assert_eq!(hex(resolved_style(Style::new(), by_role.clone(), Style::new()).color), Some(theme.color(ColorRole::Header).to_hex()));
+        assert_eq!(hex(resolved_style(Style::new(), by_role.clone(), Style::new().with_color("#445566")).color), Some("#445566ff".to_string()));
+        // An element taking its fill from the theme still beats a literal panel fill
+        let element_role = Style::new().with_color_role(ColorRole::Accent);
+        assert_eq!(hex(resolved_style(Style::new(), Style::new().with_color("#112233"), element_role).color), Some(theme.color(ColorRole::Accent).to_hex()));
+    }
+
+    #[test]
+    fn text_colors_resolve_element_over_panel_over_theme() {
+        let theme = Theme::default();
+        assert_eq!(resolved_style(Style::new(), Style::new(), Style::new()).text_color().to_hex(), theme.color(ColorRole::Text).to_hex());
+        let panel = Style::new().with_text_color("#112233");
+        assert_eq!(resolved_style(Style::new(), panel.clone(), Style::new()).text_color().to_hex(), "#112233ff");
+        assert_eq!(resolved_style(Style::new(), panel, Style::new().with_text_color("#445566")).text_color().to_hex(), "#445566ff");
+    }
+
+    #[test]
+    fn text_scales_resolve_element_over_panel_over_interface() {
+        assert_eq!(resolved_style(Style::new(), Style::new(), Style::new()).text_scale(), crate::gui::style::DEFAULT_TEXT_SCALE);
+        let interface = Style::new().with_text_scale(1.25);
+        assert_eq!(resolved_style(interface.clone(), Style::new(), Style::new()).text_scale(), 1.25);
+        let panel = Style::new().with_text_scale(1.5);
+        assert_eq!(resolved_style(interface.clone(), panel.clone(), Style::new()).text_scale(), 1.5);
+        assert_eq!(resolved_style(interface, panel, Style::new().with_text_scale(0.5)).text_scale(), 0.5);
+    }
+
+    fn spinner_tree(turn: bool) -> UiTree<()> {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        interface.set_spinners_turn(turn);
+        let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.1, 0.1));
+        panel.add_element(Element::spinner(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)));
+        interface.add_panel(panel);
+        interface
+    }
+
+    fn spinner_angle(interface: &UiTree<()>) -> Option<f32> {
+        match interface.panels[0].elements[0].kind {
+            ElementKind::Spinner { angle } => angle,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn spinners_turn_every_frame() {
+        let mut interface = spinner_tree(true);
+        let now = interface.next_wakeup().unwrap();
+        assert_eq!(interface.step_spinners(now), vec![(0, 0)]);
+        assert_eq!(interface.next_wakeup(), Some(now + animation::ANIMATION_FRAME));
+        assert!(spinner_angle(&interface).is_some());
+    }
+
+    #[test]
+    fn still_spinners_never_wake_the_event_loop() {
+        let mut interface = spinner_tree(false);
+        assert_eq!(interface.next_wakeup(), None);
+        assert!(interface.step_spinners(Instant::now() + Duration::from_secs(1)).is_empty());
+        assert_eq!(spinner_angle(&interface), None);
+
+        // Spinners already in the tree follow the setting both ways
+        interface.set_spinners_turn(true);
+        assert_eq!(spinner_angle(&interface), Some(0.0));
+        assert!(interface.next_wakeup().is_some());
+        interface.set_spinners_turn(false);
+        assert_eq!(spinner_angle(&interface), None);
+        assert_eq!(interface.next_wakeup(), None);
+    }
+
+    #[test]
+    fn hidden_spinners_stop_waking_the_event_loop_until_shown() {
+        let mut interface = spinner_tree(true);
+        interface.set_element_visible((0, 0), false);
+        let now = interface.next_wakeup().unwrap();
+        assert!(interface.step_spinners(now).is_empty());
+        assert_eq!(interface.next_wakeup(), None);
+
+        interface.set_element_visible((0, 0), true);
+        let now = interface.next_wakeup().unwrap();
+        assert_eq!(interface.step_spinners(now), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn appended_elements_leave_the_others_where_they_were() {
+        let mut interface = spinner_tree(true);
+        interface.panels[0].add_element(Element::text_input(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)).with_id("first"));
+        interface.focus(Some((0, 1)));
+
+        let appended = interface.append_elements(0, vec![
+            Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid").with_id("second"),
+            Element::spinner(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)),
+        ]);
+
+        assert_eq!(appended, 2..4);
+        assert_eq!(interface.element_index("second"), Some((0, 2)));
+        assert_eq!(interface.focused_element(), Some((0, 1)));
+        assert!(matches!(interface.panels[0].elements[3].kind, ElementKind::Spinner { angle: Some(_) }));
+    }
+
+    #[test]
+    #[should_panic(expected = "already used")]
+    fn appending_an_id_in_use_panics() {
+        let mut interface = spinner_tree(true);
+        interface.panels[0].add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid").with_id("row"));
+        interface.append_elements(0, vec![Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid").with_id("row")]);
+    }
+}
@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+
+use crate::definitions::{RenderMode, Vertex};
+
+use super::style::Border;
+
+/// Quads a `Border` adds to whatever it surrounds.
+pub(crate) const BORDER_QUADS: usize = 4;
+
+/// One axis of a `Coordinate`, resolved against the size of the screen or panel it's laid out in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Dimension {
+    /// Fraction of the parent, 0.0 at its left or top edge and 1.0 at its right or bottom edge.
+    Fraction(f32),
+    /// Pixels from the parent's left or top edge, at a scale factor of 1. They're multiplied by the interface's
+    /// scale factor, see `UiTree::set_scale_factor`, so pixel-sized UI stays the same size on HiDPI displays.
+    Pixels(f32),
+    /// `pixels` pixels on from the point `fraction` of the way across the parent, scaled like `Pixels`,
+    /// so `Anchored { fraction: 1.0, pixels: -24.0 }` stays 24 px in from the far edge at any size.
+    Anchored { fraction: f32, pixels: f32 },
+}
+
+impl Dimension {
+    /// `pixels` in from the parent's right or bottom edge.
+    pub fn from_end(pixels: f32) -> Self {
+        Dimension::Anchored { fraction: 1.0, pixels: -pixels }
+    }
+
+    /// Physical pixels from the parent's left or top edge, for a parent `extent` physical pixels across
+    /// and the interface's `scale` factor.
+    pub(crate) fn resolve(self, extent: f32, scale: f32) -> f32 {
+        match self {
+            Dimension::Fraction(fraction) => fraction * extent,
+            Dimension::Pixels(pixels) => pixels * scale,
+            Dimension::Anchored { fraction, pixels } => fraction * extent + pixels * scale,
+        }
+    }
+
+    /// The fraction and pixel parts, as they'd be written `Anchored`.
+    pub(crate) fn parts(self) -> (f32, f32) {
+        match self {
+            Dimension::Fraction(fraction) => (fraction, 0.0),
+            Dimension::Pixels(pixels) => (0.0, pixels),
+            Dimension::Anchored { fraction, pixels } => (fraction, pixels),
+        }
+    }
+
+    /// The same point moved `pixels` towards the far edge.
+    pub(crate) fn offset(self, pixels: f32) -> Self {
+        let (fraction, own_pixels) = self.parts();
+        Dimension::Anchored { fraction, pixels: own_pixels + pixels }
+    }
+}
+
+impl From<f32> for Dimension {
+    fn from(fraction: f32) -> Self {
+        Dimension::Fraction(fraction)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinate {
+    pub x: Dimension,
+    pub y: Dimension,
+}
+
+impl Coordinate {
+    /// Fractions of the parent on both axes.
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x: Dimension::Fraction(x), y: Dimension::Fraction(y) }
+    }
+
+    /// Pixels from the parent's top-left corner, see `Dimension::Pixels`.
+    pub fn px(x: f32, y: f32) -> Self {
+        Self { x: Dimension::Pixels(x), y: Dimension::Pixels(y) }
+    }
+
+    /// Any mix of units, plain `f32`s are fractions. A header strip 24 px tall across the top of its parent
+    /// runs from `Coordinate::new(0.0, 0.0)` to `Coordinate::mixed(1.0, Dimension::Pixels(24.0))`.
+    pub fn mixed(x: impl Into<Dimension>, y: impl Into<Dimension>) -> Self {
+        Self { x: x.into(), y: y.into() }
+    }
+}
+
+/// How a panel places its elements, see `Panel::with_layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Layout {
+    /// Elements are placed by their own coordinates across the whole panel.
+    #[default]
+    Absolute,
+    /// Each element gets a cell, stacked top to bottom `spacing` px apart and `padding` px in from the panel's edges.
+    /// Cells are `with_fixed_height` or share the remaining height by `with_flex`.
+    Column { spacing: f32, padding: f32 },
+    /// Like `Column` but left to right, sized by `with_fixed_width` or `with_flex`.
+    Row { spacing: f32, padding: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alignment {
+    pub vertical: VerticalAlignment,
+    pub horizontal: HorizontalAlignment
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerticalAlignment {
+    Top,
+    Center,
+    Bottom
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HorizontalAlignment {
+    Left,
+    Center,
+    Right
+}
+
+/// How an element's text is fitted to the element's width, see `Element::with_text_wrapped`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Wrap {
+    /// Breaks the text into lines between words. A single word wider than the element still runs past it.
+    Word,
+    /// Cuts the text off after the last character that fits.
+    Truncate,
+    /// Like `Truncate`, with "…" where the text was cut.
+    TruncateWithEllipsis,
+}
+
+/// How an element's texture is fitted to the element's rect, see `Element::with_icon`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IconFit {
+    /// Fills the rect, squashing the texture if their shapes differ.
+    #[default]
+    Stretch,
+    /// As large as fits inside the rect at the texture's aspect ratio, centered, leaving the rest of the rect empty.
+    Contain,
+    /// Fills the rect at the texture's aspect ratio, cropping the texture evenly on the sides that overflow.
+    Cover,
+}
+
+/// Rect in physical pixels with a top-left origin, as used by scissor rects and viewports.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct PixelRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PixelRect {
+    pub(crate) fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+
+    /// Clamps to the screen and rounds to whole pixels, `None` when nothing is left to draw.
+    pub(crate) fn to_scissor(self, screen_size: PhysicalSize<u32>) -> Option<(u32, u32, u32, u32)> {
+        let x_min = self.x.max(0.0).round() as u32;
+        let y_min = self.y.max(0.0).round() as u32;
+        let x_max = ((self.x + self.width).round().max(0.0) as u32).min(screen_size.width);
+        let y_max = ((self.y + self.height).round().max(0.0) as u32).min(screen_size.height);
+
+        if x_max <= x_min || y_max <= y_min {
+            None
+        } else {
+            Some((x_min, y_min, x_max - x_min, y_max - y_min))
+        }
+    }
+
+    /// Overlapping part of both rects, zero-sized when they don't overlap.
+    pub(crate) fn intersect(self, other: PixelRect) -> PixelRect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        PixelRect {
+            x,
+            y,
+            width: ((self.x + self.width).min(other.x + other.width) - x).max(0.0),
+            height: ((self.y + self.height).min(other.y + other.height) - y).max(0.0),
+        }
+    }
+}
+
+/// Center-origin pixel rect of a quad, `y_top` is the larger value.
+#[derive(Clone, Copy)]
+pub(crate) struct QuadRect {
+    pub(crate) x_min: f32,
+    pub(crate) x_max: f32,
+    pub(crate) y_top: f32,
+    pub(crate) y_bottom: f32,
+}
+
+impl QuadRect {
+    pub(crate) fn vertices(&self, color: [f32; 4], tex_coords: [[f32; 2]; 4], mode: RenderMode) -> [Vertex; 4] {
+        let mode = mode.shader_value();
+        let bounds = [self.x_min, self.y_bottom, self.x_max, self.y_top];
+        [
+            Vertex {
+                position: [self.x_min, self.y_top],
+                color,
+                tex_coords: tex_coords[0],
+                mode,
+                bounds,
+                corner: [0.0, 0.0],
+            }, // Top-Left
+            Vertex {
+                position: [self.x_max, self.y_top],
+                color,
+                tex_coords: tex_coords[1],
+                mode,
+                bounds,
+                corner: [0.0, 0.0],
+            }, // Top-Right
+            Vertex {
+                position: [self.x_min, self.y_bottom],
+                color,
+                tex_coords: tex_coords[3],
+                mode,
+                bounds,
+                corner: [0.0, 0.0],
+            }, // Bottom-Left
+            Vertex {
+                position: [self.x_max, self.y_bottom],
+                color,
+                tex_coords: tex_coords[2],
+                mode,
+                bounds,
+                corner: [0.0, 0.0],
+            }, // Bottom-Right
+        ]
+    }
+
+    /// From `(x_min, y_min, x_max, y_max)` as returned by `Panel::calculate_absolute_coordinates`.
+    pub(crate) fn from_bounds((x_min, y_min, x_max, y_max): (f32, f32, f32, f32)) -> QuadRect {
+        QuadRect { x_min, x_max, y_top: y_max, y_bottom: y_min }
+    }
+
+    /// Moves every edge onto the nearest physical pixel boundary so 1px features rasterize crisply.
+    /// Center-origin pixel boundaries sit on half pixels when the screen size is odd, hence the offsets.
+    pub(crate) fn snapped(self, screen_size: PhysicalSize<u32>) -> QuadRect {
+        let half_width = screen_size.width as f32 / 2.0;
+        let half_height = screen_size.height as f32 / 2.0;
+        let snap_x = |x: f32| (x + half_width).round() - half_width;
+        let snap_y = |y: f32| half_height - (half_height - y).round();
+
+        QuadRect {
+            x_min: snap_x(self.x_min),
+            x_max: snap_x(self.x_max),
+            y_top: snap_y(self.y_top),
+            y_bottom: snap_y(self.y_bottom),
+        }
+    }
+
+    pub(crate) fn to_pixel_rect(self, screen_size: PhysicalSize<u32>) -> PixelRect {
+        PixelRect {
+            x: self.x_min + screen_size.width as f32 / 2.0,
+            y: screen_size.height as f32 / 2.0 - self.y_top,
+            width: self.x_max - self.x_min,
+            height: self.y_top - self.y_bottom,
+        }
+    }
+
+    pub(crate) fn inset(&self, fraction: f32) -> QuadRect {
+        let dx = (self.x_max - self.x_min) * fraction;
+        let dy = (self.y_top - self.y_bottom) * fraction;
+        QuadRect {
+            x_min: self.x_min + dx,
+            x_max: self.x_max - dx,
+            y_top: self.y_top - dy,
+            y_bottom: self.y_bottom + dy,
+        }
+    }
+
+    /// Top, bottom, left and right strips of a `thickness` pixel frame inside this rect.
+    /// The side strips fit between the top and bottom ones so corners aren't drawn twice.
+    pub(crate) fn border_strips(&self, thickness: f32) -> [QuadRect; 4] {
+        let thickness = thickness.min((self.x_max - self.x_min) / 2.0).min((self.y_top - self.y_bottom) / 2.0).max(0.0);
+        let inner_top = self.y_top - thickness;
+        let inner_bottom = self.y_bottom + thickness;
+        [
+            QuadRect { y_bottom: inner_top, ..*self },
+            QuadRect { y_top: inner_bottom, ..*self },
+            QuadRect { x_max: self.x_min + thickness, y_top: inner_top, y_bottom: inner_bottom, ..*self },
+            QuadRect { x_min: self.x_max - thickness, y_top: inner_top, y_bottom: inner_bottom, ..*self },
+        ]
+    }
+
+    /// Vertices for `border_strips`, laid out like `vertices` one quad after another.
+    /// Borders are solid colors, so they sample nothing and need no texture coordinates.
+    /// With a `corner_radius` the frame is a single rounded ring over the whole rect instead, followed by empty
+    /// quads so it takes as much of the vertex buffer as the strips would.
+    pub(crate) fn border_vertices(&self, border: &Border, scale: f32, corner_radius: f32) -> Vec<Vertex> {
+        let color = border.color.into_vec4();
+        if corner_radius > 0.0 {
+            let mut ring = self.vertices(color, [[0.0, 0.0]; 4], RenderMode::SolidColor);
+            round_corners(&mut ring, corner_radius, border.thickness * scale);
+            let mut vertices = ring.to_vec();
+            vertices.resize(BORDER_QUADS * 4, <Vertex as bytemuck::Zeroable>::zeroed());
+            return vertices;
+        }
+        self.border_strips(border.thickness * scale).iter()
+            .flat_map(|strip| strip.vertices(color, [[0.0, 0.0]; 4], RenderMode::SolidColor))
+            .collect()
+    }
+
+    /// Overlapping part of both rects, `None` when they don't overlap.
+    pub(crate) fn intersect(&self, other: &QuadRect) -> Option<QuadRect> {
+        let rect = QuadRect {
+            x_min: self.x_min.max(other.x_min),
+            x_max: self.x_max.min(other.x_max),
+            y_top: self.y_top.min(other.y_top),
+            y_bottom: self.y_bottom.max(other.y_bottom),
+        };
+        (rect.x_max > rect.x_min && rect.y_top > rect.y_bottom).then_some(rect)
+    }
+
+    /// Clips quads laid out by `vertices` to this rect. Texture coordinates are cut along with the quad
+    /// so clipped images aren't squashed, and quads entirely outside collapse to nothing.
+    pub(crate) fn clip_vertices(&self, vertices: &mut [Vertex]) {
+        for quad in vertices.chunks_exact_mut(4) {
+            let (x_min, x_max) = (quad[0].position[0], quad[1].position[0]);
+            let (y_top, y_bottom) = (quad[0].position[1], quad[2].position[1]);
+            let Some(clipped) = (QuadRect { x_min, x_max, y_top, y_bottom }).intersect(self) else {
+                quad.fill(<Vertex as bytemuck::Zeroable>::zeroed());
+                continue;
+            };
+
+            let (u_left, u_right) = (quad[0].tex_coords[0], quad[1].tex_coords[0]);
+            let (v_top, v_bottom) = (quad[0].tex_coords[1], quad[2].tex_coords[1]);
+            for vertex in quad.iter_mut() {
+                let x = vertex.position[0].clamp(clipped.x_min, clipped.x_max);
+                let y = vertex.position[1].clamp(clipped.y_bottom, clipped.y_top);
+                vertex.position = [x, y];
+                vertex.tex_coords = [
+                    u_left + (u_right - u_left) * (x - x_min) / (x_max - x_min),
+                    v_top + (v_bottom - v_top) * (y_top - y) / (y_top - y_bottom),
+                ];
+            }
+        }
+    }
+}
+
+/// Rounds the corners of quads laid out by `QuadRect::vertices` by `radius` pixels, clamped by the shader to a capsule
+/// when it's more than half the quad's shorter side. A `ring` thickness above 0 keeps only that wide an outline.
+/// Only affects drawing, hit-testing still uses the square rect.
+pub(crate) fn round_corners(vertices: &mut [Vertex], radius: f32, ring: f32) {
+    for vertex in vertices {
+        vertex.corner = [radius.max(0.0), ring.max(0.0)];
+    }
+}
@@ -0,0 +1,340 @@
+use std::{collections::{HashMap, HashSet}, fmt, fs, io, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::definitions::{InteractionStyle, UiAtlas};
+
+use super::{color::Color, dropdown::MenuItem, element::Element, interface::UiTree, layout::{Alignment, Coordinate, Layout, Wrap}, panel::{Panel, ScrollDirection}, split::{self, SplitDirection}, theme::ColorRole};
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(PathBuf, io::Error),
+    /// Not RON, or not shaped like a `LayoutFile`. The message starts with the line and column.
+    Malformed(PathBuf, String),
+    /// An `on_click` or dropdown item names an action the application didn't bind. `element` says where it is.
+    UnknownAction { element: String, action: String },
+    /// Two panels, or two elements anywhere in the file, share an id.
+    DuplicateId(String),
+    /// A split names a panel the file doesn't have.
+    UnknownPane(String),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Io(path, e) => write!(f, "Unable to read {}: {}", path.display(), e),
+            LayoutError::Malformed(path, reason) => write!(f, "{} isn't a valid layout: {}", path.display(), reason),
+            LayoutError::UnknownAction { element, action } => write!(f, "{} uses the unknown action {:?}", element, action),
+            LayoutError::DuplicateId(id) => write!(f, "Id {:?} is used more than once", id),
+            LayoutError::UnknownPane(id) => write!(f, "No panel with id {:?} to split", id),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LayoutError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An interface written as data, so its layout can change without recompiling. Panels are added in order and
+/// the splits after them. Callbacks can't be written down, elements name actions instead, which `build` looks up
+/// in the bindings the application hands it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutFile {
+    pub panels: Vec<PanelLayout>,
+    pub splits: Vec<SplitLayout>,
+}
+
+/// A `Panel` as written in a layout file. Fields left out take the values `Panel::new` starts with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelLayout {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub start: Coordinate,
+    pub end: Coordinate,
+    #[serde(default)]
+    pub color: Option<Color>,
+    /// Wins over `color`, see `Panel::with_color_role`.
+    #[serde(default)]
+    pub color_role: Option<ColorRole>,
+    #[serde(default)]
+    pub layer: i32,
+    #[serde(default)]
+    pub layout: Layout,
+    #[serde(default)]
+    pub scroll: Option<ScrollDirection>,
+    #[serde(default)]
+    pub click_through: bool,
+    /// See `Panel::with_input_passthrough`.
+    #[serde(default)]
+    pub input_passthrough: bool,
+    #[serde(default)]
+    pub elements: Vec<ElementLayout>,
+}
+
+/// An `Element` as written in a layout file, which draws `texture` like `Element::new`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementLayout {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub start: Coordinate,
+    pub end: Coordinate,
+    #[serde(default = "solid_texture")]
+    pub texture: String,
+    #[serde(default)]
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub color_role: Option<ColorRole>,
+    #[serde(default)]
+    pub hover_color: Option<Color>,
+    #[serde(default)]
+    pub hover_color_role: Option<ColorRole>,
+    #[serde(default)]
+    pub text: Option<TextLayout>,
+    #[serde(default)]
+    pub tooltip: Option<String>,
+    #[serde(default)]
+    pub render_target: Option<String>,
+    /// Action whose event a click returns.
+    #[serde(default)]
+    pub on_click: Option<String>,
+    /// Items of the menu a click opens, see `Element::with_dropdown`.
+    #[serde(default)]
+    pub dropdown: Vec<MenuItemLayout>,
+}
+
+/// An element's text, see `Element::with_text_wrapped`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextLayout {
+    pub text: String,
+    pub alignment: Alignment,
+    /// The panel or interface style's scale when left out, like `Element::with_label`.
+    #[serde(default)]
+    pub scale: Option<f32>,
+    /// Only used along with `scale`, like `Element::with_text_wrapped`.
+    #[serde(default)]
+    pub wrap: Option<Wrap>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MenuItemLayout {
+    pub label: String,
+    pub action: String,
+}
+
+/// Arguments of a `UiTree::add_split`, with the panes written out rather than referred to by the ids it returns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitLayout {
+    pub direction: SplitDirection,
+    pub first: PaneLayout,
+    pub second: PaneLayout,
+    pub ratio: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PaneLayout {
+    /// The panel with this id.
+    Panel(String),
+    Split(Box<SplitLayout>),
+}
+
+fn solid_texture() -> String {
+    "solid".to_string()
+}
+
+impl LayoutFile {
+    /// Reads a RON layout file.
+    pub fn load(path: &Path) -> Result<Self, LayoutError> {
+        let text = fs::read_to_string(path).map_err(|e| LayoutError::Io(path.to_path_buf(), e))?;
+        ron::from_str(&text).map_err(|e| LayoutError::Malformed(path.to_path_buf(), e.to_string()))
+    }
+
+    /// Builds the interface, with clicks returning the event `bindings` holds for each action named.
+    pub fn build<E: Clone + Send + 'static>(&self, atlas: UiAtlas, bindings: &HashMap<String, E>) -> Result<UiTree<E>, LayoutError> {
+        self.build_with(atlas, bindings, |_| {})
+    }
+
+    /// Like `build`, but hands each panel to `fill` before it's added, for content that isn't known until
+    /// the interface is built, such as lists.
+    pub fn build_with<E: Clone + Send + 'static>(
+        &self,
+        atlas: UiAtlas,
+        bindings: &HashMap<String, E>,
+        mut fill: impl FnMut(&mut Panel<E>),
+    ) -> Result<UiTree<E>, LayoutError> {
+        self.check_ids()?;
+        let panels = self.panels.iter()
+            .enumerate()
+            .map(|(panel_idx, panel)| panel.build(panel_idx, bindings))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut interface = UiTree::new(atlas);
+        for mut panel in panels {
+            fill(&mut panel);
+            interface.add_panel(panel);
+        }
+        for split in &self.splits {
+            split.add_to(&mut interface)?;
+        }
+        Ok(interface)
+    }
+
+    /// `UiTree::add_panel` and `UiTree::add_split` panic on what a hand-written file easily gets wrong,
+    /// so it's caught here first.
+    fn check_ids(&self) -> Result<(), LayoutError> {
+        let mut panel_ids = HashSet::new();
+        let mut element_ids = HashSet::new();
+        for panel in &self.panels {
+            if let Some(id) = &panel.id
+                && !panel_ids.insert(id.as_str()) {
+                return Err(LayoutError::DuplicateId(id.clone()));
+            }
+            for id in panel.elements.iter().filter_map(|element| element.id.as_deref()) {
+                if !element_ids.insert(id) {
+                    return Err(LayoutError::DuplicateId(id.to_string()));
+                }
+            }
+        }
+        for split in &self.splits {
+            split.check_panes(&panel_ids)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: Clone + Send + 'static> UiTree<E> {
+    /// Loads the RON layout file at `path` and builds it, see `LayoutFile::build`.
+    pub fn from_layout_file(path: impl AsRef<Path>, atlas: UiAtlas, bindings: &HashMap<String, E>) -> Result<Self, LayoutError> {
+        LayoutFile::load(path.as_ref())?.build(atlas, bindings)
+    }
+}
+
+impl PanelLayout {
+    fn build<E: Clone + Send + 'static>(&self, panel_idx: usize, bindings: &HashMap<String, E>) -> Result<Panel<E>, LayoutError> {
+        let mut panel = Panel::new(self.start, self.end)
+            .with_layer(self.layer)
+            .with_layout(self.layout);
+        if let Some(id) = &self.id {
+            panel = panel.with_id(id);
+        }
+        if let Some(color) = &self.color {
+            panel = panel.with_color(&color.to_hex());
+        }
+        if let Some(role) = self.color_role {
+            panel = panel.with_color_role(role);
+        }
+        if let Some(direction) = self.scroll {
+            panel = panel.with_scroll(direction);
+        }
+        if self.click_through {
+            panel = panel.with_click_through();
+        }
+        if self.input_passthrough {
+            panel = panel.with_input_passthrough();
+        }
+        for (element_idx, element) in self.elements.iter().enumerate() {
+            let bind = |action: &str| bindings.get(action).cloned().ok_or_else(|| LayoutError::UnknownAction {
+                element: self.describe_element(panel_idx, element_idx),
+                action: action.to_string(),
+            });
+            panel.add_element(element.build(bind)?);
+        }
+        Ok(panel)
+    }
+
+    /// Where an element is in the file, for errors: by id where it has one, by position otherwise.
+    fn describe_element(&self, panel_idx: usize, element_idx: usize) -> String {
+        let panel = match &self.id {
+            Some(id) => format!("panel {:?}", id),
+            None => format!("panel {}", panel_idx),
+        };
+        match &self.elements[element_idx].id {
+            Some(id) => format!("Element {:?} of {}", id, panel),
+            None => format!("Element {} of {}", element_idx, panel),
+        }
+    }
+}
+
+impl ElementLayout {
+    /// `bind` looks up the event for an action name.
+    fn build<E: Clone + Send + 'static>(&self, bind: impl Fn(&str) -> Result<E, LayoutError>) -> Result<Element<E>, LayoutError> {
+        let mut element = Element::new(self.start, self.end, &self.texture);
+        if let Some(id) = &self.id {
+            element = element.with_id(id);
+        }
+        if let Some(color) = &self.color {
+            element = element.with_color(&color.to_hex());
+        }
+        if let Some(role) = self.color_role {
+            element = element.with_color_role(role);
+        }
+        if let Some(text) = &self.text {
+            element = match (text.scale, text.wrap) {
+                (Some(scale), Some(wrap)) => element.with_text_wrapped(text.alignment, &text.text, scale, wrap),
+                (Some(scale), None) => element.with_text(text.alignment, &text.text, scale),
+                (None, _) => element.with_label(text.alignment, &text.text),
+            };
+        }
+        if let Some(hover_color) = &self.hover_color {
+            element = element.with_hover_color(&hover_color.to_hex());
+        }
+        if let Some(role) = self.hover_color_role {
+            element = element.with_hover_color_role(role);
+        }
+        if let Some(tooltip) = &self.tooltip {
+            element = element.with_tooltip(tooltip);
+        }
+        if let Some(target) = &self.render_target {
+            element = element.with_render_target(target);
+        }
+        if let Some(action) = &self.on_click {
+            let event = bind(action)?;
+            element = element.with_fn(move || Some(event.clone()), InteractionStyle::OnClick);
+        }
+        if !self.dropdown.is_empty() {
+            let items = self.dropdown.iter()
+                .map(|item| Ok(MenuItem::new(&item.label, bind(&item.action)?)))
+                .collect::<Result<Vec<_>, LayoutError>>()?;
+            element = element.with_dropdown(items);
+        }
+        Ok(element)
+    }
+}
+
+impl SplitLayout {
+    fn check_panes(&self, panel_ids: &HashSet<&str>) -> Result<(), LayoutError> {
+        for pane in [&self.first, &self.second] {
+            match pane {
+                PaneLayout::Panel(id) if !panel_ids.contains(id.as_str()) => return Err(LayoutError::UnknownPane(id.clone())),
+                PaneLayout::Panel(_) => {}
+                PaneLayout::Split(split) => split.check_panes(panel_ids)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the nested splits first, returning the id of this one.
+    fn add_to<E>(&self, interface: &mut UiTree<E>) -> Result<String, LayoutError> {
+        let first = self.first.add_to(interface)?;
+        let second = self.second.add_to(interface)?;
+        let id = split::split_id(&first, &second);
+        if interface.pane_exists(&id) {
+            return Err(LayoutError::DuplicateId(id));
+        }
+        Ok(interface.add_split(self.direction, &first, &second, self.ratio))
+    }
+}
+
+impl PaneLayout {
+    fn add_to<E>(&self, interface: &mut UiTree<E>) -> Result<String, LayoutError> {
+        match self {
+            PaneLayout::Panel(id) => Ok(id.clone()),
+            PaneLayout::Split(split) => split.add_to(interface),
+        }
+    }
+}
@@ -1,2 +1,47 @@
 pub mod interface;
-pub(crate) mod camera;
\ No newline at end of file
+pub mod panel;
+pub mod element;
+pub mod color;
+pub mod layout;
+pub mod layout_file;
+pub mod renderer;
+pub mod custom_draw;
+pub mod dropdown;
+pub mod tooltip;
+pub mod toast;
+pub mod animation;
+pub mod style;
+pub mod theme;
+pub mod font;
+pub(crate) mod group;
+pub(crate) mod debug_overlay;
+pub(crate) mod text;
+pub(crate) mod quad_layout;
+pub mod camera;
+pub mod scroll;
+pub mod split;
+pub mod floating;
+#[cfg(feature = "validate-ui")]
+pub(crate) mod validate;
+
+/// The types most UI-building code needs, `use gfx::gui::prelude::*;`.
+pub mod prelude {
+    pub use super::{
+        animation::{AnimProperty, Easing},
+        color::{Color, ColorParseError},
+        custom_draw::{CustomDraw, CustomDrawCtx, CustomPrepareCtx},
+        dropdown::MenuItem,
+        element::{Element, ElementKind},
+        floating::FloatingWindow,
+        font::{FontId, Fonts},
+        interface::{ElementHandle, UiTree},
+        layout::{Alignment, Coordinate, Dimension, HorizontalAlignment, IconFit, Layout, PixelRect, VerticalAlignment, Wrap},
+        layout_file::{LayoutError, LayoutFile},
+        panel::{Panel, ScrollDirection},
+        scroll::ScrollEvent,
+        split::SplitDirection,
+        toast::ToastLevel,
+        style::{Border, FontWeight, Style, TextStyle},
+        theme::{ColorRole, Theme},
+    };
+}
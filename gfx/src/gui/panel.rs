@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+
+use super::{animation::Animated, color::Color, element::Element, group::GroupState, layout::{Coordinate, Dimension, Layout, PixelRect, QuadRect, BORDER_QUADS}, split::Edge, style::{Border, Style}, theme::{ColorRole, Theme}};
+
+pub struct Panel<E> {
+    pub elements: Vec<Element<E>>,
+    /// Stable name for the panel, see `UiTree::panel_index`.
+    pub(crate) id: Option<String>,
+    pub(crate) start_coordinate: Coordinate,
+    pub(crate) end_coordinate: Coordinate,
+    pub(crate) renderable: bool,
+    pub(crate) texture_name: String,
+    pub(crate) color: Color,
+    /// Takes the fill from the theme instead of `color`, see `with_color_role`.
+    pub(crate) color_role: Option<ColorRole>,
+    /// Blends the fill between two colors instead, see `with_gradient`.
+    pub(crate) gradient: Option<Gradient>,
+    pub(crate) style: Style,
+    pub(crate) border: Option<Border>,
+    /// Radius the fill and border corners are rounded by, in pixels before scaling, see `with_corner_radius`.
+    pub(crate) corner_radius: f32,
+    pub(crate) clip_children: bool,
+    pub(crate) scroll: Option<ScrollDirection>,
+    /// How far the content is scrolled, as a fraction of the panel's height.
+    pub(crate) scroll_offset: f32,
+    /// Panels are drawn and hit-tested by layer, higher on top, and in insertion order within a layer.
+    pub(crate) layer: i32,
+    pub(crate) click_through: bool,
+    /// Left out of hit-testing altogether, see `with_input_passthrough`.
+    pub(crate) input_passthrough: bool,
+    pub(crate) layout: Layout,
+    /// Hidden panels draw nothing and take no input, see `UiTree::set_panel_visible`.
+    pub(crate) visible: bool,
+    /// Disabled panels still block input to the panels below, but none of their elements respond to it.
+    pub(crate) enabled: bool,
+    /// The interface's scale factor, which every pixel size in the panel and its elements is multiplied by.
+    /// Kept up to date by `UiTree::add_panel` and `UiTree::set_scale_factor`.
+    pub(crate) pixel_scale: f32,
+    /// Written by the interface's animations, see `UiTree::animate_panel`.
+    pub(crate) animated: Animated,
+}
+
+/// Colors at the two edges a panel's fill blends between, see `Panel::with_gradient`.
+#[derive(Debug, Clone)]
+pub(crate) enum Gradient {
+    /// Top, then bottom.
+    Vertical(Color, Color),
+    /// Left, then right.
+    Horizontal(Color, Color),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScrollDirection {
+    Vertical,
+}
+
+impl<E> Panel<E> {
+    pub fn new(start_coordinate: Coordinate, end_coordinate: Coordinate) -> Self {
+        Self {
+            elements: Vec::new(),
+            id: None,
+            start_coordinate,
+            end_coordinate,
+            renderable: false,
+            texture_name: "solid".to_string(),
+            color: Color::from_hex("#ffffffff"),
+            color_role: None,
+            gradient: None,
+            style: Style::default(),
+            border: None,
+            corner_radius: 0.0,
+            clip_children: false,
+            scroll: None,
+            scroll_offset: 0.0,
+            layer: 0,
+            click_through: false,
+            input_passthrough: false,
+            layout: Layout::Absolute,
+            visible: true,
+            enabled: true,
+            pixel_scale: 1.0,
+            animated: Animated::default(),
+        }
+    }
+
+    /// Panics if the element's id is already used in this panel, see `UiTree::add_panel`.
+    pub fn add_element(&mut self, element: Element<E>) {
+        if let Some(id) = element.id() {
+            assert!(self.elements.iter().all(|other| other.id() != Some(id)), "element id {:?} is already used in this panel", id);
+        }
+        self.elements.push(element);
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Starts the panel hidden or shown, so it can be built once and toggled with `UiTree::set_panel_visible`.
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Starts every element of the panel disabled or enabled, see `UiTree::set_panel_enabled`.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The panel's own flags, which every element in it is narrowed by.
+    pub(crate) fn own_state(&self) -> GroupState {
+        GroupState { enabled: self.enabled, visible: self.visible }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.renderable = true;
+        self.color = Color::from_hex(color);
+        self.color_role = None;
+        self.gradient = None;
+        self
+    }
+
+    /// Fills the panel with the theme's color for `role`, which follows `UiTree::set_theme`.
+    pub fn with_color_role(mut self, role: ColorRole) -> Self {
+        self.renderable = true;
+        self.color_role = Some(role);
+        self.gradient = None;
+        self
+    }
+
+    /// Fills the panel with `top` at its top edge fading into `bottom` at its bottom edge, replacing its color.
+    /// Both are parsed like `with_color`, so the blend runs between the same linear colors a flat fill would use.
+    /// Only the panel's own quad is shaded, elements and their hover colors are drawn over it as usual.
+    pub fn with_gradient(mut self, top: &str, bottom: &str) -> Self {
+        self.renderable = true;
+        self.gradient = Some(Gradient::Vertical(Color::from_hex(top), Color::from_hex(bottom)));
+        self
+    }
+
+    /// Like `with_gradient`, fading from `left` to `right`.
+    pub fn with_horizontal_gradient(mut self, left: &str, right: &str) -> Self {
+        self.renderable = true;
+        self.gradient = Some(Gradient::Horizontal(Color::from_hex(left), Color::from_hex(right)));
+        self
+    }
+
+    /// The panel's fill, its role looked up in `theme` if it has one. For a gradient, its first color.
+    pub(crate) fn fill_color(&self, theme: &Theme) -> Color {
+        match (&self.gradient, self.color_role) {
+            (Some(Gradient::Vertical(first, _) | Gradient::Horizontal(first, _)), _) => first.clone(),
+            (None, Some(role)) => theme.color(role).clone(),
+            (None, None) => self.color.clone(),
+        }
+    }
+
+    /// Color of each corner of the panel's quad, in the order `QuadRect::vertices` lists them: top-left, top-right,
+    /// bottom-left, bottom-right. The vertex colors interpolate across the quad, which makes the gradient.
+    pub(crate) fn corner_colors(&self, theme: &Theme) -> [[f32; 4]; 4] {
+        match &self.gradient {
+            Some(Gradient::Vertical(top, bottom)) => [top.into_vec4(), top.into_vec4(), bottom.into_vec4(), bottom.into_vec4()],
+            Some(Gradient::Horizontal(left, right)) => [left.into_vec4(), right.into_vec4(), left.into_vec4(), right.into_vec4()],
+            None => [self.fill_color(theme).into_vec4(); 4],
+        }
+    }
+
+    /// Draws a `thickness_px` frame inside the panel's edges, over its elements so they can't hide it.
+    pub fn with_border(mut self, thickness_px: f32, color: &str) -> Self {
+        self.border = Some(Border::new(thickness_px, color));
+        self
+    }
+
+    /// Rounds the corners of the panel's fill and border by `radius_px`, anti-aliased. A radius of more than half the
+    /// panel's shorter side makes a capsule. Elements aren't cut to the rounded shape and input still hits the whole rect.
+    pub fn with_corner_radius(mut self, radius_px: f32) -> Self {
+        self.corner_radius = radius_px.max(0.0);
+        self
+    }
+
+    /// Quads drawn before the elements, the panel's fill if it has one.
+    pub(crate) fn background_quad_count(&self) -> usize {
+        usize::from(self.renderable)
+    }
+
+    /// Quads drawn after the elements, the panel's border if it has one.
+    pub(crate) fn foreground_quad_count(&self) -> usize {
+        if self.border.is_some() { BORDER_QUADS } else { 0 }
+    }
+
+    /// Defaults for every element in the panel, see `Style`.
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Replaces the panel's style. Every element picks it up on the next upload.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Draws the panel, its elements and their text above every panel on a lower layer, and hit-tests it before them.
+    /// Panels default to layer 0.
+    pub fn with_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    /// Lets clicks on the panel's empty space reach panels below it. Panels are otherwise opaque to input,
+    /// so a menu drawn over a header doesn't also press the header button behind it.
+    pub fn with_click_through(mut self) -> Self {
+        self.click_through = true;
+        self
+    }
+
+    /// Makes the panel invisible to input, for purely decorative panels: clicks, hover and scrolling over it reach
+    /// whatever is beneath, panels or the preview, as if it weren't there. Its elements don't react to anything either.
+    pub fn with_input_passthrough(mut self) -> Self {
+        self.input_passthrough = true;
+        self
+    }
+
+    /// Places elements in cells along a column or row instead of by their coordinates across the whole panel.
+    /// Each element's coordinates are then fractions of its cell, `Coordinate::new(0.0, 0.0)` to
+    /// `Coordinate::new(1.0, 1.0)` fills it.
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Element indices in the order they're drawn, by element layer and then insertion order.
+    /// Hit-testing walks it backwards so the topmost element wins.
+    pub(crate) fn element_draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+        order.sort_by_key(|&index| self.elements[index].layer);
+        order
+    }
+
+    /// Clips elements and their text to the panel's rect instead of letting them draw over neighbours.
+    pub fn with_clipping(mut self) -> Self {
+        self.clip_children = true;
+        self
+    }
+
+    /// Lets the content scroll with `UiTree::handle_scroll`. Implies `with_clipping`, elements outside the panel are culled.
+    pub fn with_scroll(mut self, direction: ScrollDirection) -> Self {
+        self.scroll = Some(direction);
+        self.clip_children = true;
+        self
+    }
+
+    /// Starts scrolled to `offset`, e.g. to keep the position when the page is rebuilt.
+    pub fn with_scroll_offset(mut self, offset: f32) -> Self {
+        self.scroll_offset = offset;
+        self
+    }
+
+    pub fn scroll_offset(&self) -> f32 {
+        self.scroll_offset
+    }
+
+    /// Furthest the content can scroll, so the bottom of the lowest element lines up with the bottom of the panel.
+    /// Pixel-sized elements and cells make that depend on how tall the panel is on screen.
+    fn max_scroll_offset(&self, screen_size: PhysicalSize<u32>) -> f32 {
+        let panel_rect = self.quad_rect(screen_size);
+        let panel_height = panel_rect.y_top - panel_rect.y_bottom;
+        if panel_height <= 0.0 {
+            return 0.0;
+        }
+        let content_extent = self.elements.iter().zip(self.element_frames(panel_rect))
+            .map(|(element, frame)| {
+                let rect = element.calculate_rect_relative_to_panel(frame.x_min, frame.y_bottom, frame.x_max, frame.y_top, self.pixel_scale);
+                (panel_rect.y_top - rect.y_bottom) / panel_height
+            })
+            .fold(1.0, f32::max);
+        content_extent - 1.0
+    }
+
+    /// Returns true when the offset changed.
+    pub(crate) fn scroll_by(&mut self, delta: f32, screen_size: PhysicalSize<u32>) -> bool {
+        if self.scroll.is_none() {
+            return false;
+        }
+        let previous = self.scroll_offset;
+        self.scroll_offset = (self.scroll_offset + delta).clamp(0.0, self.max_scroll_offset(screen_size));
+        self.scroll_offset != previous
+    }
+
+    /// Pulls the offset back into range after elements were removed or the panel was resized.
+    pub(crate) fn clamp_scroll(&mut self, screen_size: PhysicalSize<u32>) {
+        self.scroll_offset = self.scroll_offset.clamp(0.0, self.max_scroll_offset(screen_size));
+    }
+
+    /// One side of the panel, as a position across the window.
+    pub(crate) fn edge(&self, edge: Edge) -> Dimension {
+        match edge {
+            Edge::Left => self.start_coordinate.x,
+            Edge::Top => self.start_coordinate.y,
+            Edge::Right => self.end_coordinate.x,
+            Edge::Bottom => self.end_coordinate.y,
+        }
+    }
+
+    /// Moves one side of the panel, as splits do when their divider is dragged.
+    pub(crate) fn set_edge(&mut self, edge: Edge, position: Dimension) {
+        match edge {
+            Edge::Left => self.start_coordinate.x = position,
+            Edge::Top => self.start_coordinate.y = position,
+            Edge::Right => self.end_coordinate.x = position,
+            Edge::Bottom => self.end_coordinate.y = position,
+        }
+    }
+
+    /// Whether a window position in physical pixels falls on the panel.
+    pub(crate) fn contains(&self, x: f32, y: f32, screen_size: PhysicalSize<u32>) -> bool {
+        self.quad_rect(screen_size).to_pixel_rect(screen_size).contains(x, y)
+    }
+
+    /// Where each element is drawn in window pixels, by element index, scrolling included. Hit-testing goes
+    /// through this so it resolves coordinates and layout cells exactly like drawing does.
+    pub(crate) fn element_pixel_rects(&self, screen_size: PhysicalSize<u32>) -> Vec<PixelRect> {
+        let content = self.content_rect(self.quad_rect(screen_size));
+        self.elements.iter().zip(self.element_frames(content))
+            .map(|(element, frame)| element.layout_rect(frame, screen_size, self.pixel_scale).to_pixel_rect(screen_size))
+            .collect()
+    }
+
+    /// The rect each element's coordinates are resolved in, by element index: `content` itself for
+    /// `Layout::Absolute`, otherwise the element's cell within it.
+    pub(crate) fn element_frames(&self, content: QuadRect) -> Vec<QuadRect> {
+        let (spacing, padding, vertical) = match self.layout {
+            Layout::Absolute => return vec![content; self.elements.len()],
+            Layout::Column { spacing, padding } => (spacing, padding, true),
+            Layout::Row { spacing, padding } => (spacing, padding, false),
+        };
+        let (spacing, padding) = (spacing * self.pixel_scale, padding * self.pixel_scale);
+        let inner = QuadRect {
+            x_min: content.x_min + padding,
+            x_max: content.x_max - padding,
+            y_top: content.y_top - padding,
+            y_bottom: content.y_bottom + padding,
+        };
+        let main_extent = if vertical { inner.y_top - inner.y_bottom } else { inner.x_max - inner.x_min }.max(0.0);
+
+        // The first element always opens a cell, even if it asked to share one
+        let openers: Vec<&Element<E>> = self.elements.iter().enumerate()
+            .filter(|(index, element)| *index == 0 || !element.shares_cell)
+            .map(|(_, element)| element)
+            .collect();
+        let fixed: Vec<Option<f32>> = openers.iter()
+            .map(|element| if vertical { element.fixed_height } else { element.fixed_width })
+            .map(|size| size.map(|size| size.resolve(main_extent, self.pixel_scale).max(0.0)))
+            .collect();
+        let fixed_total: f32 = fixed.iter().flatten().sum();
+        let flex_total: f32 = openers.iter().zip(&fixed).filter(|(_, size)| size.is_none()).map(|(element, _)| element.flex).sum();
+        let spacing_total = spacing * openers.len().saturating_sub(1) as f32;
+        let free = (main_extent - fixed_total - spacing_total).max(0.0);
+
+        let mut offset = 0.0;
+        let cells: Vec<QuadRect> = openers.iter().zip(&fixed)
+            .map(|(element, size)| {
+                let length = size.unwrap_or(if flex_total > 0.0 { free * element.flex / flex_total } else { 0.0 });
+                let cell = if vertical {
+                    QuadRect { y_top: inner.y_top - offset, y_bottom: inner.y_top - offset - length, ..inner }
+                } else {
+                    QuadRect { x_min: inner.x_min + offset, x_max: inner.x_min + offset + length, ..inner }
+                };
+                offset += length + spacing;
+                cell
+            })
+            .collect();
+
+        let mut cell_index = 0;
+        self.elements.iter().enumerate()
+            .map(|(index, element)| {
+                if index > 0 && !element.shares_cell {
+                    cell_index += 1;
+                }
+                cells[cell_index]
+            })
+            .collect()
+    }
+
+    /// The frame elements are laid out in, `panel_rect` moved by the scroll offset.
+    pub(crate) fn content_rect(&self, panel_rect: QuadRect) -> QuadRect {
+        let scroll_shift = self.scroll_offset * (panel_rect.y_top - panel_rect.y_bottom);
+        QuadRect { y_top: panel_rect.y_top + scroll_shift, y_bottom: panel_rect.y_bottom + scroll_shift, ..panel_rect }
+    }
+
+    /// The panel's quad, snapped to physical pixels.
+    pub(crate) fn quad_rect(&self, screen_size: PhysicalSize<u32>) -> QuadRect {
+        QuadRect::from_bounds(self.calculate_absolute_coordinates(screen_size)).snapped(screen_size)
+    }
+
+    /// Where the panel is drawn, `quad_rect` moved and scaled by its animations. Input still uses `quad_rect`.
+    pub(crate) fn drawn_rect(&self, screen_size: PhysicalSize<u32>) -> QuadRect {
+        self.animated.apply(self.quad_rect(screen_size), self.pixel_scale)
+    }
+
+    pub(crate) fn calculate_absolute_coordinates(
+        &self,
+        screen_size: PhysicalSize<u32>,
+    ) -> (f32, f32, f32, f32) {
+        let screen_width_full = screen_size.width as f32;
+        let screen_height_full = screen_size.height as f32;
+
+        let x_min_px = self.start_coordinate.x.resolve(screen_width_full, self.pixel_scale);
+        let x_max_px = self.end_coordinate.x.resolve(screen_width_full, self.pixel_scale);
+        let y_min_px = self.start_coordinate.y.resolve(screen_height_full, self.pixel_scale);
+        let y_max_px = self.end_coordinate.y.resolve(screen_height_full, self.pixel_scale);
+
+        let half_screen_width = screen_width_full / 2.0;
+        let half_screen_height = screen_height_full / 2.0;
+
+        let x_min_ndc = x_min_px - half_screen_width;
+        let x_max_ndc = x_max_px - half_screen_width;
+
+        let y_max_ndc = half_screen_height - y_min_px;
+        let y_min_ndc = half_screen_height - y_max_px;
+
+        (x_min_ndc, y_min_ndc, x_max_ndc, y_max_ndc)
+    }
+}
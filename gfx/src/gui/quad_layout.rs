@@ -0,0 +1,200 @@
+use crate::definitions::Vertex;
+
+use super::{element::Element, panel::Panel};
+
+/// Bytes one quad takes in the vertex buffer.
+pub(crate) const QUAD_BUFFER_SIZE: wgpu::BufferAddress = 4 * std::mem::size_of::<Vertex>() as wgpu::BufferAddress;
+
+/// Consecutive quads in the vertex buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct QuadRun {
+    /// In bytes from the start of the buffer.
+    pub(crate) offset: wgpu::BufferAddress,
+    pub(crate) quads: usize,
+}
+
+impl QuadRun {
+    pub(crate) fn size(self) -> wgpu::BufferAddress {
+        self.quads as wgpu::BufferAddress * QUAD_BUFFER_SIZE
+    }
+
+    /// The first `count` quads of the run, or all of them if it's shorter.
+    pub(crate) fn first(self, count: usize) -> QuadRun {
+        QuadRun { quads: count.min(self.quads), ..self }
+    }
+
+    /// The run without its first `count` quads.
+    pub(crate) fn skip(self, count: usize) -> QuadRun {
+        let skipped = self.first(count);
+        QuadRun { offset: self.offset + skipped.size(), quads: self.quads - skipped.quads }
+    }
+}
+
+/// Where one panel's quads are: its fill, then its elements by layer, then its border. Elements appended since
+/// the layout was made come after every panel, see `QuadLayout::append_elements`.
+pub(crate) struct PanelQuads {
+    pub(crate) panel: usize,
+    pub(crate) background: QuadRun,
+    /// Indexed by element index, see `element_order` for the order they're drawn in.
+    pub(crate) elements: Vec<QuadRun>,
+    pub(crate) element_order: Vec<usize>,
+    pub(crate) foreground: QuadRun,
+}
+
+/// Every quad's place in the vertex buffer, worked out once per full update. Writing the buffer and drawing it
+/// both walk this, so they can't disagree about where a panel's or an element's quads are.
+#[derive(Default)]
+pub(crate) struct QuadLayout {
+    /// In draw order, by layer and then insertion order.
+    pub(crate) panels: Vec<PanelQuads>,
+    /// Position in `panels` of each panel index.
+    positions: Vec<usize>,
+    /// Bytes the buffer needs for all of it.
+    pub(crate) size: wgpu::BufferAddress,
+}
+
+impl QuadLayout {
+    /// Lays out `panels` one after another in `draw_order`, which has to list every panel once.
+    pub(crate) fn new<E>(panels: &[Panel<E>], draw_order: &[usize]) -> Self {
+        let mut size = 0;
+        let mut next_run = |quads: usize| {
+            let run = QuadRun { offset: size, quads };
+            size += run.size();
+            run
+        };
+        let mut positions = vec![0; panels.len()];
+        let mut panel_quads = Vec::with_capacity(draw_order.len());
+        for (position, &panel_idx) in draw_order.iter().enumerate() {
+            positions[panel_idx] = position;
+            let panel = &panels[panel_idx];
+            let background = next_run(panel.background_quad_count());
+            let element_order = panel.element_draw_order();
+            let mut elements = vec![QuadRun::default(); panel.elements.len()];
+            for &element_idx in &element_order {
+                elements[element_idx] = next_run(panel.elements[element_idx].quad_count());
+            }
+            let foreground = next_run(panel.foreground_quad_count());
+            panel_quads.push(PanelQuads { panel: panel_idx, background, elements, element_order, foreground });
+        }
+        Self { panels: panel_quads, positions, size }
+    }
+
+    /// Quads of an element as of the update that made the layout, `None` for one added since.
+    pub(crate) fn element(&self, panel_idx: usize, element_idx: usize) -> Option<QuadRun> {
+        let &position = self.positions.get(panel_idx)?;
+        self.panels[position].elements.get(element_idx).copied()
+    }
+
+    /// Gives the elements of `panel` in `appended`, added after the layout was made, runs after everything else in
+    /// the buffer. Returns false, changing nothing, when they'd go past `capacity` bytes or the layout doesn't have
+    /// the panel's other elements, and a new layout is needed.
+    pub(crate) fn append_elements<E>(&mut self, panel: &Panel<E>, panel_idx: usize, appended: std::ops::Range<usize>, capacity: wgpu::BufferAddress) -> bool {
+        let Some(&position) = self.positions.get(panel_idx) else {
+            return false;
+        };
+        let panel_quads = &mut self.panels[position];
+        let Some(elements) = panel.elements.get(appended.clone()).filter(|_| panel_quads.elements.len() == appended.start) else {
+            return false;
+        };
+        let quads: usize = elements.iter().map(Element::quad_count).sum();
+        if self.size + quads as wgpu::BufferAddress * QUAD_BUFFER_SIZE > capacity {
+            return false;
+        }
+        for element in elements {
+            let run = QuadRun { offset: self.size, quads: element.quad_count() };
+            self.size += run.size();
+            panel_quads.elements.push(run);
+        }
+        // Runs aren't in draw order in the buffer any more, `render` goes by this rather than by offset.
+        // New elements on the top layer or above go last, as sorting them all would put them.
+        let mut appended: Vec<usize> = appended.collect();
+        appended.sort_by_key(|&index| panel.elements[index].layer);
+        let top_layer = panel_quads.element_order.last().map_or(i32::MIN, |&index| panel.elements[index].layer);
+        if appended.first().is_none_or(|&first| panel.elements[first].layer >= top_layer) {
+            panel_quads.element_order.extend(appended);
+        } else {
+            panel_quads.element_order = panel.element_draw_order();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::layout::Coordinate;
+
+    fn panel(color: Option<&str>, elements: usize) -> Panel<()> {
+        let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0));
+        if let Some(color) = color {
+            panel = panel.with_color(color);
+        }
+        for _ in 0..elements {
+            panel.add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid"));
+        }
+        panel
+    }
+
+    /// Every run of the layout that has quads, as byte ranges.
+    fn ranges(layout: &QuadLayout) -> Vec<std::ops::Range<wgpu::BufferAddress>> {
+        layout.panels.iter()
+            .flat_map(|quads| [quads.background].into_iter().chain(quads.elements.iter().copied()).chain([quads.foreground]))
+            .filter(|run| run.quads > 0)
+            .map(|run| run.offset..run.offset + run.size())
+            .collect()
+    }
+
+    #[test]
+    fn panels_with_and_without_a_fill_get_their_own_quads() {
+        let panels = [panel(Some("#ff0000"), 2), panel(None, 2)];
+        let layout = QuadLayout::new(&panels, &[0, 1]);
+
+        assert!(layout.panels[0].background.quads > 0);
+        assert_eq!(layout.panels[1].background.quads, 0);
+        let first_end = layout.element(0, 1).map(|run| run.offset + run.size()).unwrap();
+        let second = layout.element(1, 0).unwrap();
+        assert!(second.offset >= first_end, "{:?} starts inside the first panel's quads", second);
+        assert_ne!(layout.element(1, 0), layout.element(0, 0));
+    }
+
+    #[test]
+    fn runs_never_overlap() {
+        let panels = [panel(None, 3), panel(Some("#00ff00"), 1), panel(None, 0), panel(Some("#0000ff"), 2)];
+        let layout = QuadLayout::new(&panels, &[2, 0, 3, 1]);
+
+        let mut ranges = ranges(&layout);
+        ranges.sort_by_key(|range| range.start);
+        for pair in ranges.windows(2) {
+            assert!(pair[0].end <= pair[1].start, "{:?} and {:?} overlap", pair[0], pair[1]);
+        }
+        assert_eq!(ranges.last().unwrap().end, layout.size);
+    }
+
+    #[test]
+    fn appended_elements_go_after_everything_else() {
+        let mut panels = [panel(Some("#ff0000"), 2), panel(None, 1)];
+        let mut layout = QuadLayout::new(&panels, &[0, 1]);
+        let end = layout.size;
+        for _ in 0..2 {
+            panels[0].add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid"));
+        }
+
+        assert!(!layout.append_elements(&panels[0], 0, 2..4, end), "appended past the buffer's capacity");
+        assert_eq!(layout.size, end);
+        assert!(layout.append_elements(&panels[0], 0, 2..4, end * 2));
+        assert_eq!(layout.element(0, 2).unwrap().offset, end);
+        assert_eq!(layout.panels[0].element_order, vec![0, 1, 2, 3]);
+        let mut ranges = ranges(&layout);
+        ranges.sort_by_key(|range| range.start);
+        for pair in ranges.windows(2) {
+            assert!(pair[0].end <= pair[1].start, "{:?} and {:?} overlap", pair[0], pair[1]);
+        }
+        // Elements the layout never had can't be appended after
+        assert!(!layout.append_elements(&panels[1], 1, 2..4, end * 2));
+
+        // One under the others goes first in draw order, wherever it is in the buffer
+        panels[0].add_element(Element::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), "solid").with_layer(-1));
+        assert!(layout.append_elements(&panels[0], 0, 4..5, end * 2));
+        assert_eq!(layout.panels[0].element_order, vec![4, 0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,548 @@
+use std::{collections::{BTreeMap, HashSet}, ops::Range};
+
+use wgpu::{Device, Queue, util::DeviceExt};
+
+use wgpu_text::{glyph_brush::{ab_glyph::FontArc, GlyphBrushBuilder, OwnedSection, Section}, BrushBuilder, TextBrush};
+use winit::dpi::PhysicalSize;
+
+use crate::{definitions::{RenderMode, UiAtlas}, gpu::create_scoped};
+
+use super::{animation, custom_draw::{CustomDrawCtx, CustomPrepareCtx, UiPassState}, element::{Element, Widget}, font::Fonts, interface::{element_state, UiTree}, layout::{round_corners, Wrap}, panel::Panel, quad_layout::{QuadLayout, QuadRun, QUAD_BUFFER_SIZE}, text};
+
+/// The GPU side of the interface: the vertex and index buffers and the text brushes a `UiTree` is drawn with.
+/// `RenderState` owns one and keeps it over rebuilds, so a new tree reuses its buffers and brushes.
+///
+/// `upload` lays a tree out and writes it, `render` draws what was last uploaded. A tree that hasn't been
+/// uploaded yet, such as one that just replaced the last, draws nothing until it is.
+pub struct UiRenderer {
+    /// Sized by the first `upload`, grown when a tree needs more.
+    vertex_buffer: Option<wgpu::Buffer>,
+    /// Where each panel's and element's quads are in `vertex_buffer`, from the last upload.
+    quad_layout: QuadLayout,
+    index_buffer: wgpu::Buffer,
+    /// One text brush per panel layer in use, sorted by layer, so `render` can draw each layer's text
+    /// before the panels above it.
+    text_layers: Vec<TextLayer>,
+    /// Surface format the text brushes are built for, and that custom draws are prepared for.
+    text_format: wgpu::TextureFormat,
+    /// Fonts the text brushes hold, they're rebuilt when a tree brings others.
+    text_fonts: Fonts,
+    /// Samples per pixel of the pass the interface is drawn in.
+    sample_count: u32,
+    /// Instance of the tree `quad_layout` and the buffers were written for, see `UiTree::instance`.
+    uploaded: Option<u64>,
+    #[cfg(feature = "validate-ui")]
+    validator: super::validate::UiValidator,
+}
+
+struct TextLayer {
+    layer: i32,
+    brush: TextBrush<FontArc>,
+    /// What `brush` was last given, so `upload_appended` can queue it again with the new elements' text.
+    sections: Vec<OwnedSection>,
+}
+
+impl UiRenderer {
+    /// Draws into passes of `format` with `sample_count` samples per pixel, as the UI pipeline does.
+    pub fn new(device: &Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let indices: &[u16] = &[0, 2, 1, 1, 2, 3];
+        Self {
+            vertex_buffer: None,
+            quad_layout: QuadLayout::default(),
+            index_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("UI Quad Index Buffer"),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            text_layers: Vec::new(),
+            text_format: format,
+            text_fonts: Fonts::default(),
+            sample_count,
+            uploaded: None,
+            #[cfg(feature = "validate-ui")]
+            validator: Default::default(),
+        }
+    }
+
+    /// Size of the vertex buffer in bytes, 0 before the first `upload`.
+    pub fn vertex_buffer_size(&self) -> u64 {
+        self.vertex_buffer.as_ref().map_or(0, wgpu::Buffer::size)
+    }
+
+    /// Lays `tree` out for a `screen_size` window and writes every quad and every layer's text, after which `render`
+    /// draws it. Layout results are kept on the tree for hit-testing and the next single-element upload: clamped
+    /// scroll offsets, truncated text, render target rects and custom draw rects, which is why it takes `&mut`.
+    pub fn upload<E>(&mut self, tree: &mut UiTree<E>, queue: &Queue, device: &Device, screen_size: PhysicalSize<u32>) {
+        tree.request_redraw();
+        self.uploaded = Some(tree.instance);
+        // Panels come and go between uploads, with a dropdown or a rebuild into a bigger tree.
+        // Growing leaves room for half as many quads again, so a menu opening doesn't reallocate it every time.
+        self.quad_layout = QuadLayout::new(&tree.panels, &tree.draw_order());
+        let needed = self.quad_layout.size;
+        if self.vertex_buffer.as_ref().is_none_or(|buffer| buffer.size() < needed) {
+            let slack = needed / QUAD_BUFFER_SIZE / 2 * QUAD_BUFFER_SIZE;
+            let elements = tree.panels.iter().map(|panel| panel.elements.len()).sum::<usize>();
+            let label = format!("UI Vertex Buffer ({} panels, {} elements, {} quads)", tree.panels.len(), elements, (needed + slack) / QUAD_BUFFER_SIZE);
+            self.vertex_buffer = Some(create_vertex_buffer(device, needed + slack, &label));
+        }
+        // Brushes hold their own copy of the font list, so another set means new brushes
+        if !tree.fonts.same_as(&self.text_fonts) {
+            self.text_fonts = tree.fonts.clone();
+            self.text_layers.clear();
+        }
+        tree.screen_size = screen_size;
+        tree.render_target_rects.clear();
+        if tree.measure.is_none() && !tree.fonts.is_empty() {
+            tree.measure = Some(GlyphBrushBuilder::using_fonts(tree.fonts.fonts().to_vec()).build());
+        }
+        let sections_by_layer = self.write_panels(tree, queue, device, None);
+
+        if self.text_fonts.is_empty() {
+            self.text_layers.clear();
+            return;
+        }
+        self.text_layers.retain(|text_layer| sections_by_layer.contains_key(&text_layer.layer));
+        for (layer, sections) in sections_by_layer {
+            let index = match self.text_layers.binary_search_by_key(&layer, |text_layer| text_layer.layer) {
+                Ok(index) => index,
+                Err(index) => {
+                    let brush = BrushBuilder::using_fonts(self.text_fonts.fonts().to_vec())
+                        .with_multisample(wgpu::MultisampleState { count: self.sample_count, mask: !0, alpha_to_coverage_enabled: false })
+                        .build(device, screen_size.width, screen_size.height, self.text_format);
+                    self.text_layers.insert(index, TextLayer { layer, brush, sections: Vec::new() });
+                    index
+                }
+            };
+            let text_layer = &mut self.text_layers[index];
+            text_layer.sections = sections.iter().map(Section::to_owned).collect();
+            text_layer.brush.resize_view(screen_size.width as f32, screen_size.height as f32, queue);
+            text_layer.brush.queue(device, queue, sections).unwrap();
+        }
+    }
+
+    /// Writes the quads of the elements in `appended` of the panel at `panel_index`, added with
+    /// `UiTree::append_elements` since the last `upload`, and queues their text. The rest of the buffer is left as it
+    /// is, only a layer the new elements show text on has its text queued again. Returns false, writing nothing, when
+    /// the tree hasn't been uploaded or the buffer has no room for them, and a full upload is needed instead.
+    pub fn upload_appended<E>(&mut self, tree: &mut UiTree<E>, panel_index: usize, appended: Range<usize>, queue: &Queue, device: &Device) -> bool {
+        let Some(capacity) = self.vertex_buffer.as_ref().filter(|_| self.uploaded == Some(tree.instance)).map(wgpu::Buffer::size) else {
+            return false;
+        };
+        let Some(panel) = tree.panels.get(panel_index) else {
+            return false;
+        };
+        if !self.quad_layout.append_elements(panel, panel_index, appended.clone(), capacity) {
+            return false;
+        }
+        tree.request_redraw();
+        let sections_by_layer = self.write_panels(tree, queue, device, Some((panel_index, appended)));
+
+        for (layer, sections) in sections_by_layer.into_iter().filter(|(_, sections)| !sections.is_empty()) {
+            let Some(text_layer) = self.text_layers.iter_mut().find(|text_layer| text_layer.layer == layer) else {
+                continue;
+            };
+            text_layer.sections.extend(sections.iter().map(Section::to_owned));
+            text_layer.brush.queue(device, queue, text_layer.sections.iter().map(OwnedSection::to_borrowed)).unwrap();
+        }
+        true
+    }
+
+    /// Writes the quads of every panel and element to where `quad_layout` puts them, which `render` reads back, and
+    /// returns the text to queue by layer. Every panel layer gets an entry, even without text, so text it no longer
+    /// has is cleared. With `appended`, only those elements of that panel are written.
+    fn write_panels<'t, E>(
+        &mut self,
+        tree: &'t mut UiTree<E>,
+        queue: &Queue,
+        device: &Device,
+        appended: Option<(usize, Range<usize>)>,
+    ) -> BTreeMap<i32, Vec<Section<'t>>> {
+        let screen_size = tree.screen_size;
+        let mut sections_by_layer: BTreeMap<i32, Vec<Section>> = BTreeMap::new();
+        // Taking references out rather than indexing lets the queued sections keep borrowing text from every element
+        let mut panels: Vec<Option<&mut Panel<E>>> = tree.panels.iter_mut().map(Some).collect();
+        for panel_quads in &self.quad_layout.panels {
+            if appended.as_ref().is_some_and(|(panel_index, _)| *panel_index != panel_quads.panel) {
+                continue;
+            }
+            let Some(panel) = panels[panel_quads.panel].take() else {
+                continue;
+            };
+            let sections_to_queue = sections_by_layer.entry(panel.layer).or_default();
+            panel.clamp_scroll(screen_size);
+            let panel_rect = panel.drawn_rect(screen_size);
+
+            if RenderMode::for_texture(&panel.texture_name) != RenderMode::SolidColor {
+                warn_missing_texture(&tree.atlas, &mut tree.missing_textures, &panel.texture_name);
+            }
+            let panel_tex_coords = tree.atlas.uv_quad(&panel.texture_name).unwrap_or([[0.0, 0.0]; 4]);
+            let panel_corner_radius = panel.corner_radius * panel.pixel_scale;
+
+            if panel_quads.background.quads > 0 && appended.is_none() {
+                let corner_colors = panel.corner_colors(&tree.theme);
+                let mut panel_vertices = panel_rect.vertices(
+                    corner_colors[0],
+                    panel_tex_coords,
+                    RenderMode::for_texture(&panel.texture_name),
+                );
+                for (vertex, color) in panel_vertices.iter_mut().zip(corner_colors) {
+                    vertex.color = color;
+                }
+                round_corners(&mut panel_vertices, panel_corner_radius, 0.0);
+                animation::fade(&mut panel_vertices, panel.animated.opacity);
+
+                #[cfg(feature = "validate-ui")]
+                self.validator.check_element(
+                    &format!("panel at ({:?}, {:?})", panel.start_coordinate.x, panel.start_coordinate.y),
+                    &panel.texture_name,
+                    &panel_vertices,
+                    tree.atlas.get(&panel.texture_name),
+                );
+
+                queue.write_buffer(
+                    self.vertex_buffer.as_ref().unwrap(),
+                    panel_quads.background.offset,
+                    bytemuck::cast_slice(&panel_vertices),
+                );
+            }
+
+            // Scrolling moves the frame elements are laid out in, scrollable panels also clip to their own rect
+            let content_rect = panel.content_rect(panel_rect);
+            let frames = panel.element_frames(content_rect);
+            let clip_rect = panel.scroll.is_some().then_some(panel_rect);
+            let text_clip = panel.clip_children.then(|| panel_rect.to_pixel_rect(screen_size));
+            let panel_style = panel.style.or(&tree.style);
+            let panel_state = panel.own_state();
+            let pixel_scale = panel.pixel_scale;
+            let panel_opacity = panel.animated.opacity;
+
+            let mut elements: Vec<Option<&mut Element<E>>> = panel.elements.iter_mut().map(Some).collect();
+            for &element_idx in &panel_quads.element_order {
+                if appended.as_ref().is_some_and(|(_, range)| !range.contains(&element_idx)) {
+                    continue;
+                }
+                let Some(element) = elements[element_idx].take() else {
+                    continue;
+                };
+                let frame = frames[element_idx];
+                let element_rect = element.animated.apply(element.layout_rect(frame, screen_size, pixel_scale), pixel_scale);
+                let opacity = panel_opacity * element.animated.opacity;
+                let style = element.style.or(&panel_style).themed(&tree.theme).with_text_scale_factor(tree.text_scale_factor).with_scale_factor(tree.scale_factor);
+                let state = element_state(&tree.groups, panel_state, element);
+                if element.render_target.is_none() && element.render_mode != RenderMode::SolidColor {
+                    warn_missing_texture(&tree.atlas, &mut tree.missing_textures, &element.texture_name);
+                }
+                let mut new_vertices = element.vertices(element_rect, &style, state, &tree.atlas, pixel_scale, clip_rect);
+                // Render targets aren't in the atlas, their fill samples the whole target texture
+                #[cfg(feature = "validate-ui")]
+                if element.render_target.is_none() {
+                    self.validator.check_element(
+                        &element.id().map(str::to_string).unwrap_or_else(|| {
+                            format!("{} element at ({:?}, {:?})", element.texture_name, element.start_coordinate.x, element.start_coordinate.y)
+                        }),
+                        &element.texture_name,
+                        &new_vertices,
+                        tree.atlas.get(&element.texture_name),
+                    );
+                }
+                animation::fade(&mut new_vertices, opacity);
+                let scale = style.text_scale();
+                let visible_text = element.text.as_ref()
+                    .map(|content| (element.kind.visible_text(content, scale, element_rect.x_max - element_rect.x_min), scale));
+                let visible_rect = match clip_rect {
+                    Some(clip_rect) => clip_rect.intersect(&element_rect),
+                    None => Some(element_rect),
+                }.filter(|_| state.visible);
+                if let Some(name) = &element.render_target
+                    && visible_rect.is_some() {
+                    // The whole element, the target keeps its size while a scrolled panel clips part of it
+                    tree.render_target_rects.insert(name.clone(), element_rect.to_pixel_rect(screen_size));
+                }
+                if let Some(slot) = element.custom_draw.as_mut() {
+                    // A culled element keeps an empty rect, which `render` skips
+                    slot.pixel_rect = visible_rect.map(|rect| rect.to_pixel_rect(screen_size)).unwrap_or_default();
+                    if visible_rect.is_some() {
+                        slot.hook.prepare(&CustomPrepareCtx {
+                            device,
+                            queue,
+                            pixel_rect: slot.pixel_rect,
+                            format: self.text_format,
+                            sample_count: self.sample_count,
+                        });
+                    }
+                }
+                queue.write_buffer(
+                    self.vertex_buffer.as_ref().unwrap(),
+                    panel_quads.elements[element_idx].offset,
+                    bytemuck::cast_slice(&new_vertices),
+                );
+
+                // Without fonts there's nothing to draw text with
+                if visible_rect.is_some() && let (Some(text_align), Some((visible, scale)), Some(measure)) = (&element.text_alignment, visible_text, tree.measure.as_mut()) {
+                    // Aligned within the element's own unsnapped rect, whatever units its coordinates are in
+                    let text_rect = element.animated.apply(element.calculate_rect_relative_to_panel(frame.x_min, frame.y_bottom, frame.x_max, frame.y_top, pixel_scale), pixel_scale)
+                        .to_pixel_rect(screen_size);
+                    let font = tree.fonts.resolve_weight(element.font, style.font_weight());
+                    let text_width = text::available_width(text_rect.width, text_align, style.padding(), scale);
+                    element.fitted_text = match element.text_wrap {
+                        Some(Wrap::Truncate) => text::truncate(measure, visible, font, scale, text_width, ""),
+                        Some(Wrap::TruncateWithEllipsis) => text::truncate(measure, visible, font, scale, text_width, "…"),
+                        Some(Wrap::Word) | None => None,
+                    };
+                    let visible = element.fitted_text.as_deref().unwrap_or(visible);
+
+                    let text_color = element.text_color(&style, state).into_vec4();
+                    element.queued_text_color = Some(text_color);
+                    let text_color = [text_color[0], text_color[1], text_color[2], text_color[3] * opacity];
+                    if let Some(section) = element.kind.queue_text(visible, font, scale, text_color, (0.0, 0.0)) {
+                        let section = match element.text_wrap {
+                            Some(Wrap::Word) => text::wrap_section(section, text_width),
+                            _ => section,
+                        };
+                        let (adjusted_x, adjusted_y) = text::aligned_position(measure, &section, text_rect, text_align, scale);
+                        let (adjusted_x, adjusted_y) = text::apply_padding((adjusted_x, adjusted_y), text_align, style.padding());
+
+                        let screen_position = if element.snap {
+                            (adjusted_x.round(), adjusted_y.round())
+                        } else {
+                            (adjusted_x, adjusted_y)
+                        };
+
+                        let section = Section { screen_position, ..section };
+                        // Shadow and outline copies go first so the brush draws them beneath the text
+                        let underlays = match &style.text_style {
+                            Some(text_style) => {
+                                // Snapped text sits on whole pixels, a fractional offset would blur its shadow
+                                let underlays: Vec<_> = text_style.underlays().into_iter()
+                                    .map(|((dx, dy), [r, g, b, a])| {
+                                        let offset = if element.snap { (dx.round(), dy.round()) } else { (dx, dy) };
+                                        (offset, [r, g, b, a * opacity])
+                                    })
+                                    .collect();
+                                text::underlay_sections(&section, &underlays)
+                            }
+                            None => Vec::new(),
+                        };
+                        // The scissor rect doesn't reach the text brush, which draws after every panel of the layer
+                        sections_to_queue.extend(underlays.into_iter().chain([section]).filter_map(|section| match text_clip {
+                            Some(clip) => text::clip_section(section, clip),
+                            None => Some(section),
+                        }));
+                    }
+                }
+            }
+
+            if let Some(border) = panel.border.as_ref().filter(|_| appended.is_none()) {
+                let mut border_vertices = panel_rect.border_vertices(border, panel.pixel_scale, panel_corner_radius);
+                animation::fade(&mut border_vertices, panel_opacity);
+                queue.write_buffer(self.vertex_buffer.as_ref().unwrap(), panel_quads.foreground.offset, bytemuck::cast_slice(&border_vertices));
+            }
+        }
+        sections_by_layer
+    }
+
+    /// Rewrites only one element's quads, for changes such as hover that leave layout and text alone.
+    /// Layout is reused from the last `upload`, so this returns false, writing nothing, when the element
+    /// hasn't been through one yet or its text would change color, and needs a full upload instead.
+    pub fn upload_element<E>(&self, tree: &UiTree<E>, panel_index: usize, element_index: usize, queue: &Queue) -> bool {
+        let Some(vertex_buffer) = self.vertex_buffer.as_ref().filter(|_| self.uploaded == Some(tree.instance)) else {
+            return false;
+        };
+        let Some(panel) = tree.panels.get(panel_index) else {
+            return false;
+        };
+        let (Some(element), Some(run)) = (panel.elements.get(element_index), self.quad_layout.element(panel_index, element_index)) else {
+            return false;
+        };
+
+        let screen_size = tree.screen_size;
+        let panel_rect = panel.drawn_rect(screen_size);
+        let frame = panel.element_frames(panel.content_rect(panel_rect))[element_index];
+        let element_rect = element.animated.apply(element.layout_rect(frame, screen_size, panel.pixel_scale), panel.pixel_scale);
+        let style = element.style.or(&panel.style.or(&tree.style)).themed(&tree.theme).with_text_scale_factor(tree.text_scale_factor).with_scale_factor(tree.scale_factor);
+        let state = element_state(&tree.groups, panel.own_state(), element);
+        if element.queued_text_color.is_some_and(|color| color != element.text_color(&style, state).into_vec4()) {
+            return false;
+        }
+        let mut vertices = element.vertices(
+            element_rect,
+            &style,
+            state,
+            &tree.atlas,
+            panel.pixel_scale,
+            panel.scroll.is_some().then_some(panel_rect),
+        );
+        animation::fade(&mut vertices, panel.animated.opacity * element.animated.opacity);
+        queue.write_buffer(vertex_buffer, run.offset, bytemuck::cast_slice(&vertices));
+        tree.request_redraw();
+        true
+    }
+
+    /// Rewrites only the quads of the group's members, see `upload_element`.
+    /// Returns false when a member hasn't been laid out yet and a full upload is needed.
+    pub fn upload_group<E>(&self, tree: &UiTree<E>, name: &str, queue: &Queue) -> bool {
+        let members = tree.groups.members(name);
+        let mut updated = true;
+        for (panel_idx, panel) in tree.panels.iter().enumerate() {
+            for (element_idx, element) in panel.elements.iter().enumerate() {
+                if element.id().is_some_and(|id| members.iter().any(|member| member == id)) {
+                    updated &= self.upload_element(tree, panel_idx, element_idx, queue);
+                }
+            }
+        }
+        updated
+    }
+
+    /// Draws every visible panel of `tree` from the last upload, each layer's text over its panels.
+    /// Returns the `draw_indexed` calls issued, one per quad.
+    pub(crate) fn render<'a, E>(
+        &'a self,
+        tree: &UiTree<E>,
+        renderpass: &mut wgpu::RenderPass<'a>,
+        pass_state: &UiPassState,
+        device: &Device,
+        queue: &Queue,
+        screen_size: PhysicalSize<u32>,
+    ) -> u32 {
+        let vertex_buffer = match &self.vertex_buffer {
+            Some(buffer) if self.uploaded == Some(tree.instance) => buffer,
+            _ => {
+                log::debug!("The UI tree hasn't been uploaded yet, skipping it this frame");
+                return 0;
+            }
+        };
+        let index_buffer = &self.index_buffer;
+        let mut draw_calls = 0;
+        renderpass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        // Drawn from the layout the buffer was written with, panels added since the last upload wait for the next one
+        let layout = &self.quad_layout.panels;
+        for (position, panel_quads) in layout.iter().enumerate() {
+            let Some(panel) = tree.panels.get(panel_quads.panel) else {
+                continue;
+            };
+            'quads: {
+                if !panel.visible {
+                    break 'quads;
+                }
+                draw_calls += draw_run(renderpass, vertex_buffer, panel_quads.background);
+
+                let panel_pixel_rect = panel.drawn_rect(screen_size).to_pixel_rect(screen_size);
+                let panel_scissor = if panel.clip_children {
+                    match panel_pixel_rect.to_scissor(screen_size) {
+                        Some(scissor) => scissor,
+                        // Entirely off-screen, and wgpu rejects a zero-sized scissor rect
+                        None => break 'quads,
+                    }
+                } else {
+                    (0, 0, screen_size.width, screen_size.height)
+                };
+                renderpass.set_scissor_rect(panel_scissor.0, panel_scissor.1, panel_scissor.2, panel_scissor.3);
+
+                let panel_state = panel.own_state();
+                for &element_idx in &panel_quads.element_order {
+                    let Some(element) = panel.elements.get(element_idx) else {
+                        continue;
+                    };
+                    if !element_state(&tree.groups, panel_state, element).visible {
+                        continue;
+                    }
+                    let run = panel_quads.elements[element_idx];
+                    match &element.render_target {
+                        Some(name) => {
+                            // Only the fill samples the target, the widget and border quads stay on the atlas
+                            if let Some(target) = pass_state.render_targets.get(name) {
+                                renderpass.set_bind_group(1, target.bind_group(), &[]);
+                                draw_calls += draw_run(renderpass, vertex_buffer, run.first(1));
+                                renderpass.set_bind_group(1, pass_state.material_bind_group, &[]);
+                            }
+                            draw_calls += draw_run(renderpass, vertex_buffer, run.skip(1));
+                        }
+                        None => draw_calls += draw_run(renderpass, vertex_buffer, run),
+                    }
+
+                    if let Some(slot) = element.custom_draw.as_ref() {
+                        let draw_rect = if panel.clip_children { slot.pixel_rect.intersect(panel_pixel_rect) } else { slot.pixel_rect };
+                        let Some((x, y, width, height)) = draw_rect.to_scissor(screen_size) else {
+                            continue;
+                        };
+                        renderpass.set_scissor_rect(x, y, width, height);
+                        slot.hook.draw(&mut CustomDrawCtx {
+                            render_pass: renderpass,
+                            pixel_rect: slot.pixel_rect,
+                            device,
+                            queue,
+                            sample_count: self.sample_count,
+                        });
+
+                        renderpass.set_scissor_rect(panel_scissor.0, panel_scissor.1, panel_scissor.2, panel_scissor.3);
+                        renderpass.set_viewport(0.0, 0.0, screen_size.width as f32, screen_size.height as f32, 0.0, 1.0);
+                        pass_state.apply(renderpass);
+                        renderpass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                    }
+                }
+
+                draw_calls += draw_run(renderpass, vertex_buffer, panel_quads.foreground);
+            }
+            renderpass.set_scissor_rect(0, 0, screen_size.width, screen_size.height);
+
+            // A layer's text goes over all of its panels and under everything on the layers above
+            let last_of_layer = layout.get(position + 1)
+                .and_then(|next| tree.panels.get(next.panel))
+                .is_none_or(|next| next.layer != panel.layer);
+            if last_of_layer && let Some(text_layer) = self.text_layers.iter().find(|text_layer| text_layer.layer == panel.layer) {
+                text_layer.brush.draw(renderpass);
+                pass_state.apply(renderpass);
+                renderpass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            }
+        }
+        draw_calls
+    }
+}
+
+/// `label` names the tree it was sized for, so errors about it say which.
+fn create_vertex_buffer(device: &Device, size: wgpu::BufferAddress, label: &str) -> wgpu::Buffer {
+    create_scoped(device, label, || device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    }))
+}
+
+/// Draws the next `count` quads of the vertex buffer, one at a time since the index buffer holds a single quad.
+fn draw_run<'a>(renderpass: &mut wgpu::RenderPass<'a>, vertex_buffer: &'a wgpu::Buffer, run: QuadRun) -> u32 {
+    for quad in 0..run.quads {
+        let offset = run.offset + quad as wgpu::BufferAddress * QUAD_BUFFER_SIZE;
+        renderpass.set_vertex_buffer(0, vertex_buffer.slice(offset..offset + QUAD_BUFFER_SIZE));
+        renderpass.draw_indexed(0..6, 0, 0..1);
+    }
+    run.quads as u32
+}
+
+/// Logs that `name` isn't in the atlas, once per name until the atlas is replaced. It's drawn with the missing
+/// texture instead.
+fn warn_missing_texture(atlas: &UiAtlas, missing_textures: &mut HashSet<String>, name: &str) {
+    if atlas.get(name).is_none() && missing_textures.insert(name.to_string()) {
+        log::warn!("texture {:?} isn't in the UI atlas, drawing it with the missing texture", name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::definitions::UiAtlasTexture;
+
+    #[test]
+    fn missing_textures_are_recorded_once_per_name() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("button".to_string(), 0, 0, 1, 1));
+        let mut missing_textures = HashSet::new();
+        for _ in 0..3 {
+            warn_missing_texture(&atlas, &mut missing_textures, "button");
+            warn_missing_texture(&atlas, &mut missing_textures, "ghost");
+        }
+        assert_eq!(missing_textures, HashSet::from(["ghost".to_string()]));
+    }
+}
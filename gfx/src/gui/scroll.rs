@@ -0,0 +1,86 @@
+use glam::Vec2;
+use winit::{dpi::PhysicalPosition, event::MouseScrollDelta};
+
+/// Logical pixels one line of a notched wheel is worth. Trackpads report pixels, so this is also how many
+/// of theirs make up a line.
+pub const PIXELS_PER_LINE: f64 = 40.0;
+
+/// Fraction of a scrollable panel's height one line of scrolling moves it, see `UiTree::handle_scroll`.
+pub const SCROLL_LINE: f32 = 0.12;
+
+/// A wheel or trackpad scroll in both units, whichever the device reported, so notched wheels and trackpads
+/// scroll alike. Positive deltas move further into the content, right and down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollEvent {
+    pub delta_lines: Vec2,
+    /// Physical pixels.
+    pub delta_pixels: Vec2,
+    /// Where the cursor was, in window pixels.
+    pub position: PhysicalPosition<f64>,
+}
+
+impl ScrollEvent {
+    /// Normalizes a `WindowEvent::MouseWheel` delta. A line is `PIXELS_PER_LINE` logical pixels at
+    /// `scale_factor`. With `shift` held, a wheel that only turns vertically scrolls sideways instead.
+    pub fn from_wheel(delta: MouseScrollDelta, position: PhysicalPosition<f64>, scale_factor: f64, shift: bool) -> Self {
+        let line_pixels = PIXELS_PER_LINE * scale_factor;
+        let (x, y) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (x as f64 * line_pixels, y as f64 * line_pixels),
+            MouseScrollDelta::PixelDelta(pixels) => (pixels.x, pixels.y),
+        };
+        // winit gives the way the content moves, wheel up is positive and should go back towards the top
+        let mut delta_pixels = -Vec2::new(x as f32, y as f32);
+        if shift && delta_pixels.x == 0.0 {
+            delta_pixels = Vec2::new(delta_pixels.y, 0.0);
+        }
+        Self {
+            delta_lines: delta_pixels / line_pixels as f32,
+            delta_pixels,
+            position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURSOR: PhysicalPosition<f64> = PhysicalPosition::new(10.0, 20.0);
+
+    #[test]
+    fn a_wheel_notch_is_a_line_at_any_scale() {
+        let notch_down = MouseScrollDelta::LineDelta(0.0, -1.0);
+        let event = ScrollEvent::from_wheel(notch_down, CURSOR, 1.0, false);
+        assert_eq!(event.delta_lines, Vec2::new(0.0, 1.0));
+        assert_eq!(event.delta_pixels, Vec2::new(0.0, 40.0));
+        assert_eq!(event.position, CURSOR);
+
+        let scaled = ScrollEvent::from_wheel(notch_down, CURSOR, 2.0, false);
+        assert_eq!(scaled.delta_lines, Vec2::new(0.0, 1.0));
+        assert_eq!(scaled.delta_pixels, Vec2::new(0.0, 80.0));
+    }
+
+    #[test]
+    fn trackpad_pixels_count_as_lines_of_their_logical_size() {
+        let swipe_up = MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 60.0));
+        let event = ScrollEvent::from_wheel(swipe_up, CURSOR, 1.5, false);
+        assert_eq!(event.delta_pixels, Vec2::new(0.0, -60.0));
+        assert_eq!(event.delta_lines, Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn shift_turns_a_vertical_wheel_sideways() {
+        let event = ScrollEvent::from_wheel(MouseScrollDelta::LineDelta(0.0, -2.0), CURSOR, 1.0, true);
+        assert_eq!(event.delta_lines, Vec2::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn native_horizontal_scrolls_are_kept_with_or_without_shift() {
+        let sideways = MouseScrollDelta::PixelDelta(PhysicalPosition::new(-40.0, 20.0));
+        for shift in [false, true] {
+            let event = ScrollEvent::from_wheel(sideways, CURSOR, 1.0, shift);
+            assert_eq!(event.delta_pixels, Vec2::new(40.0, -20.0));
+            assert_eq!(event.delta_lines, Vec2::new(1.0, -0.5));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use winit::window::CursorIcon;
+
+use super::layout::Dimension;
+
+/// Width of the divider between two split panes, in pixels scaled like `Dimension::Pixels`. It straddles the boundary, overlapping both.
+pub const DIVIDER_THICKNESS: f32 = 4.0;
+
+/// Smallest a pane can be dragged to along its split, in pixels scaled like `Dimension::Pixels`. A pane that is itself a split
+/// needs room for the minimum of each pane inside it.
+pub const MIN_PANE_SIZE: f32 = 48.0;
+
+/// Id `UiTree::add_split` gives the split of `first` and `second`.
+pub(crate) fn split_id(first: &str, second: &str) -> String {
+    format!("split:{}|{}", first, second)
+}
+
+pub(crate) const DIVIDER_COLOR: &str = "#30363dff";
+pub(crate) const DIVIDER_HOVER_COLOR: &str = "#1f6febff";
+
+/// How a split lays out its panes, named after the divider: `Vertical` puts them side by side,
+/// `Horizontal` stacks them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitDirection {
+    Vertical,
+    Horizontal,
+}
+
+impl SplitDirection {
+    /// Edge of the first pane and edge of the second that meet at the divider.
+    pub(crate) fn inner_edges(self) -> (Edge, Edge) {
+        match self {
+            SplitDirection::Vertical => (Edge::Right, Edge::Left),
+            SplitDirection::Horizontal => (Edge::Bottom, Edge::Top),
+        }
+    }
+
+    /// Edges both panes share, across the split.
+    pub(crate) fn cross_edges(self) -> [Edge; 2] {
+        match self {
+            SplitDirection::Vertical => [Edge::Top, Edge::Bottom],
+            SplitDirection::Horizontal => [Edge::Left, Edge::Right],
+        }
+    }
+
+    /// Resize arrow shown over the divider.
+    pub fn cursor_icon(self) -> CursorIcon {
+        match self {
+            SplitDirection::Vertical => CursorIcon::ColResize,
+            SplitDirection::Horizontal => CursorIcon::RowResize,
+        }
+    }
+}
+
+/// Side of a panel or pane, see `Panel::edge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+impl Edge {
+    /// Left and top come from a pane's start coordinate, right and bottom from its end.
+    pub(crate) fn is_start(self) -> bool {
+        matches!(self, Edge::Left | Edge::Top)
+    }
+
+    /// Left and right are positions across the window's width, top and bottom across its height.
+    pub(crate) fn is_x(self) -> bool {
+        matches!(self, Edge::Left | Edge::Right)
+    }
+}
+
+/// Two panes and the divider between them, see `UiTree::add_split`.
+pub(crate) struct Split {
+    pub(crate) id: String,
+    pub(crate) direction: SplitDirection,
+    /// Panel or split id of the left or top pane.
+    pub(crate) first: String,
+    pub(crate) second: String,
+    /// Where the divider sits between the outer edges of the panes, 0.0 at the start of `first`.
+    pub(crate) ratio: f32,
+    /// Id of the panel drawing the divider.
+    pub(crate) divider: String,
+}
+
+/// `ratio` of the way from `start` to `end`. Every `Dimension` is linear in its parent's extent,
+/// so the mix stays in the same place between them at any window size.
+pub(crate) fn lerp_dimension(start: Dimension, end: Dimension, ratio: f32) -> Dimension {
+    let (start_fraction, start_pixels) = start.parts();
+    let (end_fraction, end_pixels) = end.parts();
+    Dimension::Anchored {
+        fraction: start_fraction + (end_fraction - start_fraction) * ratio,
+        pixels: start_pixels + (end_pixels - start_pixels) * ratio,
+    }
+}
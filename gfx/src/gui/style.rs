@@ -0,0 +1,379 @@
+use super::{color::Color, theme::{ColorRole, Theme}};
+
+pub(crate) const DEFAULT_TEXT_SCALE: f32 = 0.7;
+const DEFAULT_HOVER_COLOR: &str = "#999999ff";
+const DEFAULT_DISABLED_COLOR: &str = "#484f58ff";
+const DEFAULT_PRESSED_COLOR: &str = "#6e7681ff";
+const DEFAULT_FOCUS_COLOR: &str = "#1f6febff";
+const DEFAULT_SELECTED_COLOR: &str = "#1f6febff";
+
+/// Solid frame drawn just inside the edges of an element or panel, on top of its fill.
+#[derive(Clone)]
+pub struct Border {
+    /// In pixels, scaled like `Dimension::Pixels`.
+    pub thickness: f32,
+    pub color: Color,
+}
+
+impl Border {
+    pub fn new(thickness: f32, color: &str) -> Self {
+        Self { thickness, color: Color::from_hex(color) }
+    }
+}
+
+/// How a weight of text should look, a hint the interface's `Fonts` honor when the font has that weight
+/// registered with `Fonts::set_weight`, and ignore otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FontWeight {
+    #[default]
+    Regular,
+    Bold,
+}
+
+/// Everything about how an element's text is drawn, as `Element::with_text` takes it. A plain scale converts into
+/// one, with the color left to the theme. The shadow and outline are extra copies of the text drawn beneath it
+/// in a darker color, so it stays readable over busy or light backgrounds.
+#[derive(Clone, Default)]
+pub struct TextStyle {
+    /// Replaces the theme's text color, see `Style::text_color`.
+    pub color: Option<Color>,
+    /// Like `color`, from the theme.
+    pub color_role: Option<ColorRole>,
+    pub scale: Option<f32>,
+    pub weight_hint: Option<FontWeight>,
+    /// Offset in pixels, scaled like `Dimension::Pixels` and positive down and to the right, and color of a single copy.
+    pub shadow: Option<((f32, f32), Color)>,
+    /// Color of a 1px outline, drawn as eight copies around the text.
+    pub outline: Option<Color>,
+}
+
+impl TextStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(Color::from_hex(color));
+        self.color_role = None;
+        self
+    }
+
+    pub fn with_color_role(mut self, role: ColorRole) -> Self {
+        self.color_role = Some(role);
+        self.color = None;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn with_weight_hint(mut self, weight: FontWeight) -> Self {
+        self.weight_hint = Some(weight);
+        self
+    }
+
+    pub fn with_shadow(mut self, offset: (f32, f32), color: &str) -> Self {
+        self.shadow = Some((offset, Color::from_hex(color)));
+        self
+    }
+
+    pub fn with_outline(mut self, color: &str) -> Self {
+        self.outline = Some(Color::from_hex(color));
+        self
+    }
+
+    /// Offset and color of every copy, in the order they're drawn: the shadow under the outline.
+    pub(crate) fn underlays(&self) -> Vec<((f32, f32), [f32; 4])> {
+        let mut underlays = Vec::new();
+        if let Some((offset, color)) = &self.shadow {
+            underlays.push((*offset, color.into_vec4()));
+        }
+        if let Some(color) = &self.outline {
+            let color = color.into_vec4();
+            for dy in [-1.0, 0.0, 1.0] {
+                for dx in [-1.0, 0.0, 1.0] {
+                    if (dx, dy) != (0.0, 0.0) {
+                        underlays.push(((dx, dy), color));
+                    }
+                }
+            }
+        }
+        underlays
+    }
+}
+
+impl From<f32> for TextStyle {
+    fn from(scale: f32) -> Self {
+        Self::new().with_scale(scale)
+    }
+}
+
+/// Optional appearance defaults. An element uses its own style first, then its panel's, then the interface's,
+/// resolved every time vertices and text are generated.
+#[derive(Clone, Default)]
+pub struct Style {
+    pub color: Option<Color>,
+    /// Takes the fill from the theme instead of `color`. A style sets one or the other, see `with_color_role`.
+    pub color_role: Option<ColorRole>,
+    pub hover_color: Option<Color>,
+    /// Like `color_role`, for `hover_color`.
+    pub hover_color_role: Option<ColorRole>,
+    /// Fill of a selected element the cursor isn't over, see `UiTree::selection`.
+    pub selected_color: Option<Color>,
+    /// Like `color_role`, for `selected_color`.
+    pub selected_color_role: Option<ColorRole>,
+    /// Fill of an element while the button pressed on it is held.
+    pub pressed_color: Option<Color>,
+    /// Fill of elements in a disabled group.
+    pub disabled_color: Option<Color>,
+    /// Fill of the element Tab moved keyboard focus to, see `UiTree::focus_next`.
+    pub focus_color: Option<Color>,
+    pub text_color: Option<Color>,
+    /// Like `color_role`, for `text_color`.
+    pub text_color_role: Option<ColorRole>,
+    /// Text color of an element under the cursor, `text_color` when unset.
+    pub hover_text_color: Option<Color>,
+    /// Text color of elements in a disabled group, the theme's secondary text color by default.
+    pub disabled_text_color: Option<Color>,
+    pub text_scale: Option<f32>,
+    pub font_weight: Option<FontWeight>,
+    /// Pixels between an element's edge and its text, on the side the text is aligned to, scaled like `Dimension::Pixels`.
+    pub padding: Option<f32>,
+    /// Shadow or outline for the text, none by default. Its color, scale and weight are ignored here,
+    /// `with_text_style` moves them to the fields above.
+    pub text_style: Option<TextStyle>,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(Color::from_hex(color));
+        self.color_role = None;
+        self
+    }
+
+    /// Fills with the theme's color for `role`, replacing a literal `color`.
+    pub fn with_color_role(mut self, role: ColorRole) -> Self {
+        self.color_role = Some(role);
+        self.color = None;
+        self
+    }
+
+    pub fn with_hover_color(mut self, color: &str) -> Self {
+        self.hover_color = Some(Color::from_hex(color));
+        self.hover_color_role = None;
+        self
+    }
+
+    pub fn with_hover_color_role(mut self, role: ColorRole) -> Self {
+        self.hover_color_role = Some(role);
+        self.hover_color = None;
+        self
+    }
+
+    pub fn with_selected_color(mut self, color: &str) -> Self {
+        self.selected_color = Some(Color::from_hex(color));
+        self.selected_color_role = None;
+        self
+    }
+
+    pub fn with_selected_color_role(mut self, role: ColorRole) -> Self {
+        self.selected_color_role = Some(role);
+        self.selected_color = None;
+        self
+    }
+
+    pub fn with_pressed_color(mut self, color: &str) -> Self {
+        self.pressed_color = Some(Color::from_hex(color));
+        self
+    }
+
+    pub fn with_disabled_color(mut self, color: &str) -> Self {
+        self.disabled_color = Some(Color::from_hex(color));
+        self
+    }
+
+    pub fn with_focus_color(mut self, color: &str) -> Self {
+        self.focus_color = Some(Color::from_hex(color));
+        self
+    }
+
+    pub fn with_text_color(mut self, color: &str) -> Self {
+        self.text_color = Some(Color::from_hex(color));
+        self.text_color_role = None;
+        self
+    }
+
+    pub fn with_text_color_role(mut self, role: ColorRole) -> Self {
+        self.text_color_role = Some(role);
+        self.text_color = None;
+        self
+    }
+
+    pub fn with_hover_text_color(mut self, color: &str) -> Self {
+        self.hover_text_color = Some(Color::from_hex(color));
+        self
+    }
+
+    pub fn with_disabled_text_color(mut self, color: &str) -> Self {
+        self.disabled_text_color = Some(Color::from_hex(color));
+        self
+    }
+
+    pub fn with_text_scale(mut self, scale: f32) -> Self {
+        self.text_scale = Some(scale);
+        self
+    }
+
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = Some(padding);
+        self
+    }
+
+    /// Sets the text color, scale and weight `text_style` has, and its shadow and outline if it has either.
+    /// A style that only sets a scale leaves the shadow of a panel or interface style in place.
+    pub fn with_text_style(mut self, text_style: TextStyle) -> Self {
+        if let Some(color) = &text_style.color {
+            self.text_color = Some(color.clone());
+            self.text_color_role = None;
+        }
+        if let Some(role) = text_style.color_role {
+            self.text_color_role = Some(role);
+            self.text_color = None;
+        }
+        self.text_scale = text_style.scale.or(self.text_scale);
+        self.font_weight = text_style.weight_hint.or(self.font_weight);
+        if text_style.shadow.is_some() || text_style.outline.is_some() {
+            self.text_style = Some(text_style);
+        }
+        self
+    }
+
+    /// Fields set on `self` win, the rest come from `fallback`. A color and its role count as one field.
+    pub fn or(&self, fallback: &Style) -> Style {
+        let (color, color_role) = color_or(&self.color, self.color_role, &fallback.color, fallback.color_role);
+        let (hover_color, hover_color_role) = color_or(&self.hover_color, self.hover_color_role, &fallback.hover_color, fallback.hover_color_role);
+        let (selected_color, selected_color_role) = color_or(&self.selected_color, self.selected_color_role, &fallback.selected_color, fallback.selected_color_role);
+        let (text_color, text_color_role) = color_or(&self.text_color, self.text_color_role, &fallback.text_color, fallback.text_color_role);
+        Style {
+            color,
+            color_role,
+            hover_color,
+            hover_color_role,
+            selected_color,
+            selected_color_role,
+            pressed_color: self.pressed_color.clone().or_else(|| fallback.pressed_color.clone()),
+            disabled_color: self.disabled_color.clone().or_else(|| fallback.disabled_color.clone()),
+            focus_color: self.focus_color.clone().or_else(|| fallback.focus_color.clone()),
+            text_color,
+            text_color_role,
+            hover_text_color: self.hover_text_color.clone().or_else(|| fallback.hover_text_color.clone()),
+            disabled_text_color: self.disabled_text_color.clone().or_else(|| fallback.disabled_text_color.clone()),
+            text_scale: self.text_scale.or(fallback.text_scale),
+            font_weight: self.font_weight.or(fallback.font_weight),
+            padding: self.padding.or(fallback.padding),
+            text_style: self.text_style.clone().or_else(|| fallback.text_style.clone()),
+        }
+    }
+
+    /// The resolved style with its color roles looked up in `theme`, and the theme's colors under anything still unset.
+    pub(crate) fn themed(self, theme: &Theme) -> Style {
+        let color = self.color_role.map(|role| theme.color(role).clone()).or(self.color);
+        let hover_color = self.hover_color_role.map(|role| theme.color(role).clone()).or(self.hover_color);
+        let selected_color = self.selected_color_role.map(|role| theme.color(role).clone()).or(self.selected_color);
+        let text_color = self.text_color_role.map(|role| theme.color(role).clone()).or(self.text_color);
+        Style {
+            color,
+            color_role: None,
+            hover_color,
+            hover_color_role: None,
+            selected_color,
+            selected_color_role: None,
+            text_color,
+            text_color_role: None,
+            ..self
+        }.or(&theme.style())
+    }
+
+    /// The resolved style with its text scale multiplied by `factor`, see `UiTree::set_text_scale_factor`.
+    pub(crate) fn with_text_scale_factor(self, factor: f32) -> Style {
+        Style { text_scale: Some(self.text_scale() * factor), ..self }
+    }
+
+    /// The resolved style at the interface's scale factor, see `UiTree::set_scale_factor`:
+    /// text, padding and shadow offsets all multiplied by `factor`.
+    pub(crate) fn with_scale_factor(self, factor: f32) -> Style {
+        let text_style = self.text_style.clone().map(|text_style| TextStyle {
+            shadow: text_style.shadow.map(|((x, y), color)| ((x * factor, y * factor), color)),
+            ..text_style
+        });
+        Style { padding: Some(self.padding() * factor), text_style, ..self.with_text_scale_factor(factor) }
+    }
+
+    pub(crate) fn color(&self) -> Color {
+        self.color.clone().unwrap_or_else(|| Color::from_hex("#ffffffff"))
+    }
+
+    pub(crate) fn hover_color(&self) -> Color {
+        self.hover_color.clone().unwrap_or_else(|| Color::from_hex(DEFAULT_HOVER_COLOR))
+    }
+
+    pub(crate) fn selected_color(&self) -> Color {
+        self.selected_color.clone().unwrap_or_else(|| Color::from_hex(DEFAULT_SELECTED_COLOR))
+    }
+
+    pub(crate) fn pressed_color(&self) -> Color {
+        self.pressed_color.clone().unwrap_or_else(|| Color::from_hex(DEFAULT_PRESSED_COLOR))
+    }
+
+    pub(crate) fn focus_color(&self) -> Color {
+        self.focus_color.clone().unwrap_or_else(|| Color::from_hex(DEFAULT_FOCUS_COLOR))
+    }
+
+    pub(crate) fn disabled_color(&self) -> Color {
+        self.disabled_color.clone().unwrap_or_else(|| Color::from_hex(DEFAULT_DISABLED_COLOR))
+    }
+
+    pub(crate) fn text_color(&self) -> Color {
+        self.text_color.clone().unwrap_or_else(|| Color::from_hex("#ffffffff"))
+    }
+
+    pub(crate) fn hover_text_color(&self) -> Color {
+        self.hover_text_color.clone().unwrap_or_else(|| self.text_color())
+    }
+
+    pub(crate) fn disabled_text_color(&self) -> Color {
+        self.disabled_text_color.clone().unwrap_or_else(|| self.text_color())
+    }
+
+    pub(crate) fn text_scale(&self) -> f32 {
+        self.text_scale.unwrap_or(DEFAULT_TEXT_SCALE)
+    }
+
+    pub(crate) fn padding(&self) -> f32 {
+        self.padding.unwrap_or(0.0)
+    }
+
+    pub(crate) fn font_weight(&self) -> FontWeight {
+        self.font_weight.unwrap_or_default()
+    }
+}
+
+/// A color field and its role as `Style::or` resolves them, together from whichever style sets either.
+fn color_or(
+    color: &Option<Color>,
+    role: Option<ColorRole>,
+    fallback_color: &Option<Color>,
+    fallback_role: Option<ColorRole>,
+) -> (Option<Color>, Option<ColorRole>) {
+    if color.is_some() || role.is_some() {
+        (color.clone(), role)
+    } else {
+        (fallback_color.clone(), fallback_role)
+    }
+}
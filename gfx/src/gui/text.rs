@@ -0,0 +1,189 @@
+use wgpu_text::glyph_brush::{ab_glyph::FontArc, FontId, GlyphCruncher, Layout, Section, Text};
+
+use super::layout::{Alignment, HorizontalAlignment, PixelRect, VerticalAlignment};
+
+/// Limits a section to `clip` by shrinking its layout bounds, so the brush cuts off anything past the right or bottom edge.
+/// Sections that start outside `clip` are dropped.
+pub(crate) fn clip_section(section: Section<'_>, clip: PixelRect) -> Option<Section<'_>> {
+    let (x, y) = section.screen_position;
+    let (clip_right, clip_bottom) = (clip.x + clip.width, clip.y + clip.height);
+    if x < clip.x || y < clip.y || x >= clip_right || y >= clip_bottom {
+        return None;
+    }
+
+    let bounds = ((clip_right - x).min(section.bounds.0), (clip_bottom - y).min(section.bounds.1));
+    Some(Section { bounds, ..section })
+}
+
+/// Copies of `section` moved by each underlay's offset and recolored, to queue before it so they draw beneath.
+/// `section` itself isn't moved, so the effect doesn't shift where the text appears to sit.
+pub(crate) fn underlay_sections<'a>(section: &Section<'a>, underlays: &[((f32, f32), [f32; 4])]) -> Vec<Section<'a>> {
+    let (x, y) = section.screen_position;
+    underlays.iter()
+        .map(|&((dx, dy), color)| Section {
+            screen_position: (x + dx, y + dy),
+            text: section.text.iter().map(|text| text.with_color(color)).collect(),
+            ..section.clone()
+        })
+        .collect()
+}
+
+/// Approximate advance of one glyph at scale 1.0, exact for a monospaced font like the editor's ComicMono.
+/// Alignment measures the text instead, this is left for insets and the text input caret.
+pub(crate) const GLYPH_WIDTH: f32 = 15.0;
+
+/// Pixel height text is laid out at for a text scale of 1.0.
+pub(crate) const FONT_SIZE: f32 = 30.0;
+
+/// Width left for text in an element `width` pixels wide, after `padding` on both sides
+/// and the inset left aligned text gets.
+pub(crate) fn available_width(width: f32, alignment: &Alignment, padding: f32, scale: f32) -> f32 {
+    let inset = match alignment.horizontal {
+        HorizontalAlignment::Left => GLYPH_WIDTH * scale,
+        HorizontalAlignment::Center | HorizontalAlignment::Right => 0.0,
+    };
+    (width - inset - 2.0 * padding).max(0.0)
+}
+
+/// Lays `section` out in lines at most `width` pixels wide, broken between words.
+pub(crate) fn wrap_section(section: Section<'_>, width: f32) -> Section<'_> {
+    Section { bounds: (width, f32::INFINITY), layout: Layout::default_wrap(), ..section }
+}
+
+/// The longest start of `text` in `font` that fits in `width` pixels with `ellipsis` after it, or `None` when all of `text` fits.
+pub(crate) fn truncate(measure: &mut impl GlyphCruncher<FontArc>, text: &str, font: FontId, scale: f32, width: f32, ellipsis: &str) -> Option<String> {
+    if line_width(measure, text, font, scale) <= width {
+        return None;
+    }
+
+    // Longer starts are never narrower, so the cut can be searched for
+    let ends: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+    let shortened = |end: usize| format!("{}{ellipsis}", text[..end].trim_end());
+    let fitting = ends.partition_point(|&end| line_width(measure, &shortened(end), font, scale) <= width);
+    Some(match fitting {
+        0 => String::new(),
+        fitting => shortened(ends[fitting - 1]),
+    })
+}
+
+/// Width and height of `text` laid out on one line in `font`.
+pub(crate) fn text_size(measure: &mut impl GlyphCruncher<FontArc>, text: &str, font: FontId, scale: f32) -> (f32, f32) {
+    let section = Section::default().add_text(Text::new(text).with_scale(FONT_SIZE * scale).with_font_id(font));
+    measure.glyph_bounds(&section).map_or((0.0, 0.0), |bounds| (bounds.width(), bounds.height()))
+}
+
+fn line_width(measure: &mut impl GlyphCruncher<FontArc>, text: &str, font: FontId, scale: f32) -> f32 {
+    let section = Section::default().add_text(Text::new(text).with_scale(FONT_SIZE * scale).with_font_id(font));
+    measure.glyph_bounds(&section).map_or(0.0, |bounds| bounds.width())
+}
+
+/// Moves a text position `padding` pixels away from the edges it is aligned to.
+pub(crate) fn apply_padding((x, y): (f32, f32), alignment: &Alignment, padding: f32) -> (f32, f32) {
+    let x = match alignment.horizontal {
+        HorizontalAlignment::Left => x + padding,
+        HorizontalAlignment::Center => x,
+        HorizontalAlignment::Right => x - padding,
+    };
+    let y = match alignment.vertical {
+        VerticalAlignment::Top => y + padding,
+        VerticalAlignment::Center => y,
+        VerticalAlignment::Bottom => y - padding,
+    };
+    (x, y)
+}
+
+/// Screen position that places `section`'s measured glyph bounds inside `rect` as `alignment` asks.
+/// `section` is measured where it stands, so it can be built at any position. Left aligned text starts one glyph
+/// in from the edge, where the text input caret expects it.
+pub(crate) fn aligned_position(
+    measure: &mut impl GlyphCruncher<FontArc>,
+    section: &Section<'_>,
+    rect: PixelRect,
+    alignment: &Alignment,
+    scale: f32,
+) -> (f32, f32) {
+    // Empty text has no bounds and is aligned as a zero sized box
+    let (x, y) = section.screen_position;
+    let (offset_x, offset_y, width, height) = match measure.glyph_bounds(section) {
+        Some(bounds) => (bounds.min.x - x, bounds.min.y - y, bounds.width(), bounds.height()),
+        None => (0.0, 0.0, 0.0, 0.0),
+    };
+
+    let left = match alignment.horizontal {
+        HorizontalAlignment::Left => rect.x + GLYPH_WIDTH * scale,
+        HorizontalAlignment::Center => rect.x + (rect.width - width) / 2.0,
+        HorizontalAlignment::Right => rect.x + rect.width - width,
+    };
+    let top = match alignment.vertical {
+        VerticalAlignment::Top => rect.y,
+        VerticalAlignment::Center => rect.y + (rect.height - height) / 2.0,
+        VerticalAlignment::Bottom => rect.y + rect.height - height,
+    };
+    (left - offset_x, top - offset_y)
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu_text::glyph_brush::{GlyphBrush, GlyphBrushBuilder};
+
+    use super::*;
+
+    /// Measures with a proportional font, where a per-character guess would be off.
+    fn measure() -> GlyphBrush<()> {
+        let font = FontArc::try_from_slice(include_bytes!("../../../projects/Comic Sans MS.ttf")).unwrap();
+        GlyphBrushBuilder::using_font(font).build()
+    }
+
+    const RECT: PixelRect = PixelRect { x: 100.0, y: 50.0, width: 600.0, height: 80.0 };
+
+    /// Measured bounds of `text` once moved to where `alignment` places it in `RECT`, as (left, top, right, bottom).
+    fn aligned_bounds(text: &str, alignment: Alignment) -> (f32, f32, f32, f32) {
+        let mut measure = measure();
+        let section = Section::default().add_text(Text::new(text).with_scale(FONT_SIZE * 0.7));
+        let screen_position = aligned_position(&mut measure, &section, RECT, &alignment, 0.7);
+        let bounds = measure.glyph_bounds(&Section { screen_position, ..section }).unwrap();
+        (bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y)
+    }
+
+    #[test]
+    fn centered_text_is_centered_whatever_its_length() {
+        let centered = Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center };
+        let rect_middle = RECT.x + RECT.width / 2.0;
+        for text in ["Hi", "Wide glyphs and narrow ones: WWWW iiii!!"] {
+            let (left, _, right, _) = aligned_bounds(text, centered);
+            assert!(((left + right) / 2.0 - rect_middle).abs() <= 1.0, "{:?} is centered on {}", text, (left + right) / 2.0);
+        }
+    }
+
+    #[test]
+    fn text_meets_the_edges_it_is_aligned_to() {
+        let horizontal = [HorizontalAlignment::Left, HorizontalAlignment::Center, HorizontalAlignment::Right];
+        let vertical = [VerticalAlignment::Top, VerticalAlignment::Center, VerticalAlignment::Bottom];
+        for (horizontal, vertical) in horizontal.into_iter().flat_map(|h| vertical.map(|v| (h, v))) {
+            let (left, top, right, bottom) = aligned_bounds("Level 3", Alignment { vertical, horizontal });
+            let x_error = match horizontal {
+                HorizontalAlignment::Left => left - (RECT.x + GLYPH_WIDTH * 0.7),
+                HorizontalAlignment::Center => (left + right) / 2.0 - (RECT.x + RECT.width / 2.0),
+                HorizontalAlignment::Right => right - (RECT.x + RECT.width),
+            };
+            let y_error = match vertical {
+                VerticalAlignment::Top => top - RECT.y,
+                VerticalAlignment::Center => (top + bottom) / 2.0 - (RECT.y + RECT.height / 2.0),
+                VerticalAlignment::Bottom => bottom - (RECT.y + RECT.height),
+            };
+            assert!(x_error.abs() <= 1.0 && y_error.abs() <= 1.0, "{:?} {:?} is off by ({}, {})", horizontal, vertical, x_error, y_error);
+        }
+    }
+
+    #[test]
+    fn wrapped_lines_are_centered_as_a_block() {
+        let mut measure = measure();
+        let centered = Alignment { vertical: VerticalAlignment::Center, horizontal: HorizontalAlignment::Center };
+        let section = wrap_section(Section::default().add_text(Text::new("A title long enough to wrap onto more lines").with_scale(FONT_SIZE)), 200.0);
+        let screen_position = aligned_position(&mut measure, &section, RECT, &centered, 1.0);
+        let bounds = measure.glyph_bounds(&Section { screen_position, ..section }).unwrap();
+        assert!(bounds.height() > FONT_SIZE * 1.5);
+        assert!(((bounds.min.y + bounds.max.y) / 2.0 - (RECT.y + RECT.height / 2.0)).abs() <= 1.0);
+        assert!(((bounds.min.x + bounds.max.x) / 2.0 - (RECT.x + RECT.width / 2.0)).abs() <= 1.0);
+    }
+}
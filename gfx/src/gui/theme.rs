@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use super::{color::Color, style::Style};
+
+/// A color panels and elements take from the interface's `Theme` rather than naming it, see
+/// `Element::with_color_role` and `Panel::with_color_role`. It's looked up every upload, so `UiTree::set_theme`
+/// recolors them without a rebuild.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorRole {
+    /// Behind everything, the window's clear color.
+    Background,
+    Panel,
+    /// Header bars and the controls set into panels.
+    Header,
+    Text,
+    /// Hints, placeholders and disabled labels, text that shouldn't stand out.
+    TextSecondary,
+    /// Primary buttons and keyboard focus.
+    Accent,
+    Hover,
+    Error,
+}
+
+impl ColorRole {
+    pub const ALL: [ColorRole; 8] = [
+        ColorRole::Background,
+        ColorRole::Panel,
+        ColorRole::Header,
+        ColorRole::Text,
+        ColorRole::TextSecondary,
+        ColorRole::Accent,
+        ColorRole::Hover,
+        ColorRole::Error,
+    ];
+
+    /// Name of the role in theme files.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ColorRole::Background => "background",
+            ColorRole::Panel => "panel",
+            ColorRole::Header => "header",
+            ColorRole::Text => "text",
+            ColorRole::TextSecondary => "text_secondary",
+            ColorRole::Accent => "accent",
+            ColorRole::Hover => "hover",
+            ColorRole::Error => "error",
+        }
+    }
+}
+
+/// The colors behind every `ColorRole`. Text, disabled text, hover and focus colors also fill in for panels and
+/// elements that don't set their own, underneath the interface's `Style`.
+#[derive(Clone)]
+pub struct Theme {
+    pub background: Color,
+    pub panel: Color,
+    pub header: Color,
+    pub text: Color,
+    pub text_secondary: Color,
+    pub accent: Color,
+    pub hover: Color,
+    pub error: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: Color::from_hex("#21262dff"),
+            panel: Color::from_hex("#161b22ff"),
+            header: Color::from_hex("#0d1117ff"),
+            text: Color::from_hex("#ffffffff"),
+            text_secondary: Color::from_hex("#8b949eff"),
+            accent: Color::from_hex("#1f6febff"),
+            hover: Color::from_hex("#999999ff"),
+            error: Color::from_hex("#f85149ff"),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Color::from_hex("#d0d7deff"),
+            panel: Color::from_hex("#f6f8faff"),
+            header: Color::from_hex("#ffffffff"),
+            text: Color::from_hex("#1f2328ff"),
+            text_secondary: Color::from_hex("#59636eff"),
+            accent: Color::from_hex("#0969daff"),
+            hover: Color::from_hex("#afb8c1ff"),
+            error: Color::from_hex("#cf222eff"),
+        }
+    }
+
+    pub fn color(&self, role: ColorRole) -> &Color {
+        match role {
+            ColorRole::Background => &self.background,
+            ColorRole::Panel => &self.panel,
+            ColorRole::Header => &self.header,
+            ColorRole::Text => &self.text,
+            ColorRole::TextSecondary => &self.text_secondary,
+            ColorRole::Accent => &self.accent,
+            ColorRole::Hover => &self.hover,
+            ColorRole::Error => &self.error,
+        }
+    }
+
+    pub fn color_mut(&mut self, role: ColorRole) -> &mut Color {
+        match role {
+            ColorRole::Background => &mut self.background,
+            ColorRole::Panel => &mut self.panel,
+            ColorRole::Header => &mut self.header,
+            ColorRole::Text => &mut self.text,
+            ColorRole::TextSecondary => &mut self.text_secondary,
+            ColorRole::Accent => &mut self.accent,
+            ColorRole::Hover => &mut self.hover,
+            ColorRole::Error => &mut self.error,
+        }
+    }
+
+    /// What the frame is cleared to before anything is drawn, `background`, unless the interface sets its own
+    /// with `UiTree::set_clear_color`.
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.background.to_wgpu()
+    }
+
+    /// The bottom of the style cascade, below the interface's own style.
+    pub(crate) fn style(&self) -> Style {
+        Style {
+            hover_color: Some(self.hover.clone()),
+            selected_color: Some(self.accent.clone()),
+            focus_color: Some(self.accent.clone()),
+            text_color: Some(self.text.clone()),
+            disabled_text_color: Some(self.text_secondary.clone()),
+            ..Style::default()
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Instant;
+
+use super::interface::WakeupId;
+
+/// Most toasts shown at once, the newest. Older ones collapse into a "+N more" entry above them.
+pub const TOAST_MAX_VISIBLE: usize = 5;
+
+/// Size of each toast and the gap between them and the window edge, in pixels scaled like `Dimension::Pixels`.
+pub(crate) const TOAST_WIDTH: f32 = 320.0;
+pub(crate) const TOAST_HEIGHT: f32 = 32.0;
+pub(crate) const TOAST_SPACING: f32 = 6.0;
+pub(crate) const TOAST_MARGIN: f32 = 12.0;
+
+pub(crate) const TOAST_BORDER_COLOR: &str = "#30363dff";
+pub(crate) const TOAST_MORE_COLOR: &str = "#161b22ff";
+
+/// How serious a toast is, which picks its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToastLevel {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    pub(crate) fn color(self) -> &'static str {
+        match self {
+            ToastLevel::Info => "#1c2b45ff",
+            ToastLevel::Warning => "#4d3800ff",
+            ToastLevel::Error => "#5a1d1dff",
+        }
+    }
+}
+
+/// A message pushed with `UiTree::push_toast`, shown until `expires` or a click on it.
+#[derive(Debug, Clone)]
+pub(crate) struct Toast {
+    pub(crate) level: ToastLevel,
+    pub(crate) message: String,
+    pub(crate) expires: Instant,
+    pub(crate) wakeup: WakeupId,
+}
@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+use super::interface::WakeupId;
+
+/// How long the cursor has to rest on an element built `with_tooltip` before its tooltip shows, unless
+/// `UiTree::set_tooltip_delay` changes it.
+pub const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+
+/// How far the cursor can drift, in pixels scaled like `Dimension::Pixels`, and still count as resting.
+pub const TOOLTIP_MOVE_TOLERANCE: f32 = 4.0;
+
+/// Space between the tooltip text and its border, in pixels scaled like `Dimension::Pixels`.
+pub(crate) const TOOLTIP_PADDING: f32 = 6.0;
+
+/// Offset of the tooltip's top-left corner from the cursor, in pixels scaled like `Dimension::Pixels`,
+/// far enough down to clear the pointer.
+pub(crate) const TOOLTIP_OFFSET: (f32, f32) = (12.0, 20.0);
+
+pub(crate) const TOOLTIP_COLOR: &str = "#1c2128ff";
+pub(crate) const TOOLTIP_BORDER_COLOR: &str = "#30363dff";
+
+/// The cursor resting on an element with a tooltip, which shows once `due` passes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PendingTooltip {
+    /// Panel and element index of the element built `with_tooltip`.
+    pub(crate) source: (usize, usize),
+    /// Where the cursor came to rest, in physical pixels.
+    pub(crate) anchor: (f32, f32),
+    pub(crate) due: Instant,
+    pub(crate) wakeup: WakeupId,
+    /// Index of the overlay panel showing the text once it's up, kept up to date as other overlays come and go.
+    pub(crate) panel: Option<usize>,
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use crate::definitions::{UiAtlasTexture, Vertex};
+
+/// Debug checks on the vertices `UiRenderer::upload` emits, compiled in with the `validate-ui` feature.
+/// Bad texture coordinates otherwise only show up as subtly wrong pixels, so each element is reported once
+/// with the values that failed.
+#[derive(Default)]
+pub(crate) struct UiValidator {
+    warned: HashSet<String>,
+}
+
+impl UiValidator {
+    /// `element` names the element in warnings, `entry` is its atlas entry if the atlas has one.
+    /// Its first quad is the fill, which should sample exactly inside `entry`.
+    pub(crate) fn check_element(&mut self, element: &str, texture_name: &str, vertices: &[Vertex], entry: Option<&UiAtlasTexture>) {
+        if self.warned.contains(element) {
+            return;
+        }
+        if let Some(problem) = find_problem(texture_name, vertices, entry) {
+            log::warn!("UI element {}: {}", element, problem);
+            self.warned.insert(element.to_string());
+        }
+    }
+}
+
+fn find_problem(texture_name: &str, vertices: &[Vertex], entry: Option<&UiAtlasTexture>) -> Option<String> {
+    if let Some(vertex) = vertices.iter().find(|vertex| !vertex.position.iter().all(|value| value.is_finite())) {
+        return Some(format!("non-finite vertex position {:?}", vertex.position));
+    }
+    if let Some(vertex) = vertices.iter().find(|vertex| !vertex.tex_coords.iter().all(|value| (0.0..=1.0).contains(value))) {
+        return Some(format!("texture coordinate {:?} is outside [0, 1]", vertex.tex_coords));
+    }
+
+    // Quads are laid out top-left, top-right, bottom-left, bottom-right, and clipped-away quads are all zero
+    for quad in vertices.chunks_exact(4).filter(|quad| quad.iter().any(|vertex| vertex.position != [0.0, 0.0])) {
+        let (top_left, top_right, bottom_left) = (quad[0].tex_coords, quad[1].tex_coords, quad[2].tex_coords);
+        if top_left[0] > top_right[0] || top_left[1] > bottom_left[1] {
+            return Some(format!("quad is flipped, top-left uv {:?}, top-right {:?}, bottom-left {:?}", top_left, top_right, bottom_left));
+        }
+    }
+
+    let fill = vertices.get(..4).filter(|fill| fill.iter().any(|vertex| vertex.position != [0.0, 0.0]))?;
+    let Some(((u_0, v_0), (u_1, v_1))) = entry.and_then(|entry| entry.start_coord.zip(entry.end_coord)) else {
+        return Some(format!("no atlas entry for texture {:?}, it is drawn with the missing texture instead", texture_name));
+    };
+    // Clipping shrinks the fill's uv rect but never moves it outside the entry
+    let epsilon = 1e-4;
+    fill.iter()
+        .find(|vertex| {
+            let [u, v] = vertex.tex_coords;
+            u < u_0 - epsilon || u > u_1 + epsilon || v < v_0 - epsilon || v > v_1 + epsilon
+        })
+        .map(|vertex| format!(
+            "uv {:?} is outside its atlas entry {:?} ({}, {}) to ({}, {})", vertex.tex_coords, texture_name, u_0, v_0, u_1, v_1,
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::definitions::UiAtlas;
+
+    /// A quad over the whole window sampling `(u_0, v_0)` to `(u_1, v_1)`, corners in the order the renderer writes them.
+    fn quad((u_0, v_0): (f32, f32), (u_1, v_1): (f32, f32)) -> Vec<Vertex> {
+        [([-1.0, 1.0], [u_0, v_0]), ([1.0, 1.0], [u_1, v_0]), ([-1.0, -1.0], [u_0, v_1]), ([1.0, -1.0], [u_1, v_1])]
+            .map(|(position, tex_coords)| Vertex { position, tex_coords, ..bytemuck::Zeroable::zeroed() })
+            .to_vec()
+    }
+
+    /// A 4 by 4 atlas whose "button" entry is its top left quarter.
+    fn atlas() -> UiAtlas {
+        let mut atlas = UiAtlas::new(4, 4);
+        atlas.add_entry(UiAtlasTexture::new("button".to_string(), 0, 0, 2, 2));
+        atlas
+    }
+
+    fn entry_quad(entry: &UiAtlasTexture) -> Vec<Vertex> {
+        quad(entry.start_coord.unwrap(), entry.end_coord.unwrap())
+    }
+
+    #[test]
+    fn quads_sampling_their_entry_pass() {
+        let atlas = atlas();
+        let mut validator = UiValidator::default();
+        validator.check_element("play", "button", &entry_quad(atlas.get("button").unwrap()), atlas.get("button"));
+        assert!(validator.warned.is_empty());
+    }
+
+    #[test]
+    fn an_entry_moved_by_a_repack_warns() {
+        let atlas = atlas();
+        let vertices = entry_quad(atlas.get("button").unwrap());
+        // The atlas was repacked under the element, its entry is now the bottom right quarter
+        let mut moved = atlas.get("button").unwrap().clone();
+        moved.start_coord = Some((0.625, 0.625));
+        moved.end_coord = Some((0.875, 0.875));
+
+        let mut validator = UiValidator::default();
+        validator.check_element("play", "button", &vertices, Some(&moved));
+        assert!(validator.warned.contains("play"));
+        assert!(find_problem("button", &vertices, Some(&moved)).unwrap().contains("outside its atlas entry"));
+    }
+
+    #[test]
+    fn each_element_warns_once() {
+        let mut validator = UiValidator::default();
+        let flipped = quad((0.25, 0.25), (0.25, 0.0));
+        validator.check_element("play", "button", &flipped, None);
+        validator.check_element("play", "button", &quad((2.0, 0.0), (3.0, 1.0)), None);
+        validator.check_element("stop", "button", &flipped, None);
+        assert_eq!(validator.warned.len(), 2);
+    }
+
+    #[test]
+    fn flipped_quads_are_reported() {
+        let atlas = atlas();
+        let entry = atlas.get("button").unwrap();
+        let ((u_0, v_0), (u_1, v_1)) = (entry.start_coord.unwrap(), entry.end_coord.unwrap());
+        let upside_down = quad((u_0, v_1), (u_1, v_0));
+        assert!(find_problem("button", &upside_down, Some(entry)).unwrap().contains("flipped"));
+    }
+
+    #[test]
+    fn coordinates_outside_the_atlas_are_reported() {
+        let atlas = atlas();
+        let beyond = quad((0.5, 0.5), (1.5, 1.0));
+        assert!(find_problem("button", &beyond, atlas.get("button")).unwrap().contains("outside [0, 1]"));
+    }
+
+    #[test]
+    fn non_finite_positions_are_reported() {
+        let atlas = atlas();
+        let mut vertices = entry_quad(atlas.get("button").unwrap());
+        vertices[3].position = [f32::NAN, 0.0];
+        assert!(find_problem("button", &vertices, atlas.get("button")).unwrap().contains("non-finite"));
+    }
+
+    #[test]
+    fn missing_entries_are_reported_but_clipped_quads_are_not() {
+        let vertices = quad((0.0, 0.0), (1.0, 1.0));
+        assert!(find_problem("ghost", &vertices, None).unwrap().contains("no atlas entry"));
+        let clipped = vec![bytemuck::Zeroable::zeroed(); 4];
+        assert_eq!(find_problem("ghost", &clipped, None), None);
+    }
+}
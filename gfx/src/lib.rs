@@ -1,102 +1,214 @@
-use std::{iter, sync::{Arc, Mutex}};
+use std::{collections::HashMap, path::Path, sync::{atomic::{AtomicBool, Ordering}, Arc}, time::{Duration, Instant}};
 
 use wgpu::util::DeviceExt;
-use winit::{dpi::PhysicalSize, window::Window};
+use glam::{IVec2, Vec2};
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, window::Window};
 
-use crate::{definitions::{ColorExt, GuiPageState, Vertex}, gui::{camera::{Camera2D, Camera2DUniform}, interface::Interface}};
+use crate::{definitions::{TileInstance, UiAtlas, Vertex}, gpu::{check_atlas_size, create_scoped, texture_bytes}, gui::{camera::{Camera2D, Camera2DUniform, CameraMut}, custom_draw::UiPassState, interface::UiTree, layout::PixelRect, renderer::UiRenderer, toast::ToastLevel}, grid::Grid, render_target::RenderTarget, tiles::TileRenderer};
 
 mod builder;
 pub mod definitions;
+mod diagnostics;
+mod frame_clock;
+mod gpu;
+mod grid;
 pub mod gui;
+mod options;
+mod render_target;
+mod simple;
+mod tiles;
+
+pub use builder::ShaderError;
+pub use diagnostics::RenderDiagnostics;
+pub use frame_clock::FrameClock;
+pub use gpu::GpuContext;
+pub use grid::GridConfig;
+pub use options::RenderOptions;
+pub use simple::{run_simple, KeyAction};
+pub use tiles::TILE_CHUNK_SIZE;
+
+/// Render target the level preview is drawn into, show it with `Element::with_render_target(PREVIEW_TARGET)`.
+pub const PREVIEW_TARGET: &str = "preview";
+
+/// The interface's shader, see `RenderState::reload_shaders`.
+const UI_SHADER_FILE: &str = "ui_shader.wgsl";
+
+/// Depth buffer format of the preview pass. The UI pass has none, it paints in order.
+const PREVIEW_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Labels of the two passes a frame is drawn in, the last one recorded is named by errors the device reports.
+const PREVIEW_PASS: &str = "Preview Pass";
+const UI_PASS: &str = "UI Pass";
+
+/// How long the toast saying the surface was recreated stays up.
+const SURFACE_LOST_TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Color format of a headless `RenderState`'s target, laid out like `image::RgbaImage` so it copies straight into one.
+const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Where a `RenderState` draws its frames.
+enum RenderOutput {
+    /// Presented to a window.
+    Surface {
+        surface: wgpu::Surface<'static>,
+        window: Arc<Window>,
+        /// Present modes the surface supports on this adapter, see `set_present_mode`.
+        present_modes: Vec<wgpu::PresentMode>,
+    },
+    /// Drawn into a texture and read back with `render_to_image`. The texture is made by `configure_surface`,
+    /// so there's none while the size is 0x0.
+    Offscreen { texture: Option<wgpu::Texture> },
+}
 
 pub struct RenderState {
-    surface: wgpu::Surface<'static>,
-    pub device: wgpu::Device,
-    pub queue: wgpu::Queue,
+    output: RenderOutput,
+    /// Device, queue and UI atlas, shared with the states of windows opened from this one.
+    gpu: Arc<GpuContext>,
+    /// Size and format of what frames are drawn into. A headless state never configures a surface with it,
+    /// its texture is created to match instead.
     pub config: wgpu::SurfaceConfiguration,
     is_surface_configured: bool,
     ui_pipeline: wgpu::RenderPipeline,
-    preview_pipeline: wgpu::RenderPipeline,
-    pub window: Arc<Window>,
+    /// Color format the pipelines and render targets were built for, the surface's. Anything rebuilt later
+    /// has to use it too, the passes mix their output.
+    pipeline_format: wgpu::TextureFormat,
+    /// Samples per pixel of both passes and everything drawn in them, 1 without MSAA.
+    sample_count: u32,
+    /// Multisampled color texture the UI pass draws into and resolves to the surface, sized with it.
+    msaa_view: Option<wgpu::TextureView>,
 
     pub size: PhysicalSize<u32>,
+    /// Physical pixels per logical pixel of the monitor the window is on, see `set_scale_factor`.
+    scale_factor: f64,
 
     camera_2d: Camera2D,
     camera_buffer_2d: wgpu::Buffer,
     camera_bind_group_2d: wgpu::BindGroup,
 
-    triangle_vertex_buffer: wgpu::Buffer,
-    interface_arc: Arc<Mutex<Interface>>,
-    pub gui_state: GuiPageState,
+    /// Separate from `camera_2d`, which maps the UI to the window and never moves.
+    preview_camera: Camera2D,
+    preview_camera_buffer: wgpu::Buffer,
+    preview_camera_bind_group: wgpu::BindGroup,
 
-    gui_material_bind_group: wgpu::BindGroup,
+    /// Drawn across the preview under the level, see `set_grid`.
+    grid: Grid,
+    /// The level's tiles, drawn over the grid, see `set_tile_chunk`.
+    tiles: TileRenderer,
+    /// Offscreen textures UI elements can show, keyed by name. Each is sized to the element showing it.
+    render_targets: HashMap<String, RenderTarget>,
+    /// Where the element showing `PREVIEW_TARGET` was last laid out, in window pixels.
+    preview_rect: Option<PixelRect>,
+    /// Buffers and text brushes the interface is drawn with. The `UiTree` itself belongs to the application,
+    /// which hands it to every call that lays it out or draws it.
+    ui_renderer: UiRenderer,
+    /// Whether the level preview is drawn, set by the application for pages that show it.
+    pub show_preview: bool,
+    /// Set by changes outside the interface, such as camera moves, see `take_redraw_request`.
+    needs_redraw: AtomicBool,
+    /// Asks for a frame after every frame, for while the preview animates.
+    continuous_rendering: bool,
+    /// Times every frame `render` draws, see `last_frame_time` and `fps`.
+    frame_clock: FrameClock,
+    /// Draw calls the interface issued in the last frame, see `draw_calls`.
+    draw_calls: u32,
 }
 
 impl RenderState {
-    pub async fn new(window: Arc<Window>, interface_arc: Arc<Mutex<Interface>>) -> anyhow::Result<RenderState> {
-        let size = window.inner_size();
+    /// Draws into `window`. `atlas_image` holds the pixels for the `UiAtlas` the interface was created with.
+    pub async fn new<E>(window: Arc<Window>, interface: &mut UiTree<E>, atlas_image: &image::RgbaImage, options: &RenderOptions) -> anyhow::Result<RenderState> {
+        let (instance, surface, adapter) = request_adapter(Some(&window), options).await?;
+        let surface = surface.expect("request_adapter makes a surface for the window it's given");
+        let gpu = Arc::new(GpuContext::new(instance, adapter, atlas_image).await?);
+        Self::with_surface(gpu, surface, window, interface, options)
+    }
 
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
-            ..Default::default()
-        });
+    /// Draws into another window with the device and UI atlas of `gpu`, taken from an existing state with `gpu()`.
+    /// `interface` has to be made with the same `UiAtlas`. Each window keeps its own surface, size and layout.
+    pub fn new_window<E>(gpu: Arc<GpuContext>, window: Arc<Window>, interface: &mut UiTree<E>, options: &RenderOptions) -> anyhow::Result<RenderState> {
+        let surface = gpu.instance.create_surface(window.clone())?;
+        if !gpu.adapter.is_surface_supported(&surface) {
+            anyhow::bail!("{} can't draw to the new window", gpu.adapter_info.name);
+        }
+        Self::with_surface(gpu, surface, window, interface, options)
+    }
 
-        let surface = instance.create_surface(window.clone()).unwrap();
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await?;
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                required_limits: if cfg!(target_arch = "wasm32") {
-                    wgpu::Limits::downlevel_webgl2_defaults()
-                } else {
-                    wgpu::Limits::default()
-                },
-                memory_hints: Default::default(),
-                trace: wgpu::Trace::Off,
-            })
-            .await?;
+    /// Draws into a `width` x `height` texture instead of a window, for tests and thumbnails.
+    /// Frames are read back with `render_to_image`. The scale factor is 1 until `set_scale_factor`.
+    /// As with a window, the interface needs an `update_interface` before its panels show up in a frame.
+    pub async fn new_headless<E>(width: u32, height: u32, interface: &mut UiTree<E>, atlas_image: &image::RgbaImage, options: &RenderOptions) -> anyhow::Result<RenderState> {
+        let (instance, _, adapter) = request_adapter(None, options).await?;
+        let gpu = Arc::new(GpuContext::new(instance, adapter, atlas_image).await?);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: HEADLESS_FORMAT,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            desired_maximum_frame_latency: 2,
+            view_formats: vec![],
+        };
+        Self::build(gpu, RenderOutput::Offscreen { texture: None }, config, 1.0, interface, options)
+    }
+
+    /// Picks the format and present mode for `surface` and builds a state presenting to it.
+    fn with_surface<E>(
+        gpu: Arc<GpuContext>,
+        surface: wgpu::Surface<'static>,
+        window: Arc<Window>,
+        interface: &mut UiTree<E>,
+        options: &RenderOptions,
+    ) -> anyhow::Result<RenderState> {
+        let size = window.inner_size();
+        let scale_factor = window.scale_factor();
+
+        let surface_caps = surface.get_capabilities(&gpu.adapter);
+        let surface_format = pick_surface_format(&surface_caps.formats);
+        let present_mode = if surface_caps.present_modes.contains(&options.present_mode) {
+            options.present_mode
+        } else {
+            log::warn!("The surface can't present with {:?}, using Fifo", options.present_mode);
+            wgpu::PresentMode::Fifo
+        };
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode,
+            alpha_mode: surface_caps.alpha_modes[0],
+            desired_maximum_frame_latency: 2,
+            view_formats: vec![],
+        };
+        let output = RenderOutput::Surface { surface, window, present_modes: surface_caps.present_modes };
+        Self::build(gpu, output, config, scale_factor, interface, options)
+    }
 
+    /// Everything both kinds of `RenderState` share, drawing into `output` at the size and format in `config`.
+    fn build<E>(
+        gpu: Arc<GpuContext>,
+        output: RenderOutput,
+        config: wgpu::SurfaceConfiguration,
+        scale_factor: f64,
+        interface: &mut UiTree<E>,
+        options: &RenderOptions,
+    ) -> anyhow::Result<RenderState> {
+        let size = PhysicalSize::new(config.width, config.height);
+        let surface_format = config.format;
+        let device = &gpu.device;
 
         let camera_2d = Camera2D::new(size.width, size.height);
 
-        let camera_uniform_2d = Camera2DUniform {
-            view_proj: camera_2d.build_view_projection_matrix().to_cols_array_2d(),
-        };
+        let camera_uniform_2d = Camera2DUniform::new(&camera_2d);
         let camera_buffer_2d = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Camera 2D Uniform Buffer"),
             contents: bytemuck::cast_slice(&[camera_uniform_2d]),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        let camera_bind_group_layout_2d = 
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer { 
-                            ty: wgpu::BufferBindingType::Uniform, 
-                            has_dynamic_offset: false, 
-                            min_binding_size: None 
-                        },
-                        count: None,
-                    }
-                ],
-                label: Some("Camera 2D Bind Group Layout"),
-            });
-
         let camera_bind_group_2d = device.create_bind_group(&wgpu::BindGroupDescriptor { 
             label: Some("Camera 2D Bind Group"), 
-            layout: &camera_bind_group_layout_2d, 
+            layout: &gpu.camera_bind_group_layout, 
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -105,237 +217,635 @@ impl RenderState {
             ] 
         });
 
-        let surface_caps = surface.get_capabilities(&adapter);
-
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            desired_maximum_frame_latency: 2,
-            view_formats: vec![],
-        };
-
-        let diffuse_bytes = include_bytes!("../../app/atlas.png");
-        let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
-        let diffuse_rgba = diffuse_image.to_rgba8();
-
-        use image::GenericImageView;
-        let dimensions = diffuse_image.dimensions();
+        // Sized to the preview element once the interface has been laid out
+        let preview_camera = Camera2D::new(1, 1);
+        let preview_camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Preview Camera Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[Camera2DUniform::new(&preview_camera)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let preview_camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Preview Camera Bind Group"),
+            layout: &gpu.camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: preview_camera_buffer.as_entire_binding(),
+                }
+            ]
+        });
 
-        let texture_size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
-        };
-        let diffuse_texture = device.create_texture(
-            &wgpu::TextureDescriptor {
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                label: Some("diffuse_texture"),
-                view_formats: &[],
-            }
-        );
+        let sample_count = supported_sample_count(&gpu.adapter, surface_format, options.sample_count);
 
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &diffuse_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &diffuse_rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            texture_size,
-        );
+        gpu.check_atlas(interface.atlas())?;
+        interface.set_scale_factor(scale_factor as f32);
+        let ui_renderer = UiRenderer::new(device, surface_format, sample_count);
 
-        let diffuse_texture_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let ui_pipeline = build_ui_pipeline(device, [&gpu.camera_bind_group_layout, &gpu.material_bind_group_layout], surface_format, sample_count, None)?;
 
-        let gui_material_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None
-                    }
-                ],
-                label: Some("texture_bind_group_layout"),
-            });
+        let grid = Grid::new(device, &gpu.camera_bind_group_layout, surface_format, PREVIEW_DEPTH_FORMAT, sample_count)?;
+        let tiles = TileRenderer::new(
+            device,
+            &gpu.camera_bind_group_layout,
+            &gpu.material_bind_group_layout,
+            surface_format,
+            PREVIEW_DEPTH_FORMAT,
+            sample_count,
+            grid.config().cell_size(),
+        )?;
 
-        let gui_material_bind_group = device.create_bind_group(
-        &wgpu::BindGroupDescriptor {
-                label: Some("GUI Material Bind Group"),
-                layout: &gui_material_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&diffuse_texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&diffuse_sampler),
-                    }
-                ],
-            }
+        let mut render_targets = HashMap::new();
+        render_targets.insert(
+            PREVIEW_TARGET.to_string(),
+            RenderTarget::new(device, &gpu.material_bind_group_layout, &gpu.sampler, surface_format, sample_count, PhysicalSize::new(1, 1), "Preview Render Target")
+                .with_depth(device, PREVIEW_DEPTH_FORMAT),
         );
 
-        let ui_pipeline = builder::PipeLineBuilder::new(&device)
-            .set_pixel_format(wgpu::TextureFormat::Bgra8UnormSrgb)
-            .add_vertex_buffer_layout(Vertex::desc())
-            .add_bind_group_layout(&camera_bind_group_layout_2d)
-            .add_bind_group_layout(&gui_material_bind_group_layout)
-            .set_shader_module("ui_shader.wgsl", "vs_main", "fs_main")
-            .build("Render Pipeline");
-
-        let preview_pipeline = builder::PipeLineBuilder::new(&device)
-            .set_pixel_format(wgpu::TextureFormat::Bgra8UnormSrgb)
-            .add_vertex_buffer_layout(Vertex::desc())
-            .set_shader_module("preview_shader.wgsl", "vs_main", "fs_main")
-            .build("Preview Pipeline");
-
-        let triangle_vertices = [
-            Vertex { position: [0.0, 0.5], color: [1.0, 0.0, 0.0, 1.0], tex_coords: [0.0, 0.0] },  // Top (green)
-            Vertex { position: [-0.5, -0.5], color: [0.0, 1.0, 0.0, 1.0], tex_coords: [0.0, 0.0] }, // Bottom-left (blue)
-            Vertex { position: [0.5, -0.5], color: [0.0, 0.0, 1.0, 1.0], tex_coords: [0.0, 0.0] }, // Bottom-right (yellow)
-        ];
-
-        let triangle_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Triangle Vertex Buffer"),
-            contents: bytemuck::cast_slice(&triangle_vertices),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST
-        });
-
-        Ok(Self {
-            surface,
-            device,
-            queue,
+        let mut state = Self {
+            output,
+            gpu,
             config,
             is_surface_configured: false,
-            window,
             ui_pipeline,
-            preview_pipeline,
+            pipeline_format: surface_format,
+            sample_count,
+            msaa_view: None,
 
             size,
+            scale_factor,
 
             camera_2d,
             camera_buffer_2d,
             camera_bind_group_2d,
-            triangle_vertex_buffer,
-            interface_arc,
-            gui_state: GuiPageState::ProjectView,
-            gui_material_bind_group,
-        })
+            preview_camera,
+            preview_camera_buffer,
+            preview_camera_bind_group,
+            grid,
+            tiles,
+            render_targets,
+            preview_rect: None,
+            ui_renderer,
+            show_preview: false,
+            needs_redraw: AtomicBool::new(true),
+            continuous_rendering: false,
+            frame_clock: FrameClock::new(),
+            draw_calls: 0,
+        };
+        // A minimized window is 0x0, the first resize to a real size configures it instead
+        state.configure_surface();
+        Ok(state)
+    }
+
+    /// The device, queue and UI atlas this state draws with, for opening another window on them with `new_window`.
+    pub fn gpu(&self) -> &Arc<GpuContext> {
+        &self.gpu
     }
 
-    pub fn resize(&mut self, width: u32, height: u32) {
-        if width > 0 && height > 0 {
+    /// The window drawn into, `None` for a headless state.
+    pub fn window(&self) -> Option<&Arc<Window>> {
+        match &self.output {
+            RenderOutput::Surface { window, .. } => Some(window),
+            RenderOutput::Offscreen { .. } => None,
+        }
+    }
+
+    /// Configures the surface for `config`, or recreates the headless texture at its size, unless that's 0x0,
+    /// which wgpu doesn't allow. The MSAA texture is recreated to match. Returns whether frames can be drawn.
+    fn configure_surface(&mut self) -> bool {
+        self.is_surface_configured = self.config.width > 0 && self.config.height > 0;
+        if self.is_surface_configured {
+            match &mut self.output {
+                RenderOutput::Surface { surface, .. } => surface.configure(&self.gpu.device, &self.config),
+                RenderOutput::Offscreen { texture } => {
+                    let device = &self.gpu.device;
+                    *texture = Some(create_scoped(device, "Headless Color Texture", || device.create_texture(&wgpu::TextureDescriptor {
+                        size: wgpu::Extent3d {
+                            width: self.config.width,
+                            height: self.config.height,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: self.config.format,
+                        usage: self.config.usage,
+                        label: Some("Headless Color Texture"),
+                        view_formats: &[],
+                    })));
+                }
+            }
+            let device = &self.gpu.device;
+            self.msaa_view = (self.sample_count > 1).then(|| {
+                create_scoped(device, "MSAA Color Texture", || device.create_texture(&wgpu::TextureDescriptor {
+                    size: wgpu::Extent3d {
+                        width: self.config.width,
+                        height: self.config.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: self.sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.config.format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    label: Some("MSAA Color Texture"),
+                    view_formats: &[],
+                })).create_view(&wgpu::TextureViewDescriptor::default())
+            });
+        }
+        self.is_surface_configured
+    }
+
+    /// Reconfigures the surface to present with `present_mode`, or with `Fifo` when the surface can't.
+    /// Returns the mode in use afterwards. A headless state presents nothing and takes any mode.
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let supported = match &self.output {
+            RenderOutput::Surface { present_modes, .. } => present_modes.contains(&present_mode),
+            RenderOutput::Offscreen { .. } => true,
+        };
+        let present_mode = if supported {
+            present_mode
+        } else {
+            log::warn!("The surface can't present with {:?}, using Fifo", present_mode);
+            wgpu::PresentMode::Fifo
+        };
+        if self.config.present_mode != present_mode {
+            self.config.present_mode = present_mode;
+            self.configure_surface();
+            self.request_redraw();
+        }
+        present_mode
+    }
+
+    /// Marks the window as needing a frame. Nothing is drawn until the application sees it in `take_redraw_request`.
+    pub fn request_redraw(&self) {
+        self.needs_redraw.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether a frame is needed, because something in the state or `interface` was invalidated since the last call
+    /// or rendering is continuous. Clears the request, the application should follow a true with `Window::request_redraw`.
+    pub fn take_redraw_request<E>(&self, interface: &UiTree<E>) -> bool {
+        let interface_changed = interface.take_redraw_request();
+        self.needs_redraw.swap(false, Ordering::Relaxed) | interface_changed || self.continuous_rendering
+    }
+
+    /// Redraws every frame while on, for animation in the preview. Off by default, frames are then only
+    /// drawn when something changes and the event loop sleeps in between.
+    pub fn set_continuous_rendering(&mut self, continuous: bool) {
+        self.continuous_rendering = continuous;
+    }
+
+    pub fn is_continuous_rendering(&self) -> bool {
+        self.continuous_rendering
+    }
+
+    /// Time between the last two frames drawn, idle time in between included, see `FrameClock`.
+    pub fn last_frame_time(&self) -> Duration {
+        self.frame_clock.last_frame_time()
+    }
+
+    /// Time each of the latest frames drawn took, oldest first, see `FrameClock`.
+    pub fn frame_times(&self) -> Vec<Duration> {
+        self.frame_clock.frame_times().collect()
+    }
+
+    /// Frames per second over the latest frames drawn, see `FrameClock`.
+    pub fn fps(&self) -> f32 {
+        self.frame_clock.fps()
+    }
+
+    /// `draw_indexed` calls the interface issued for the last frame, one per quad drawn.
+    pub fn draw_calls(&self) -> u32 {
+        self.draw_calls
+    }
+
+    /// Size of the interface's vertex buffer in bytes, see `UiRenderer::vertex_buffer_size`.
+    pub fn vertex_buffer_size(&self) -> u64 {
+        self.ui_renderer.vertex_buffer_size()
+    }
+
+    /// The adapter, surface and buffer sizes this state draws with and an estimate of its texture memory,
+    /// as of the last frame.
+    pub fn diagnostics(&self) -> RenderDiagnostics {
+        let format = self.pipeline_format;
+        let (width, height) = (self.config.width, self.config.height);
+        let (atlas_width, atlas_height) = self.gpu.atlas_dimensions();
+        let (tile_width, tile_height) = self.tiles.atlas_dimensions();
+        let mut textures = texture_bytes(wgpu::TextureFormat::Rgba8UnormSrgb, atlas_width, atlas_height, 1)
+            + texture_bytes(wgpu::TextureFormat::Rgba8UnormSrgb, tile_width, tile_height, 1)
+            + self.render_targets.values().map(RenderTarget::texture_bytes).sum::<u64>();
+        if self.msaa_view.is_some() {
+            textures += texture_bytes(format, width, height, self.sample_count);
+        }
+        if let RenderOutput::Offscreen { texture: Some(_) } = &self.output {
+            textures += texture_bytes(format, width, height, 1);
+        }
+        RenderDiagnostics {
+            adapter: self.gpu.adapter_info.clone(),
+            surface_format: format,
+            present_mode: self.config.present_mode,
+            size: self.size,
+            sample_count: self.sample_count,
+            vertex_buffer_bytes: self.ui_renderer.vertex_buffer_size(),
+            tile_buffer_bytes: self.tiles.instance_buffer_size(),
+            texture_bytes: textures,
+            draw_calls: self.draw_calls,
+        }
+    }
+
+    /// Lays `interface` out at the window's size and uploads it, after which frames draw it as it is now.
+    /// Call it after any change to the tree that should show.
+    pub fn update_interface<E>(&mut self, interface: &mut UiTree<E>) {
+        self.ui_renderer.upload(interface, &self.gpu.queue, &self.gpu.device, self.size);
+        self.sync_preview_target(interface);
+    }
+
+    /// Uploads only the element at `index` of `interface`, see `UiRenderer::upload_element`. Returns false when it
+    /// needs an `update_interface` instead.
+    pub fn update_interface_element<E>(&self, interface: &UiTree<E>, index: (usize, usize)) -> bool {
+        self.ui_renderer.upload_element(interface, index.0, index.1, &self.gpu.queue)
+    }
+
+    /// Uploads only the elements `UiTree::append_elements` just added to the panel at `panel_index`, or the whole
+    /// interface when they don't fit in the buffer it was last uploaded to. See `UiRenderer::upload_appended`.
+    pub fn update_interface_appended<E>(&mut self, interface: &mut UiTree<E>, panel_index: usize, appended: std::ops::Range<usize>) {
+        if !self.ui_renderer.upload_appended(interface, panel_index, appended, &self.gpu.queue, &self.gpu.device) {
+            self.update_interface(interface);
+        }
+    }
+
+    /// Resizing to 0x0, as minimizing does, stops drawing until a resize to a real size.
+    /// `interface` is laid out again at the new size.
+    pub fn resize<E>(&mut self, width: u32, height: u32, interface: &mut UiTree<E>) {
+        if width == 0 || height == 0 {
+            // Zeroed so `render` doesn't configure the surface back to the old size while minimized
+            self.config.width = 0;
+            self.config.height = 0;
+            self.is_surface_configured = false;
+        } else {
             self.size = PhysicalSize::new(width, height);
             self.config.width = width;
             self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
-            self.is_surface_configured = true;
+            self.configure_surface();
 
             self.camera_2d.update_screen_size(PhysicalSize::new(width, height));
-            self.queue.write_buffer(
-                &self.camera_buffer_2d, 
-                0, 
-            bytemuck::cast_slice(&[Camera2DUniform {
-                view_proj: self.camera_2d.build_view_projection_matrix().to_cols_array_2d(),
-            }]));
-            let mut intfc = self.interface_arc.lock().unwrap();
-            intfc.update_vertices_and_queue_text(self.size, &self.queue, &self.device);
+            self.gpu.queue.write_buffer(&self.camera_buffer_2d, 0, bytemuck::cast_slice(&[Camera2DUniform::new(&self.camera_2d)]));
+            self.update_interface(interface);
+        }
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Lays `interface` out again at `scale_factor`, from `WindowEvent::ScaleFactorChanged` when the window
+    /// moves to a monitor with a different DPI. The resize winit sends along with it resizes the surface.
+    pub fn set_scale_factor<E>(&mut self, scale_factor: f64, interface: &mut UiTree<E>) {
+        if self.scale_factor == scale_factor {
+            return;
+        }
+        self.scale_factor = scale_factor;
+        interface.set_scale_factor(scale_factor as f32);
+        if self.is_surface_configured {
+            self.update_interface(interface);
+        }
+        self.request_redraw();
+    }
+
+    /// Sizes the preview target and camera to the element showing the preview, as laid out by the last
+    /// upload of `interface`. The target is only recreated when that size changes.
+    fn sync_preview_target<E>(&mut self, interface: &UiTree<E>) {
+        self.preview_rect = interface.render_target_rect(PREVIEW_TARGET);
+        let Some(rect) = self.preview_rect else {
+            return;
+        };
+        let size = PhysicalSize::new((rect.width.round() as u32).max(1), (rect.height.round() as u32).max(1));
+        if self.render_targets.get(PREVIEW_TARGET).is_some_and(|target| target.size() == size) {
+            return;
+        }
+        let target = RenderTarget::new(&self.gpu.device, &self.gpu.material_bind_group_layout, &self.gpu.sampler, self.pipeline_format, self.sample_count, size, "Preview Render Target")
+            .with_depth(&self.gpu.device, PREVIEW_DEPTH_FORMAT);
+        self.render_targets.insert(PREVIEW_TARGET.to_string(), target);
+        self.preview_camera_mut().update_screen_size(size);
+    }
+
+    /// Whether `cursor` is over the element showing the level preview, false while `show_preview` is off.
+    pub fn is_cursor_over_preview(&self, cursor: PhysicalPosition<f64>) -> bool {
+        let (x, y) = (cursor.x as f32, cursor.y as f32);
+        self.show_preview && self.preview_rect.is_some_and(|rect| {
+            x >= rect.x && y >= rect.y && x < rect.x + rect.width && y < rect.y + rect.height
+        })
+    }
+
+    /// Moves the preview so its content follows a cursor dragged `delta` physical pixels.
+    pub fn pan_camera(&mut self, delta: Vec2) {
+        self.preview_camera_mut().pan(delta);
+    }
+
+    pub fn camera_zoom(&self) -> f32 {
+        self.preview_camera.zoom()
+    }
+
+    /// Clamped to 0.1-64.0, about the preview's center.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.preview_camera_mut().set_zoom(zoom);
+    }
+
+    /// Multiplies the preview zoom by `factor`, keeping what's under `cursor` (window position) in place.
+    pub fn zoom_at(&mut self, cursor: PhysicalPosition<f64>, factor: f32) {
+        let origin = self.preview_rect.map(|rect| Vec2::new(rect.x, rect.y)).unwrap_or_default();
+        self.preview_camera_mut().zoom_at(Vec2::new(cursor.x as f32, cursor.y as f32) - origin, factor);
+    }
+
+    pub fn preview_camera(&self) -> &Camera2D {
+        &self.preview_camera
+    }
+
+    /// The preview camera, uploaded with a redraw requested once the returned handle is dropped.
+    /// The GUI has its own camera, so nothing done here moves the interface.
+    pub fn preview_camera_mut(&mut self) -> CameraMut<'_> {
+        CameraMut {
+            camera: &mut self.preview_camera,
+            queue: &self.gpu.queue,
+            buffer: &self.preview_camera_buffer,
+            needs_redraw: &self.needs_redraw,
         }
     }
 
-    pub fn render(&self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+    /// Replaces the preview camera, keeping it sized to the preview target.
+    pub fn set_preview_camera(&mut self, mut camera: Camera2D) {
+        if let Some(target) = self.render_targets.get(PREVIEW_TARGET) {
+            camera.update_screen_size(target.size());
+        }
+        *self.preview_camera_mut() = camera;
+    }
+
+    /// Spacing and colors of the grid drawn behind the level in the preview. Tiles fill its cells,
+    /// so they're sized by the spacing too.
+    pub fn set_grid(&mut self, config: GridConfig) {
+        self.tiles.set_cell_size(&self.gpu.queue, config.cell_size());
+        self.grid.set_config(&self.gpu.queue, config);
+        self.request_redraw();
+    }
+
+    pub fn grid(&self) -> &GridConfig {
+        self.grid.config()
+    }
+
+    /// Cell of the level under `cursor` (window position), `None` unless it's over the preview.
+    pub fn cursor_to_cell(&self, cursor: PhysicalPosition<f64>) -> Option<IVec2> {
+        if !self.is_cursor_over_preview(cursor) {
+            return None;
+        }
+        let origin = self.preview_rect.map(|rect| Vec2::new(rect.x, rect.y)).unwrap_or_default();
+        let world = self.preview_camera.screen_to_world(Vec2::new(cursor.x as f32, cursor.y as f32) - origin);
+        Some((world / self.grid.config().cell_size()).floor().as_ivec2())
+    }
+
+    /// Swaps in the atlas tiles are drawn from, packed from the project's assets rather than the editor's.
+    /// Chunks already set keep their texture coordinates, set them again if the atlas was packed differently.
+    pub fn set_tile_atlas(&mut self, atlas_image: &image::RgbaImage) -> anyhow::Result<()> {
+        self.tiles.set_atlas(&self.gpu.device, &self.gpu.queue, &self.gpu.material_bind_group_layout, atlas_image)?;
+        self.request_redraw();
+        Ok(())
+    }
+
+    /// Replaces the tiles drawn for `chunk`, the `TILE_CHUNK_SIZE` by `TILE_CHUNK_SIZE` cells starting at
+    /// `chunk * TILE_CHUNK_SIZE`. Only that chunk is uploaded, an empty `instances` removes it.
+    pub fn set_tile_chunk(&mut self, chunk: IVec2, instances: &[TileInstance]) {
+        self.tiles.set_chunk(&self.gpu.device, &self.gpu.queue, chunk, instances);
+        self.request_redraw();
+    }
+
+    /// Removes every tile, for switching to another level.
+    pub fn clear_tiles(&mut self) {
+        self.tiles.clear();
+        self.request_redraw();
+    }
+
+    /// Swaps in a newly generated atlas. The texture is allocated again rather than overwritten,
+    /// so the new atlas may be a different size. `interface` is given `atlas_data` and uploaded again,
+    /// elements whose texture is no longer in it are drawn with `solid`. Windows sharing this state's
+    /// `GpuContext` draw from the new texture too, their interfaces need `atlas_data` as well.
+    pub fn reload_atlas<E>(&mut self, atlas_image: &image::RgbaImage, atlas_data: UiAtlas, interface: &mut UiTree<E>) -> anyhow::Result<()> {
+        check_atlas_size(atlas_image.dimensions(), &atlas_data)?;
+        self.gpu.set_atlas(atlas_image)?;
+
+        interface.set_atlas(atlas_data);
+        self.update_interface(interface);
+        Ok(())
+    }
+
+    /// Builds the interface, grid and tile pipelines again from the shader files in the working directory, the
+    /// repository root when the editor is run with cargo, so shader edits show without a restart. A file that
+    /// can't be read falls back to the shader compiled in. A pipeline whose shader doesn't build keeps drawing
+    /// with the old one, the error is logged and the first is returned.
+    pub fn reload_shaders(&mut self) -> Result<(), ShaderError> {
+        let shader_dir = std::env::current_dir().unwrap_or_default();
+        let ui = build_ui_pipeline(
+            &self.gpu.device,
+            [&self.gpu.camera_bind_group_layout, &self.gpu.material_bind_group_layout],
+            self.pipeline_format,
+            self.sample_count,
+            Some(&shader_dir),
+        );
+        let grid = self.grid.reload_shader(
+            &self.gpu.device,
+            &self.gpu.camera_bind_group_layout,
+            self.pipeline_format,
+            PREVIEW_DEPTH_FORMAT,
+            self.sample_count,
+            &shader_dir,
+        );
+        let tiles = self.tiles.reload_shader(
+            &self.gpu.device,
+            [&self.gpu.camera_bind_group_layout, &self.gpu.material_bind_group_layout],
+            self.pipeline_format,
+            PREVIEW_DEPTH_FORMAT,
+            self.sample_count,
+            &shader_dir,
+        );
+
+        let mut first_error = None;
+        for result in [ui.map(|pipeline| self.ui_pipeline = pipeline), grid, tiles] {
+            if let Err(e) = result {
+                log::error!("Keeping the old pipeline: {}", e);
+                first_error.get_or_insert(e);
+            }
+        }
+        self.request_redraw();
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Draws a frame of `interface` as last uploaded and presents it, or leaves it in the texture for a headless state.
+    /// A lost or outdated surface is reconfigured and the frame retried once, only a second failure is returned.
+    /// A lost one is also reported with an info toast, outdated ones come with every resize on some platforms.
+    pub fn render<E>(&mut self, interface: &mut UiTree<E>) -> Result<(), wgpu::SurfaceError> {
+        // We can't render unless the surface is configured, which a minimized window can't be
+        if !self.is_surface_configured && !self.configure_surface() {
+            return Ok(());
+        }
+        // The interface may have been rebuilt with the preview element moved or resized since the last frame
+        self.sync_preview_target(interface);
+
+        if let RenderOutput::Offscreen { texture: Some(texture) } = &self.output {
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.draw_calls = self.draw_frame(&view, interface);
+            self.frame_clock.tick(Instant::now());
+            return Ok(());
+        }
+        let Some(output) = self.acquire_surface_texture(interface)? else {
+            return Ok(());
+        };
+        debug_assert_eq!(output.texture.format(), self.pipeline_format, "surface format changed without rebuilding the pipelines");
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.draw_calls = self.draw_frame(&view, interface);
+        output.present();
+        self.frame_clock.tick(Instant::now());
 
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder")
+        Ok(())
+    }
+
+    /// Renders a frame of `interface` with a headless state and reads it back, see `new_headless`.
+    /// Fails for a state drawing to a window, or one resized to 0x0.
+    pub fn render_to_image<E>(&mut self, interface: &mut UiTree<E>) -> anyhow::Result<image::RgbaImage> {
+        if self.window().is_some() {
+            anyhow::bail!("Only a headless RenderState renders to an image");
+        }
+        self.render(interface)?;
+        let RenderOutput::Offscreen { texture: Some(texture) } = &self.output else {
+            anyhow::bail!("Nothing to read back from a 0x0 RenderState");
+        };
+        let (width, height) = (self.config.width, self.config.height);
+
+        // Rows in the buffer have to start on 256 byte boundaries, the padding is dropped again below
+        let row_bytes = 4 * width;
+        let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Readback Buffer"),
+            size: padded_row_bytes as wgpu::BufferAddress * height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder")
         });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
 
-        let interface_guard = self.interface_arc.lock().unwrap();
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            // The receiver only goes away if this function has already failed
+            let _ = sender.send(result);
+        });
+        self.gpu.device.poll(wgpu::PollType::Wait)?;
+        receiver.recv()??;
 
+        let mut pixels = Vec::with_capacity(row_bytes as usize * height as usize);
         {
+            let mapped = slice.get_mapped_range();
+            for row in mapped.chunks(padded_row_bytes as usize) {
+                pixels.extend_from_slice(&row[..row_bytes as usize]);
+            }
+        }
+        buffer.unmap();
+        image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("The {}x{} frame read back short", width, height))
+    }
+
+    /// The surface's next texture, after reconfiguring once if it was lost or outdated. `None` when there's nothing
+    /// to draw into, because the surface can't be configured at the window's size or the state is headless.
+    fn acquire_surface_texture<E>(&mut self, interface: &mut UiTree<E>) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+        let RenderOutput::Surface { surface, .. } = &self.output else {
+            return Ok(None);
+        };
+        match surface.get_current_texture() {
+            Ok(output) => Ok(Some(output)),
+            Err(e @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                if !self.configure_surface() {
+                    return Ok(None);
+                }
+                if e == wgpu::SurfaceError::Lost {
+                    log::info!("Recreated the lost window surface");
+                    interface.push_toast(ToastLevel::Info, "The display was reset, drawing resumed".to_string(), SURFACE_LOST_TOAST_DURATION);
+                    self.update_interface(interface);
+                }
+                match &self.output {
+                    RenderOutput::Surface { surface, .. } => surface.get_current_texture().map(Some),
+                    RenderOutput::Offscreen { .. } => Ok(None),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records and submits the preview and UI passes, drawing into `view`, the surface's texture or the headless one.
+    /// Returns the draw calls the interface issued.
+    fn draw_frame<E>(&self, view: &wgpu::TextureView, interface: &UiTree<E>) -> u32 {
+        // Read every frame, a theme switch recolors the clear along with the interface
+        let preview_clear_color = interface.theme().clear_color();
+        let clear_color = interface.clear_color();
+        let mut encoder = self.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Frame Encoder")
+        });
+
+        // Drawn first so the UI pass samples this frame's preview
+        if self.show_preview && self.preview_rect.is_some() && let Some(target) = self.render_targets.get(PREVIEW_TARGET) {
+            let (target_view, resolve_target) = target.attachment_views();
+            self.gpu.set_last_pass(PREVIEW_PASS);
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
+                label: Some(PREVIEW_PASS),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: target_view,
+                    resolve_target,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::from_hex("#21262d")),
+                        load: wgpu::LoadOp::Clear(preview_clear_color),
                         store: wgpu::StoreOp::Store,
                     },
                     depth_slice: None,
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: target.depth_view().map(|view| wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
 
-            render_pass.set_pipeline(&self.ui_pipeline);
-            render_pass.set_bind_group(0, &self.camera_bind_group_2d, &[]);
-            render_pass.set_bind_group(1, &self.gui_material_bind_group, &[]);
-
-            interface_guard.render(&mut render_pass);
-
-            interface_guard.draw_text_brush(&mut render_pass);
-
-            /*if self.gui_state == GuiPageState::ProjectView {
-                render_pass.set_pipeline(&self.preview_pipeline);
-                render_pass.set_viewport(0.0, 0.0, self.size.width as f32 / 2.0, self.size.height as f32 / 2.0, 0.0, 1.0);
-                render_pass.set_vertex_buffer(0, self.triangle_vertex_buffer.slice(..));
-                render_pass.draw(0..3, 0..1);
-            }*/
+            self.grid.draw(&mut render_pass, &self.preview_camera_bind_group);
+            self.tiles.draw(&mut render_pass, &self.preview_camera_bind_group);
         }
 
-        
+        let draw_calls;
         {
+            // With MSAA the pass draws into the multisampled texture and resolves into the surface
+            let (pass_view, resolve_target) = match &self.msaa_view {
+                Some(msaa_view) => (msaa_view, Some(view)),
+                None => (view, None),
+            };
+            self.gpu.set_last_pass(UI_PASS);
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
+                label: Some(UI_PASS),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: pass_view,
+                    resolve_target,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
+                        load: wgpu::LoadOp::Clear(clear_color),
                         store: wgpu::StoreOp::Store,
                     },
                     depth_slice: None,
@@ -345,71 +855,329 @@ impl RenderState {
                 timestamp_writes: None,
             });
 
-            if self.gui_state == GuiPageState::ProjectView {
-                render_pass.set_pipeline(&self.preview_pipeline);
-                render_pass.set_viewport(0.0, 0.0, self.size.width as f32 / 2.0, self.size.height as f32 / 2.0, 0.0, 1.0);
-                render_pass.set_vertex_buffer(0, self.triangle_vertex_buffer.slice(..));
-                render_pass.draw(0..3, 0..1);
+            let atlas_bind_group = self.gpu.atlas_bind_group();
+            let ui_pass_state = UiPassState {
+                pipeline: &self.ui_pipeline,
+                camera_bind_group: &self.camera_bind_group_2d,
+                material_bind_group: &atlas_bind_group,
+                render_targets: &self.render_targets,
+            };
+            ui_pass_state.apply(&mut render_pass);
+
+            draw_calls = self.ui_renderer.render(interface, &mut render_pass, &ui_pass_state, &self.gpu.device, &self.gpu.queue, self.size);
+        }
+
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+        draw_calls
+    }
+}
+
+/// Tries each of `options`' adapter attempts in turn, returning the first adapter that can draw to `window`
+/// with its instance and surface, or the first adapter at all without a window. The error lists every attempt
+/// and why it failed.
+async fn request_adapter(window: Option<&Arc<Window>>, options: &RenderOptions) -> anyhow::Result<(wgpu::Instance, Option<wgpu::Surface<'static>>, wgpu::Adapter)> {
+    let mut failures = Vec::new();
+    for (backends, force_fallback_adapter) in options.adapter_attempts() {
+        let attempt = if force_fallback_adapter { format!("{:?} (fallback adapter)", backends) } else { format!("{:?}", backends) };
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let surface = match window.map(|window| instance.create_surface(window.clone())).transpose() {
+            Ok(surface) => surface,
+            Err(e) => {
+                log::warn!("Unable to create a surface on {}: {}", attempt, e);
+                failures.push(format!("{}: no surface ({})", attempt, e));
+                continue;
+            }
+        };
+        let request = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: options.power_preference,
+            compatible_surface: surface.as_ref(),
+            force_fallback_adapter,
+        });
+        match request.await {
+            Ok(adapter) => {
+                if !failures.is_empty() {
+                    log::warn!("Using {:?} on {} after: {}", adapter.get_info().name, attempt, failures.join("; "));
+                }
+                return Ok((instance, surface, adapter));
+            }
+            Err(e) => {
+                log::warn!("No adapter on {}: {}", attempt, e);
+                failures.push(format!("{}: no adapter ({})", attempt, e));
             }
         }
-        
+    }
+    match window {
+        Some(_) => anyhow::bail!("No graphics adapter can draw to the window. Tried:\n{}", failures.join("\n")),
+        None => anyhow::bail!("No graphics adapter is available. Tried:\n{}", failures.join("\n")),
+    }
+}
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+/// The interface's pipeline, `layouts` are the camera and atlas layouts. Built with the compiled in shader, or the one in `shader_dir` when given.
+fn build_ui_pipeline(
+    device: &wgpu::Device,
+    layouts: [&wgpu::BindGroupLayout; 2],
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    shader_dir: Option<&Path>,
+) -> Result<wgpu::RenderPipeline, ShaderError> {
+    let mut builder = builder::PipeLineBuilder::new(device);
+    builder
+        .set_pixel_format(format)
+        .set_sample_count(sample_count)
+        // Colors and glyph edges with alpha below 1 show what's under them
+        .set_blend_state(wgpu::BlendState::ALPHA_BLENDING)
+        .add_vertex_buffer_layout(Vertex::desc())
+        .add_bind_group_layout(layouts[0])
+        .add_bind_group_layout(layouts[1])
+        .set_shader_module(UI_SHADER_FILE, include_str!("../../ui_shader.wgsl"), "vs_main", "fs_main");
+    if let Some(shader_dir) = shader_dir {
+        builder.set_shader_file(shader_dir.join(UI_SHADER_FILE));
+    }
+    builder.build("UI Pipeline")
+}
 
-        Ok(())
+/// The first sRGB format of the ones a surface supports, so colors are encoded on write, or the first one if none is.
+/// The surface is configured with it and every pipeline drawing into it is built for it.
+fn pick_surface_format(formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+    formats.iter().copied().find(|format| format.is_srgb()).unwrap_or(formats[0])
+}
+
+/// `requested` if the adapter can multisample `format` that many times, otherwise 1.
+fn supported_sample_count(adapter: &wgpu::Adapter, format: wgpu::TextureFormat, requested: u32) -> u32 {
+    if requested <= 1 || adapter.get_texture_format_features(format).flags.sample_count_supported(requested) {
+        return requested.max(1);
     }
+    log::warn!("The adapter can't draw {:?} with {}x MSAA, drawing without it", format, requested);
+    1
+}
 
-    pub fn old_render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let interface = self.interface_arc.lock().unwrap();
-        self.window.request_redraw();
-        //let ui_group = self.interface.get_render_data();
-        
-        // We can't render unless the surface is configured
-        if !self.is_surface_configured {
-            return Ok(());
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+    use crate::{definitions::{InteractionStyle, UiAtlasTexture}, gui::{element::Element, layout::Coordinate, panel::Panel}};
+
+    /// A headless state for `interface`, with a white "solid" texture, or `None` where there's no adapter at all.
+    fn headless(width: u32, height: u32, interface: &mut UiTree<()>) -> Option<RenderState> {
+        let atlas_image = image::RgbaImage::from_pixel(1, 1, Rgba([255; 4]));
+        match pollster::block_on(RenderState::new_headless(width, height, interface, &atlas_image, &RenderOptions::default())) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                eprintln!("Skipping, no adapter to render with: {}", e);
+                None
+            }
         }
+    }
 
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+    #[test]
+    fn surfaces_prefer_an_srgb_format() {
+        use wgpu::TextureFormat::*;
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+        assert_eq!(pick_surface_format(&[Bgra8Unorm, Rgba8UnormSrgb, Bgra8UnormSrgb]), Rgba8UnormSrgb);
+        assert_eq!(pick_surface_format(&[Rgba16Float, Bgra8Unorm]), Rgba16Float);
+    }
 
+    #[test]
+    fn pipelines_are_built_for_the_configured_format() {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let Some(mut state) = headless(64, 64, &mut interface) else {
+            return;
+        };
+        assert_eq!(state.pipeline_format, state.config.format);
+        assert_eq!(state.config.format, HEADLESS_FORMAT);
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("UI Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::from_hex("#ffff")),
-                    store: wgpu::StoreOp::Store,
-                },
-                depth_slice: None
-            })],
-            depth_stencil_attachment: None,
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
+        state.render_to_image(&mut interface).unwrap();
+        let RenderOutput::Offscreen { texture: Some(texture) } = &state.output else {
+            panic!("a headless state draws into a texture");
+        };
+        assert_eq!(texture.format(), state.pipeline_format);
+    }
 
-        render_pass.set_pipeline(&self.ui_pipeline);
-        render_pass.set_bind_group(0, &self.camera_bind_group_2d, &[]);
-        //render_pass.set_bind_group(1, &self.diffuse_bind_group, &[]);
-        interface.render(&mut render_pass);
+    #[test]
+    fn headless_frames_show_each_panel_in_its_color() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+        let mut interface = UiTree::new(atlas);
+        interface.add_panel(Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.5, 1.0)).with_color("#ff0000"));
+        interface.add_panel(Panel::new(Coordinate::new(0.5, 0.0), Coordinate::new(1.0, 1.0)).with_color("#0000ff"));
+        let Some(mut state) = headless(800, 600, &mut interface) else {
+            return;
+        };
+        state.update_interface(&mut interface);
 
-        
+        let frame = state.render_to_image(&mut interface).unwrap();
+        assert_eq!(frame.dimensions(), (800, 600));
+        assert_eq!(*frame.get_pixel(200, 300), Rgba([255, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(600, 300), Rgba([0, 0, 255, 255]));
+    }
 
-        drop(render_pass);
+    #[test]
+    fn appended_elements_draw_without_a_full_upload() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+        let mut interface = UiTree::new(atlas);
+        let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)).with_color("#000000");
+        for column in 0..4 {
+            let x = column as f32 * 0.25;
+            panel.add_element(Element::new(Coordinate::new(x, 0.0), Coordinate::new(x + 0.25, 0.5), "solid").with_color("#ff0000"));
+        }
+        interface.add_panel(panel);
+        let Some(mut state) = headless(800, 600, &mut interface) else {
+            return;
+        };
+        state.update_interface(&mut interface);
+        let size = state.vertex_buffer_size();
 
-        self.queue.submit(iter::once(encoder.finish()));
-        output.present();
+        let appended = interface.append_elements(0, vec![
+            Element::new(Coordinate::new(0.0, 0.5), Coordinate::new(1.0, 1.0), "solid").with_color("#0000ff"),
+        ]);
+        assert!(state.ui_renderer.upload_appended(&mut interface, 0, appended, &state.gpu.queue, &state.gpu.device));
+        assert_eq!(state.vertex_buffer_size(), size);
 
-        Ok(())
+        let frame = state.render_to_image(&mut interface).unwrap();
+        assert_eq!(*frame.get_pixel(100, 150), Rgba([255, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(700, 150), Rgba([255, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(400, 450), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn frames_draw_right_after_construction() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+        let mut interface = UiTree::new(atlas);
+        interface.add_panel(Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0)).with_color("#00ff00"));
+        let Some(mut state) = headless(64, 48, &mut interface) else {
+            return;
+        };
+        // No resize has happened, the surface was configured on creation
+        assert!(state.is_surface_configured);
+        state.render(&mut interface).unwrap();
+        state.update_interface(&mut interface);
+
+        let frame = state.render_to_image(&mut interface).unwrap();
+        assert_eq!(frame.dimensions(), (64, 48));
+        assert_eq!(*frame.get_pixel(32, 24), Rgba([0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn zero_sized_states_draw_once_resized() {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let Some(mut state) = headless(0, 0, &mut interface) else {
+            return;
+        };
+        assert!(!state.is_surface_configured);
+        state.render(&mut interface).unwrap();
+        assert!(state.render_to_image(&mut interface).is_err());
+
+        state.resize(32, 16, &mut interface);
+        assert_eq!(state.render_to_image(&mut interface).unwrap().dimensions(), (32, 16));
+    }
+
+    #[test]
+    fn minimized_states_skip_frames_until_restored() {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let Some(mut state) = headless(64, 48, &mut interface) else {
+            return;
+        };
+        state.resize(0, 0, &mut interface);
+        // Rendering doesn't configure the surface back to the size it had before minimizing
+        state.render(&mut interface).unwrap();
+        assert!(!state.is_surface_configured);
+
+        state.resize(64, 48, &mut interface);
+        assert_eq!(state.render_to_image(&mut interface).unwrap().dimensions(), (64, 48));
+    }
+
+    #[test]
+    fn the_debug_overlay_graphs_frame_times() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+        let mut interface = UiTree::new(atlas);
+        // One frame far over the graph's top, its bar fills the left three quarters of the graph
+        interface.show_debug_overlay(Vec::new(), vec![Duration::from_secs(1)]);
+        let Some(mut state) = headless(320, 240, &mut interface) else {
+            return;
+        };
+        state.update_interface(&mut interface);
+
+        let frame = state.render_to_image(&mut interface).unwrap();
+        assert_eq!(*frame.get_pixel(40, 30), Rgba([0xf8, 0x51, 0x49, 255]));
+        assert_ne!(*frame.get_pixel(215, 30), Rgba([0xf8, 0x51, 0x49, 255]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn a_hover_transition_rewrites_only_the_two_elements() {
+        let mut atlas = UiAtlas::new(1, 1);
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 1, 1));
+        let mut interface: UiTree<()> = UiTree::new(atlas);
+        let mut panel = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0));
+        for (start, end) in [(0.0, 0.5), (0.5, 1.0)] {
+            panel.add_element(Element::new(Coordinate::new(start, 0.0), Coordinate::new(end, 1.0), "solid")
+                .with_color("#ff0000")
+                .with_hover_color("#0000ff"));
+        }
+        interface.add_panel(panel);
+        // Switched instantly, so the frame shows where the hover ends up rather than the start of a fade
+        interface.set_hover_transition(Duration::ZERO);
+        let Some(mut state) = headless(200, 100, &mut interface) else {
+            return;
+        };
+        state.update_interface(&mut interface);
+        interface.handle_interaction(PhysicalPosition::new(50.0, 50.0), state.size, InteractionStyle::OnHover);
+        state.update_interface(&mut interface);
+
+        let moved = interface.handle_interaction(PhysicalPosition::new(150.0, 50.0), state.size, InteractionStyle::OnHover);
+        assert_eq!(moved.hover_changed, [(0, 0), (0, 1)]);
+        // Each is one write of the element's own quads, a full upload would be needed if either returned false
+        for &index in &moved.hover_changed {
+            assert!(state.update_interface_element(&interface, index));
+        }
+
+        let frame = state.render_to_image(&mut interface).unwrap();
+        assert_eq!(*frame.get_pixel(50, 50), Rgba([255, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(150, 50), Rgba([0, 0, 255, 255]));
+    }
+
+    /// A page with a sidebar of buttons, and a menu of twelve items over it while `menu_open`.
+    fn page(menu_open: bool) -> UiTree<()> {
+        let mut interface = UiTree::new(UiAtlas::new(1, 1));
+        let mut sidebar = Panel::new(Coordinate::new(0.0, 0.0), Coordinate::new(0.2, 1.0)).with_color("#202020");
+        for row in 0..8 {
+            let y = row as f32 * 0.1;
+            sidebar.add_element(Element::new(Coordinate::new(0.0, y), Coordinate::new(1.0, y + 0.1), "solid"));
+        }
+        interface.add_panel(sidebar);
+        if menu_open {
+            let mut menu = Panel::new(Coordinate::new(0.3, 0.1), Coordinate::new(0.7, 0.9)).with_color("#303030");
+            for row in 0..12 {
+                let y = row as f32 / 12.0;
+                menu.add_element(Element::new(Coordinate::new(0.0, y), Coordinate::new(1.0, y + 1.0 / 12.0), "solid"));
+            }
+            interface.add_panel(menu);
+        }
+        interface
+    }
+
+    #[test]
+    fn toggling_a_menu_reuses_the_vertex_buffer() {
+        let mut interface = page(false);
+        let Some(mut state) = headless(320, 240, &mut interface) else {
+            return;
+        };
+        // The buffer only ever grows, so every allocation shows as a new size
+        let mut sizes = vec![state.vertex_buffer_size()];
+        for toggle in 1..=100 {
+            let mut rebuilt = page(toggle % 2 == 1);
+            state.update_interface(&mut rebuilt);
+            if sizes.last() != Some(&state.vertex_buffer_size()) {
+                sizes.push(state.vertex_buffer_size());
+            }
+        }
+        // Nothing before the first upload, then at most one allocation for each page
+        assert!(sizes.len() <= 3, "the vertex buffer was allocated {} times: {:?}", sizes.len() - 1, sizes);
+    }
+}
@@ -0,0 +1,39 @@
+/// How `RenderState::new` picks a GPU and presents frames.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Backends tried first. When none of them has a usable adapter every backend is tried, then the fallback adapter.
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Goes straight to the software adapter, slow but independent of GPU drivers.
+    pub force_fallback_adapter: bool,
+    /// Used when the surface supports it, otherwise `Fifo`, which every surface does.
+    pub present_mode: wgpu::PresentMode,
+    /// 1, or 4 for MSAA, which falls back to 1 when the adapter can't do it.
+    pub sample_count: u32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::PRIMARY,
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: false,
+            present_mode: wgpu::PresentMode::Fifo,
+            sample_count: 1,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Backends and fallback flag of each adapter request, in the order they're tried.
+    pub(crate) fn adapter_attempts(&self) -> Vec<(wgpu::Backends, bool)> {
+        let mut attempts = vec![(self.backends, self.force_fallback_adapter)];
+        if !self.force_fallback_adapter {
+            if self.backends != wgpu::Backends::all() {
+                attempts.push((wgpu::Backends::all(), false));
+            }
+            attempts.push((wgpu::Backends::all(), true));
+        }
+        attempts
+    }
+}
@@ -0,0 +1,128 @@
+use winit::dpi::PhysicalSize;
+
+use crate::gpu::{create_scoped, texture_bytes};
+
+/// Offscreen texture a pass draws into and UI elements sample, see `Element::with_render_target`.
+pub(crate) struct RenderTarget {
+    view: wgpu::TextureView,
+    /// Multisampled texture passes draw into and resolve to `view`, when the target has more than one sample.
+    msaa_view: Option<wgpu::TextureView>,
+    /// Depth buffer the same size and sample count as the target, see `with_depth`.
+    depth_view: Option<wgpu::TextureView>,
+    bind_group: wgpu::BindGroup,
+    size: PhysicalSize<u32>,
+    sample_count: u32,
+    /// Estimated memory of the textures above, see `texture_bytes`.
+    texture_bytes: u64,
+    /// The target's label, the depth buffer's is derived from it.
+    label: String,
+}
+
+impl RenderTarget {
+    /// `layout` and `sampler` are the UI material's, so the UI pipeline can bind the target in place of the atlas.
+    /// `format` and `sample_count` have to match the pipelines drawing into the target.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        size: PhysicalSize<u32>,
+        label: &str,
+    ) -> Self {
+        let size = PhysicalSize::new(size.width.max(1), size.height.max(1));
+        let label = &format!("{} ({}x{})", label, size.width, size.height);
+        let texture = create_scoped(device, label, || device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            label: Some(label),
+            view_formats: &[],
+        }));
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let msaa_label = format!("{} MSAA", label);
+        let msaa_view = (sample_count > 1).then(|| {
+            create_scoped(device, &msaa_label, || device.create_texture(&wgpu::TextureDescriptor {
+                size: texture.size(),
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                label: Some(&msaa_label),
+                view_formats: &[],
+            })).create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        let texture_bytes = texture_bytes(format, size.width, size.height, 1)
+            + msaa_view.as_ref().map_or(0, |_| texture_bytes(format, size.width, size.height, sample_count));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                }
+            ],
+        });
+        Self { view, msaa_view, depth_view: None, bind_group, size, sample_count, texture_bytes, label: label.to_string() }
+    }
+
+    /// Gives the target a `format` depth buffer, for passes whose pipeline tests depth.
+    pub(crate) fn with_depth(mut self, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let label = format!("{} Depth", self.label);
+        let texture = create_scoped(device, &label, || device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some(&label),
+            view_formats: &[],
+        }));
+        self.texture_bytes += texture_bytes(format, self.size.width, self.size.height, self.sample_count);
+        self.depth_view = Some(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        self
+    }
+
+    /// The view a pass draws into and the one it resolves to, which is `view` itself without multisampling.
+    pub(crate) fn attachment_views(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(&self.view)),
+            None => (&self.view, None),
+        }
+    }
+
+    pub(crate) fn depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.depth_view.as_ref()
+    }
+
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub(crate) fn size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// Estimated memory of the target's textures, multisampled and depth ones included.
+    pub(crate) fn texture_bytes(&self) -> u64 {
+        self.texture_bytes
+    }
+}
@@ -0,0 +1,208 @@
+use std::{sync::Arc, time::Instant};
+
+use winit::{application::ApplicationHandler, dpi::PhysicalPosition, event::{ElementState, MouseButton, WindowEvent}, event_loop::{ActiveEventLoop, ControlFlow, EventLoop}, keyboard::{KeyCode, PhysicalKey}, window::{Fullscreen, Window, WindowId}};
+
+use crate::{definitions::InteractionStyle, gui::{interface::UiTree, scroll::ScrollEvent}, RenderOptions, RenderState};
+
+/// What `run_simple` does with a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Closes the window and returns from `run_simple`.
+    Exit,
+    /// Switches between a borderless fullscreen window and the normal one.
+    ToggleFullscreen,
+}
+
+impl KeyAction {
+    /// Escape exits and F11 toggles fullscreen, other keys do nothing.
+    pub fn for_key(key: KeyCode) -> Option<KeyAction> {
+        match key {
+            KeyCode::Escape => Some(KeyAction::Exit),
+            KeyCode::F11 => Some(KeyAction::ToggleFullscreen),
+            _ => None,
+        }
+    }
+}
+
+/// Opens a window titled `title` showing `interface` and runs until it's closed, for programs that want the
+/// interface without the editor around it. `atlas_image` holds the pixels of the atlas `interface` was made with.
+///
+/// The mouse drives the interface like it does in the editor. Events its elements return are handed to
+/// `on_event` along with the tree, which is uploaded again afterwards so changes made there show. The keyboard
+/// only does what `KeyAction::for_key` says.
+pub fn run_simple<E: 'static>(
+    title: &str,
+    interface: UiTree<E>,
+    atlas_image: image::RgbaImage,
+    on_event: impl FnMut(E, &mut UiTree<E>) + 'static,
+) -> anyhow::Result<()> {
+    let event_loop = EventLoop::new()?;
+    event_loop.set_control_flow(ControlFlow::Wait);
+    let mut app = SimpleApp {
+        title: title.to_string(),
+        interface,
+        atlas_image: Some(atlas_image),
+        on_event,
+        render_state: None,
+        cursor_position: None,
+        error: None,
+    };
+    event_loop.run_app(&mut app)?;
+    match app.error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+struct SimpleApp<E, F> {
+    title: String,
+    interface: UiTree<E>,
+    /// Taken when the render state is created.
+    atlas_image: Option<image::RgbaImage>,
+    on_event: F,
+    render_state: Option<RenderState>,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    /// Why the window couldn't be opened, returned from `run_simple` once the loop exits.
+    error: Option<anyhow::Error>,
+}
+
+impl<E, F: FnMut(E, &mut UiTree<E>)> SimpleApp<E, F> {
+    fn create_render_state(&mut self, event_loop: &ActiveEventLoop) -> anyhow::Result<RenderState> {
+        let window = Arc::new(event_loop.create_window(Window::default_attributes().with_title(self.title.as_str()))?);
+        let atlas_image = self.atlas_image.take().ok_or_else(|| anyhow::anyhow!("the render state was already created"))?;
+        pollster::block_on(RenderState::new(window, &mut self.interface, &atlas_image, &RenderOptions::default()))
+    }
+
+    fn handle_key(&self, event_loop: &ActiveEventLoop, action: KeyAction) {
+        match action {
+            KeyAction::Exit => event_loop.exit(),
+            KeyAction::ToggleFullscreen => {
+                if let Some(window) = self.render_state.as_ref().and_then(RenderState::window) {
+                    let fullscreen = window.fullscreen().is_none().then_some(Fullscreen::Borderless(None));
+                    window.set_fullscreen(fullscreen);
+                }
+            }
+        }
+    }
+}
+
+impl<E, F: FnMut(E, &mut UiTree<E>)> ApplicationHandler for SimpleApp<E, F> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.render_state.is_some() {
+            return;
+        }
+        match self.create_render_state(event_loop) {
+            Ok(render_state) => self.render_state = Some(render_state),
+            Err(e) => {
+                self.error = Some(e);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        if self.interface.tick(now) {
+            rs.update_interface(&mut self.interface);
+        }
+        let turned = self.interface.step_spinners(now);
+        if !turned.iter().all(|&index| rs.update_interface_element(&self.interface, index)) {
+            rs.update_interface(&mut self.interface);
+        }
+        match self.interface.next_wakeup() {
+            Some(deadline) => event_loop.set_control_flow(ControlFlow::WaitUntil(deadline)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
+        if rs.take_redraw_request(&self.interface)
+            && let Some(window) = rs.window() {
+            window.request_redraw();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+        let Some(screen_size) = rs.window().map(|window| window.inner_size()) else {
+            return;
+        };
+        let interface = &mut self.interface;
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => rs.resize(size.width, size.height, interface),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => rs.set_scale_factor(scale_factor, interface),
+            WindowEvent::RedrawRequested => {
+                if let Err(e) = rs.render(interface) {
+                    log::error!("Unable to render {}", e);
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let previous = self.cursor_position.replace(position);
+                if interface.captured_element().is_some() {
+                    if interface.handle_captured_move(position, screen_size) {
+                        rs.update_interface(interface);
+                    }
+                } else {
+                    if let Some(previous) = previous
+                        && interface.pressed_element().is_some() {
+                        let delta = ((position.x - previous.x) as f32, (position.y - previous.y) as f32);
+                        interface.handle_interaction(position, screen_size, InteractionStyle::OnDrag { delta });
+                    }
+                    let hovered = interface.handle_interaction(position, screen_size, InteractionStyle::OnHover);
+                    if !hovered.hover_changed.is_empty() | interface.track_tooltip(position, Instant::now()) {
+                        rs.update_interface(interface);
+                    }
+                }
+            }
+            WindowEvent::CursorLeft { .. } if interface.hide_tooltip() => {
+                rs.update_interface(interface);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                if let Some(position) = self.cursor_position {
+                    let scroll = ScrollEvent::from_wheel(delta, position, rs.scale_factor(), false);
+                    let result = interface.handle_scroll(scroll.position, screen_size, scroll.delta_lines);
+                    if result.scrolled.is_some() {
+                        rs.update_interface(interface);
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                let interaction = if state.is_pressed() {
+                    InteractionStyle::OnPress
+                } else {
+                    interface.end_capture();
+                    InteractionStyle::OnRelease
+                };
+                if let Some(position) = self.cursor_position {
+                    interface.handle_interaction(position, screen_size, interaction);
+                    rs.update_interface(interface);
+                }
+            }
+            WindowEvent::Focused(false) => {
+                interface.end_capture();
+                interface.cancel_press();
+            }
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed && !event.repeat => {
+                if let PhysicalKey::Code(code) = event.physical_key
+                    && let Some(action) = KeyAction::for_key(code) {
+                    self.handle_key(event_loop, action);
+                }
+            }
+            _ => {}
+        }
+
+        let events = self.interface.drain_events();
+        if !events.is_empty() {
+            for event in events {
+                (self.on_event)(event, &mut self.interface);
+            }
+            if let Some(rs) = self.render_state.as_mut() {
+                rs.update_interface(&mut self.interface);
+            }
+        }
+    }
+}
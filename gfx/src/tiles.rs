@@ -0,0 +1,285 @@
+use std::{collections::HashMap, path::Path};
+
+use glam::IVec2;
+use wgpu::util::DeviceExt;
+
+use crate::{builder::{PipeLineBuilder, ShaderError}, definitions::TileInstance, gpu::{self, create_scoped}};
+
+const SHADER_FILE: &str = "tile_shader.wgsl";
+
+/// Cells along each side of a tile chunk, see `RenderState::set_tile_chunk`.
+pub const TILE_CHUNK_SIZE: i32 = 16;
+/// Instances a chunk's slot in the instance buffer holds, one per cell.
+const CHUNK_CAPACITY: u32 = (TILE_CHUNK_SIZE * TILE_CHUNK_SIZE) as u32;
+const INSTANCE_SIZE: wgpu::BufferAddress = std::mem::size_of::<TileInstance>() as wgpu::BufferAddress;
+/// Chunk slots the instance buffer starts with, it doubles whenever it runs out.
+const INITIAL_SLOTS: u32 = 16;
+
+/// Cell size as `tile_shader.wgsl` reads it.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TileUniform {
+    cell_size: f32,
+    _padding: [f32; 3],
+}
+
+impl TileUniform {
+    fn new(cell_size: f32) -> Self {
+        Self { cell_size, _padding: [0.0; 3] }
+    }
+}
+
+/// Where a chunk's instances are in the instance buffer.
+#[derive(Clone, Copy)]
+struct ChunkSlot {
+    slot: u32,
+    count: u32,
+}
+
+/// Draws the level's tiles in the preview pass, one instanced quad per occupied cell.
+///
+/// The instance buffer is split into fixed slots of `CHUNK_CAPACITY` instances, one per chunk of
+/// `TILE_CHUNK_SIZE` by `TILE_CHUNK_SIZE` cells. Changing a chunk rewrites only its slot, so an edit uploads at most
+/// one chunk's worth of instances however big the level is.
+pub(crate) struct TileRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    /// Layout of `uniform_bind_group`, kept for building the pipeline again in `reload_shader`.
+    uniform_layout: wgpu::BindGroupLayout,
+    /// The tile atlas, nothing is drawn until `set_atlas`.
+    atlas_bind_group: Option<wgpu::BindGroup>,
+    /// Size of the tile atlas texture, 0x0 before `set_atlas`.
+    atlas_dimensions: (u32, u32),
+    /// Sampler for the tile atlas. Nearest, so tiles butting up against each other don't bleed at the edges.
+    sampler: wgpu::Sampler,
+    instance_buffer: wgpu::Buffer,
+    /// Slots `instance_buffer` has room for.
+    slot_capacity: u32,
+    chunks: HashMap<IVec2, ChunkSlot>,
+    /// Slots of removed chunks, reused before the buffer grows.
+    free_slots: Vec<u32>,
+    /// Slots handed out so far, freed ones included.
+    slots_used: u32,
+}
+
+impl TileRenderer {
+    /// Draws in the preview pass, into a `format` target with `sample_count` samples and a `depth_format` depth
+    /// attachment. The preview camera is bound at group 0 and the atlas at group 1, with `atlas_layout`.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        atlas_layout: &wgpu::BindGroupLayout,
+        format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        cell_size: f32,
+    ) -> Result<Self, ShaderError> {
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tile Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[TileUniform::new(cell_size)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("Tile Bind Group Layout"),
+        });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Tile Bind Group"),
+            layout: &uniform_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                }
+            ]
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let layouts = [camera_bind_group_layout, atlas_layout, &uniform_layout];
+        let pipeline = Self::build_pipeline(device, layouts, format, depth_format, sample_count, None)?;
+
+        Ok(Self {
+            pipeline,
+            uniform_buffer,
+            uniform_bind_group,
+            uniform_layout,
+            atlas_bind_group: None,
+            atlas_dimensions: (0, 0),
+            sampler,
+            instance_buffer: create_instance_buffer(device, INITIAL_SLOTS),
+            slot_capacity: INITIAL_SLOTS,
+            chunks: HashMap::new(),
+            free_slots: Vec::new(),
+            slots_used: 0,
+        })
+    }
+
+    /// Builds the pipeline again with the shader read from `shader_dir`, for the same target `new` was given.
+    /// `layouts` are the camera and atlas layouts. The old pipeline stays if the shader doesn't build.
+    pub(crate) fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        layouts: [&wgpu::BindGroupLayout; 2],
+        format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        shader_dir: &Path,
+    ) -> Result<(), ShaderError> {
+        let layouts = [layouts[0], layouts[1], &self.uniform_layout];
+        self.pipeline = Self::build_pipeline(device, layouts, format, depth_format, sample_count, Some(shader_dir))?;
+        Ok(())
+    }
+
+    /// With the compiled in shader, or the one in `shader_dir` when given.
+    fn build_pipeline(
+        device: &wgpu::Device,
+        layouts: [&wgpu::BindGroupLayout; 3],
+        format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        shader_dir: Option<&Path>,
+    ) -> Result<wgpu::RenderPipeline, ShaderError> {
+        let mut builder = PipeLineBuilder::new(device);
+        // Tiles are level content, tested against the depth the grid leaves at the far plane
+        builder
+            .set_pixel_format(format)
+            .set_depth_stencil(depth_format, wgpu::CompareFunction::Less)
+            .set_sample_count(sample_count)
+            .set_blend_state(wgpu::BlendState::ALPHA_BLENDING)
+            .set_topology(wgpu::PrimitiveTopology::TriangleStrip)
+            .add_vertex_buffer_layout(TileInstance::desc())
+            .add_bind_group_layout(layouts[0])
+            .add_bind_group_layout(layouts[1])
+            .add_bind_group_layout(layouts[2])
+            .set_shader_module(SHADER_FILE, include_str!("../../tile_shader.wgsl"), "vs_main", "fs_main");
+        if let Some(shader_dir) = shader_dir {
+            builder.set_shader_file(shader_dir.join(SHADER_FILE));
+        }
+        builder.build("Tile Pipeline")
+    }
+
+    /// Swaps in a newly packed tile atlas. Instances keep their texture coordinates, so chunks need writing again
+    /// unless the atlas was packed the same.
+    pub(crate) fn set_atlas(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout, atlas_image: &image::RgbaImage) -> anyhow::Result<()> {
+        self.atlas_bind_group = Some(gpu::create_atlas_bind_group(device, queue, layout, &self.sampler, atlas_image, "Tile Atlas")?);
+        self.atlas_dimensions = atlas_image.dimensions();
+        Ok(())
+    }
+
+    /// Size of the instance buffer in bytes.
+    pub(crate) fn instance_buffer_size(&self) -> u64 {
+        self.instance_buffer.size()
+    }
+
+    /// Size of the tile atlas texture, 0x0 before `set_atlas`.
+    pub(crate) fn atlas_dimensions(&self) -> (u32, u32) {
+        self.atlas_dimensions
+    }
+
+    /// World units along each side of a cell.
+    pub(crate) fn set_cell_size(&self, queue: &wgpu::Queue, cell_size: f32) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[TileUniform::new(cell_size)]));
+    }
+
+    /// Replaces the instances of `chunk`, writing only its slot. An empty `instances` frees the slot.
+    /// Anything past `TILE_CHUNK_SIZE` squared instances is dropped, a chunk has no more cells than that.
+    pub(crate) fn set_chunk(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, chunk: IVec2, instances: &[TileInstance]) {
+        if instances.is_empty() {
+            if let Some(removed) = self.chunks.remove(&chunk) {
+                self.free_slots.push(removed.slot);
+            }
+            return;
+        }
+        if instances.len() > CHUNK_CAPACITY as usize {
+            log::warn!("Chunk {} has {} tiles, only drawing the first {}", chunk, instances.len(), CHUNK_CAPACITY);
+        }
+        let instances = &instances[..instances.len().min(CHUNK_CAPACITY as usize)];
+
+        let slot = match self.chunks.get(&chunk) {
+            Some(existing) => existing.slot,
+            None => self.allocate_slot(device, queue),
+        };
+        queue.write_buffer(&self.instance_buffer, slot_offset(slot), bytemuck::cast_slice(instances));
+        self.chunks.insert(chunk, ChunkSlot { slot, count: instances.len() as u32 });
+    }
+
+    /// Drops every chunk. The instance buffer keeps its size for the next level.
+    pub(crate) fn clear(&mut self) {
+        self.chunks.clear();
+        self.free_slots.clear();
+        self.slots_used = 0;
+    }
+
+    fn allocate_slot(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> u32 {
+        if let Some(slot) = self.free_slots.pop() {
+            return slot;
+        }
+        if self.slots_used == self.slot_capacity {
+            // Copied on the GPU, the chunks already uploaded aren't written again
+            let capacity = self.slot_capacity * 2;
+            let buffer = create_instance_buffer(device, capacity);
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Tile Instance Buffer Growth Encoder")
+            });
+            encoder.copy_buffer_to_buffer(&self.instance_buffer, 0, &buffer, 0, slot_offset(self.slot_capacity));
+            queue.submit(std::iter::once(encoder.finish()));
+            self.instance_buffer = buffer;
+            self.slot_capacity = capacity;
+        }
+        self.slots_used += 1;
+        self.slots_used - 1
+    }
+
+    /// One draw per chunk, with the preview camera bound at group 0. Draws nothing before `set_atlas`.
+    pub(crate) fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, camera_bind_group: &'a wgpu::BindGroup) {
+        let Some(atlas_bind_group) = self.atlas_bind_group.as_ref() else {
+            return;
+        };
+        if self.chunks.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, atlas_bind_group, &[]);
+        render_pass.set_bind_group(2, &self.uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        for chunk in self.chunks.values() {
+            let first = chunk.slot * CHUNK_CAPACITY;
+            render_pass.draw(0..4, first..first + chunk.count);
+        }
+    }
+}
+
+fn slot_offset(slot: u32) -> wgpu::BufferAddress {
+    slot as wgpu::BufferAddress * CHUNK_CAPACITY as wgpu::BufferAddress * INSTANCE_SIZE
+}
+
+fn create_instance_buffer(device: &wgpu::Device, slots: u32) -> wgpu::Buffer {
+    let label = format!("Tile Instance Buffer ({} chunk slots)", slots);
+    create_scoped(device, &label, || device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&label),
+        size: slot_offset(slots),
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    }))
+}
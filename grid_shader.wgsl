@@ -0,0 +1,69 @@
+struct Camera2DUniform {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera2DUniform;
+
+struct GridUniform {
+    spacing: f32,
+    major_every: f32,
+    color_major: vec4<f32>,
+    color_minor: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> grid: GridUniform;
+
+// Lines fewer than this many pixels apart are hidden, they'd blur into a flat tint
+const MIN_LINE_GAP: f32 = 4.0;
+// Lines at least this many pixels apart are drawn at full strength
+const FULL_LINE_GAP: f32 = 16.0;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec2<f32>,
+};
+
+// One triangle covering the whole target: (-1, -1), (3, -1) and (-1, 3) in clip space
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let clip = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    // On the far plane, where the depth buffer is cleared to
+    out.clip_position = vec4<f32>(clip, 1.0, 1.0);
+    // The camera is orthographic, so world positions interpolate linearly across the triangle
+    out.world_position = (camera.inv_view_proj * vec4<f32>(clip, 0.0, 1.0)).xy;
+    return out;
+}
+
+// Fragment shader
+
+// How much of the pixel at `world` a line of a grid `spacing` apart covers, 1 on the line down to 0 a pixel away.
+// `pixel` is the world size of a screen pixel, which keeps lines a pixel wide however far the camera is zoomed.
+fn line_coverage(world: vec2<f32>, spacing: f32, pixel: vec2<f32>) -> f32 {
+    let distance = abs(fract(world / spacing - 0.5) - 0.5) * spacing / pixel;
+    return 1.0 - min(min(distance.x, distance.y), 1.0);
+}
+
+// Fades lines out as they close up, so a zoomed out grid doesn't shimmer
+fn gap_fade(spacing: f32, pixel: vec2<f32>) -> f32 {
+    return smoothstep(MIN_LINE_GAP, FULL_LINE_GAP, spacing / max(pixel.x, pixel.y));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = fwidth(in.world_position);
+    let major_spacing = grid.spacing * grid.major_every;
+
+    let minor = line_coverage(in.world_position, grid.spacing, pixel) * gap_fade(grid.spacing, pixel);
+    let major = line_coverage(in.world_position, major_spacing, pixel) * gap_fade(major_spacing, pixel);
+
+    let minor_color = vec4<f32>(grid.color_minor.rgb, grid.color_minor.a * minor);
+    let major_color = vec4<f32>(grid.color_major.rgb, grid.color_major.a * major);
+    return mix(minor_color, major_color, major);
+}
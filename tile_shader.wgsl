@@ -0,0 +1,68 @@
+struct Camera2DUniform {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera2DUniform;
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+struct TileUniform {
+    cell_size: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> tiles: TileUniform;
+
+struct InstanceInput {
+    @location(0) cell: vec2<i32>,
+    @location(1) uv_rect: vec4<f32>,
+};
+
+// Squares along each side of the placeholder's checkerboard
+const PLACEHOLDER_SQUARES: f32 = 4.0;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    // Position within the cell, 0 to 1 across and up
+    @location(1) corner: vec2<f32>,
+    // 1 for a placeholder, whose uv_rect has no width
+    @location(2) @interpolate(flat) placeholder: u32,
+};
+
+// Drawn as a four vertex strip per instance: bottom-left, bottom-right, top-left, top-right
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+    let world_position = (vec2<f32>(instance.cell) + corner) * tiles.cell_size;
+    out.clip_position = camera.view_proj * vec4<f32>(world_position, 0.0, 1.0);
+    // World y points up and image rows go down, so the top of the cell samples the top of the image
+    out.tex_coords = instance.uv_rect.xy + vec2<f32>(corner.x, 1.0 - corner.y) * instance.uv_rect.zw;
+    out.corner = corner;
+    out.placeholder = select(0u, 1u, instance.uv_rect.z <= 0.0);
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Sampled before branching, texture sampling has to stay in uniform control flow
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    if in.placeholder == 1u {
+        let square = vec2<u32>(floor(in.corner * PLACEHOLDER_SQUARES));
+        let magenta = ((square.x + square.y) & 1u) == 0u;
+        return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(1.0, 0.0, 1.0, 1.0), magenta);
+    }
+    return color;
+}
@@ -19,12 +19,19 @@ struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) color: vec4<f32>,
     @location(2) tex_coords: vec2<f32>,
+    @location(3) mode: u32,
+    @location(4) bounds: vec4<f32>,
+    @location(5) corner: vec2<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) @interpolate(flat) mode: u32,
+    @location(3) position: vec2<f32>,
+    @location(4) @interpolate(flat) bounds: vec4<f32>,
+    @location(5) @interpolate(flat) corner: vec2<f32>,
 };
 
 @vertex
@@ -35,15 +42,58 @@ fn vs_main(
     out.clip_position = camera.view_proj * vec4<f32>(in.position, 0.0, 1.0);
     out.color = in.color;
     out.tex_coords = in.tex_coords;
+    out.mode = in.mode;
+    out.position = in.position;
+    out.bounds = in.bounds;
+    out.corner = in.corner;
     return out;
 }
 
+// Signed distance from `p` to a rect `half_size` from its center with corners rounded by `radius`,
+// negative inside. The radius is clamped to half the shorter side, which makes a capsule.
+fn rounded_rect_distance(p: vec2<f32>, center: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let r = min(radius, min(half_size.x, half_size.y));
+    let q = abs(p - center) - half_size + vec2<f32>(r);
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+}
+
+// Share of the pixel covered by the quad's rounded shape, a filled rect or a `ring` wide outline of one.
+fn corner_coverage(p: vec2<f32>, bounds: vec4<f32>, radius: f32, ring: f32) -> f32 {
+    let center = (bounds.xy + bounds.zw) * 0.5;
+    let half_size = (bounds.zw - bounds.xy) * 0.5;
+    var coverage = clamp(0.5 - rounded_rect_distance(p, center, half_size, radius), 0.0, 1.0);
+    if ring > 0.0 {
+        let inner_half = max(half_size - vec2<f32>(ring), vec2<f32>(0.0));
+        let inner = rounded_rect_distance(p, center, inner_half, max(radius - ring, 0.0));
+        coverage = coverage * clamp(0.5 + inner, 0.0, 1.0);
+    }
+    return coverage;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var final_color: vec4<f32>;
+    // Sampled before branching, textureSample has to be called in uniform control flow
+    let sampled = textureSample(my_texture, my_sampler, in.tex_coords);
 
-    final_color = textureSample(my_texture, my_sampler, in.tex_coords);
-    final_color = final_color * vec4<f32>(in.color);
+    var color: vec4<f32>;
+    switch in.mode {
+        // Texture
+        case 1u: {
+            color = sampled;
+        }
+        // TextureTinted
+        case 2u: {
+            color = sampled * in.color;
+        }
+        // SolidColor
+        default: {
+            color = in.color;
+        }
+    }
 
-    return final_color;
+    // Square quads skip the distance field entirely so they come out exactly as they are
+    if in.corner.x > 0.0 {
+        color.a = color.a * corner_coverage(in.position, in.bounds, in.corner.x, in.corner.y);
+    }
+    return color;
 }
\ No newline at end of file